@@ -7,6 +7,8 @@ mod chord;
 pub mod did;
 /// Finger table for Rings
 pub mod finger;
+/// Implement quorum-gated VNode
+pub mod quorum;
 mod stabilization;
 /// Implement Subring with VNode
 pub mod subring;
@@ -0,0 +1,327 @@
+#![warn(missing_docs)]
+//! Quorum-gated virtual nodes.
+//!
+//! A [QuorumGroup] is a set of BLS accounts with a threshold. The virtual node of a group can
+//! only be written by [VNodeOperation::QuorumWrite](super::vnode::VNodeOperation::QuorumWrite),
+//! which carries an [AggregateVerification] of at least `threshold` members over the written
+//! data. The node storing the virtual node checks the quorum before applying the write, and
+//! plain operations on it are rejected.
+//!
+//! The accepted [QuorumWrite] is stored as is, so that anyone fetching the virtual node can
+//! verify the approval again without trusting the storing node. The ttl of co-signature is
+//! only checked when the write is accepted, a stored write stays verifiable after it expires.
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::vnode::VNodeType;
+use super::vnode::VirtualNode;
+use crate::dht::Did;
+use crate::ecc::keccak256;
+use crate::error::Error;
+use crate::error::Result;
+use crate::message::AggregateVerification;
+use crate::message::Encoded;
+use crate::message::Encoder;
+
+/// The prefix of topics of quorum groups, see [QuorumGroup::topic].
+pub const QUORUM_TOPIC_PREFIX: &str = "rings/quorum/";
+
+/// A group of accounts which approves writes to its virtual node together.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuorumGroup {
+    /// name of group
+    pub name: String,
+    /// accounts of members, sorted and deduplicated
+    pub members: Vec<Did>,
+    /// least number of members to approve a write
+    pub threshold: usize,
+}
+
+/// Data approved by a quorum of [QuorumGroup].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuorumWrite {
+    /// group which the data is written for
+    pub group: QuorumGroup,
+    /// the written data
+    pub data: Encoded,
+    /// co-signature of members over [QuorumWrite::cosign_data]
+    pub cosignature: AggregateVerification,
+}
+
+impl QuorumGroup {
+    /// Create a new QuorumGroup, the threshold should be between 1 and the number of members.
+    pub fn new(name: &str, mut members: Vec<Did>, threshold: usize) -> Result<Self> {
+        members.sort();
+        members.dedup();
+        if threshold == 0 || threshold > members.len() {
+            return Err(Error::InvalidQuorumGroup(format!(
+                "threshold {} of {} members",
+                threshold,
+                members.len()
+            )));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            members,
+            threshold,
+        })
+    }
+
+    /// The topic of virtual node of group, which commits to name, members and threshold.
+    pub fn topic(&self) -> Result<String> {
+        let bytes = bincode::serialize(self).map_err(Error::BincodeSerialize)?;
+        Ok(format!(
+            "{}{}",
+            QUORUM_TOPIC_PREFIX,
+            hex::encode(keccak256(&bytes))
+        ))
+    }
+
+    /// Did of virtual node of group.
+    pub fn did(&self) -> Result<Did> {
+        VirtualNode::gen_did(&self.topic()?)
+    }
+
+    /// Check the co-signers are members of group and reach the threshold.
+    pub fn check_quorum(&self, cosignature: &AggregateVerification) -> Result<()> {
+        let signers = cosignature.signers();
+        if let Some(outsider) = signers.iter().find(|s| !self.members.contains(s)) {
+            return Err(Error::NotQuorumMember(*outsider));
+        }
+        if signers.len() < self.threshold {
+            return Err(Error::QuorumNotReached(signers.len(), self.threshold));
+        }
+        Ok(())
+    }
+}
+
+impl QuorumWrite {
+    /// The data that members should sign by
+    /// [AggregateVerificationBuilder](crate::message::AggregateVerificationBuilder).
+    pub fn cosign_data(group: &QuorumGroup, data: &Encoded) -> Result<Vec<u8>> {
+        let bytes = bincode::serialize(&(group, data)).map_err(Error::BincodeSerialize)?;
+        Ok(keccak256(&bytes).to_vec())
+    }
+
+    /// Verify the co-signature and the quorum of group, the ttl of co-signature is not checked,
+    /// see [AggregateVerification::verify_signature].
+    pub fn verify(&self) -> Result<()> {
+        if !self
+            .cosignature
+            .verify_signature(&Self::cosign_data(&self.group, &self.data)?)
+        {
+            return Err(Error::VerifySignatureFailed);
+        }
+        self.group.check_quorum(&self.cosignature)
+    }
+
+    /// Did of virtual node written to.
+    pub fn did(&self) -> Result<Did> {
+        self.group.did()
+    }
+}
+
+impl TryFrom<QuorumWrite> for VirtualNode {
+    type Error = Error;
+    fn try_from(write: QuorumWrite) -> Result<Self> {
+        let data = serde_json::to_string(&write).map_err(|_| Error::SerializeToString)?;
+        Ok(Self {
+            did: write.did()?,
+            data: vec![data.encode()?],
+            kind: VNodeType::Quorum,
+        })
+    }
+}
+
+impl TryFrom<VirtualNode> for QuorumWrite {
+    type Error = Error;
+    fn try_from(vnode: VirtualNode) -> Result<Self> {
+        match (&vnode.kind, vnode.data.first()) {
+            (VNodeType::Quorum, Some(data)) => {
+                let decoded: String = data.decode()?;
+                serde_json::from_str(&decoded).map_err(Error::Deserialize)
+            }
+            _ => Err(Error::InvalidVNodeType),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::dht::vnode::VNodeOperation;
+    use crate::ecc::signers::bls;
+    use crate::ecc::SecretKey;
+    use crate::message::AggregateVerificationBuilder;
+    use crate::session::SessionSk;
+    use crate::session::SessionSkBuilder;
+
+    pub(crate) fn bls_session(key: SecretKey) -> SessionSk {
+        let pk = bls::public_key(&key).unwrap();
+        let builder = SessionSkBuilder::new(hex::encode(pk.0), "bls".to_string());
+        let sig = bls::sign(key, builder.unsigned_proof().as_bytes()).unwrap();
+        builder.set_session_sig(sig.0.to_vec()).build().unwrap()
+    }
+
+    pub(crate) fn cosign(
+        keys: &[SecretKey],
+        group: &QuorumGroup,
+        data: &Encoded,
+    ) -> AggregateVerification {
+        let mut builder =
+            AggregateVerificationBuilder::new(&QuorumWrite::cosign_data(group, data).unwrap());
+        let proof = builder.unsigned_proof();
+        for key in keys {
+            let sig = bls::sign(*key, &proof).unwrap();
+            builder
+                .add_signature(bls_session(*key).session(), &sig.0)
+                .unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_aggregate_verification_builder() {
+        let keys = [bls::random_sk().unwrap(), bls::random_sk().unwrap()];
+        let sessions = keys.map(bls_session);
+        let data = b"data".to_vec();
+
+        let mut builder = AggregateVerificationBuilder::new(&data);
+        let proof = builder.unsigned_proof();
+        let sig0 = bls::sign(keys[0], &proof).unwrap();
+        let sig1 = bls::sign(keys[1], &proof).unwrap();
+        builder
+            .add_signature(sessions[0].session(), &sig0.0)
+            .unwrap();
+        builder
+            .add_signature(sessions[1].session(), &sig1.0)
+            .unwrap();
+
+        // Duplicated co-signer, signature of other key and secp256k1 account are rejected.
+        assert!(matches!(
+            builder.add_signature(sessions[0].session(), &sig0.0),
+            Err(Error::DuplicatedCoSigner(_))
+        ));
+        let mut other = AggregateVerificationBuilder::new(&data);
+        assert!(matches!(
+            other.add_signature(sessions[0].session(), &sig1.0),
+            Err(Error::VerifySignatureFailed)
+        ));
+        let secp_session = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+        assert!(matches!(
+            other.add_signature(secp_session.session(), &sig0.0),
+            Err(Error::NotBlsAccount(_))
+        ));
+        assert!(matches!(other.build(), Err(Error::NoCoSigner)));
+
+        let cosignature = builder.build().unwrap();
+        assert_eq!(cosignature.sig.len(), 96);
+        assert!(cosignature.verify(&data));
+        assert!(!cosignature.verify(b"other"));
+        assert_eq!(
+            cosignature.signers(),
+            sessions.iter().map(|s| s.account_did()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_quorum_write() {
+        let keys = [
+            bls::random_sk().unwrap(),
+            bls::random_sk().unwrap(),
+            bls::random_sk().unwrap(),
+        ];
+        let members = keys.map(|k| bls_session(k).account_did()).to_vec();
+        assert!(QuorumGroup::new("group", members.clone(), 0).is_err());
+        assert!(QuorumGroup::new("group", members.clone(), 4).is_err());
+        let group = QuorumGroup::new("group", members, 2).unwrap();
+        let data = "approved".to_string().encode().unwrap();
+
+        let write = QuorumWrite {
+            group: group.clone(),
+            data: data.clone(),
+            cosignature: cosign(&keys[..2], &group, &data),
+        };
+        write.verify().unwrap();
+        let vnode = VirtualNode::try_from(write.clone()).unwrap();
+        assert_eq!(vnode.did, group.did().unwrap());
+        assert_eq!(QuorumWrite::try_from(vnode.clone()).unwrap(), write);
+
+        // Below threshold, outsider and other data are rejected.
+        let below = QuorumWrite {
+            cosignature: cosign(&keys[..1], &group, &data),
+            ..write.clone()
+        };
+        assert!(matches!(below.verify(), Err(Error::QuorumNotReached(1, 2))));
+        let outsider = bls::random_sk().unwrap();
+        let forged = QuorumWrite {
+            cosignature: cosign(&[keys[0], outsider], &group, &data),
+            ..write.clone()
+        };
+        assert!(matches!(forged.verify(), Err(Error::NotQuorumMember(_))));
+        let tampered = QuorumWrite {
+            data: "tampered".to_string().encode().unwrap(),
+            ..write.clone()
+        };
+        assert!(tampered.verify().is_err());
+
+        // The virtual node is only written by quorum, and newer writes replace older ones.
+        let empty = VNodeOperation::QuorumWrite(write.clone())
+            .gen_default_vnode()
+            .unwrap();
+        assert!(empty.operate(VNodeOperation::QuorumWrite(below)).is_err());
+        let stored = empty
+            .operate(VNodeOperation::QuorumWrite(write.clone()))
+            .unwrap();
+        assert_eq!(stored, vnode);
+        let plain = VirtualNode {
+            did: vnode.did,
+            data: vec!["plain".to_string().encode().unwrap()],
+            kind: VNodeType::Data,
+        };
+        assert!(stored
+            .operate(VNodeOperation::Overwrite(plain.clone()))
+            .is_err());
+        assert!(stored.operate(VNodeOperation::Extend(plain)).is_err());
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let data = "updated".to_string().encode().unwrap();
+        let update = QuorumWrite {
+            group: group.clone(),
+            data: data.clone(),
+            cosignature: cosign(&keys[1..], &group, &data),
+        };
+        let updated = stored
+            .operate(VNodeOperation::QuorumWrite(update.clone()))
+            .unwrap();
+        assert_eq!(QuorumWrite::try_from(updated.clone()).unwrap(), update);
+        assert!(matches!(
+            updated.operate(VNodeOperation::QuorumWrite(write)),
+            Err(Error::QuorumWriteOutdated)
+        ));
+
+        // Expired write is rejected, but the stored one is still verifiable after it expires.
+        let mut builder = AggregateVerificationBuilder::new(
+            &QuorumWrite::cosign_data(&group, &update.data).unwrap(),
+        )
+        .set_ttl(1);
+        let proof = builder.unsigned_proof();
+        for key in &keys[..2] {
+            let sig = bls::sign(*key, &proof).unwrap();
+            builder
+                .add_signature(bls_session(*key).session(), &sig.0)
+                .unwrap();
+        }
+        let expired = QuorumWrite {
+            cosignature: builder.build().unwrap(),
+            ..update
+        };
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        expired.verify().unwrap();
+        assert!(matches!(
+            empty.operate(VNodeOperation::QuorumWrite(expired)),
+            Err(Error::AggregateVerificationExpired)
+        ));
+    }
+}
@@ -97,7 +97,7 @@ impl Stabilizer {
             for s in successor_list {
                 tracing::debug!("STABILIZATION notify_predecessor: {:?}", s);
                 let payload =
-                    MessagePayload::new_send(msg.clone(), self.transport.session_sk(), s, s)?;
+                    MessagePayload::new_send(msg.clone(), &self.transport.session_sk()?, s, s)?;
                 self.transport.send_payload(payload).await?;
             }
             Ok(())
@@ -123,7 +123,7 @@ impl Stabilizer {
                     });
                     let payload = MessagePayload::new_send(
                         msg.clone(),
-                        self.transport.session_sk(),
+                        &self.transport.session_sk()?,
                         closest_predecessor,
                         closest_predecessor,
                     )?;
@@ -6,6 +6,7 @@ use num_bigint::BigUint;
 use serde::Deserialize;
 use serde::Serialize;
 
+use super::quorum::QuorumWrite;
 use super::subring::Subring;
 use crate::consts::VNODE_DATA_MAX_LEN;
 use crate::dht::Did;
@@ -27,6 +28,8 @@ pub enum VNodeType {
     /// A relayed but unreached message, which should be stored on
     /// the successor of the destination Did.
     RelayMessage,
+    /// Data approved by a quorum of [QuorumGroup](super::quorum::QuorumGroup).
+    Quorum,
 }
 
 /// VNode Operations
@@ -43,6 +46,9 @@ pub enum VNodeOperation {
     Touch(VirtualNode),
     /// Join subring.
     JoinSubring(String, Did),
+    /// Write data approved by a quorum to the virtual node of its group.
+    /// This operation will create VirtualNode if it's not existed.
+    QuorumWrite(QuorumWrite),
 }
 
 /// A `VirtualNode` is a piece of data with [VNodeType] and [Did]. You can save it to
@@ -54,6 +60,7 @@ pub enum VNodeOperation {
 /// * If type value is [VNodeType::RelayMessage], it's the destination Did of
 /// message plus 1 (to ensure that the message is sent to the successor of destination),
 /// thus while destination node going online, it will sync message from its successor.
+/// * If type value is [VNodeType::Quorum], it's sha1 of the topic of its quorum group.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VirtualNode {
     /// The did of `VirtualNode` make it unique, and can be stored and retrieved on DHT.
@@ -82,6 +89,7 @@ impl VNodeOperation {
             VNodeOperation::Extend(vnode) => vnode.did,
             VNodeOperation::Touch(vnode) => vnode.did,
             VNodeOperation::JoinSubring(name, _) => VirtualNode::gen_did(name)?,
+            VNodeOperation::QuorumWrite(write) => write.did()?,
         })
     }
 
@@ -92,6 +100,7 @@ impl VNodeOperation {
             VNodeOperation::Extend(vnode) => vnode.kind,
             VNodeOperation::Touch(vnode) => vnode.kind,
             VNodeOperation::JoinSubring(..) => VNodeType::Subring,
+            VNodeOperation::QuorumWrite(_) => VNodeType::Quorum,
         }
     }
 
@@ -172,6 +181,7 @@ impl VirtualNode {
             VNodeOperation::Extend(vnode) => self.extend(vnode),
             VNodeOperation::Touch(vnode) => self.touch(vnode),
             VNodeOperation::JoinSubring(_, did) => self.join_subring(did),
+            VNodeOperation::QuorumWrite(write) => self.quorum_write(write),
         }
     }
 
@@ -268,6 +278,27 @@ impl VirtualNode {
         subring.finger.join(did);
         subring.try_into()
     }
+
+    /// This method is used to write data approved by a quorum of group.
+    /// The co-signature of write should not be expired, and the write should be newer than the
+    /// stored one, a non-quorum virtual node at the did of group is replaced.
+    /// The handler of [VNodeOperation::QuorumWrite].
+    pub fn quorum_write(&self, write: QuorumWrite) -> Result<Self> {
+        if write.cosignature.is_expired() {
+            return Err(Error::AggregateVerificationExpired);
+        }
+        write.verify()?;
+        if self.did != write.did()? {
+            return Err(Error::VNodeDidNotEqual);
+        }
+        if self.kind == VNodeType::Quorum && !self.data.is_empty() {
+            let stored = QuorumWrite::try_from(self.clone())?;
+            if write.cosignature.ts_ms <= stored.cosignature.ts_ms {
+                return Err(Error::QuorumWriteOutdated);
+            }
+        }
+        write.try_into()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,509 @@
+#![warn(missing_docs)]
+//! Account directory of devices.
+//!
+//! The [Did] of a node is the did of the account of its session, so each device of a user runs
+//! a node with an account of its own. To appear as one user, the user's [Account] signs a
+//! [DeviceLink] for each device, which declares "the node of did is my device for the time
+//! period {ts, ttl}". The account may be a wallet or an external signer that never runs a node.
+//!
+//! Each device publishes a [DeviceEntry] to the DHT periodically, in the data virtual node of
+//! [account_directory_topic]. An entry carries the link and is signed by the current session of
+//! device, with a short ttl, so the directory only lists devices which are online recently and
+//! follows their session renewals. Anyone can append data to the virtual node, so the resolver
+//! skips entries which are not verified, expired or revoked, and keeps the latest one of each
+//! device.
+//!
+//! A device is removed from the account before its link expires by a
+//! [SessionRevocation](crate::revocation::SessionRevocation) of the account, whose session id
+//! is the did of device.
+//!
+//! To link a device and resolve the directory:
+//! ```
+//! use rings_core::dht::vnode::VirtualNode;
+//! use rings_core::dht::Did;
+//! use rings_core::directory::account_directory_topic;
+//! use rings_core::directory::DeviceEntry;
+//! use rings_core::directory::DeviceLinkBuilder;
+//! use rings_core::message::Encoded;
+//! use rings_core::revocation::RevocationList;
+//! use rings_core::session::SessionSk;
+//!
+//! let user_secret_key = rings_core::ecc::SecretKey::random();
+//! let account: Did = user_secret_key.address().into();
+//!
+//! // The device runs a node with its own account.
+//! let device_sk = SessionSk::new_with_seckey(&rings_core::ecc::SecretKey::random()).unwrap();
+//!
+//! let builder = DeviceLinkBuilder::new(
+//!     account.to_string(),
+//!     "secp256k1".to_string(),
+//!     device_sk.account_did().to_string(),
+//! );
+//! let sig = user_secret_key.sign(&builder.unsigned_proof().unwrap());
+//! let link = builder.set_sig(sig.to_vec()).build().unwrap();
+//!
+//! let entry = DeviceEntry::new(link, &device_sk, 90 * 1000).unwrap();
+//! let vnode: VirtualNode = (
+//!     account_directory_topic(account),
+//!     Encoded::try_from(&entry).unwrap(),
+//! )
+//!     .try_into()
+//!     .unwrap();
+//! let devices = DeviceEntry::resolve(&vnode, account, &RevocationList::default());
+//! assert_eq!(devices, vec![entry]);
+//! ```
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use rings_derive::wasm_export;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::consts::DEFAULT_SESSION_TTL_MS;
+use crate::dht::vnode::VirtualNode;
+use crate::dht::Did;
+use crate::error::Error;
+use crate::error::Result;
+use crate::message::Encoded;
+use crate::revocation::RevocationList;
+use crate::session::verify_account_sig;
+use crate::session::Account;
+use crate::session::AccountSigner;
+use crate::session::Session;
+use crate::session::SessionSk;
+use crate::utils;
+
+/// The prefix of topics which device entries are published to, see [account_directory_topic].
+pub const ACCOUNT_DIRECTORY_TOPIC_PREFIX: &str = "rings/accounts/";
+
+/// The topic of data virtual node which the devices of account are published to.
+pub fn account_directory_topic(account: Did) -> String {
+    format!("{}{}", ACCOUNT_DIRECTORY_TOPIC_PREFIX, account)
+}
+
+fn pack_link(device: Did, ts_ms: u128, ttl_ms: u64) -> String {
+    format!("device\n{}\n{}\n{}", device, ts_ms, ttl_ms)
+}
+
+/// DeviceLinkBuilder is used to build a [DeviceLink].
+///
+/// Like [SessionSkBuilder](crate::session::SessionSkBuilder), the account signs the
+/// `unsigned_proof` outside and set the signature back by `set_sig`.
+#[wasm_export]
+pub struct DeviceLinkBuilder {
+    account_entity: String,
+    account_type: String,
+    device: String,
+    ttl_ms: u64,
+    ts_ms: u128,
+    sig: Vec<u8>,
+}
+
+/// A record signed by [Account] to declare that a node is one of its devices.
+#[wasm_export]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct DeviceLink {
+    /// Account who owns the device.
+    account: Account,
+    /// Did of the node of device.
+    device: Did,
+    /// Lifetime of link.
+    ttl_ms: u64,
+    /// Timestamp when link created.
+    ts_ms: u128,
+    /// Signature of account.
+    sig: Vec<u8>,
+}
+
+/// A record signed by the session of device, which announces that the device is online.
+#[wasm_export]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct DeviceEntry {
+    /// Link of device to account.
+    link: DeviceLink,
+    /// Session of device.
+    session: Session,
+    /// Lifetime of entry, the device is considered offline after it.
+    ttl_ms: u64,
+    /// Timestamp when entry created.
+    ts_ms: u128,
+    /// Signature of session.
+    sig: Vec<u8>,
+}
+
+// A DeviceLink can be converted to a string using JSON and then encoded with base58.
+// To load the DeviceLink from a string, use `DeviceLink::from_str`.
+impl FromStr for DeviceLink {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = base58_monero::decode_check(s).map_err(|_| Error::Decode)?;
+        serde_json::from_slice(&s).map_err(Error::Deserialize)
+    }
+}
+
+// A DeviceEntry can be converted to a string using JSON and then encoded with base58.
+// To load the DeviceEntry from a string, use `DeviceEntry::from_str`.
+impl FromStr for DeviceEntry {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = base58_monero::decode_check(s).map_err(|_| Error::Decode)?;
+        serde_json::from_slice(&s).map_err(Error::Deserialize)
+    }
+}
+
+#[wasm_export]
+impl DeviceLinkBuilder {
+    /// Create a new DeviceLinkBuilder.
+    /// The "account_entity" and "account_type" are the same as [SessionSkBuilder](crate::session::SessionSkBuilder::new).
+    /// The "device" is the did of the node of device.
+    pub fn new(account_entity: String, account_type: String, device: String) -> DeviceLinkBuilder {
+        Self {
+            account_entity,
+            account_type,
+            device,
+            ttl_ms: DEFAULT_SESSION_TTL_MS,
+            ts_ms: utils::get_epoch_ms(),
+            sig: vec![],
+        }
+    }
+
+    /// Set the lifetime of link. It should be called before `unsigned_proof`.
+    pub fn set_ttl(mut self, ttl_ms: u64) -> Self {
+        self.ttl_ms = ttl_ms;
+        self
+    }
+
+    /// Construct unsigned_info string for signing.
+    pub fn unsigned_proof(&self) -> Result<String> {
+        Ok(pack_link(
+            Did::from_str(&self.device)?,
+            self.ts_ms,
+            self.ttl_ms,
+        ))
+    }
+
+    /// Set the signature of link that signed by account.
+    pub fn set_sig(mut self, sig: Vec<u8>) -> Self {
+        self.sig = sig;
+        self
+    }
+
+    /// Build the [DeviceLink].
+    pub fn build(self) -> Result<DeviceLink> {
+        let link = DeviceLink {
+            account: Account::try_from((self.account_entity, self.account_type))?,
+            device: Did::from_str(&self.device)?,
+            ttl_ms: self.ttl_ms,
+            ts_ms: self.ts_ms,
+            sig: self.sig,
+        };
+        link.verify()?;
+        Ok(link)
+    }
+}
+
+impl DeviceLinkBuilder {
+    /// Create a new DeviceLinkBuilder for the account of signer.
+    pub async fn from_signer<S>(signer: &S, device: String) -> Result<Self>
+    where S: AccountSigner + ?Sized {
+        let (account_entity, account_type) = signer.account().await?;
+        Ok(Self::new(account_entity, account_type, device))
+    }
+
+    /// Sign the unsigned proof by signer and set the signature.
+    /// It should be called after the lifetime is set.
+    pub async fn sign_by<S>(self, signer: &S) -> Result<Self>
+    where S: AccountSigner + ?Sized {
+        let sig = signer.sign(&self.unsigned_proof()?).await?;
+        Ok(self.set_sig(sig))
+    }
+}
+
+impl DeviceLink {
+    /// Verify the link is signed by its account and not expired.
+    pub fn verify(&self) -> Result<()> {
+        if self.is_expired() {
+            return Err(Error::InvalidDeviceLink(format!(
+                "link of device {} is expired",
+                self.device
+            )));
+        }
+        let auth_bytes = pack_link(self.device, self.ts_ms, self.ttl_ms);
+        if !verify_account_sig(&self.account, auth_bytes.as_bytes(), &self.sig) {
+            return Err(Error::VerifySignatureFailed);
+        }
+        Ok(())
+    }
+
+    /// Check link is expired or not.
+    pub fn is_expired(&self) -> bool {
+        utils::get_epoch_ms() > self.expires_at()
+    }
+
+    /// Timestamp in milliseconds when link expires.
+    pub fn expires_at(&self) -> u128 {
+        self.ts_ms + self.ttl_ms as u128
+    }
+
+    /// Get account did of link.
+    pub fn account_did(&self) -> Did {
+        self.account.did()
+    }
+
+    /// Did of the node of device.
+    pub fn device(&self) -> Did {
+        self.device
+    }
+}
+
+#[wasm_export]
+impl DeviceLink {
+    /// Dump link to string. It can be restored using `DeviceLink::from_str`.
+    pub fn dump(&self) -> Result<String> {
+        let s = serde_json::to_string(&self).map_err(|_| Error::SerializeError)?;
+        base58_monero::encode_check(s.as_bytes()).map_err(|_| Error::Encode)
+    }
+}
+
+impl DeviceEntry {
+    /// Create an entry of the device of session, which is valid for `ttl_ms`.
+    pub fn new(link: DeviceLink, session_sk: &SessionSk, ttl_ms: u64) -> Result<Self> {
+        let mut entry = Self {
+            link,
+            session: session_sk.session(),
+            ttl_ms,
+            ts_ms: utils::get_epoch_ms(),
+            sig: vec![],
+        };
+        entry.sig = session_sk.sign(&entry.pack()?)?;
+        entry.verify()?;
+        Ok(entry)
+    }
+
+    /// Pack the entry without signature for signing.
+    fn pack(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(&(&self.link, &self.session, self.ttl_ms, self.ts_ms))
+            .map_err(|_| Error::SerializeError)
+    }
+
+    /// Verify the entry is signed by an unexpired session of the linked device.
+    pub fn verify(&self) -> Result<()> {
+        if self.session.account_did() != self.link.device {
+            return Err(Error::InvalidDeviceLink(format!(
+                "session of account {} cannot sign entry of device {}",
+                self.session.account_did(),
+                self.link.device
+            )));
+        }
+        self.link.verify()?;
+        self.session.verify(&self.pack()?, &self.sig)
+    }
+
+    /// Check entry is expired or not, the device is considered offline if expired.
+    pub fn is_expired(&self) -> bool {
+        utils::get_epoch_ms() > self.ts_ms + self.ttl_ms as u128
+    }
+
+    /// Check the device or its session is revoked.
+    pub fn is_revoked(&self, revocations: &RevocationList) -> bool {
+        revocations.contains(self.link.device, self.link.account_did())
+            || revocations.is_revoked(&self.session)
+    }
+
+    /// Pick the online devices of account from the data virtual node of
+    /// [account_directory_topic], by the latest valid entry of each device.
+    /// The most recently announced devices come first.
+    pub fn resolve(vnode: &VirtualNode, account: Did, revocations: &RevocationList) -> Vec<Self> {
+        let mut latest: HashMap<Did, Self> = HashMap::new();
+        // Entries are verified before picking the latest, so a forged one can't hide the device.
+        for entry in vnode
+            .data
+            .iter()
+            .filter_map(|encoded| DeviceEntry::from_str(encoded.value()).ok())
+            .filter(|entry| entry.account_did() == account)
+            .filter(|entry| {
+                entry
+                    .verify()
+                    .map_err(|e| tracing::debug!("invalid device entry of {}: {:?}", account, e))
+                    .is_ok()
+            })
+        {
+            if !latest
+                .get(&entry.device())
+                .is_some_and(|e| e.ts_ms >= entry.ts_ms)
+            {
+                latest.insert(entry.device(), entry);
+            }
+        }
+
+        let mut devices: Vec<Self> = latest
+            .into_values()
+            .filter(|entry| !entry.is_expired() && !entry.is_revoked(revocations))
+            .collect();
+        devices.sort_by(|a, b| b.ts_ms.cmp(&a.ts_ms));
+        devices
+    }
+
+    /// Link of device to account.
+    pub fn link(&self) -> &DeviceLink {
+        &self.link
+    }
+
+    /// Get account did of entry.
+    pub fn account_did(&self) -> Did {
+        self.link.account_did()
+    }
+
+    /// Did of the node of device.
+    pub fn device(&self) -> Did {
+        self.link.device
+    }
+
+    /// Session of device.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Timestamp when entry created.
+    pub fn ts_ms(&self) -> u128 {
+        self.ts_ms
+    }
+
+    /// Timestamp in milliseconds when entry expires.
+    pub fn expires_at(&self) -> u128 {
+        self.ts_ms + self.ttl_ms as u128
+    }
+}
+
+#[wasm_export]
+impl DeviceEntry {
+    /// Dump entry to string. It can be restored using `DeviceEntry::from_str`.
+    pub fn dump(&self) -> Result<String> {
+        let s = serde_json::to_string(&self).map_err(|_| Error::SerializeError)?;
+        base58_monero::encode_check(s.as_bytes()).map_err(|_| Error::Encode)
+    }
+}
+
+impl TryFrom<&DeviceEntry> for Encoded {
+    type Error = Error;
+
+    fn try_from(entry: &DeviceEntry) -> Result<Self> {
+        Ok(entry.dump()?.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ecc::SecretKey;
+    use crate::revocation::SessionRevocationBuilder;
+
+    fn link(key: &SecretKey, device: Did) -> DeviceLink {
+        let builder = DeviceLinkBuilder::new(
+            Did::from(key.address()).to_string(),
+            "secp256k1".to_string(),
+            device.to_string(),
+        );
+        let sig = key.sign(&builder.unsigned_proof().unwrap());
+        builder.set_sig(sig.to_vec()).build().unwrap()
+    }
+
+    fn vnode_of(account: Did, entries: &[&DeviceEntry]) -> VirtualNode {
+        let mut data: Vec<Encoded> = entries
+            .iter()
+            .map(|e| Encoded::try_from(*e).unwrap())
+            .collect();
+        data.push("invalid".into());
+        VirtualNode {
+            did: VirtualNode::gen_did(&account_directory_topic(account)).unwrap(),
+            data,
+            kind: crate::dht::vnode::VNodeType::Data,
+        }
+    }
+
+    #[test]
+    fn test_resolve_devices_of_account() {
+        let key = SecretKey::random();
+        let account: Did = key.address().into();
+        let laptop = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+        let phone = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+        let laptop_link = link(&key, laptop.account_did());
+        let phone_link = link(&key, phone.account_did());
+
+        let dump = laptop_link.dump().unwrap();
+        assert_eq!(DeviceLink::from_str(&dump).unwrap(), laptop_link);
+
+        let old = DeviceEntry::new(laptop_link.clone(), &laptop, 90 * 1000).unwrap();
+        let offline = DeviceEntry::new(phone_link, &phone, 0).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let new = DeviceEntry::new(laptop_link, &laptop, 90 * 1000).unwrap();
+
+        let revocations = RevocationList::default();
+        let vnode = vnode_of(account, &[&old, &offline, &new]);
+        assert_eq!(DeviceEntry::resolve(&vnode, account, &revocations), vec![
+            new
+        ]);
+
+        // Devices of other account are skipped.
+        let other: Did = SecretKey::random().address().into();
+        assert!(DeviceEntry::resolve(&vnode, other, &revocations).is_empty());
+    }
+
+    #[test]
+    fn test_reject_forged_entry() {
+        let key = SecretKey::random();
+        let account: Did = key.address().into();
+        let device = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+
+        // A link not signed by account.
+        let builder = DeviceLinkBuilder::new(
+            account.to_string(),
+            "secp256k1".to_string(),
+            device.account_did().to_string(),
+        );
+        let sig = SecretKey::random().sign(&builder.unsigned_proof().unwrap());
+        assert!(builder.set_sig(sig.to_vec()).build().is_err());
+
+        // An entry signed by session of other device.
+        let attacker = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+        assert!(DeviceEntry::new(link(&key, device.account_did()), &attacker, 90 * 1000).is_err());
+
+        // A tampered entry.
+        let mut tampered =
+            DeviceEntry::new(link(&key, device.account_did()), &device, 90 * 1000).unwrap();
+        tampered.ttl_ms = u64::MAX / 2;
+        assert!(tampered.verify().is_err());
+
+        let vnode = vnode_of(account, &[&tampered]);
+        let revocations = RevocationList::default();
+        assert!(DeviceEntry::resolve(&vnode, account, &revocations).is_empty());
+    }
+
+    #[test]
+    fn test_skip_revoked_device() {
+        let key = SecretKey::random();
+        let account: Did = key.address().into();
+        let device = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+        let entry = DeviceEntry::new(link(&key, device.account_did()), &device, 90 * 1000).unwrap();
+        let vnode = vnode_of(account, &[&entry]);
+
+        // Device is revoked by account like a session.
+        let builder = SessionRevocationBuilder::new(
+            account.to_string(),
+            "secp256k1".to_string(),
+            device.account_did().to_string(),
+        );
+        let sig = key.sign(&builder.unsigned_proof().unwrap());
+        let revocations = RevocationList::default();
+        assert_eq!(DeviceEntry::resolve(&vnode, account, &revocations), vec![
+            entry
+        ]);
+        revocations
+            .insert(builder.set_sig(sig.to_vec()).build().unwrap())
+            .unwrap();
+        assert!(DeviceEntry::resolve(&vnode, account, &revocations).is_empty());
+    }
+}
@@ -0,0 +1,497 @@
+#![warn(missing_docs)]
+//! DID documents of nodes.
+//!
+//! A [Did] is only an address, peers learn nothing else about a node before connecting to it.
+//! Each node publishes a [DidDocument] to the DHT, in the data virtual node of
+//! [did_document_topic], which describes how to reach the node and what it speaks:
+//! its session pubkey and account type, supported protocols and version, advertised services,
+//! external endpoint URL and transport capabilities.
+//!
+//! A document is signed by the session of node, so it's only valid while the session is valid
+//! and not revoked. Anyone can append data to the virtual node, so the resolver skips records
+//! which are not signed by a session of the did, and picks the latest one.
+//!
+//! To build and resolve a [DidDocument]:
+//! ```
+//! use rings_core::dht::vnode::VirtualNode;
+//! use rings_core::document::did_document_topic;
+//! use rings_core::document::DidDocument;
+//! use rings_core::document::DidDocumentBuilder;
+//! use rings_core::document::Transport;
+//! use rings_core::message::Encoded;
+//! use rings_core::revocation::RevocationList;
+//! use rings_core::session::SessionSk;
+//!
+//! let key = rings_core::ecc::SecretKey::random();
+//! let session_sk = SessionSk::new_with_seckey(&key).unwrap();
+//!
+//! let document = DidDocumentBuilder::new("0.1.0".to_string())
+//!     .service("ipfs".to_string())
+//!     .endpoint("http://127.0.0.1:50001".to_string())
+//!     .build(&session_sk)
+//!     .unwrap();
+//! assert!(document.supports(Transport::Http));
+//!
+//! let did = session_sk.account_did();
+//! let vnode: VirtualNode = (
+//!     did_document_topic(did),
+//!     Encoded::try_from(&document).unwrap(),
+//! )
+//!     .try_into()
+//!     .unwrap();
+//! let resolved = DidDocument::resolve(&vnode, did, &RevocationList::default());
+//! assert_eq!(resolved, Some(document));
+//! ```
+
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+
+use rings_derive::wasm_export;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::dht::vnode::VirtualNode;
+use crate::dht::Did;
+use crate::ecc::PublicKey;
+use crate::error::Error;
+use crate::error::Result;
+use crate::message::Encoded;
+use crate::revocation::RevocationList;
+use crate::session::Session;
+use crate::session::SessionSk;
+use crate::utils;
+
+/// The prefix of topics which DID documents are published to, see [did_document_topic].
+pub const DID_DOCUMENT_TOPIC_PREFIX: &str = "rings/did_documents/";
+
+/// The topic of data virtual node which the DID documents of did are published to.
+pub fn did_document_topic(did: Did) -> String {
+    format!("{}{}", DID_DOCUMENT_TOPIC_PREFIX, did)
+}
+
+/// Check if endpoint URL is on a public host. Hosts of loopback, private, link-local and
+/// unspecified addresses, and `localhost`, are not public, so that a document can't make peers
+/// request services of their local network.
+pub fn is_public_endpoint(endpoint: &str) -> bool {
+    let Ok(url) = url::Url::parse(endpoint) else {
+        return false;
+    };
+    match url.host() {
+        Some(url::Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost")
+        }
+        Some(url::Host::Ipv4(ip)) => is_public_ip(IpAddr::V4(ip)),
+        Some(url::Host::Ipv6(ip)) => is_public_ip(IpAddr::V6(ip)),
+        None => false,
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    // 100.64.0.0/10 is shared address space of carrier-grade NAT.
+    let shared = a == 100 && (b & 0xc0) == 64;
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || shared)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    // fc00::/7 is unique local, fe80::/10 is link-local.
+    let unique_local = (first & 0xfe00) == 0xfc00;
+    let link_local = (first & 0xffc0) == 0xfe80;
+    !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+/// Transport which a node can be connected by.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    /// WebRTC connection, whose offer and answer are relayed through the DHT.
+    Webrtc,
+    /// WebRTC connection, whose offer and answer are exchanged by http with the endpoint
+    /// of document.
+    Http,
+}
+
+impl std::fmt::Display for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Transport::Webrtc => write!(f, "webrtc"),
+            Transport::Http => write!(f, "http"),
+        }
+    }
+}
+
+/// DidDocumentBuilder is used to build a [DidDocument], which is signed by [SessionSk].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DidDocumentBuilder {
+    protocols: Vec<String>,
+    version: String,
+    services: Vec<String>,
+    endpoint: Option<String>,
+    transports: Vec<Transport>,
+}
+
+/// A record signed by the session of node, which describes how to reach the node.
+#[wasm_export]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct DidDocument {
+    /// Did of node, which is the did of its account.
+    id: Did,
+    /// Session of node, which carries the account and its type.
+    session: Session,
+    /// Public key of session, the address of which is the session id.
+    session_pubkey: PublicKey<33>,
+    /// Protocols supported by node.
+    protocols: Vec<String>,
+    /// Version of node.
+    version: String,
+    /// Services advertised by node.
+    services: Vec<String>,
+    /// External endpoint URL of node.
+    endpoint: Option<String>,
+    /// Transports which node can be connected by.
+    transports: Vec<Transport>,
+    /// Timestamp when document created.
+    ts_ms: u128,
+    /// Signature of session.
+    sig: Vec<u8>,
+}
+
+// A DidDocument can be converted to a string using JSON and then encoded with base58.
+// To load the DidDocument from a string, use `DidDocument::from_str`.
+impl FromStr for DidDocument {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = base58_monero::decode_check(s).map_err(|_| Error::Decode)?;
+        serde_json::from_slice(&s).map_err(Error::Deserialize)
+    }
+}
+
+impl DidDocumentBuilder {
+    /// Create a new DidDocumentBuilder with version of node.
+    /// The node is connectable by [Transport::Webrtc] by default.
+    pub fn new(version: String) -> Self {
+        Self {
+            protocols: vec![],
+            version,
+            services: vec![],
+            endpoint: None,
+            transports: vec![Transport::Webrtc],
+        }
+    }
+
+    /// Add a protocol supported by node.
+    pub fn protocol(mut self, protocol: String) -> Self {
+        self.protocols.push(protocol);
+        self
+    }
+
+    /// Add a service advertised by node.
+    pub fn service(mut self, service: String) -> Self {
+        self.services.push(service);
+        self
+    }
+
+    /// Set external endpoint URL of node, which makes it connectable by [Transport::Http].
+    pub fn endpoint(mut self, endpoint: String) -> Self {
+        self.endpoint = Some(endpoint);
+        if !self.transports.contains(&Transport::Http) {
+            self.transports.push(Transport::Http);
+        }
+        self
+    }
+
+    /// Set transports which node can be connected by.
+    pub fn transports(mut self, transports: Vec<Transport>) -> Self {
+        self.transports = transports;
+        self
+    }
+
+    /// Build the [DidDocument] signed by session.
+    pub fn build(self, session_sk: &SessionSk) -> Result<DidDocument> {
+        let mut document = DidDocument {
+            id: session_sk.account_did(),
+            session: session_sk.session(),
+            session_pubkey: session_sk.pubkey(),
+            protocols: self.protocols,
+            version: self.version,
+            services: self.services,
+            endpoint: self.endpoint,
+            transports: self.transports,
+            ts_ms: utils::get_epoch_ms(),
+            sig: vec![],
+        };
+        document.sig = session_sk.sign(&document.pack()?)?;
+        document.verify()?;
+        Ok(document)
+    }
+}
+
+impl DidDocument {
+    /// Pack the document without signature for signing.
+    fn pack(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(&(
+            self.id,
+            &self.session,
+            &self.session_pubkey,
+            &self.protocols,
+            &self.version,
+            &self.services,
+            &self.endpoint,
+            &self.transports,
+            self.ts_ms,
+        ))
+        .map_err(|_| Error::SerializeError)
+    }
+
+    /// Verify the document is signed by an unexpired session of its did.
+    pub fn verify(&self) -> Result<()> {
+        if self.session.account_did() != self.id {
+            return Err(Error::InvalidDidDocument(format!(
+                "session of account {} cannot sign document of {}",
+                self.session.account_did(),
+                self.id
+            )));
+        }
+        if Did::from(self.session_pubkey.address()) != self.session.session_id() {
+            return Err(Error::InvalidDidDocument(
+                "session pubkey mismatch".to_string(),
+            ));
+        }
+        self.session.verify(&self.pack()?, &self.sig)
+    }
+
+    /// Pick the latest valid document of did from the data virtual node of
+    /// [did_document_topic]. Documents signed by revoked sessions are skipped.
+    pub fn resolve(vnode: &VirtualNode, did: Did, revocations: &RevocationList) -> Option<Self> {
+        vnode
+            .data
+            .iter()
+            .filter_map(|encoded| DidDocument::from_str(encoded.value()).ok())
+            .filter(|document| document.id == did)
+            .filter(|document| {
+                document
+                    .verify()
+                    .map_err(|e| tracing::debug!("invalid did document of {}: {:?}", did, e))
+                    .is_ok()
+            })
+            .filter(|document| !revocations.is_revoked(&document.session))
+            .max_by_key(|document| document.ts_ms)
+    }
+
+    /// Did of node.
+    pub fn id(&self) -> Did {
+        self.id
+    }
+
+    /// Session of node.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Public key of session.
+    pub fn session_pubkey(&self) -> PublicKey<33> {
+        self.session_pubkey
+    }
+
+    /// Protocols supported by node.
+    pub fn protocols(&self) -> &[String] {
+        &self.protocols
+    }
+
+    /// Version of node.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Services advertised by node.
+    pub fn services(&self) -> &[String] {
+        &self.services
+    }
+
+    /// External endpoint URL of node.
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    /// Transports which node can be connected by.
+    pub fn transports(&self) -> &[Transport] {
+        &self.transports
+    }
+
+    /// Check if node can be connected by transport.
+    pub fn supports(&self, transport: Transport) -> bool {
+        self.transports.contains(&transport)
+    }
+
+    /// Timestamp when document created.
+    pub fn ts_ms(&self) -> u128 {
+        self.ts_ms
+    }
+}
+
+#[wasm_export]
+impl DidDocument {
+    /// Dump document to string. It can be restored using `DidDocument::from_str`.
+    pub fn dump(&self) -> Result<String> {
+        let s = serde_json::to_string(&self).map_err(|_| Error::SerializeError)?;
+        base58_monero::encode_check(s.as_bytes()).map_err(|_| Error::Encode)
+    }
+}
+
+impl TryFrom<&DidDocument> for Encoded {
+    type Error = Error;
+
+    fn try_from(document: &DidDocument) -> Result<Self> {
+        Ok(document.dump()?.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ecc::SecretKey;
+    use crate::revocation::SessionRevocationBuilder;
+
+    fn vnode_of(did: Did, documents: &[&DidDocument]) -> VirtualNode {
+        let mut data: Vec<Encoded> = documents
+            .iter()
+            .map(|d| Encoded::try_from(*d).unwrap())
+            .collect();
+        data.push("invalid".into());
+        VirtualNode {
+            did: VirtualNode::gen_did(&did_document_topic(did)).unwrap(),
+            data,
+            kind: crate::dht::vnode::VNodeType::Data,
+        }
+    }
+
+    #[test]
+    fn test_resolve_latest_document() {
+        let key = SecretKey::random();
+        let did: Did = key.address().into();
+        let session_sk = SessionSk::new_with_seckey(&key).unwrap();
+
+        let old = DidDocumentBuilder::new("0.1.0".to_string())
+            .build(&session_sk)
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let new = DidDocumentBuilder::new("0.2.0".to_string())
+            .protocol("rings/1".to_string())
+            .endpoint("http://127.0.0.1:50001".to_string())
+            .build(&session_sk)
+            .unwrap();
+        assert_eq!(new.transports(), &[Transport::Webrtc, Transport::Http]);
+
+        let dump = new.dump().unwrap();
+        assert_eq!(DidDocument::from_str(&dump).unwrap(), new);
+
+        let revocations = RevocationList::default();
+        let vnode = vnode_of(did, &[&new, &old]);
+        assert_eq!(DidDocument::resolve(&vnode, did, &revocations), Some(new));
+
+        // Documents of other did are skipped.
+        let other: Did = SecretKey::random().address().into();
+        assert_eq!(DidDocument::resolve(&vnode, other, &revocations), None);
+    }
+
+    #[test]
+    fn test_reject_forged_document() {
+        let key = SecretKey::random();
+        let did: Did = key.address().into();
+        let session_sk = SessionSk::new_with_seckey(&key).unwrap();
+
+        // A document signed by session of other account.
+        let attacker = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+        let mut forged = DidDocumentBuilder::new("0.1.0".to_string())
+            .endpoint("http://evil.example".to_string())
+            .build(&attacker)
+            .unwrap();
+        forged.id = did;
+        assert!(forged.verify().is_err());
+
+        // A tampered document.
+        let mut tampered = DidDocumentBuilder::new("0.1.0".to_string())
+            .build(&session_sk)
+            .unwrap();
+        tampered.endpoint = Some("http://evil.example".to_string());
+        assert!(tampered.verify().is_err());
+
+        let vnode = vnode_of(did, &[&forged, &tampered]);
+        let revocations = RevocationList::default();
+        assert_eq!(DidDocument::resolve(&vnode, did, &revocations), None);
+    }
+
+    #[test]
+    fn test_skip_revoked_session() {
+        let key = SecretKey::random();
+        let did: Did = key.address().into();
+        let session_sk = SessionSk::new_with_seckey(&key).unwrap();
+        let document = DidDocumentBuilder::new("0.1.0".to_string())
+            .build(&session_sk)
+            .unwrap();
+
+        let builder = SessionRevocationBuilder::new(
+            did.to_string(),
+            "secp256k1".to_string(),
+            session_sk.session().session_id().to_string(),
+        );
+        let sig = key.sign(&builder.unsigned_proof().unwrap());
+        let revocations = RevocationList::default();
+        revocations
+            .insert(builder.set_sig(sig.to_vec()).build().unwrap())
+            .unwrap();
+
+        let vnode = vnode_of(did, &[&document]);
+        assert_eq!(DidDocument::resolve(&vnode, did, &revocations), None);
+    }
+
+    #[test]
+    fn test_public_endpoint() {
+        for endpoint in [
+            "http://1.2.3.4:50001",
+            "https://node.example.com",
+            "http://[2001:db8::1]:50001",
+        ] {
+            assert!(is_public_endpoint(endpoint), "{}", endpoint);
+        }
+        for endpoint in [
+            "http://127.0.0.1:50001",
+            "http://localhost:50000",
+            "http://api.localhost.",
+            "http://10.0.0.1",
+            "http://172.16.0.1",
+            "http://192.168.1.1",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1",
+            "http://0.0.0.0:50000",
+            "http://[::1]:50000",
+            "http://[fe80::1]",
+            "http://[fd00::1]",
+            "http://[::ffff:127.0.0.1]",
+            "http://0x7f000001",
+            "not a url",
+        ] {
+            assert!(!is_public_endpoint(endpoint), "{}", endpoint);
+        }
+    }
+}
@@ -17,6 +17,7 @@ use subtle::CtOption;
 use crate::error::Error;
 use crate::error::Result;
 pub mod elgamal;
+pub mod pre;
 pub mod signers;
 mod types;
 use elliptic_curve::generic_array::typenum::U32;
@@ -324,6 +325,13 @@ impl PublicKey<33> {
     }
 }
 
+impl PublicKey<48> {
+    /// Address of BLS public key, which is the last 20 bytes of keccak256 of compressed G1 point.
+    pub fn address(&self) -> PublicKeyAddress {
+        PublicKeyAddress::from_slice(&keccak256(&self.0)[12..])
+    }
+}
+
 /// Recover PublicKey from RawMessage using signature.
 pub fn recover<S>(message: &[u8], signature: S) -> Result<PublicKey<33>>
 where S: AsRef<[u8]> {
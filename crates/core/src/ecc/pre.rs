@@ -0,0 +1,692 @@
+//! Threshold proxy re-encryption on secp256k1, which lets a group read data encrypted once.
+//! ref: <https://github.com/nucypher/umbral-doc/blob/master/umbral-doc.pdf>
+//!
+//! Data is sealed under the public key of a delegating key, usually a key created for a group,
+//! and stored as [SealedData]. The owner of delegating key can open it directly.
+//!
+//! To grant access to a member with receiving key `B`, the owner splits a re-encryption key
+//! into `n` [KeyFrag] with threshold `t` by [generate_kfrags], and gives one to each proxy.
+//! A proxy transforms the [Capsule] of sealed data into a [CapsuleFrag] by [reencrypt], and the
+//! member opens the data with `t` of them by [SealedData::open_reencrypted].
+//!
+//! Proxies learn nothing about the data, and the member can't open data without proxies, so
+//! access is revoked by asking proxies to drop their key fragments. Nothing is re-uploaded when
+//! access is granted or revoked.
+//!
+//! # Algorithm
+//! Delegating key is `(a, A = aG)`, receiving key is `(b, B = bG)`.
+//! * Encapsulate: pick `r`, `u`, let `E = rG`, `V = uG`, `s = u + r·h(E, V)`. The capsule is
+//!   `(E, V, s)`, the data key is derived from `(r + u)·A`.
+//! * Decapsulate by `a`: `(r + u)·A = a·(E + V)`.
+//! * Key fragments: pick precursor `x`, `X = xG`, `d = h(X, B, xB)`. Shamir shares of `a/d`
+//!   are the fragments.
+//! * Re-encrypt by fragment `k`: `E1 = k·E`, `V1 = k·V`.
+//! * Decapsulate by `b` with `t` capsule fragments: combine `E'`, `V'` by Lagrange interpolation,
+//!   `d = h(X, B, bX)`, then `(r + u)·A = d·(E' + V')`.
+//!
+//! Each key fragment carries a commitment `U1 = k·G` signed by the delegating key, and each
+//! capsule fragment carries a [CorrectnessProof] that `E1`, `V1` and `U1` share the same `k`.
+//! The member verifies fragments by [CapsuleFrag::verify] and only opens data with verified
+//! ones, so a proxy can't make opening fail with a wrong fragment.
+
+use aes_gcm::aead::Aead;
+use aes_gcm::aead::KeyInit;
+use aes_gcm::Aes256Gcm;
+use aes_gcm::Nonce;
+use libsecp256k1::curve::Affine;
+use libsecp256k1::curve::Jacobian;
+use libsecp256k1::curve::Scalar;
+use libsecp256k1::ECMULT_CONTEXT;
+use libsecp256k1::ECMULT_GEN_CONTEXT;
+use rand::RngCore;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+
+use crate::ecc::PublicKey;
+use crate::ecc::SecretKey;
+use crate::error::Error;
+use crate::error::Result;
+use crate::message::Decoder;
+use crate::message::Encoded;
+use crate::message::Encoder;
+
+const NONCE_LEN: usize = 12;
+
+/// Encapsulated data key of [SealedData].
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capsule {
+    e: PublicKey<33>,
+    v: PublicKey<33>,
+    s: [u8; 32],
+}
+
+/// A share of re-encryption key from delegating key to receiving key, held by a proxy.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyFrag {
+    /// Random id of fragment, which determines its share index.
+    pub id: [u8; 32],
+    /// Share of re-encryption key.
+    rk: [u8; 32],
+    /// Precursor of re-encryption key.
+    pub precursor: PublicKey<33>,
+    /// Public key of the owner of data.
+    pub delegating: PublicKey<33>,
+    /// Public key of the member who is granted access.
+    pub receiving: PublicKey<33>,
+    /// Number of fragments required to open data.
+    pub threshold: usize,
+    /// Commitment of re-encryption key share, `k·G`.
+    pub commitment: PublicKey<33>,
+    /// Signature of public parts of fragment by delegating key, which is also carried by
+    /// capsule fragments for members to verify.
+    pub sig: Vec<u8>,
+}
+
+/// A capsule re-encrypted by a proxy with a [KeyFrag].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CapsuleFrag {
+    e1: PublicKey<33>,
+    v1: PublicKey<33>,
+    /// Id of key fragment.
+    pub id: [u8; 32],
+    /// Precursor of re-encryption key.
+    pub precursor: PublicKey<33>,
+    /// Number of fragments required to open data.
+    pub threshold: usize,
+    /// Commitment of the key fragment.
+    pub commitment: PublicKey<33>,
+    /// Signature of the key fragment by delegating key.
+    pub kfrag_sig: Vec<u8>,
+    /// Proof that fragment is re-encrypted by the committed key fragment.
+    pub proof: CorrectnessProof,
+}
+
+/// Non-interactive proof of equal discrete logarithms, that `E1 = k·E`, `V1 = k·V` and
+/// `U1 = k·G` for the same `k`. The prover picks `t` and responds `z = t + c·k`, where
+/// `c = h(E, V, E1, V1, U1, t·E, t·V, t·G)`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CorrectnessProof {
+    te: PublicKey<33>,
+    tv: PublicKey<33>,
+    tu: PublicKey<33>,
+    z: [u8; 32],
+}
+
+/// Data encrypted by AES-256-GCM, whose key is encapsulated in capsule.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SealedData {
+    /// Public key which data is sealed under.
+    pub delegating: PublicKey<33>,
+    /// Encapsulated data key.
+    pub capsule: Capsule,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+fn scalar_from_bytes(bytes: &[u8; 32]) -> Scalar {
+    let mut s = Scalar::default();
+    // Overflowed value is reduced.
+    let _ = s.set_b32(bytes);
+    s
+}
+
+fn random_scalar() -> Scalar {
+    SecretKey::random().into()
+}
+
+fn hash_to_scalar(domain: &[u8], parts: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha256::new();
+    hasher.update(domain);
+    for part in parts {
+        hasher.update(part);
+    }
+    scalar_from_bytes(&hasher.finalize().into())
+}
+
+fn affine(key: &PublicKey<33>) -> Result<Affine> {
+    (*key).try_into()
+}
+
+fn normalized(point: &Jacobian) -> Affine {
+    let mut a = Affine::from_gej(point);
+    a.x.normalize();
+    a.y.normalize();
+    a
+}
+
+fn to_key(point: &Jacobian) -> Result<PublicKey<33>> {
+    if point.is_infinity() {
+        return Err(Error::InvalidPublicKey);
+    }
+    normalized(point).try_into()
+}
+
+fn mul(point: &Affine, k: &Scalar) -> Jacobian {
+    let mut r = Jacobian::default();
+    ECMULT_CONTEXT.ecmult_const(&mut r, point, k);
+    r
+}
+
+fn mul_g(k: &Scalar) -> Jacobian {
+    let mut r = Jacobian::default();
+    ECMULT_GEN_CONTEXT.ecmult_gen(&mut r, k);
+    r
+}
+
+fn add(a: &PublicKey<33>, b: &PublicKey<33>) -> Result<Jacobian> {
+    Ok(Jacobian::from_ge(&affine(a)?).add_ge_var(&affine(b)?, None))
+}
+
+/// AES key derived from shared point of capsule.
+fn derive_key(shared: &PublicKey<33>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"rings/pre/key");
+    hasher.update(shared.0);
+    hasher.finalize().into()
+}
+
+/// `d = h(X, B, xB)` of key fragments, computed by both owner and member.
+fn precursor_secret(
+    precursor: &PublicKey<33>,
+    receiving: &PublicKey<33>,
+    dh: &Jacobian,
+) -> Result<Scalar> {
+    Ok(hash_to_scalar(b"rings/pre/precursor", &[
+        &precursor.0,
+        &receiving.0,
+        &to_key(dh)?.0,
+    ]))
+}
+
+/// Share index of a key fragment.
+fn share_index(id: &[u8; 32], precursor: &PublicKey<33>) -> Scalar {
+    hash_to_scalar(b"rings/pre/index", &[id, &precursor.0])
+}
+
+/// Lagrange coefficient of `xs[i]` at 0.
+fn lagrange(xs: &[Scalar], i: usize) -> Result<Scalar> {
+    let mut num = Scalar::from_int(1);
+    let mut den = Scalar::from_int(1);
+    for (j, xj) in xs.iter().enumerate() {
+        if j == i {
+            continue;
+        }
+        let diff = *xj + (-xs[i]);
+        if diff.is_zero() {
+            return Err(Error::Reencryption(
+                "duplicated capsule fragments".to_string(),
+            ));
+        }
+        num *= xj;
+        den *= &diff;
+    }
+    Ok(num * den.inv())
+}
+
+impl Capsule {
+    fn h(e: &PublicKey<33>, v: &PublicKey<33>) -> Scalar {
+        hash_to_scalar(b"rings/pre/capsule", &[&e.0, &v.0])
+    }
+
+    /// Create a capsule for delegating key, returns the capsule and the shared point.
+    fn encapsulate(delegating: &PublicKey<33>) -> Result<(Self, PublicKey<33>)> {
+        let r = random_scalar();
+        let u = random_scalar();
+        let e = to_key(&mul_g(&r))?;
+        let v = to_key(&mul_g(&u))?;
+        let s = u + r * Self::h(&e, &v);
+        let shared = to_key(&mul(&affine(delegating)?, &(r + u)))?;
+        Ok((Self { e, v, s: s.b32() }, shared))
+    }
+
+    /// Digest of capsule, which identifies the sealed data in re-encryption requests.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.e.0);
+        hasher.update(self.v.0);
+        hasher.update(self.s);
+        hasher.finalize().into()
+    }
+
+    /// Check that `sG = V + h(E, V)·E`, so that proxies only re-encrypt well formed capsules.
+    pub fn verify(&self) -> bool {
+        let check = || -> Result<bool> {
+            let lhs = to_key(&mul_g(&scalar_from_bytes(&self.s)))?;
+            let he = mul(&affine(&self.e)?, &Self::h(&self.e, &self.v));
+            let rhs = to_key(&he.add_ge_var(&affine(&self.v)?, None))?;
+            Ok(lhs == rhs)
+        };
+        check().unwrap_or(false)
+    }
+
+    /// Shared point of capsule, opened by delegating key.
+    fn open(&self, delegating: &SecretKey) -> Result<PublicKey<33>> {
+        if !self.verify() {
+            return Err(Error::InvalidCapsule);
+        }
+        let ev = normalized(&add(&self.e, &self.v)?);
+        to_key(&mul(&ev, &(*delegating).into()))
+    }
+
+    /// Shared point of capsule, opened by receiving key with capsule fragments.
+    fn open_reencrypted(
+        &self,
+        receiving: &SecretKey,
+        cfrags: &[CapsuleFrag],
+    ) -> Result<PublicKey<33>> {
+        let first = cfrags
+            .first()
+            .ok_or_else(|| Error::Reencryption("no capsule fragments".to_string()))?;
+        if cfrags.len() < first.threshold {
+            return Err(Error::Reencryption(format!(
+                "{} of {} capsule fragments",
+                cfrags.len(),
+                first.threshold
+            )));
+        }
+        let cfrags = &cfrags[..first.threshold];
+        if cfrags.iter().any(|c| c.precursor != first.precursor) {
+            return Err(Error::Reencryption(
+                "capsule fragments of different key fragments".to_string(),
+            ));
+        }
+
+        let xs: Vec<Scalar> = cfrags
+            .iter()
+            .map(|c| share_index(&c.id, &c.precursor))
+            .collect();
+        let mut combined = Jacobian::default();
+        combined.set_infinity();
+        for (i, cfrag) in cfrags.iter().enumerate() {
+            let l = lagrange(&xs, i)?;
+            let ev1 = normalized(&add(&cfrag.e1, &cfrag.v1)?);
+            combined = combined.add_var(&mul(&ev1, &l), None);
+        }
+
+        let dh = mul(&affine(&first.precursor)?, &(*receiving).into());
+        let d = precursor_secret(&first.precursor, &receiving.pubkey(), &dh)?;
+        if combined.is_infinity() {
+            return Err(Error::Reencryption("invalid capsule fragments".to_string()));
+        }
+        to_key(&mul(&normalized(&combined), &d))
+    }
+}
+
+/// Digest of public parts of a key fragment, which is signed by delegating key.
+fn kfrag_digest(
+    id: &[u8; 32],
+    commitment: &PublicKey<33>,
+    precursor: &PublicKey<33>,
+    delegating: &PublicKey<33>,
+    receiving: &PublicKey<33>,
+    threshold: usize,
+) -> Vec<u8> {
+    [
+        &id[..],
+        &commitment.0[..],
+        &precursor.0[..],
+        &delegating.0[..],
+        &receiving.0[..],
+        &(threshold as u64).to_be_bytes()[..],
+    ]
+    .concat()
+}
+
+fn signed_by(digest: &[u8], sig: &[u8], key: &PublicKey<33>) -> bool {
+    crate::ecc::recover(digest, sig)
+        .map(|pk| pk == *key)
+        .unwrap_or(false)
+}
+
+impl KeyFrag {
+    /// Check that fragment is signed by its delegating key, and its share matches the
+    /// commitment.
+    pub fn verify(&self) -> bool {
+        let digest = kfrag_digest(
+            &self.id,
+            &self.commitment,
+            &self.precursor,
+            &self.delegating,
+            &self.receiving,
+            self.threshold,
+        );
+        signed_by(&digest, &self.sig, &self.delegating)
+            && to_key(&mul_g(&scalar_from_bytes(&self.rk))).ok() == Some(self.commitment)
+    }
+}
+
+impl CorrectnessProof {
+    fn challenge(points: &[&PublicKey<33>]) -> Scalar {
+        let parts: Vec<&[u8]> = points.iter().map(|p| &p.0[..]).collect();
+        hash_to_scalar(b"rings/pre/proof", &parts)
+    }
+
+    /// Check `z·X = T + c·Y`, where `zx` is `z·X`.
+    fn check(zx: &Jacobian, t: &PublicKey<33>, c: &Scalar, y: &PublicKey<33>) -> Result<bool> {
+        let lhs = to_key(zx)?;
+        let cy = normalized(&mul(&affine(y)?, c));
+        let rhs = to_key(&Jacobian::from_ge(&affine(t)?).add_ge_var(&cy, None))?;
+        Ok(lhs == rhs)
+    }
+}
+
+impl CapsuleFrag {
+    /// Check that fragment is re-encrypted from capsule by a key fragment, which is signed by
+    /// delegating key for receiving key.
+    pub fn verify(
+        &self,
+        capsule: &Capsule,
+        delegating: &PublicKey<33>,
+        receiving: &PublicKey<33>,
+    ) -> bool {
+        let digest = kfrag_digest(
+            &self.id,
+            &self.commitment,
+            &self.precursor,
+            delegating,
+            receiving,
+            self.threshold,
+        );
+        if !signed_by(&digest, &self.kfrag_sig, delegating) {
+            return false;
+        }
+        let proof = &self.proof;
+        let c = CorrectnessProof::challenge(&[
+            &capsule.e,
+            &capsule.v,
+            &self.e1,
+            &self.v1,
+            &self.commitment,
+            &proof.te,
+            &proof.tv,
+            &proof.tu,
+        ]);
+        let z = scalar_from_bytes(&proof.z);
+        let check = || -> Result<bool> {
+            let ze = mul(&affine(&capsule.e)?, &z);
+            let zv = mul(&affine(&capsule.v)?, &z);
+            Ok(CorrectnessProof::check(&ze, &proof.te, &c, &self.e1)?
+                && CorrectnessProof::check(&zv, &proof.tv, &c, &self.v1)?
+                && CorrectnessProof::check(&mul_g(&z), &proof.tu, &c, &self.commitment)?)
+        };
+        check().unwrap_or(false)
+    }
+}
+
+/// Split re-encryption key from `delegating` to `receiving` into `n` fragments,
+/// any `threshold` of them can re-encrypt a capsule for `receiving`.
+pub fn generate_kfrags(
+    delegating: &SecretKey,
+    receiving: &PublicKey<33>,
+    threshold: usize,
+    n: usize,
+) -> Result<Vec<KeyFrag>> {
+    if threshold == 0 || threshold > n {
+        return Err(Error::InvalidKeyFrag(format!(
+            "threshold {} of {} fragments",
+            threshold, n
+        )));
+    }
+    let x = random_scalar();
+    let precursor = to_key(&mul_g(&x))?;
+    let d = precursor_secret(&precursor, receiving, &mul(&affine(receiving)?, &x))?;
+
+    let a: Scalar = (*delegating).into();
+    let mut coefficients = vec![a * d.inv()];
+    coefficients.extend((1..threshold).map(|_| random_scalar()));
+
+    (0..n)
+        .map(|_| {
+            let mut id = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut id);
+            let index = share_index(&id, &precursor);
+            // Horner's method
+            let rk = coefficients
+                .iter()
+                .rev()
+                .fold(Scalar::default(), |acc, c| acc * index + *c);
+            let commitment = to_key(&mul_g(&rk))?;
+            let digest = kfrag_digest(
+                &id,
+                &commitment,
+                &precursor,
+                &delegating.pubkey(),
+                receiving,
+                threshold,
+            );
+            Ok(KeyFrag {
+                id,
+                rk: rk.b32(),
+                precursor,
+                delegating: delegating.pubkey(),
+                receiving: *receiving,
+                threshold,
+                commitment,
+                sig: delegating.sign_raw(&digest).to_vec(),
+            })
+        })
+        .collect()
+}
+
+/// Re-encrypt capsule with a key fragment, which is done by proxy.
+pub fn reencrypt(capsule: &Capsule, kfrag: &KeyFrag) -> Result<CapsuleFrag> {
+    if !capsule.verify() {
+        return Err(Error::InvalidCapsule);
+    }
+    let rk = scalar_from_bytes(&kfrag.rk);
+    let e = affine(&capsule.e)?;
+    let v = affine(&capsule.v)?;
+    let e1 = to_key(&mul(&e, &rk))?;
+    let v1 = to_key(&mul(&v, &rk))?;
+
+    let t = random_scalar();
+    let te = to_key(&mul(&e, &t))?;
+    let tv = to_key(&mul(&v, &t))?;
+    let tu = to_key(&mul_g(&t))?;
+    let c = CorrectnessProof::challenge(&[
+        &capsule.e,
+        &capsule.v,
+        &e1,
+        &v1,
+        &kfrag.commitment,
+        &te,
+        &tv,
+        &tu,
+    ]);
+    let z = t + c * rk;
+
+    Ok(CapsuleFrag {
+        e1,
+        v1,
+        id: kfrag.id,
+        precursor: kfrag.precursor,
+        threshold: kfrag.threshold,
+        commitment: kfrag.commitment,
+        kfrag_sig: kfrag.sig.clone(),
+        proof: CorrectnessProof {
+            te,
+            tv,
+            tu,
+            z: z.b32(),
+        },
+    })
+}
+
+impl SealedData {
+    /// Seal data under delegating key.
+    pub fn seal(delegating: &PublicKey<33>, data: &[u8]) -> Result<Self> {
+        let (capsule, shared) = Capsule::encapsulate(delegating)?;
+        let cipher = Aes256Gcm::new_from_slice(&derive_key(&shared))
+            .map_err(|e| Error::Reencryption(e.to_string()))?;
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), data)
+            .map_err(|e| Error::Reencryption(e.to_string()))?;
+        Ok(Self {
+            delegating: *delegating,
+            capsule,
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    fn decrypt(&self, shared: &PublicKey<33>) -> Result<Vec<u8>> {
+        if self.nonce.len() != NONCE_LEN {
+            return Err(Error::Reencryption("invalid nonce".to_string()));
+        }
+        let cipher = Aes256Gcm::new_from_slice(&derive_key(shared))
+            .map_err(|e| Error::Reencryption(e.to_string()))?;
+        cipher
+            .decrypt(Nonce::from_slice(&self.nonce), self.ciphertext.as_slice())
+            .map_err(|e| Error::Reencryption(e.to_string()))
+    }
+
+    /// Open data by delegating key.
+    pub fn open(&self, delegating: &SecretKey) -> Result<Vec<u8>> {
+        self.decrypt(&self.capsule.open(delegating)?)
+    }
+
+    /// Open data by receiving key, with at least `threshold` capsule fragments from proxies.
+    /// Fragments failing [CapsuleFrag::verify] are skipped, and `threshold` of verified ones
+    /// from the same grant are used.
+    pub fn open_reencrypted(
+        &self,
+        receiving: &SecretKey,
+        cfrags: &[CapsuleFrag],
+    ) -> Result<Vec<u8>> {
+        let receiving_pk = receiving.pubkey();
+        let mut verified: Vec<&CapsuleFrag> = cfrags
+            .iter()
+            .filter(|c| c.verify(&self.capsule, &self.delegating, &receiving_pk))
+            .collect();
+        verified.sort_by_key(|c| c.id);
+        verified.dedup_by_key(|c| c.id);
+        let selected = verified
+            .iter()
+            .find_map(|first| {
+                let group: Vec<CapsuleFrag> = verified
+                    .iter()
+                    .filter(|c| c.precursor == first.precursor)
+                    .map(|c| (*c).clone())
+                    .collect();
+                (group.len() >= first.threshold).then_some(group)
+            })
+            .ok_or_else(|| {
+                Error::Reencryption(format!(
+                    "{} of {} capsule fragments verified, not enough",
+                    verified.len(),
+                    cfrags.len()
+                ))
+            })?;
+        self.decrypt(&self.capsule.open_reencrypted(receiving, &selected)?)
+    }
+}
+
+impl TryFrom<&SealedData> for Encoded {
+    type Error = Error;
+
+    fn try_from(sealed: &SealedData) -> Result<Self> {
+        bincode::serialize(sealed)
+            .map_err(Error::BincodeSerialize)?
+            .encode()
+    }
+}
+
+impl Decoder for SealedData {
+    fn from_encoded(encoded: &Encoded) -> Result<Self> {
+        let data = Vec::from_encoded(encoded)?;
+        bincode::deserialize(&data).map_err(Error::BincodeDeserialize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_open_by_delegating_key() {
+        let group = SecretKey::random();
+        let sealed = SealedData::seal(&group.pubkey(), b"hello group").unwrap();
+        assert!(sealed.capsule.verify());
+        assert_eq!(sealed.open(&group).unwrap(), b"hello group");
+        assert!(sealed.open(&SecretKey::random()).is_err());
+
+        let encoded = Encoded::try_from(&sealed).unwrap();
+        assert_eq!(SealedData::from_encoded(&encoded).unwrap(), sealed);
+    }
+
+    #[test]
+    fn test_threshold_reencryption() {
+        let group = SecretKey::random();
+        let member = SecretKey::random();
+        let sealed = SealedData::seal(&group.pubkey(), b"hello member").unwrap();
+
+        let kfrags = generate_kfrags(&group, &member.pubkey(), 2, 3).unwrap();
+        assert!(kfrags.iter().all(|k| k.verify()));
+        let cfrags: Vec<CapsuleFrag> = kfrags
+            .iter()
+            .map(|k| reencrypt(&sealed.capsule, k).unwrap())
+            .collect();
+
+        // Any 2 of 3 fragments open the data.
+        for pair in [[0, 1], [1, 2], [2, 0]] {
+            let picked = [cfrags[pair[0]].clone(), cfrags[pair[1]].clone()];
+            assert_eq!(
+                sealed.open_reencrypted(&member, &picked).unwrap(),
+                b"hello member"
+            );
+        }
+
+        // Wrong fragment of a proxy fails verification, and is skipped in opening.
+        let (group_pk, member_pk) = (group.pubkey(), member.pubkey());
+        assert!(cfrags
+            .iter()
+            .all(|c| c.verify(&sealed.capsule, &group_pk, &member_pk)));
+        let mut wrong = cfrags[0].clone();
+        wrong.e1 = SecretKey::random().pubkey();
+        assert!(!wrong.verify(&sealed.capsule, &group_pk, &member_pk));
+        assert!(!cfrags[0].verify(&sealed.capsule, &member_pk, &member_pk));
+        let other = SealedData::seal(&group_pk, b"other").unwrap();
+        assert!(!cfrags[0].verify(&other.capsule, &group_pk, &member_pk));
+        let picked = [
+            wrong,
+            cfrags[1].clone(),
+            cfrags[1].clone(),
+            cfrags[2].clone(),
+        ];
+        assert_eq!(
+            sealed.open_reencrypted(&member, &picked).unwrap(),
+            b"hello member"
+        );
+        assert!(sealed.open_reencrypted(&member, &picked[..3]).is_err());
+
+        // Fragments are not enough below threshold, or for other members.
+        assert!(sealed.open_reencrypted(&member, &cfrags[..1]).is_err());
+        assert!(sealed
+            .open_reencrypted(&SecretKey::random(), &cfrags)
+            .is_err());
+        assert!(generate_kfrags(&group, &member.pubkey(), 4, 3).is_err());
+    }
+
+    #[test]
+    fn test_reject_forged_kfrag_and_capsule() {
+        let group = SecretKey::random();
+        let member = SecretKey::random();
+        let mut kfrag = generate_kfrags(&group, &member.pubkey(), 1, 1)
+            .unwrap()
+            .remove(0);
+        let mut uncommitted = kfrag.clone();
+        uncommitted.rk = random_scalar().b32();
+        assert!(!uncommitted.verify());
+        kfrag.receiving = SecretKey::random().pubkey();
+        assert!(!kfrag.verify());
+
+        let mut sealed = SealedData::seal(&group.pubkey(), b"data").unwrap();
+        sealed.capsule.s = random_scalar().b32();
+        assert!(!sealed.capsule.verify());
+        assert!(reencrypt(&sealed.capsule, &kfrag).is_err());
+    }
+}
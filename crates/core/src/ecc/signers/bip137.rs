@@ -103,7 +103,7 @@ mod test {
 
     #[test]
     fn test_verify() {
-        let pubkey = PublicKey::from_hex_string(
+        let pubkey = PublicKey::<33>::from_hex_string(
             "026a626503429a973dc4fcde64fa7932158a20c69b79c9eab1245577dd43674dc5",
         )
         .unwrap();
@@ -74,6 +74,15 @@ impl TryFrom<Fr> for SecretKey {
     }
 }
 
+impl TryFrom<&[u8]> for Signature {
+    type Error = Error;
+    fn try_from(s: &[u8]) -> Result<Self> {
+        Ok(Signature(
+            s.try_into().map_err(|_| Error::BlsAffineDecodeFailed)?,
+        ))
+    }
+}
+
 impl TryFrom<Signature> for G2Projective {
     type Error = Error;
     fn try_from(s: Signature) -> Result<Self> {
@@ -181,6 +190,18 @@ pub fn aggregate(signatures: &[Signature]) -> Result<Signature> {
         .try_into()
 }
 
+/// Aggregate public keys by adding them together. Calculated by pk = \sum_{i = 0}^n pk_i.
+/// If all signers sign the same message, the aggregated signature can be verified by the
+/// aggregated public key with only two pairings.
+pub fn aggregate_public_keys(pks: &[PublicKey<48>]) -> Result<PublicKey<48>> {
+    pks.iter()
+        .map(|pk| (*pk).try_into())
+        .collect::<Result<Vec<G1Projective>>>()?
+        .iter()
+        .sum::<G1Projective>()
+        .try_into()
+}
+
 /// Converts a BLS private key to a BLS public key.
 /// Get the public key for this private key. Calculated by pk = g1 * sk.
 pub fn public_key(key: &SecretKey) -> Result<PublicKey<48>> {
@@ -245,4 +266,23 @@ mod test {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn test_aggregate_same_message() {
+        let key1 = random_sk().unwrap();
+        let key2 = random_sk().unwrap();
+        let msg = "hello quorum".as_bytes();
+
+        let sig1 = sign(key1, msg).unwrap();
+        let sig2 = sign(key2, msg).unwrap();
+        let sig_agg = aggregate(&[sig1.clone(), sig2]).unwrap();
+
+        let pks = [public_key(&key1).unwrap(), public_key(&key2).unwrap()];
+        let pk_agg = aggregate_public_keys(&pks).unwrap();
+        assert!(super::verify(&[msg], &sig_agg, &[pk_agg]).unwrap());
+        assert!(!super::verify(&[msg], &sig1, &[pk_agg]).unwrap());
+
+        let sig: Signature = sig_agg.0.as_slice().try_into().unwrap();
+        assert_eq!(sig, sig_agg);
+    }
 }
@@ -4,3 +4,4 @@ pub mod ed25519;
 pub mod eip191;
 pub mod secp256k1;
 pub mod secp256r1;
+pub mod webauthn;
@@ -0,0 +1,206 @@
+//! Verify WebAuthn assertions signed by a passkey.
+//! ref: <https://www.w3.org/TR/webauthn-2/#sctn-verifying-assertion>
+//!
+//! The message to sign is passed to authenticator as the challenge:
+//!
+//! ```js
+//! const assertion = await navigator.credentials.get({
+//!   publicKey: {
+//!     challenge: new TextEncoder().encode(unsignedProof),
+//!     allowCredentials: [{ type: "public-key", id: credentialId }],
+//!   },
+//! });
+//! const { authenticatorData, clientDataJSON, signature } = assertion.response;
+//! ```
+//!
+//! Authenticator signs `authenticatorData || sha256(clientDataJSON)` with its P-256 key,
+//! the signature is DER encoded. The three fields are packed into [Assertion] for verification.
+//!
+//! Origin and relying party id are not checked, since there is no server that the assertion is
+//! made for. The challenge binds the assertion to the message.
+
+use ecdsa::signature::hazmat::PrehashVerifier;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use subtle::CtOption;
+
+use crate::ecc::PublicKey;
+use crate::ecc::PublicKeyAddress;
+use crate::error::Error;
+use crate::error::Result;
+
+/// Length of rpIdHash, flags and signCount in authenticatorData.
+const AUTHENTICATOR_DATA_MIN_LEN: usize = 37;
+/// User Present flag in authenticatorData.
+const FLAG_USER_PRESENT: u8 = 0x01;
+
+/// Response of `navigator.credentials.get`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Assertion {
+    /// Raw `authenticatorData`.
+    pub authenticator_data: Vec<u8>,
+    /// Raw `clientDataJSON`.
+    pub client_data_json: Vec<u8>,
+    /// DER encoded P-256 signature.
+    pub signature: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    ty: String,
+    challenge: String,
+}
+
+impl Assertion {
+    /// Pack assertion into bytes, which is used as signature of account.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(Error::BincodeSerialize)
+    }
+
+    /// Unpack assertion from bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).map_err(Error::BincodeDeserialize)
+    }
+
+    /// Check that client data is of an assertion, and its challenge is the message.
+    fn check_client_data(&self, msg: &[u8]) -> Result<()> {
+        let client_data: ClientData =
+            serde_json::from_slice(&self.client_data_json).map_err(Error::Deserialize)?;
+        if client_data.ty != "webauthn.get" {
+            return Err(Error::InvalidWebAuthnAssertion(format!(
+                "unexpected type {}",
+                client_data.ty
+            )));
+        }
+        let challenge = base64::decode_config(&client_data.challenge, base64::URL_SAFE_NO_PAD)
+            .map_err(|e| Error::InvalidWebAuthnAssertion(e.to_string()))?;
+        if challenge != msg {
+            return Err(Error::InvalidWebAuthnAssertion(
+                "challenge mismatch".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that user was present when authenticator signed.
+    fn check_authenticator_data(&self) -> Result<()> {
+        if self.authenticator_data.len() < AUTHENTICATOR_DATA_MIN_LEN {
+            return Err(Error::InvalidWebAuthnAssertion(
+                "authenticator data too short".to_string(),
+            ));
+        }
+        if self.authenticator_data[32] & FLAG_USER_PRESENT == 0 {
+            return Err(Error::InvalidWebAuthnAssertion(
+                "user not present".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Hash of data signed by authenticator: `sha256(authenticatorData || sha256(clientDataJSON))`.
+    fn signed_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.authenticator_data);
+        hasher.update(Sha256::digest(&self.client_data_json));
+        hasher.finalize().into()
+    }
+
+    fn verify_signature(&self, pubkey: &PublicKey<33>) -> Result<()> {
+        let ct_pk: CtOption<Result<ecdsa::VerifyingKey<p256::NistP256>>> =
+            (*pubkey).ct_try_into_secp256r1_pubkey();
+        if ct_pk.is_none().into() {
+            return Err(Error::InvalidPublicKey);
+        }
+        let pk = ct_pk.unwrap()?;
+        let sig = p256::ecdsa::Signature::from_der(&self.signature)?;
+        // Authenticators don't normalize s of signature.
+        let sig = sig.normalize_s().unwrap_or(sig);
+        pk.verify_prehash(&self.signed_hash(), &sig)?;
+        Ok(())
+    }
+}
+
+/// Verify that `sig` is a packed [Assertion] of passkey `pubkey`, whose challenge is `msg`.
+pub fn verify(
+    msg: &[u8],
+    address: &PublicKeyAddress,
+    sig: impl AsRef<[u8]>,
+    pubkey: &PublicKey<33>,
+) -> bool {
+    if pubkey.address() != *address {
+        return false;
+    }
+    Assertion::from_bytes(sig.as_ref())
+        .and_then(|assertion| {
+            assertion.check_client_data(msg)?;
+            assertion.check_authenticator_data()?;
+            assertion.verify_signature(pubkey)
+        })
+        .map_err(|e| tracing::debug!("WebAuthn assertion verify failed: {:?}", e))
+        .is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PUBKEY: &str = "17a6afd392fcbe4ac9270a599a9c5732c4f838ce35ea2234d389d8f0c367f3f5dcab906352e27289002c7f2c96039ddce7c1b5aad8b87ba94984d4c8b4f95702";
+    const CHALLENGE: &str = "0x11e807fcc88dd319270493fb2e822e388fe36ab0\n1700000000000\n2592000000";
+    const AUTHENTICATOR_DATA: &str =
+        "49960de5880e8c687434170f6476605b8fe4aeb9a28632c7995cf3ba831d97630500000001";
+    const CLIENT_DATA_JSON: &str = r#"{"type":"webauthn.get","challenge":"MHgxMWU4MDdmY2M4OGRkMzE5MjcwNDkzZmIyZTgyMmUzODhmZTM2YWIwCjE3MDAwMDAwMDAwMDAKMjU5MjAwMDAwMA","origin":"http://localhost:8080","crossOrigin":false}"#;
+    const SIGNATURE: &str = "3046022100c8fdc249f0f93e5b6d610776c3e75871119717665ba0f9425ce109c3cfd08a61022100d40d8e9c6411371379ead14eb0e3f2abc087770ef8f053bbd8e1604a90cd49e6";
+
+    fn fixed_assertion() -> Assertion {
+        Assertion {
+            authenticator_data: hex::decode(AUTHENTICATOR_DATA).unwrap(),
+            client_data_json: CLIENT_DATA_JSON.as_bytes().to_vec(),
+            signature: hex::decode(SIGNATURE).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_verify_fixed_assertion() {
+        let pk = PublicKey::<33>::from_hex_string(PUBKEY).unwrap();
+        let sig = fixed_assertion().to_bytes().unwrap();
+        assert!(verify(CHALLENGE.as_bytes(), &pk.address(), &sig, &pk));
+
+        // Challenge should be the message.
+        assert!(!verify(b"other message", &pk.address(), &sig, &pk));
+
+        // Key of the negated point is rejected.
+        let other = PublicKey::<33>::from_hex_string(&format!("{}03", &PUBKEY[..126])).unwrap();
+        assert!(!verify(
+            CHALLENGE.as_bytes(),
+            &other.address(),
+            &sig,
+            &other
+        ));
+    }
+
+    #[test]
+    fn test_reject_tampered_assertion() {
+        let pk = PublicKey::<33>::from_hex_string(PUBKEY).unwrap();
+
+        // Signature doesn't cover modified authenticator data.
+        let mut assertion = fixed_assertion();
+        assertion.authenticator_data[36] = 2;
+        let sig = assertion.to_bytes().unwrap();
+        assert!(!verify(CHALLENGE.as_bytes(), &pk.address(), sig, &pk));
+
+        // User present flag is required.
+        let mut assertion = fixed_assertion();
+        assertion.authenticator_data[32] = 0x04;
+        assert!(assertion.check_authenticator_data().is_err());
+
+        // Client data of credential creation is not an assertion.
+        let mut assertion = fixed_assertion();
+        assertion.client_data_json = CLIENT_DATA_JSON
+            .replace("webauthn.get", "webauthn.create")
+            .into_bytes();
+        assert!(assertion.check_client_data(CHALLENGE.as_bytes()).is_err());
+    }
+}
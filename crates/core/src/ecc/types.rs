@@ -85,6 +85,20 @@ impl PublicKey<33> {
     }
 }
 
+impl PublicKey<48> {
+    /// from raw [u8] of compressed G1 point, the length should be 48
+    pub fn from_u8(value: &[u8]) -> Result<PublicKey<48>> {
+        let data: [u8; 48] = value.try_into().map_err(|_| Error::PublicKeyBadFormat)?;
+        Ok(PublicKey(data))
+    }
+
+    /// convert public_key from hex string
+    pub fn from_hex_string(value: &str) -> Result<PublicKey<48>> {
+        let v = hex::decode(value)?;
+        Self::from_u8(v.as_slice())
+    }
+}
+
 impl<'de> serde::de::Visitor<'de> for PublicKeyVisitor<33> {
     type Value = PublicKey<33>;
 
@@ -100,6 +114,26 @@ impl<'de> serde::de::Visitor<'de> for PublicKeyVisitor<33> {
 impl<'de> Deserialize<'de> for PublicKey<33> {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where D: serde::de::Deserializer<'de> {
-        deserializer.deserialize_str(PublicKeyVisitor)
+        deserializer.deserialize_str(PublicKeyVisitor::<33>)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for PublicKeyVisitor<48> {
+    type Value = PublicKey<48>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a bytestring of in length 48")
+    }
+    fn visit_str<E>(self, value: &str) -> std::result::Result<Self::Value, E>
+    where E: serde::de::Error {
+        let value = base58_monero::decode_check(value).map_err(|e| E::custom(e))?;
+        PublicKey::<48>::from_u8(&value).map_err(|e| E::custom(e))
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey<48> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where D: serde::de::Deserializer<'de> {
+        deserializer.deserialize_str(PublicKeyVisitor::<48>)
     }
 }
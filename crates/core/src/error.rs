@@ -28,6 +28,18 @@ pub enum Error {
     #[error("ECDSA or EdDSA pubkey bad format")]
     PublicKeyBadFormat,
 
+    #[error("Invalid WebAuthn assertion: {0}")]
+    InvalidWebAuthnAssertion(String),
+
+    #[error("Invalid capsule of proxy re-encryption")]
+    InvalidCapsule,
+
+    #[error("Invalid key fragment: {0}")]
+    InvalidKeyFrag(String),
+
+    #[error("Proxy re-encryption failed: {0}")]
+    Reencryption(String),
+
     #[error("Failed to decode vector to bls affine")]
     BlsAffineDecodeFailed,
 
@@ -55,6 +67,18 @@ pub enum Error {
     #[error("The type of VirtualNode is not allowed to be joined as a subring")]
     VNodeNotJoinable,
 
+    #[error("Invalid quorum group: {0}")]
+    InvalidQuorumGroup(String),
+
+    #[error("Co-signer {0} is not a member of quorum group")]
+    NotQuorumMember(crate::dht::Did),
+
+    #[error("Quorum not reached, {0} of {1} members co-signed")]
+    QuorumNotReached(usize, usize),
+
+    #[error("Quorum write is not newer than the stored one")]
+    QuorumWriteOutdated,
+
     #[error("Encode a byte vector into a base58-check string, adds 4 bytes checksum")]
     Encode,
 
@@ -100,6 +124,30 @@ pub enum Error {
     #[error("Unknown account")]
     UnknownAccount,
 
+    #[error("BLS account has no secp256k1 public key")]
+    BlsAccountNoEcdsaKey,
+
+    #[error("Account of session {0} is not a BLS account")]
+    NotBlsAccount(crate::dht::Did),
+
+    #[error("Account signer error: {0}")]
+    AccountSigner(String),
+
+    #[error("Invalid capability: {0}")]
+    InvalidCapability(String),
+
+    #[error("Session {0} is not permitted to send the message")]
+    CapabilityDenied(crate::dht::Did),
+
+    #[error("Account {0} co-signed more than once")]
+    DuplicatedCoSigner(crate::dht::Did),
+
+    #[error("Aggregate verification has no co-signer")]
+    NoCoSigner,
+
+    #[error("Aggregate verification is expired")]
+    AggregateVerificationExpired,
+
     #[error("Failed on verify message signature")]
     VerifySignatureFailed,
 
@@ -375,6 +423,27 @@ pub enum Error {
     #[error("Session is expired")]
     SessionExpired,
 
+    #[error("Session {0} is revoked")]
+    SessionRevoked(crate::dht::Did),
+
+    #[error("Revocation storage lock poisoned")]
+    RevocationStorageLockFailed,
+
+    #[error("Only verified revocations of account are accepted by virtual node {0}")]
+    InvalidRevocation(crate::dht::Did),
+
+    #[error("Session of account {0} cannot replace session of account {1}")]
+    SessionAccountMismatch(crate::dht::Did, crate::dht::Did),
+
+    #[error("Invalid DID document: {0}")]
+    InvalidDidDocument(String),
+
+    #[error("Invalid device link: {0}")]
+    InvalidDeviceLink(String),
+
+    #[error("Swarm callback error: {0}")]
+    SwarmCallback(String),
+
     #[error("Transport error: {0}")]
     Transport(#[from] rings_transport::error::Error),
 
@@ -61,11 +61,14 @@
 #![cfg_attr(target_arch = "wasm32", allow(clippy::arc_with_non_send_sync))]
 
 pub mod dht;
+pub mod directory;
+pub mod document;
 pub mod ecc;
 pub mod error;
 pub mod macros;
 pub mod message;
 pub mod prelude;
+pub mod revocation;
 pub mod session;
 pub mod storage;
 pub mod swarm;
@@ -13,13 +13,16 @@ use crate::message::types::FindSuccessorSend;
 use crate::message::types::Message;
 use crate::message::types::QueryForTopoInfoReport;
 use crate::message::types::QueryForTopoInfoSend;
+use crate::message::types::SessionRenewal;
 use crate::message::types::Then;
 use crate::message::FindSuccessorReportHandler;
 use crate::message::FindSuccessorThen;
 use crate::message::HandleMsg;
 use crate::message::MessageHandler;
 use crate::message::MessagePayload;
+use crate::message::MessageVerificationExt;
 use crate::message::PayloadSender;
+use crate::swarm::callback::SwarmEvent;
 
 /// QueryForTopoInfoSend is direct message
 #[cfg_attr(feature = "wasm", async_trait(?Send))]
@@ -36,6 +39,30 @@ impl HandleMsg<QueryForTopoInfoSend> for MessageHandler {
     }
 }
 
+/// SessionRenewal is direct message, the new session is notified to application by
+/// [SwarmEvent::SessionRenewed].
+#[cfg_attr(feature = "wasm", async_trait(?Send))]
+#[cfg_attr(not(feature = "wasm"), async_trait)]
+impl HandleMsg<SessionRenewal> for MessageHandler {
+    async fn handle(&self, ctx: &MessagePayload, msg: &SessionRenewal) -> Result<()> {
+        if ctx.relay.destination != self.dht.did {
+            return Ok(());
+        }
+        let peer = ctx.transaction.signer();
+        let session = ctx.transaction.verification.session.clone();
+        tracing::info!(
+            "peer {} renewed session {} to {}",
+            peer,
+            msg.previous,
+            session.session_id()
+        );
+        self.swarm_callback
+            .on_event(&SwarmEvent::SessionRenewed { peer, session })
+            .await
+            .map_err(|e| Error::SwarmCallback(e.to_string()))
+    }
+}
+
 /// Try join received node into DHT after received from TopoInfo.
 #[cfg_attr(feature = "wasm", async_trait(?Send))]
 #[cfg_attr(not(feature = "wasm"), async_trait)]
@@ -5,6 +5,7 @@ use std::sync::Arc;
 use async_recursion::async_recursion;
 use async_trait::async_trait;
 
+use crate::dht::quorum::QuorumWrite;
 use crate::dht::vnode::VirtualNode;
 use crate::dht::ChordStorage;
 use crate::dht::ChordStorageCache;
@@ -24,6 +25,7 @@ use crate::message::MessageHandler;
 use crate::message::MessagePayload;
 use crate::message::PayloadSender;
 use crate::prelude::vnode::VNodeOperation;
+use crate::revocation::guard_vnode_operation;
 use crate::swarm::transport::SwarmTransport;
 use crate::swarm::Swarm;
 
@@ -39,6 +41,8 @@ pub trait ChordStorageInterface<const REDUNDANT: u16> {
     async fn storage_append_data(&self, topic: &str, data: Encoded) -> Result<()>;
     /// append data to Data type virtual node uniquely
     async fn storage_touch_data(&self, topic: &str, data: Encoded) -> Result<()>;
+    /// write data approved by a quorum to the virtual node of its group
+    async fn storage_quorum_write(&self, write: QuorumWrite) -> Result<()>;
 }
 
 /// ChordStorageInterfaceCacheChecker defines the interface for checking the local cache of the DHT.
@@ -172,6 +176,12 @@ pub(super) async fn handle_storage_operate_act(
     }
 }
 
+/// Guard operation with the virtual node stored locally, see [guard_vnode_operation].
+async fn guard_operation(dht: &PeerRing, op: VNodeOperation) -> Result<VNodeOperation> {
+    let this = dht.storage.get(&op.did()?.to_string()).await.ok().flatten();
+    guard_vnode_operation(this.as_ref(), op)
+}
+
 #[cfg_attr(feature = "wasm", async_trait(?Send))]
 #[cfg_attr(not(feature = "wasm"), async_trait)]
 impl ChordStorageInterfaceCacheChecker for Swarm {
@@ -195,7 +205,7 @@ impl<const REDUNDANT: u16> ChordStorageInterface<REDUNDANT> for Swarm {
 
     /// Store VirtualNode, `TryInto<VirtualNode>` is implemented for alot of types
     async fn storage_store(&self, vnode: VirtualNode) -> Result<()> {
-        let op = VNodeOperation::Overwrite(vnode);
+        let op = guard_operation(&self.dht, VNodeOperation::Overwrite(vnode)).await?;
         let act = <PeerRing as ChordStorage<_, REDUNDANT>>::vnode_operate(&self.dht, op).await?;
         handle_storage_store_act(self.transport.clone(), act).await?;
         Ok(())
@@ -203,7 +213,7 @@ impl<const REDUNDANT: u16> ChordStorageInterface<REDUNDANT> for Swarm {
 
     async fn storage_append_data(&self, topic: &str, data: Encoded) -> Result<()> {
         let vnode: VirtualNode = (topic.to_string(), data).try_into()?;
-        let op = VNodeOperation::Extend(vnode);
+        let op = guard_operation(&self.dht, VNodeOperation::Extend(vnode)).await?;
         let act = <PeerRing as ChordStorage<_, REDUNDANT>>::vnode_operate(&self.dht, op).await?;
         handle_storage_store_act(self.transport.clone(), act).await?;
         Ok(())
@@ -211,7 +221,16 @@ impl<const REDUNDANT: u16> ChordStorageInterface<REDUNDANT> for Swarm {
 
     async fn storage_touch_data(&self, topic: &str, data: Encoded) -> Result<()> {
         let vnode: VirtualNode = (topic.to_string(), data).try_into()?;
-        let op = VNodeOperation::Touch(vnode);
+        let op = guard_operation(&self.dht, VNodeOperation::Touch(vnode)).await?;
+        let act = <PeerRing as ChordStorage<_, REDUNDANT>>::vnode_operate(&self.dht, op).await?;
+        handle_storage_store_act(self.transport.clone(), act).await?;
+        Ok(())
+    }
+
+    async fn storage_quorum_write(&self, write: QuorumWrite) -> Result<()> {
+        write.verify()?;
+        self.transport.check_cosigners(&write.cosignature)?;
+        let op = VNodeOperation::QuorumWrite(write);
         let act = <PeerRing as ChordStorage<_, REDUNDANT>>::vnode_operate(&self.dht, op).await?;
         handle_storage_store_act(self.transport.clone(), act).await?;
         Ok(())
@@ -250,9 +269,12 @@ impl HandleMsg<FoundVNode> for MessageHandler {
 #[cfg_attr(not(feature = "wasm"), async_trait)]
 impl HandleMsg<VNodeOperation> for MessageHandler {
     async fn handle(&self, ctx: &MessagePayload, msg: &VNodeOperation) -> Result<()> {
+        if let VNodeOperation::QuorumWrite(write) = msg {
+            self.transport.check_cosigners(&write.cosignature)?;
+        }
+        let op = guard_operation(&self.dht, msg.clone()).await?;
         // For relay message, set redundant to 1
-        let action =
-            <PeerRing as ChordStorage<_, 1>>::vnode_operate(&self.dht, msg.clone()).await?;
+        let action = <PeerRing as ChordStorage<_, 1>>::vnode_operate(&self.dht, op).await?;
         handle_storage_operate_act(self.transport.clone(), ctx, &action).await
     }
 }
@@ -265,7 +287,13 @@ impl HandleMsg<SyncVNodeWithSuccessor> for MessageHandler {
         for data in msg.data.iter().cloned() {
             // only simply store here
             // For relay message, set redundant to 1
-            let op = VNodeOperation::Overwrite(data);
+            let op = match guard_operation(&self.dht, VNodeOperation::Overwrite(data)).await {
+                Ok(op) => op,
+                Err(e) => {
+                    tracing::warn!("drop synced vnode: {:?}", e);
+                    continue;
+                }
+            };
             let act = <PeerRing as ChordStorage<_, 1>>::vnode_operate(&self.dht, op).await?;
             handle_storage_store_act(self.transport.clone(), act).await?;
         }
@@ -466,4 +494,87 @@ mod test {
 
         Ok(())
     }
+
+    #[cfg(not(feature = "redundant"))]
+    #[tokio::test]
+    async fn test_quorum_write() -> Result<()> {
+        use crate::dht::quorum::tests::bls_session;
+        use crate::dht::quorum::tests::cosign;
+        use crate::dht::quorum::QuorumGroup;
+        use crate::ecc::signers::bls;
+
+        let keys = gen_ordered_keys(2);
+        let (key1, key2) = (keys[0], keys[1]);
+        let node1 = prepare_node(key1).await;
+        let node2 = prepare_node(key2).await;
+
+        manually_establish_connection(&node1.swarm, &node2.swarm).await;
+        wait_for_msgs([&node1, &node2]).await;
+        assert_no_more_msg([&node1, &node2]).await;
+
+        let members = [bls::random_sk()?, bls::random_sk()?];
+        let group = QuorumGroup::new(
+            "quorum",
+            members
+                .iter()
+                .map(|k| bls_session(*k).account_did())
+                .collect(),
+            2,
+        )?;
+        let data = "approved".to_string().encode()?;
+        let write = QuorumWrite {
+            group: group.clone(),
+            data: data.clone(),
+            cosignature: cosign(&members, &group, &data),
+        };
+        let vid = group.did()?;
+
+        // Make sure the data is stored on node2.
+        let (node1, node2) = if vid.in_range(node2.did(), node2.did(), node1.did()) {
+            (node1, node2)
+        } else {
+            (node2, node1)
+        };
+
+        // Write below threshold is rejected before sending.
+        let below = QuorumWrite {
+            cosignature: cosign(&members[..1], &group, &data),
+            ..write.clone()
+        };
+        assert!(
+            <Swarm as ChordStorageInterface<1>>::storage_quorum_write(&node1.swarm, below)
+                .await
+                .is_err()
+        );
+
+        <Swarm as ChordStorageInterface<1>>::storage_quorum_write(&node1.swarm, write.clone())
+            .await
+            .unwrap();
+        wait_for_msgs([&node1, &node2]).await;
+        assert_no_more_msg([&node1, &node2]).await;
+
+        // Plain overwrite of the quorum virtual node is rejected by the storing node.
+        let plain = VirtualNode {
+            did: vid,
+            data: vec!["plain".to_string().encode()?],
+            kind: VNodeType::Data,
+        };
+        <Swarm as ChordStorageInterface<1>>::storage_store(&node1.swarm, plain)
+            .await
+            .unwrap();
+        wait_for_msgs([&node1, &node2]).await;
+        assert_no_more_msg([&node1, &node2]).await;
+
+        <Swarm as ChordStorageInterface<1>>::storage_fetch(&node1.swarm, vid)
+            .await
+            .unwrap();
+        wait_for_msgs([&node1, &node2]).await;
+        assert_no_more_msg([&node1, &node2]).await;
+
+        let vnode = node1.swarm.storage_check_cache(vid).await.unwrap();
+        assert_eq!(vnode, VirtualNode::try_from(write.clone())?);
+        QuorumWrite::try_from(vnode)?.verify()?;
+
+        Ok(())
+    }
 }
@@ -24,6 +24,8 @@ pub use handlers::HandleMsg;
 pub use handlers::MessageHandler;
 
 mod protocols;
+pub use protocols::AggregateVerification;
+pub use protocols::AggregateVerificationBuilder;
 pub use protocols::MessageRelay;
 pub use protocols::MessageVerification;
 pub use protocols::MessageVerificationExt;
@@ -19,6 +19,8 @@ use super::encoder::Encoder;
 use super::protocols::MessageRelay;
 use super::protocols::MessageVerification;
 use super::protocols::MessageVerificationExt;
+use super::types::CustomMessage;
+use super::types::Message;
 use crate::dht::Chord;
 use crate::dht::Did;
 use crate::dht::PeerRing;
@@ -131,6 +133,30 @@ impl Transaction {
         })
     }
 
+    /// Check the session of sender is permitted to send the message of transaction.
+    /// The `service_of` names the service that a CustomMessage accesses, see
+    /// [MessageVerification::permits].
+    pub fn check_capabilities<F>(&self, service_of: F) -> Result<()>
+    where F: FnOnce(&CustomMessage) -> Option<String> {
+        if !self.verification.session.is_restricted() {
+            return Ok(());
+        }
+        let message: Message = self.data()?;
+        let service = match &message {
+            Message::CustomMessage(msg) => service_of(msg),
+            _ => None,
+        };
+        if !self
+            .verification
+            .permits(&message, self.destination, service.as_deref())
+        {
+            return Err(Error::CapabilityDenied(
+                self.verification.session.session_id(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Deserializes the data field into a `T` instance.
     pub fn data<T>(&self) -> Result<T>
     where T: DeserializeOwned {
@@ -234,8 +260,8 @@ impl Decoder for MessagePayload {
 #[cfg_attr(feature = "wasm", async_trait(?Send))]
 #[cfg_attr(not(feature = "wasm"), async_trait)]
 pub trait PayloadSender {
-    /// Get the session sk
-    fn session_sk(&self) -> &SessionSk;
+    /// Get the current session sk, it may be replaced when the session is renewed.
+    fn session_sk(&self) -> Result<SessionSk>;
 
     /// Get access to DHT.
     fn dht(&self) -> Arc<PeerRing>;
@@ -278,7 +304,7 @@ pub trait PayloadSender {
     where
         T: Serialize + Send,
     {
-        let payload = MessagePayload::new_send(msg, self.session_sk(), next_hop, destination)?;
+        let payload = MessagePayload::new_send(msg, &self.session_sk()?, next_hop, destination)?;
         let tx_id = payload.transaction.tx_id;
         self.send_payload(payload).await?;
         Ok(tx_id)
@@ -302,22 +328,23 @@ pub trait PayloadSender {
     async fn send_report_message<T>(&self, payload: &MessagePayload, msg: T) -> Result<()>
     where T: Serialize + Send {
         let relay = payload.relay.report(self.dht().did)?;
+        let session_sk = self.session_sk()?;
 
         let transaction = Transaction::new(
             relay.destination,
             payload.transaction.tx_id,
             msg,
-            self.session_sk(),
+            &session_sk,
         )?;
 
-        let pl = MessagePayload::new(transaction, self.session_sk(), relay)?;
+        let pl = MessagePayload::new(transaction, &session_sk, relay)?;
         self.send_payload(pl).await
     }
 
     /// Forward a payload message by relay.
     /// It just create a new payload, cloned data, resigned with session and send
     async fn forward_by_relay(&self, payload: &MessagePayload, relay: MessageRelay) -> Result<()> {
-        let new_pl = MessagePayload::new(payload.transaction.clone(), self.session_sk(), relay)?;
+        let new_pl = MessagePayload::new(payload.transaction.clone(), &self.session_sk()?, relay)?;
         self.send_payload(new_pl).await
     }
 
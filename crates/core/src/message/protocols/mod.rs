@@ -2,5 +2,7 @@ mod relay;
 mod verify;
 
 pub use self::relay::MessageRelay;
+pub use self::verify::AggregateVerification;
+pub use self::verify::AggregateVerificationBuilder;
 pub use self::verify::MessageVerification;
 pub use self::verify::MessageVerificationExt;
@@ -2,6 +2,8 @@
 
 //! Implementation of Message Verification.
 
+use std::collections::HashSet;
+
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -9,7 +11,13 @@ use crate::consts::DEFAULT_TTL_MS;
 use crate::consts::MAX_TTL_MS;
 use crate::consts::TS_OFFSET_TOLERANCE_MS;
 use crate::dht::Did;
+use crate::ecc::signers::bls;
+use crate::ecc::PublicKey;
+use crate::error::Error;
 use crate::error::Result;
+use crate::message::Message;
+use crate::session::Account;
+use crate::session::Capability;
 use crate::session::Session;
 use crate::session::SessionSk;
 use crate::utils::get_epoch_ms;
@@ -64,6 +72,187 @@ impl MessageVerification {
             })
             .is_ok()
     }
+
+    /// Check the capabilities of session permit sending the message to destination.
+    /// A CustomMessage is also permitted if it accesses a service that the session can access,
+    /// the `service` is named by [SwarmCallback::custom_message_service](crate::swarm::callback::SwarmCallback::custom_message_service).
+    pub fn permits(&self, msg: &Message, destination: Did, service: Option<&str>) -> bool {
+        let session = &self.session;
+        match msg {
+            Message::SearchVNode(_) => session.permits(&Capability::ReadDht),
+            Message::OperateVNode(_) | Message::SyncVNodeWithSuccessor(_) => {
+                session.permits(&Capability::WriteDht)
+            }
+            Message::CustomMessage(_) => {
+                session.permits_custom_message(destination)
+                    || service.is_some_and(|name| session.permits_service(name))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Aggregate Verification carries a single BLS signature co-signed by several BLS accounts on
+/// the same data. Both the size of signature and the cost of verifying it are constant,
+/// no matter how many co-signers there are.
+///
+/// Use [AggregateVerificationBuilder] to collect signatures of co-signers.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct AggregateVerification {
+    /// The [Session]s of co-signers, whose accounts should be [Account::BLS].
+    /// The session signature also proves the possession of account key, which prevents
+    /// rogue key attacks on the aggregated public key.
+    pub sessions: Vec<Session>,
+    /// The time to live of the co-signature in milliseconds.
+    pub ttl_ms: u64,
+    /// The timestamp of the co-signature in milliseconds.
+    pub ts_ms: u128,
+    /// The aggregated BLS signature of co-signers.
+    pub sig: Vec<u8>,
+}
+
+/// AggregateVerificationBuilder is used to build an [AggregateVerification].
+///
+/// Every co-signer signs `unsigned_proof` by its BLS account key, then the signature is added
+/// with the session of co-signer by `add_signature`. Finally, call `build` to aggregate them.
+pub struct AggregateVerificationBuilder {
+    data: Vec<u8>,
+    ttl_ms: u64,
+    ts_ms: u128,
+    sessions: Vec<Session>,
+    sigs: Vec<bls::Signature>,
+}
+
+fn pack_aggregate(data: &[u8], ts_ms: u128, ttl_ms: u64) -> Vec<u8> {
+    // Prefixed to separate co-signatures from the session proofs signed by the same key.
+    let mut msg = b"rings/aggregate\n".to_vec();
+    msg.extend_from_slice(&pack_msg(data, ts_ms, ttl_ms));
+    msg
+}
+
+/// Verify the session and get the BLS public key of its account.
+fn bls_account_pubkey(session: &Session) -> Result<PublicKey<48>> {
+    session.verify_self()?;
+    match session.account() {
+        Account::BLS(pk) => Ok(*pk),
+        _ => Err(Error::NotBlsAccount(session.account_did())),
+    }
+}
+
+impl AggregateVerificationBuilder {
+    /// Create a new AggregateVerificationBuilder for data.
+    pub fn new(data: &[u8]) -> Self {
+        Self {
+            data: data.to_vec(),
+            ttl_ms: DEFAULT_TTL_MS,
+            ts_ms: get_epoch_ms(),
+            sessions: vec![],
+            sigs: vec![],
+        }
+    }
+
+    /// Set the lifetime of co-signature, it should be set before co-signers sign.
+    pub fn set_ttl(mut self, ttl_ms: u64) -> Self {
+        self.ttl_ms = ttl_ms;
+        self
+    }
+
+    /// The bytes that co-signers should sign.
+    pub fn unsigned_proof(&self) -> Vec<u8> {
+        pack_aggregate(&self.data, self.ts_ms, self.ttl_ms)
+    }
+
+    /// Add the signature of a co-signer. The signature is checked at once,
+    /// so that a bad co-signer can be identified before aggregation.
+    pub fn add_signature(&mut self, session: Session, sig: &[u8]) -> Result<()> {
+        let pk = bls_account_pubkey(&session)?;
+        let signer = session.account_did();
+        if self.sessions.iter().any(|s| s.account_did() == signer) {
+            return Err(Error::DuplicatedCoSigner(signer));
+        }
+
+        let sig = bls::Signature::try_from(sig)?;
+        if !bls::verify(&[self.unsigned_proof().as_slice()], &sig, &[pk])? {
+            return Err(Error::VerifySignatureFailed);
+        }
+
+        self.sessions.push(session);
+        self.sigs.push(sig);
+        Ok(())
+    }
+
+    /// Aggregate the signatures into [AggregateVerification].
+    pub fn build(self) -> Result<AggregateVerification> {
+        if self.sigs.is_empty() {
+            return Err(Error::NoCoSigner);
+        }
+        Ok(AggregateVerification {
+            sessions: self.sessions,
+            ttl_ms: self.ttl_ms,
+            ts_ms: self.ts_ms,
+            sig: bls::aggregate(&self.sigs)?.0.to_vec(),
+        })
+    }
+}
+
+impl AggregateVerification {
+    /// Checks whether the co-signature is expired.
+    pub fn is_expired(&self) -> bool {
+        let now = get_epoch_ms();
+        self.ttl_ms > MAX_TTL_MS
+            || self.ts_ms > now + TS_OFFSET_TOLERANCE_MS
+            || now > self.ts_ms + self.ttl_ms as u128
+    }
+
+    /// Verify the co-signature of data, it should not be expired.
+    pub fn verify(&self, data: &[u8]) -> bool {
+        if self.is_expired() {
+            tracing::warn!(
+                "AggregateVerification verify failed: {:?}",
+                Error::AggregateVerificationExpired
+            );
+            return false;
+        }
+        self.verify_signature(data)
+    }
+
+    /// Verify the co-signature of data regardless of its ttl, for the co-signatures which are
+    /// checked to be fresh when they're accepted, and kept to be verified by others later.
+    pub fn verify_signature(&self, data: &[u8]) -> bool {
+        self.check(data)
+            .map_err(|e| {
+                tracing::warn!("AggregateVerification verify failed: {:?}", e);
+            })
+            .is_ok()
+    }
+
+    fn check(&self, data: &[u8]) -> Result<()> {
+        if self.sessions.is_empty() {
+            return Err(Error::NoCoSigner);
+        }
+
+        let mut signers = HashSet::new();
+        let mut pks = vec![];
+        for session in &self.sessions {
+            if !signers.insert(session.account_did()) {
+                return Err(Error::DuplicatedCoSigner(session.account_did()));
+            }
+            pks.push(bls_account_pubkey(session)?);
+        }
+
+        let pk = bls::aggregate_public_keys(&pks)?;
+        let sig = bls::Signature::try_from(self.sig.as_slice())?;
+        let msg = pack_aggregate(data, self.ts_ms, self.ttl_ms);
+        if !bls::verify(&[msg.as_slice()], &sig, &[pk])? {
+            return Err(Error::VerifySignatureFailed);
+        }
+        Ok(())
+    }
+
+    /// Get dids of co-signers.
+    pub fn signers(&self) -> Vec<Did> {
+        self.sessions.iter().map(|s| s.account_did()).collect()
+    }
 }
 
 /// This trait helps a struct with `MessageVerification` field to `verify` itself.
@@ -155,6 +155,14 @@ pub struct SyncVNodeWithSuccessor {
     pub data: Vec<VirtualNode>,
 }
 
+/// MessageType use to announce a renewed session to connected peers.
+/// The new session is carried by the verification of payload.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SessionRenewal {
+    /// The session id of replaced session.
+    pub previous: Did,
+}
+
 /// MessageType use to customize message, will be handle by `custom_message` method.
 #[derive(Deserialize, Serialize, Clone)]
 pub struct CustomMessage(pub Vec<u8>);
@@ -213,6 +221,8 @@ pub enum Message {
     QueryForTopoInfoReport(QueryForTopoInfoReport),
     /// A chunk that can be deserialized to a payload.
     Chunk(Chunk),
+    /// Announcement of a renewed session.
+    SessionRenewal(SessionRenewal),
 }
 
 impl std::fmt::Display for Message {
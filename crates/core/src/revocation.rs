@@ -0,0 +1,617 @@
+#![warn(missing_docs)]
+//! Revocation of leaked session keys.
+//!
+//! A [Session] is valid until its ttl is reached, so a leaked [SessionSk](crate::session::SessionSk)
+//! can be used by anyone during that period. To invalidate it in advance, the [Account] who
+//! delegated the session signs a [SessionRevocation] and publishes it to the DHT, in the data
+//! virtual node of its own [revocation_topic].
+//!
+//! Each node keeps a [RevocationList] as local cache of the revocations. The accounts whose
+//! sessions are checked against the list recently are remembered, so that their virtual nodes
+//! can be merged into the list periodically. Messages carrying a revoked session are rejected by swarm.
+//! With a [RevocationStorage] set, the cached revocations are kept across restarts.
+//!
+//! Nodes storing the virtual node of [revocation_topic] only accept verified revocations of
+//! the account, and never evict them, see [guard_vnode_operation].
+//!
+//! To build a [SessionRevocation]:
+//! ```
+//! use rings_core::dht::Did;
+//! use rings_core::revocation::RevocationList;
+//! use rings_core::revocation::SessionRevocationBuilder;
+//! use rings_core::session::SessionSk;
+//!
+//! let user_secret_key = rings_core::ecc::SecretKey::random();
+//! let user_secret_key_did: Did = user_secret_key.address().into();
+//! let session_sk = SessionSk::new_with_seckey(&user_secret_key).unwrap();
+//! let session = session_sk.session();
+//!
+//! let builder = SessionRevocationBuilder::new(
+//!     user_secret_key_did.to_string(),
+//!     "secp256k1".to_string(),
+//!     session.session_id().to_string(),
+//! );
+//! let sig = user_secret_key
+//!     .sign(&builder.unsigned_proof().unwrap())
+//!     .to_vec();
+//! let revocation = builder.set_sig(sig).build().unwrap();
+//!
+//! let revocations = RevocationList::default();
+//! revocations.insert(revocation).unwrap();
+//! assert!(revocations.is_revoked(&session));
+//! ```
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use dashmap::DashMap;
+use rings_derive::wasm_export;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::dht::vnode::VNodeOperation;
+use crate::dht::vnode::VNodeType;
+use crate::dht::vnode::VirtualNode;
+use crate::dht::Did;
+use crate::error::Error;
+use crate::error::Result;
+use crate::message::Encoded;
+use crate::session::verify_account_sig;
+use crate::session::Account;
+use crate::session::AccountSigner;
+use crate::session::Session;
+use crate::storage::KvStorageInterface;
+use crate::utils;
+
+/// The prefix of topics which revocations are published to, see [revocation_topic].
+pub const REVOCATION_TOPIC_PREFIX: &str = "rings/session_revocations/";
+
+/// Max number of accounts whose revocations are followed by a [RevocationList], the least
+/// recently seen accounts are dropped first.
+pub const MAX_WATCHED_ACCOUNTS: usize = 4096;
+
+/// The topic of data virtual node which the revocations of account are published to.
+pub fn revocation_topic(account: Did) -> String {
+    format!("{}{}", REVOCATION_TOPIC_PREFIX, account)
+}
+
+/// Guard operation on a data virtual node, before it's applied to `this`, the stored one.
+///
+/// If the virtual node is the [revocation_topic] of an account, which is told by the
+/// revocations it holds, every data written to it should be a verified revocation of the account,
+/// otherwise [Error::InvalidRevocation] is returned. If the virtual node is stored, the operation
+/// is turned into an [VNodeOperation::Overwrite] of the stored revocations and the new ones, so
+/// that revocations are never evicted like the oldest data of [VirtualNode::extend]. Operations
+/// on other virtual nodes are returned as is.
+pub fn guard_vnode_operation(
+    this: Option<&VirtualNode>,
+    op: VNodeOperation,
+) -> Result<VNodeOperation> {
+    let (VNodeOperation::Overwrite(vnode)
+    | VNodeOperation::Extend(vnode)
+    | VNodeOperation::Touch(vnode)) = &op
+    else {
+        return Ok(op);
+    };
+    if vnode.kind != VNodeType::Data {
+        return Ok(op);
+    }
+    let decode = |e: &Encoded| SessionRevocation::from_str(e.value()).ok();
+    let stored = this.map(|v| v.data.as_slice()).unwrap_or_default();
+    let Some(account) = stored
+        .iter()
+        .chain(vnode.data.iter())
+        .filter_map(decode)
+        .map(|r| r.account_did())
+        .find(|account| {
+            VirtualNode::gen_did(&revocation_topic(*account)).is_ok_and(|did| did == vnode.did)
+        })
+    else {
+        return Ok(op);
+    };
+
+    let mut revocations = stored
+        .iter()
+        .filter_map(decode)
+        .filter(|r| r.account_did() == account && r.verify().is_ok())
+        .collect::<Vec<_>>();
+    for encoded in vnode.data.iter() {
+        let revocation = decode(encoded)
+            .filter(|r| r.account_did() == account && r.verify().is_ok())
+            .ok_or(Error::InvalidRevocation(vnode.did))?;
+        if !revocations.contains(&revocation) {
+            revocations.push(revocation);
+        }
+    }
+    if this.is_none() {
+        return Ok(op);
+    }
+    Ok(VNodeOperation::Overwrite(VirtualNode {
+        did: vnode.did,
+        data: revocations
+            .iter()
+            .map(Encoded::try_from)
+            .collect::<Result<Vec<_>>>()?,
+        kind: VNodeType::Data,
+    }))
+}
+
+/// `RevocationStorage` is the type accepted by [RevocationList::set_storage].
+#[cfg(feature = "wasm")]
+pub type RevocationStorage = Box<dyn KvStorageInterface<SessionRevocation>>;
+
+/// `RevocationStorage` is the type accepted by [RevocationList::set_storage].
+#[cfg(not(feature = "wasm"))]
+pub type RevocationStorage = Box<dyn KvStorageInterface<SessionRevocation> + Send + Sync>;
+
+fn pack_revocation(session_id: Did, ts_ms: u128) -> String {
+    format!("revoke\n{}\n{}", session_id, ts_ms)
+}
+
+/// SessionRevocationBuilder is used to build a [SessionRevocation].
+///
+/// Like [SessionSkBuilder](crate::session::SessionSkBuilder), the account signs the
+/// `unsigned_proof` outside and set the signature back by `set_sig`.
+#[wasm_export]
+pub struct SessionRevocationBuilder {
+    account_entity: String,
+    account_type: String,
+    session_id: String,
+    ts_ms: u128,
+    sig: Vec<u8>,
+}
+
+/// A record signed by [Account] to declare that one of its sessions should not be trusted anymore.
+#[wasm_export]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct SessionRevocation {
+    /// Did of the revoked session.
+    session_id: Did,
+    /// Account who delegated the session.
+    account: Account,
+    /// Timestamp when revocation created.
+    ts_ms: u128,
+    /// Signature of account.
+    sig: Vec<u8>,
+}
+
+/// Local cache of verified revocations, keyed by session id and account did.
+#[derive(Default)]
+pub struct RevocationList {
+    table: DashMap<(Did, Did), SessionRevocation>,
+    /// accounts whose sessions are checked and when they're last seen in milliseconds,
+    /// their revocations are fetched from DHT
+    watched: DashMap<Did, u128>,
+    /// persistent storage of revocations
+    storage: RwLock<Option<Arc<RevocationStorage>>>,
+}
+
+// A SessionRevocation can be converted to a string using JSON and then encoded with base58.
+// To load the SessionRevocation from a string, use `SessionRevocation::from_str`.
+impl FromStr for SessionRevocation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = base58_monero::decode_check(s).map_err(|_| Error::Decode)?;
+        serde_json::from_slice(&s).map_err(Error::Deserialize)
+    }
+}
+
+#[wasm_export]
+impl SessionRevocationBuilder {
+    /// Create a new SessionRevocationBuilder.
+    /// The "account_entity" and "account_type" are the same as [SessionSkBuilder](crate::session::SessionSkBuilder::new).
+    /// The "session_id" is the did of session to be revoked.
+    pub fn new(
+        account_entity: String,
+        account_type: String,
+        session_id: String,
+    ) -> SessionRevocationBuilder {
+        Self {
+            account_entity,
+            account_type,
+            session_id,
+            ts_ms: utils::get_epoch_ms(),
+            sig: vec![],
+        }
+    }
+
+    /// Construct unsigned_info string for signing.
+    pub fn unsigned_proof(&self) -> Result<String> {
+        Ok(pack_revocation(
+            Did::from_str(&self.session_id)?,
+            self.ts_ms,
+        ))
+    }
+
+    /// Set the signature of revocation that signed by account.
+    pub fn set_sig(mut self, sig: Vec<u8>) -> Self {
+        self.sig = sig;
+        self
+    }
+
+    /// Build the [SessionRevocation].
+    pub fn build(self) -> Result<SessionRevocation> {
+        let revocation = SessionRevocation {
+            session_id: Did::from_str(&self.session_id)?,
+            account: Account::try_from((self.account_entity, self.account_type))?,
+            ts_ms: self.ts_ms,
+            sig: self.sig,
+        };
+        revocation.verify()?;
+        Ok(revocation)
+    }
+}
+
+impl SessionRevocationBuilder {
+    /// Create a new SessionRevocationBuilder for the account of signer.
+    pub async fn from_signer<S>(signer: &S, session_id: String) -> Result<Self>
+    where S: AccountSigner + ?Sized {
+        let (account_entity, account_type) = signer.account().await?;
+        Ok(Self::new(account_entity, account_type, session_id))
+    }
+
+    /// Sign the unsigned proof by signer and set the signature.
+    pub async fn sign_by<S>(self, signer: &S) -> Result<Self>
+    where S: AccountSigner + ?Sized {
+        let sig = signer.sign(&self.unsigned_proof()?).await?;
+        Ok(self.set_sig(sig))
+    }
+}
+
+impl SessionRevocation {
+    /// Verify the revocation is signed by its account.
+    pub fn verify(&self) -> Result<()> {
+        let auth_bytes = pack_revocation(self.session_id, self.ts_ms);
+        if !verify_account_sig(&self.account, auth_bytes.as_bytes(), &self.sig) {
+            return Err(Error::VerifySignatureFailed);
+        }
+        Ok(())
+    }
+
+    /// Check if the revocation applies to the session.
+    /// A session can only be revoked by the account who delegated it.
+    pub fn revokes(&self, session: &Session) -> bool {
+        self.session_id == session.session_id() && self.account_did() == session.account_did()
+    }
+
+    /// Did of the revoked session.
+    pub fn session_id(&self) -> Did {
+        self.session_id
+    }
+
+    /// Get account did of revocation.
+    pub fn account_did(&self) -> Did {
+        self.account.did()
+    }
+}
+
+#[wasm_export]
+impl SessionRevocation {
+    /// Dump revocation to string. It can be restored using `SessionRevocation::from_str`.
+    pub fn dump(&self) -> Result<String> {
+        let s = serde_json::to_string(&self).map_err(|_| Error::SerializeError)?;
+        base58_monero::encode_check(s.as_bytes()).map_err(|_| Error::Encode)
+    }
+}
+
+fn storage_key(session_id: Did, account_did: Did) -> String {
+    format!("{}:{}", account_did, session_id)
+}
+
+impl RevocationList {
+    /// Set storage of revocations, revocations already in storage are verified and loaded.
+    /// Returns the number of loaded revocations.
+    pub async fn set_storage(&self, storage: RevocationStorage) -> Result<usize> {
+        let mut loaded = 0;
+        for (key, revocation) in storage.get_all().await? {
+            match self.insert(revocation) {
+                Ok(_) => loaded += 1,
+                Err(e) => tracing::warn!("invalid revocation {} in storage: {:?}", key, e),
+            }
+        }
+        *self
+            .storage
+            .write()
+            .map_err(|_| Error::RevocationStorageLockFailed)? = Some(Arc::new(storage));
+        Ok(loaded)
+    }
+
+    /// Verify and insert a revocation, and write it to storage if set.
+    /// Returns false if it's already known.
+    pub async fn save(&self, revocation: SessionRevocation) -> Result<bool> {
+        let key = storage_key(revocation.session_id, revocation.account_did());
+        if !self.insert(revocation.clone())? {
+            return Ok(false);
+        }
+        let storage = self.storage.read().ok().and_then(|s| s.clone());
+        if let Some(storage) = storage {
+            storage.put(&key, &revocation).await?;
+        }
+        Ok(true)
+    }
+
+    /// Verify and insert a revocation into memory. Returns false if it's already known.
+    pub fn insert(&self, revocation: SessionRevocation) -> Result<bool> {
+        revocation.verify()?;
+        Ok(self
+            .table
+            .insert(
+                (revocation.session_id, revocation.account_did()),
+                revocation,
+            )
+            .is_none())
+    }
+
+    /// Check if the session is revoked. The account of session is watched from now on.
+    pub fn is_revoked(&self, session: &Session) -> bool {
+        self.contains(session.session_id(), session.account_did())
+    }
+
+    /// Check if the session id is revoked by account. It also applies to the devices linked to
+    /// account, see [DeviceLink](crate::directory::DeviceLink).
+    /// The account is watched from now on.
+    pub fn contains(&self, session_id: Did, account_did: Did) -> bool {
+        self.watch(account_did);
+        self.table.contains_key(&(session_id, account_did))
+    }
+
+    /// Follow revocations of account, or refresh it if it's followed already.
+    /// At most [MAX_WATCHED_ACCOUNTS] accounts are followed, the least recently seen one is
+    /// dropped to make room for a new one.
+    pub fn watch(&self, account_did: Did) {
+        if self
+            .watched
+            .insert(account_did, utils::get_epoch_ms())
+            .is_some()
+            || self.watched.len() <= MAX_WATCHED_ACCOUNTS
+        {
+            return;
+        }
+        let oldest = self
+            .watched
+            .iter()
+            .filter(|entry| *entry.key() != account_did)
+            .min_by_key(|entry| *entry.value())
+            .map(|entry| *entry.key());
+        if let Some(oldest) = oldest {
+            self.watched.remove(&oldest);
+        }
+    }
+
+    /// List accounts whose revocations should be fetched from their [revocation_topic].
+    pub fn watched_accounts(&self) -> Vec<Did> {
+        self.watched.iter().map(|entry| *entry.key()).collect()
+    }
+
+    /// Merge revocations stored in the data virtual node of [revocation_topic] of account.
+    /// Invalid records and records of other accounts are skipped.
+    /// Returns the number of new revocations.
+    pub async fn merge_vnode(&self, vnode: &VirtualNode, account_did: Did) -> usize {
+        let mut merged = 0;
+        for encoded in vnode.data.iter() {
+            let revocation = match SessionRevocation::from_str(encoded.value()) {
+                Ok(revocation) => revocation,
+                Err(e) => {
+                    tracing::warn!("failed to decode revocation: {:?}", e);
+                    continue;
+                }
+            };
+            if revocation.account_did() != account_did {
+                continue;
+            }
+            match self.save(revocation.clone()).await {
+                Ok(true) => merged += 1,
+                Ok(false) => {}
+                Err(e) => tracing::warn!("invalid revocation {:?}: {:?}", revocation, e),
+            }
+        }
+        merged
+    }
+
+    /// List all revocations.
+    pub fn list(&self) -> Vec<SessionRevocation> {
+        self.table.iter().map(|r| r.value().clone()).collect()
+    }
+}
+
+impl TryFrom<&SessionRevocation> for Encoded {
+    type Error = Error;
+
+    fn try_from(revocation: &SessionRevocation) -> Result<Self> {
+        Ok(revocation.dump()?.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dht::vnode::VNodeType;
+    use crate::ecc::SecretKey;
+    use crate::message::Encoder;
+    use crate::session::SessionSk;
+    use crate::storage::MemStorage;
+
+    fn revoke(key: &SecretKey, session: &Session) -> SessionRevocation {
+        let builder = SessionRevocationBuilder::new(
+            Did::from(key.address()).to_string(),
+            "secp256k1".to_string(),
+            session.session_id().to_string(),
+        );
+        let sig = key.sign(&builder.unsigned_proof().unwrap());
+        builder.set_sig(sig.to_vec()).build().unwrap()
+    }
+
+    #[test]
+    fn test_revocation_list() {
+        let key = SecretKey::random();
+        let session = SessionSk::new_with_seckey(&key).unwrap().session();
+        let other = SessionSk::new_with_seckey(&key).unwrap().session();
+
+        let revocation = revoke(&key, &session);
+        let dump = revocation.dump().unwrap();
+        assert_eq!(SessionRevocation::from_str(&dump).unwrap(), revocation);
+
+        let list = RevocationList::default();
+        assert!(list.insert(revocation.clone()).unwrap());
+        assert!(!list.insert(revocation).unwrap());
+        assert!(list.is_revoked(&session));
+        assert!(!list.is_revoked(&other));
+    }
+
+    #[test]
+    fn test_revocation_of_other_account() {
+        let key = SecretKey::random();
+        let attacker = SecretKey::random();
+        let session = SessionSk::new_with_seckey(&key).unwrap().session();
+
+        // Signature of other key is rejected.
+        let builder = SessionRevocationBuilder::new(
+            Did::from(key.address()).to_string(),
+            "secp256k1".to_string(),
+            session.session_id().to_string(),
+        );
+        let sig = attacker.sign(&builder.unsigned_proof().unwrap());
+        assert!(builder.set_sig(sig.to_vec()).build().is_err());
+
+        // A valid revocation of other account doesn't revoke the session.
+        let revocation = revoke(&attacker, &session);
+        let list = RevocationList::default();
+        list.insert(revocation).unwrap();
+        assert!(!list.is_revoked(&session));
+    }
+
+    #[tokio::test]
+    async fn test_merge_vnode() {
+        let key = SecretKey::random();
+        let account = Did::from(key.address());
+        let session = SessionSk::new_with_seckey(&key).unwrap().session();
+        let revocation = revoke(&key, &session);
+
+        // Revocation of other account published to the topic is skipped.
+        let other = SecretKey::random();
+        let other_session = SessionSk::new_with_seckey(&other).unwrap().session();
+        let other_revocation = revoke(&other, &other_session);
+
+        let vnode = VirtualNode {
+            did: VirtualNode::gen_did(&revocation_topic(account)).unwrap(),
+            data: vec![
+                Encoded::try_from(&revocation).unwrap(),
+                Encoded::try_from(&other_revocation).unwrap(),
+                "invalid".into(),
+            ],
+            kind: VNodeType::Data,
+        };
+
+        let list = RevocationList::default();
+        assert_eq!(list.merge_vnode(&vnode, account).await, 1);
+        assert_eq!(list.merge_vnode(&vnode, account).await, 0);
+        assert!(list.is_revoked(&session));
+        assert!(!list.is_revoked(&other_session));
+    }
+
+    #[test]
+    fn test_watched_accounts() {
+        let key = SecretKey::random();
+        let session = SessionSk::new_with_seckey(&key).unwrap().session();
+
+        let list = RevocationList::default();
+        assert!(list.watched_accounts().is_empty());
+        assert!(!list.is_revoked(&session));
+        assert_eq!(list.watched_accounts(), vec![session.account_did()]);
+
+        // The least recently seen account is dropped when the list is full.
+        let accounts = (0..MAX_WATCHED_ACCOUNTS)
+            .map(|_| Did::from(SecretKey::random().address()))
+            .collect::<Vec<_>>();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        for account in accounts.iter() {
+            list.watch(*account);
+        }
+        let watched = list.watched_accounts();
+        assert_eq!(watched.len(), MAX_WATCHED_ACCOUNTS);
+        assert!(!watched.contains(&session.account_did()));
+
+        // Account seen again is followed again.
+        assert!(!list.is_revoked(&session));
+        let watched = list.watched_accounts();
+        assert_eq!(watched.len(), MAX_WATCHED_ACCOUNTS);
+        assert!(watched.contains(&session.account_did()));
+    }
+
+    #[test]
+    fn test_guard_vnode_operation() {
+        let key = SecretKey::random();
+        let account = Did::from(key.address());
+        let topic = revocation_topic(account);
+        let revocations = (0..3)
+            .map(|_| {
+                let session = SessionSk::new_with_seckey(&key).unwrap().session();
+                Encoded::try_from(&revoke(&key, &session)).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let vnode = |data: Encoded| VirtualNode::try_from((topic.clone(), data)).unwrap();
+
+        // Data of other topics are not touched.
+        let op = VNodeOperation::Extend(VirtualNode::try_from("other".to_string()).unwrap());
+        assert_eq!(guard_vnode_operation(None, op.clone()).unwrap(), op);
+
+        // Verified revocation of account is accepted.
+        let op = VNodeOperation::Touch(vnode(revocations[0].clone()));
+        assert_eq!(guard_vnode_operation(None, op.clone()).unwrap(), op);
+        let stored = vnode(revocations[0].clone());
+
+        // Other data, revocations of other accounts and forged ones are rejected.
+        let other = SecretKey::random();
+        let other_session = SessionSk::new_with_seckey(&other).unwrap().session();
+        let mut forged = revoke(&key, &other_session);
+        forged.sig = vec![0; 65];
+        for data in [
+            "junk".to_string().encode().unwrap(),
+            Encoded::try_from(&revoke(&other, &other_session)).unwrap(),
+            Encoded::try_from(&forged).unwrap(),
+        ] {
+            let op = VNodeOperation::Extend(vnode(data));
+            assert!(matches!(
+                guard_vnode_operation(Some(&stored), op),
+                Err(Error::InvalidRevocation(_))
+            ));
+        }
+
+        // Stored revocations are kept, the operation is turned into overwrite.
+        let mut update = vnode(revocations[1].clone());
+        update.data.push(revocations[2].clone());
+        let op = VNodeOperation::Overwrite(update);
+        let VNodeOperation::Overwrite(guarded) = guard_vnode_operation(Some(&stored), op).unwrap()
+        else {
+            panic!("expect overwrite");
+        };
+        assert_eq!(guarded.data, revocations);
+    }
+
+    #[tokio::test]
+    async fn test_persist_revocations() {
+        let key = SecretKey::random();
+        let session = SessionSk::new_with_seckey(&key).unwrap().session();
+        let revocation = revoke(&key, &session);
+
+        let list = RevocationList::default();
+        list.set_storage(Box::new(MemStorage::new())).await.unwrap();
+        assert!(list.save(revocation.clone()).await.unwrap());
+        assert!(!list.save(revocation).await.unwrap());
+        let storage = list.storage.read().unwrap().clone().unwrap();
+        let saved = storage.get_all().await.unwrap();
+        assert_eq!(saved.len(), 1);
+
+        // Revocations are loaded back from storage, invalid ones are skipped.
+        let mut forged = saved[0].1.clone();
+        forged.sig = vec![0; 65];
+        let reloaded = MemStorage::new();
+        reloaded.put(&saved[0].0, &saved[0].1).await.unwrap();
+        reloaded.put("forged", &forged).await.unwrap();
+        let restored = RevocationList::default();
+        assert_eq!(restored.set_storage(Box::new(reloaded)).await.unwrap(), 1);
+        assert!(restored.is_revoked(&session));
+    }
+}
@@ -67,12 +67,18 @@
 //!    sessionBuilder = sessionBuilder.set_session_sig(signature)
 //!    let sessionSk: SessionSk = sessionBuilder.build()
 //! ```
-
+//!
+//! A session has full authority of the account by default. To hand a session to an untrusted
+//! party, the account can restrict it to some [Capability]s by `add_capability` of
+//! [SessionSkBuilder]. The capabilities are included in the unsigned proof, so they are signed
+//! by the account and can't be removed. Receivers reject messages beyond the capabilities of
+//! the sender session, see [MessageVerification::permits](crate::message::MessageVerification::permits).
 //!
 //! See [SessionSk] and [SessionSkBuilder] for details.
 
 use std::str::FromStr;
 
+use async_trait::async_trait;
 use rings_derive::wasm_export;
 use serde::Deserialize;
 use serde::Serialize;
@@ -80,6 +86,7 @@ use serde::Serialize;
 use crate::consts::DEFAULT_SESSION_TTL_MS;
 use crate::dht::Did;
 use crate::ecc::keccak256;
+use crate::ecc::pre::SealedData;
 use crate::ecc::signers;
 use crate::ecc::PublicKey;
 use crate::ecc::SecretKey;
@@ -87,8 +94,27 @@ use crate::error::Error;
 use crate::error::Result;
 use crate::utils;
 
-fn pack_session(session_id: Did, ts_ms: u128, ttl_ms: u64) -> String {
-    format!("{}\n{}\n{}", session_id, ts_ms, ttl_ms)
+fn pack_session(session_id: Did, ts_ms: u128, ttl_ms: u64, capabilities: &[Capability]) -> String {
+    let mut proof = format!("{}\n{}\n{}", session_id, ts_ms, ttl_ms);
+    for capability in capabilities {
+        proof.push_str(&format!("\n{}", capability));
+    }
+    proof
+}
+
+/// Verify that `auth_bytes` is signed by the account.
+pub(crate) fn verify_account_sig(account: &Account, auth_bytes: &[u8], sig: &[u8]) -> bool {
+    match account {
+        Account::Secp256k1(did) => signers::secp256k1::verify(auth_bytes, &(*did).into(), sig),
+        Account::EIP191(did) => signers::eip191::verify(auth_bytes, &(*did).into(), sig),
+        Account::BIP137(did) => signers::bip137::verify(auth_bytes, &(*did).into(), sig),
+        Account::Ed25519(pk) => signers::ed25519::verify(auth_bytes, &pk.address(), sig, pk),
+        Account::Secp256r1(pk) => signers::secp256r1::verify(auth_bytes, &pk.address(), sig, pk),
+        Account::WebAuthn(pk) => signers::webauthn::verify(auth_bytes, &pk.address(), sig, pk),
+        Account::BLS(pk) => signers::bls::Signature::try_from(sig)
+            .and_then(|sig| signers::bls::verify(&[auth_bytes], &sig, &[*pk]))
+            .unwrap_or(false),
+    }
 }
 
 /// SessionSkBuilder is used to build a [SessionSk].
@@ -110,6 +136,8 @@ pub struct SessionSkBuilder {
     ts_ms: u128,
     /// Signature of session
     sig: Vec<u8>,
+    /// Capabilities of session, unrestricted if empty.
+    capabilities: Vec<Capability>,
 }
 
 /// SessionSk holds the [Session] and its session private key.
@@ -136,6 +164,7 @@ pub struct SessionSk {
 /// To verify the session is provided by the account, use session.verify_self().
 /// To verify the message, use session.verify(msg, sig).
 #[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone)]
+#[serde(from = "SessionEncoding", into = "SessionEncoding")]
 pub struct Session {
     /// Did of session, this is hash of sessionPk
     session_id: Did,
@@ -147,10 +176,109 @@ pub struct Session {
     ts_ms: u128,
     /// Signature to verify that the session was signed by the account.
     sig: Vec<u8>,
+    /// Capabilities that the session is restricted to. The session is unrestricted if empty.
+    capabilities: Vec<Capability>,
+}
+
+/// The encoding of [Session]. An unrestricted session is encoded as it was before capabilities
+/// were introduced. A restricted session wraps its account and capabilities into
+/// [SessionAccount::Restricted], so that nodes unaware of capabilities fail to decode it,
+/// instead of taking it as an unrestricted one.
+#[derive(Deserialize, Serialize)]
+struct SessionEncoding {
+    session_id: Did,
+    account: SessionAccount,
+    ttl_ms: u64,
+    ts_ms: u128,
+    sig: Vec<u8>,
+}
+
+/// The account slot of [SessionEncoding]. It mirrors the variants of [Account] in the same
+/// order, and new variants of [Account] should be appended after `Restricted`.
+#[derive(Deserialize, Serialize)]
+#[allow(clippy::upper_case_acronyms)]
+enum SessionAccount {
+    Secp256k1(Did),
+    Secp256r1(PublicKey<33>),
+    EIP191(Did),
+    BIP137(Did),
+    Ed25519(PublicKey<33>),
+    BLS(PublicKey<48>),
+    WebAuthn(PublicKey<33>),
+    Restricted(Account, Vec<Capability>),
+}
+
+impl From<Session> for SessionEncoding {
+    fn from(session: Session) -> Self {
+        let account = if session.capabilities.is_empty() {
+            match session.account {
+                Account::Secp256k1(did) => SessionAccount::Secp256k1(did),
+                Account::Secp256r1(pk) => SessionAccount::Secp256r1(pk),
+                Account::EIP191(did) => SessionAccount::EIP191(did),
+                Account::BIP137(did) => SessionAccount::BIP137(did),
+                Account::Ed25519(pk) => SessionAccount::Ed25519(pk),
+                Account::BLS(pk) => SessionAccount::BLS(pk),
+                Account::WebAuthn(pk) => SessionAccount::WebAuthn(pk),
+            }
+        } else {
+            SessionAccount::Restricted(session.account, session.capabilities)
+        };
+        Self {
+            session_id: session.session_id,
+            account,
+            ttl_ms: session.ttl_ms,
+            ts_ms: session.ts_ms,
+            sig: session.sig,
+        }
+    }
+}
+
+impl From<SessionEncoding> for Session {
+    fn from(encoding: SessionEncoding) -> Self {
+        let (account, capabilities) = match encoding.account {
+            SessionAccount::Secp256k1(did) => (Account::Secp256k1(did), vec![]),
+            SessionAccount::Secp256r1(pk) => (Account::Secp256r1(pk), vec![]),
+            SessionAccount::EIP191(did) => (Account::EIP191(did), vec![]),
+            SessionAccount::BIP137(did) => (Account::BIP137(did), vec![]),
+            SessionAccount::Ed25519(pk) => (Account::Ed25519(pk), vec![]),
+            SessionAccount::BLS(pk) => (Account::BLS(pk), vec![]),
+            SessionAccount::WebAuthn(pk) => (Account::WebAuthn(pk), vec![]),
+            SessionAccount::Restricted(account, capabilities) => (account, capabilities),
+        };
+        Self {
+            session_id: encoding.session_id,
+            account,
+            ttl_ms: encoding.ttl_ms,
+            ts_ms: encoding.ts_ms,
+            sig: encoding.sig,
+            capabilities,
+        }
+    }
+}
+
+/// Capability restricts what a [Session] is permitted to do.
+///
+/// In the unsigned proof, each capability is a line of string:
+/// - `custom_message` or `custom_message:<did>,<did>,...`
+/// - `read_dht`
+/// - `write_dht`
+/// - `service:<name>`
+///
+/// Messages for maintaining the DHT, like connecting and stabilization, are always permitted.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone)]
+pub enum Capability {
+    /// Send CustomMessage to the destinations, or to anyone if empty.
+    CustomMessage(Vec<Did>),
+    /// Search virtual nodes in DHT.
+    ReadDht,
+    /// Operate virtual nodes in DHT, including syncing them to successor.
+    WriteDht,
+    /// Access the service of name provided by remote nodes.
+    Service(String),
 }
 
 /// We will support as many protocols/algorithms as possible.
-/// Currently, it comprises Secp256k1, EIP191, BIP137, and Ed25519.
+/// Currently, it comprises Secp256k1, Secp256r1, EIP191, BIP137, Ed25519, BLS and WebAuthn.
 /// We welcome any issues and PRs for additional implementations.
 #[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone)]
 pub enum Account {
@@ -164,6 +292,11 @@ pub enum Account {
     BIP137(Did),
     /// ed25519
     Ed25519(PublicKey<33>),
+    /// bls12-381, the public key is a compressed G1 point
+    BLS(PublicKey<48>),
+    /// passkey of P-256, which signs with WebAuthn assertion
+    /// ref: <https://www.w3.org/TR/webauthn-2/>
+    WebAuthn(PublicKey<33>),
 }
 
 impl TryFrom<(String, String)> for Account {
@@ -172,17 +305,52 @@ impl TryFrom<(String, String)> for Account {
     fn try_from((account_entity, account_type): (String, String)) -> Result<Self> {
         match account_type.as_str() {
             "secp256k1" => Ok(Account::Secp256k1(Did::from_str(&account_entity)?)),
-            "secp256r1" => Ok(Account::Secp256r1(PublicKey::from_hex_string(
+            "secp256r1" => Ok(Account::Secp256r1(PublicKey::<33>::from_hex_string(
                 &account_entity,
             )?)),
             "eip191" => Ok(Account::EIP191(Did::from_str(&account_entity)?)),
             "bip137" => Ok(Account::BIP137(Did::from_str(&account_entity)?)),
             "ed25519" => Ok(Account::Ed25519(PublicKey::try_from_b58t(&account_entity)?)),
+            "bls" => Ok(Account::BLS(PublicKey::<48>::from_hex_string(
+                &account_entity,
+            )?)),
+            "webauthn" => Ok(Account::WebAuthn(PublicKey::<33>::from_hex_string(
+                &account_entity,
+            )?)),
             _ => Err(Error::UnknownAccount),
         }
     }
 }
 
+impl Account {
+    /// The (account_entity, account_type) pair of account, which is the inverse of
+    /// `Account::try_from`.
+    pub fn to_entity_and_type(&self) -> Result<(String, String)> {
+        Ok(match self {
+            Account::Secp256k1(did) => (did.to_string(), "secp256k1".to_string()),
+            Account::Secp256r1(pk) => (hex::encode(pk.0), "secp256r1".to_string()),
+            Account::EIP191(did) => (did.to_string(), "eip191".to_string()),
+            Account::BIP137(did) => (did.to_string(), "bip137".to_string()),
+            Account::Ed25519(pk) => (pk.to_base58_string()?, "ed25519".to_string()),
+            Account::BLS(pk) => (hex::encode(pk.0), "bls".to_string()),
+            Account::WebAuthn(pk) => (hex::encode(pk.0), "webauthn".to_string()),
+        })
+    }
+
+    /// Did of account.
+    pub fn did(&self) -> Did {
+        match self {
+            Account::Secp256k1(did) => *did,
+            Account::BIP137(did) => *did,
+            Account::EIP191(did) => *did,
+            Account::Ed25519(pk) => pk.address().into(),
+            Account::Secp256r1(pk) => pk.address().into(),
+            Account::BLS(pk) => pk.address().into(),
+            Account::WebAuthn(pk) => pk.address().into(),
+        }
+    }
+}
+
 // A SessionSk can be converted to a string using JSON and then encoded with base58.
 // To load the SessionSk from a string, use `SessionSk::from_str`.
 impl FromStr for SessionSk {
@@ -209,6 +377,7 @@ impl SessionSkBuilder {
             ttl_ms: DEFAULT_SESSION_TTL_MS,
             ts_ms: utils::get_epoch_ms(),
             sig: vec![],
+            capabilities: vec![],
         }
     }
 
@@ -224,7 +393,23 @@ impl SessionSkBuilder {
 
     /// Construct unsigned_info string for signing.
     pub fn unsigned_proof(&self) -> String {
-        pack_session(self.sk.address().into(), self.ts_ms, self.ttl_ms)
+        pack_session(
+            self.sk.address().into(),
+            self.ts_ms,
+            self.ttl_ms,
+            &self.capabilities,
+        )
+    }
+
+    /// Create a builder of a new session key for the same account and lifetime of `session_sk`.
+    /// It's used to renew a session before it expires, the account still needs to sign
+    /// the new unsigned proof.
+    pub fn renew(session_sk: &SessionSk) -> Result<SessionSkBuilder> {
+        let session = &session_sk.session;
+        let (account_entity, account_type) = session.account.to_entity_and_type()?;
+        let mut builder = Self::new(account_entity, account_type).set_ttl(session.ttl_ms);
+        builder.capabilities = session.capabilities.clone();
+        Ok(builder)
     }
 
     /// Set the signature of session that signed by account.
@@ -233,6 +418,31 @@ impl SessionSkBuilder {
         self
     }
 
+    /// Set the signature of session by the assertion of a WebAuthn account.
+    /// The fields are those of `AuthenticatorAssertionResponse`, which is returned by
+    /// `navigator.credentials.get` with `unsigned_proof` as challenge.
+    pub fn set_webauthn_assertion(
+        mut self,
+        authenticator_data: Vec<u8>,
+        client_data_json: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<SessionSkBuilder> {
+        self.sig = signers::webauthn::Assertion {
+            authenticator_data,
+            client_data_json,
+            signature,
+        }
+        .to_bytes()?;
+        Ok(self)
+    }
+
+    /// Restrict the session to a capability, in the string form of [Capability].
+    /// It should be called before `unsigned_proof`.
+    pub fn add_capability(mut self, capability: String) -> Result<SessionSkBuilder> {
+        self.capabilities.push(Capability::from_str(&capability)?);
+        Ok(self)
+    }
+
     /// Set the lifetime of session.
     pub fn set_ttl(mut self, ttl_ms: u64) -> Self {
         self.ttl_ms = ttl_ms;
@@ -248,6 +458,7 @@ impl SessionSkBuilder {
             ttl_ms: self.ttl_ms,
             ts_ms: self.ts_ms,
             sig: self.sig,
+            capabilities: self.capabilities,
         };
 
         session.verify_self()?;
@@ -259,10 +470,55 @@ impl SessionSkBuilder {
     }
 }
 
+/// AccountSigner signs the unsigned proofs for an [Account], so that the account key can be
+/// kept outside of this program, for example in a hardware token or a separate signer process.
+#[cfg_attr(feature = "wasm", async_trait(?Send))]
+#[cfg_attr(not(feature = "wasm"), async_trait)]
+pub trait AccountSigner {
+    /// The (account_entity, account_type) pair of account, see [SessionSkBuilder::new].
+    async fn account(&self) -> Result<(String, String)>;
+
+    /// Sign the unsigned proof, returns the signature of account.
+    async fn sign(&self, proof: &str) -> Result<Vec<u8>>;
+}
+
+/// Sign as a secp256k1 account by the secret key in memory.
+#[cfg_attr(feature = "wasm", async_trait(?Send))]
+#[cfg_attr(not(feature = "wasm"), async_trait)]
+impl AccountSigner for SecretKey {
+    async fn account(&self) -> Result<(String, String)> {
+        Ok((
+            Did::from(self.address()).to_string(),
+            "secp256k1".to_string(),
+        ))
+    }
+
+    async fn sign(&self, proof: &str) -> Result<Vec<u8>> {
+        Ok(SecretKey::sign(self, proof).to_vec())
+    }
+}
+
+impl SessionSkBuilder {
+    /// Create a new SessionSkBuilder for the account of signer.
+    pub async fn from_signer<S>(signer: &S) -> Result<SessionSkBuilder>
+    where S: AccountSigner + ?Sized {
+        let (account_entity, account_type) = signer.account().await?;
+        Ok(Self::new(account_entity, account_type))
+    }
+
+    /// Sign the unsigned proof by signer and set the signature.
+    /// It should be called after the lifetime and capabilities are set.
+    pub async fn sign_by<S>(self, signer: &S) -> Result<SessionSkBuilder>
+    where S: AccountSigner + ?Sized {
+        let sig = signer.sign(&self.unsigned_proof()).await?;
+        Ok(self.set_session_sig(sig))
+    }
+}
+
 impl Session {
     /// Pack the session into a string for verification or public key recovery.
     pub fn pack(&self) -> Vec<u8> {
-        pack_session(self.session_id, self.ts_ms, self.ttl_ms)
+        pack_session(self.session_id, self.ts_ms, self.ttl_ms, &self.capabilities)
             .as_bytes()
             .to_vec()
     }
@@ -270,7 +526,27 @@ impl Session {
     /// Check session is expired or not.
     pub fn is_expired(&self) -> bool {
         let now = utils::get_epoch_ms();
-        now > self.ts_ms + self.ttl_ms as u128
+        now > self.expires_at()
+    }
+
+    /// Timestamp in milliseconds when session expires.
+    pub fn expires_at(&self) -> u128 {
+        self.ts_ms + self.ttl_ms as u128
+    }
+
+    /// Lifetime of session in milliseconds.
+    pub fn ttl_ms(&self) -> u64 {
+        self.ttl_ms
+    }
+
+    /// Did of session, which is the address of session key.
+    pub fn session_id(&self) -> Did {
+        self.session_id
+    }
+
+    /// Get the account of session.
+    pub fn account(&self) -> &Account {
+        &self.account
     }
 
     /// Verify session.
@@ -279,21 +555,7 @@ impl Session {
             return Err(Error::SessionExpired);
         }
 
-        let auth_bytes = self.pack();
-
-        if !(match self.account {
-            Account::Secp256k1(did) => {
-                signers::secp256k1::verify(&auth_bytes, &did.into(), &self.sig)
-            }
-            Account::EIP191(did) => signers::eip191::verify(&auth_bytes, &did.into(), &self.sig),
-            Account::BIP137(did) => signers::bip137::verify(&auth_bytes, &did.into(), &self.sig),
-            Account::Ed25519(ref pk) => {
-                signers::ed25519::verify(&auth_bytes, &pk.address(), &self.sig, pk)
-            }
-            Account::Secp256r1(ref pk) => {
-                signers::secp256r1::verify(&auth_bytes, &pk.address(), &self.sig, pk)
-            }
-        }) {
+        if !verify_account_sig(&self.account, &self.pack(), &self.sig) {
             return Err(Error::VerifySignatureFailed);
         }
 
@@ -318,17 +580,87 @@ impl Session {
             Account::EIP191(_) => signers::eip191::recover(&auth_bytes, &self.sig),
             Account::Ed25519(ref pk) => Ok(*pk),
             Account::Secp256r1(ref pk) => Ok(*pk),
+            Account::BLS(_) => Err(Error::BlsAccountNoEcdsaKey),
+            Account::WebAuthn(ref pk) => Ok(*pk),
         }
     }
 
     /// Get account did.
     pub fn account_did(&self) -> Did {
-        match self.account {
-            Account::Secp256k1(did) => did,
-            Account::BIP137(did) => did,
-            Account::EIP191(did) => did,
-            Account::Ed25519(ref pk) => pk.address().into(),
-            Account::Secp256r1(ref pk) => pk.address().into(),
+        self.account.did()
+    }
+
+    /// Capabilities that the session is restricted to.
+    pub fn capabilities(&self) -> &[Capability] {
+        &self.capabilities
+    }
+
+    /// Check if the session is restricted by capabilities.
+    pub fn is_restricted(&self) -> bool {
+        !self.capabilities.is_empty()
+    }
+
+    /// Check if the session is permitted to send CustomMessage to destination.
+    pub fn permits_custom_message(&self, destination: Did) -> bool {
+        !self.is_restricted()
+            || self.capabilities.iter().any(|c| match c {
+                Capability::CustomMessage(dids) => dids.is_empty() || dids.contains(&destination),
+                _ => false,
+            })
+    }
+
+    /// Check if the session is permitted to access the service of name.
+    pub fn permits_service(&self, name: &str) -> bool {
+        !self.is_restricted()
+            || self
+                .capabilities
+                .iter()
+                .any(|c| matches!(c, Capability::Service(s) if s == name))
+    }
+
+    /// Check if the session has the capability, or is unrestricted.
+    pub fn permits(&self, capability: &Capability) -> bool {
+        !self.is_restricted() || self.capabilities.contains(capability)
+    }
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Capability::CustomMessage(dids) if dids.is_empty() => write!(f, "custom_message"),
+            Capability::CustomMessage(dids) => {
+                let dids = dids.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+                write!(f, "custom_message:{}", dids.join(","))
+            }
+            Capability::ReadDht => write!(f, "read_dht"),
+            Capability::WriteDht => write!(f, "write_dht"),
+            Capability::Service(name) => write!(f, "service:{}", name),
+        }
+    }
+}
+
+impl FromStr for Capability {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, arg) = match s.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg)),
+            None => (s, None),
+        };
+        match (kind, arg) {
+            ("custom_message", None) => Ok(Capability::CustomMessage(vec![])),
+            ("custom_message", Some(dids)) => Ok(Capability::CustomMessage(
+                dids.split(',')
+                    .map(Did::from_str)
+                    .collect::<Result<Vec<_>>>()?,
+            )),
+            ("read_dht", None) => Ok(Capability::ReadDht),
+            ("write_dht", None) => Ok(Capability::WriteDht),
+            // Service name is one line, otherwise it can forge other capabilities in proof.
+            ("service", Some(name)) if !name.is_empty() && !name.contains('\n') => {
+                Ok(Capability::Service(name.to_string()))
+            }
+            _ => Err(Error::InvalidCapability(s.to_string())),
         }
     }
 }
@@ -353,6 +685,11 @@ impl SessionSk {
         self.session.clone()
     }
 
+    /// Public key of session, the address of which is the session id.
+    pub fn pubkey(&self) -> PublicKey<33> {
+        self.sk.pubkey()
+    }
+
     /// Sign message with session.
     pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
         let key = self.sk;
@@ -360,11 +697,21 @@ impl SessionSk {
         Ok(signers::secp256k1::sign(key, &h).to_vec())
     }
 
+    /// Open data sealed to public key of session.
+    pub fn unseal(&self, sealed: &SealedData) -> Result<Vec<u8>> {
+        sealed.open(&self.sk)
+    }
+
     /// Get account did from session.
     pub fn account_did(&self) -> Did {
         self.session.account_did()
     }
 
+    /// Timestamp in milliseconds when session expires.
+    pub fn expires_at(&self) -> u128 {
+        self.session.expires_at()
+    }
+
     /// Dump session_sk to string, allowing user to save it in a config file.
     /// It can be restored using `SessionSk::from_str`.
     pub fn dump(&self) -> Result<String> {
@@ -402,4 +749,266 @@ mod test {
         let sm2 = SessionSk::from_str(&dump).unwrap();
         assert_eq!(sm, sm2);
     }
+
+    #[test]
+    pub fn test_renew() {
+        let key = SecretKey::random();
+        let sm = SessionSk::new_with_seckey(&key).unwrap();
+
+        let builder = SessionSkBuilder::renew(&sm).unwrap();
+        let sig = key.sign(&builder.unsigned_proof());
+        let renewed = builder.set_session_sig(sig.to_vec()).build().unwrap();
+
+        assert_eq!(renewed.account_did(), sm.account_did());
+        assert_eq!(renewed.session().ttl_ms(), sm.session().ttl_ms());
+        assert_ne!(renewed.session().session_id(), sm.session().session_id());
+
+        // Account of other types is restored from its entity and type.
+        for account in [
+            Account::Secp256r1(key.pubkey()),
+            Account::Ed25519(key.pubkey()),
+            Account::BLS(signers::bls::public_key(&signers::bls::random_sk().unwrap()).unwrap()),
+            Account::WebAuthn(key.pubkey()),
+        ] {
+            let (entity, ty) = account.to_entity_and_type().unwrap();
+            assert_eq!(Account::try_from((entity, ty)).unwrap(), account);
+        }
+    }
+
+    #[test]
+    pub fn test_bls_session() {
+        let key = signers::bls::random_sk().unwrap();
+        let pk = signers::bls::public_key(&key).unwrap();
+
+        let builder = SessionSkBuilder::new(hex::encode(pk.0), "bls".to_string());
+        let sig = signers::bls::sign(key, builder.unsigned_proof().as_bytes()).unwrap();
+        let sm = builder.set_session_sig(sig.0.to_vec()).build().unwrap();
+
+        let session = sm.session();
+        assert_eq!(session.account(), &Account::BLS(pk));
+        assert_eq!(session.account_did(), Did::from(pk.address()));
+        assert!(session.account_pubkey().is_err());
+
+        // Session is restored from dump with BLS account.
+        let sm2 = SessionSk::from_str(&sm.dump().unwrap()).unwrap();
+        assert_eq!(sm, sm2);
+
+        // Signature of other BLS key is rejected.
+        let other = signers::bls::random_sk().unwrap();
+        let builder = SessionSkBuilder::new(hex::encode(pk.0), "bls".to_string());
+        let sig = signers::bls::sign(other, builder.unsigned_proof().as_bytes()).unwrap();
+        assert!(builder.set_session_sig(sig.0.to_vec()).build().is_err());
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[tokio::test]
+    async fn test_session_from_signer() {
+        let key = SecretKey::random();
+        let sm = SessionSkBuilder::from_signer(&key)
+            .await
+            .unwrap()
+            .set_ttl(1000 * 60)
+            .sign_by(&key)
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(sm.account_did(), Did::from(key.address()));
+        assert_eq!(sm.session().ttl_ms(), 1000 * 60);
+
+        // Signature of other signer is rejected.
+        let other = SecretKey::random();
+        let builder = SessionSkBuilder::from_signer(&key)
+            .await
+            .unwrap()
+            .sign_by(&other)
+            .await
+            .unwrap();
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    pub fn test_restricted_session() {
+        use crate::message::CustomMessage;
+        use crate::message::Message;
+        use crate::message::MessageVerification;
+        use crate::message::SearchVNode;
+
+        let key = SecretKey::random();
+        let friend: Did = SecretKey::random().address().into();
+        let stranger: Did = SecretKey::random().address().into();
+
+        let builder = SessionSkBuilder::new(
+            Did::from(key.address()).to_string(),
+            "secp256k1".to_string(),
+        )
+        .add_capability(format!("custom_message:{}", friend))
+        .unwrap()
+        .add_capability("read_dht".to_string())
+        .unwrap();
+        assert!(builder
+            .capabilities
+            .iter()
+            .all(|c| Capability::from_str(&c.to_string()).unwrap() == *c));
+        let proof = builder.unsigned_proof();
+        assert!(proof.ends_with(&format!("\ncustom_message:{}\nread_dht", friend)));
+
+        let sig = key.sign(&proof);
+        let sm = builder.set_session_sig(sig.to_vec()).build().unwrap();
+        let session = sm.session();
+        assert!(session.is_restricted());
+        assert!(session.permits_custom_message(friend));
+        assert!(!session.permits_custom_message(stranger));
+        assert!(!session.permits_service("ipfs"));
+
+        let custom = Message::CustomMessage(CustomMessage(vec![1, 2, 3]));
+        let search = Message::SearchVNode(SearchVNode { vid: stranger });
+        let verification = MessageVerification::new(b"data", &sm).unwrap();
+        assert!(verification.permits(&custom, friend, None));
+        assert!(!verification.permits(&custom, stranger, None));
+        assert!(verification.permits(&search, stranger, None));
+
+        // Session of a service only sends CustomMessage accessing that service.
+        let builder = SessionSkBuilder::new(
+            Did::from(key.address()).to_string(),
+            "secp256k1".to_string(),
+        )
+        .add_capability("service:ipfs".to_string())
+        .unwrap();
+        let sig = key.sign(&builder.unsigned_proof());
+        let service_sm = builder.set_session_sig(sig.to_vec()).build().unwrap();
+        let verification = MessageVerification::new(b"data", &service_sm).unwrap();
+        assert!(verification.permits(&custom, stranger, Some("ipfs")));
+        assert!(!verification.permits(&custom, stranger, Some("web")));
+        assert!(!verification.permits(&custom, stranger, None));
+        assert!(!verification.permits(&search, stranger, None));
+
+        // Capabilities are signed by account, so they can't be dropped.
+        let mut forged = session.clone();
+        forged.capabilities.clear();
+        assert!(forged.verify_self().is_err());
+
+        // Renewed session keeps the capabilities.
+        let builder = SessionSkBuilder::renew(&sm).unwrap();
+        let sig = key.sign(&builder.unsigned_proof());
+        let renewed = builder.set_session_sig(sig.to_vec()).build().unwrap();
+        assert_eq!(renewed.session().capabilities(), session.capabilities());
+
+        // Capability in bad format is rejected.
+        for capability in [
+            "write_dht:x",
+            "service:",
+            "service:a\nread_dht",
+            "custom_message:x",
+        ] {
+            assert!(Capability::from_str(capability).is_err());
+        }
+    }
+
+    #[test]
+    pub fn test_session_encoding() {
+        // The encoding of session before capabilities were introduced.
+        #[derive(Deserialize, Serialize)]
+        struct LegacySession {
+            session_id: Did,
+            account: Account,
+            ttl_ms: u64,
+            ts_ms: u128,
+            sig: Vec<u8>,
+        }
+
+        let key = SecretKey::random();
+        let session = SessionSk::new_with_seckey(&key).unwrap().session();
+        let legacy = LegacySession {
+            session_id: session.session_id,
+            account: session.account.clone(),
+            ttl_ms: session.ttl_ms,
+            ts_ms: session.ts_ms,
+            sig: session.sig.clone(),
+        };
+
+        // Unrestricted session is encoded as before.
+        let bytes = bincode::serialize(&session).unwrap();
+        assert_eq!(bytes, bincode::serialize(&legacy).unwrap());
+        assert_eq!(bincode::deserialize::<Session>(&bytes).unwrap(), session);
+        assert_eq!(
+            serde_json::to_string(&session).unwrap(),
+            serde_json::to_string(&legacy).unwrap()
+        );
+
+        // Restricted session can't be decoded as an unrestricted one.
+        let builder = SessionSkBuilder::new(
+            Did::from(key.address()).to_string(),
+            "secp256k1".to_string(),
+        )
+        .add_capability("read_dht".to_string())
+        .unwrap();
+        let sig = key.sign(&builder.unsigned_proof());
+        let restricted = builder.set_session_sig(sig.to_vec()).build().unwrap();
+        let bytes = bincode::serialize(&restricted.session()).unwrap();
+        assert!(bincode::deserialize::<LegacySession>(&bytes).is_err());
+        assert_eq!(
+            bincode::deserialize::<Session>(&bytes).unwrap(),
+            restricted.session()
+        );
+        let json = serde_json::to_string(&restricted.session()).unwrap();
+        assert!(serde_json::from_str::<LegacySession>(&json).is_err());
+        assert_eq!(
+            serde_json::from_str::<Session>(&json).unwrap(),
+            restricted.session()
+        );
+    }
+
+    #[test]
+    pub fn test_webauthn_session() {
+        use sha2::Digest;
+
+        // P-256 key of the fixed vectors in signers::secp256r1.
+        let pk = PublicKey::<33>::from_hex_string("17a6afd392fcbe4ac9270a599a9c5732c4f838ce35ea2234d389d8f0c367f3f5dcab906352e27289002c7f2c96039ddce7c1b5aad8b87ba94984d4c8b4f95702").unwrap();
+        let sk =
+            SecretKey::try_from("2544acda37415a476d42312969926dc48e529867036cec71922d4177ea9c1038")
+                .unwrap();
+        let authenticator_data = hex::decode(
+            "49960de5880e8c687434170f6476605b8fe4aeb9a28632c7995cf3ba831d97630500000001",
+        )
+        .unwrap();
+
+        // Act as authenticator to sign the unsigned proof as challenge.
+        let sign_assertion = |builder: SessionSkBuilder, ty: &str| {
+            let challenge =
+                base64::encode_config(builder.unsigned_proof(), base64::URL_SAFE_NO_PAD);
+            let client_data_json = format!(
+                r#"{{"type":"{}","challenge":"{}","origin":"http://localhost:8080"}}"#,
+                ty, challenge
+            )
+            .into_bytes();
+            let mut signed = authenticator_data.clone();
+            signed.extend_from_slice(&sha2::Sha256::digest(&client_data_json));
+            let raw = signers::secp256r1::sign(sk, &sha2::Sha256::digest(&signed).into());
+            let signature = p256::ecdsa::Signature::from_slice(&raw)
+                .unwrap()
+                .to_der()
+                .as_bytes()
+                .to_vec();
+            builder
+                .set_webauthn_assertion(authenticator_data.clone(), client_data_json, signature)
+                .unwrap()
+                .build()
+        };
+
+        let builder = SessionSkBuilder::new(hex::encode(pk.0), "webauthn".to_string());
+        let sm = sign_assertion(builder, "webauthn.get").unwrap();
+        let session = sm.session();
+        assert_eq!(session.account(), &Account::WebAuthn(pk));
+        assert_eq!(session.account_did(), Did::from(pk.address()));
+        assert_eq!(session.account_pubkey().unwrap(), pk);
+
+        // Session is restored from dump with WebAuthn account.
+        let sm2 = SessionSk::from_str(&sm.dump().unwrap()).unwrap();
+        assert_eq!(sm, sm2);
+
+        // Assertion of credential creation is rejected.
+        let builder = SessionSkBuilder::new(hex::encode(pk.0), "webauthn".to_string());
+        assert!(sign_assertion(builder, "webauthn.create").is_err());
+    }
 }
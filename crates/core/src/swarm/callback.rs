@@ -10,11 +10,13 @@ use crate::chunk::ChunkList;
 use crate::chunk::ChunkManager;
 use crate::consts::TRANSPORT_MTU;
 use crate::dht::Did;
+use crate::message::CustomMessage;
 use crate::message::HandleMsg;
 use crate::message::Message;
 use crate::message::MessageHandler;
 use crate::message::MessagePayload;
 use crate::message::MessageVerificationExt;
+use crate::session::Session;
 use crate::swarm::transport::SwarmTransport;
 
 type CallbackError = Box<dyn std::error::Error>;
@@ -38,6 +40,13 @@ pub enum SwarmEvent {
         /// The final state of the connection.
         state: WebrtcConnectionState,
     },
+    /// Indicates that a connected peer renewed its session.
+    SessionRenewed {
+        /// The did of remote peer.
+        peer: Did,
+        /// The new session of remote peer.
+        session: Session,
+    },
 }
 
 /// Any object that implements this trait can be used as a callback for the swarm.
@@ -59,6 +68,17 @@ pub trait SwarmCallback {
     async fn on_event(&self, _event: &SwarmEvent) -> Result<(), CallbackError> {
         Ok(())
     }
+
+    /// Name of the service that a CustomMessage accesses, if any. It's invoked before
+    /// validation when the sender session is restricted, a CustomMessage beyond
+    /// `custom_message` capabilities is only permitted by the capability of this service.
+    fn custom_message_service(
+        &self,
+        _payload: &MessagePayload,
+        _msg: &CustomMessage,
+    ) -> Option<String> {
+        None
+    }
 }
 
 /// [InnerSwarmCallback] wraps [SharedSwarmCallback] with inner handling for a specific connection.
@@ -114,6 +134,7 @@ impl InnerSwarmCallback {
             Message::QueryForTopoInfoReport(ref msg) => {
                 self.message_handler.handle(payload, msg).await
             }
+            Message::SessionRenewal(ref msg) => self.message_handler.handle(payload, msg).await,
             Message::Chunk(ref msg) => {
                 if let Some(data) = self.chunk_list.lock().await.handle(msg.clone()) {
                     return self.on_message(cid, &data).await;
@@ -142,6 +163,10 @@ impl TransportCallback for InnerSwarmCallback {
             tracing::error!("Cannot verify msg or it's expired: {:?}", payload);
             return Err("Cannot verify msg or it's expired".into());
         }
+        self.transport.check_revocation(&payload)?;
+        payload
+            .transaction
+            .check_capabilities(|msg| self.callback.custom_message_service(&payload, msg))?;
         self.callback.on_validate(&payload).await?;
         self.handle_payload(cid, &payload).await
     }
@@ -24,6 +24,9 @@ use crate::message::Message;
 use crate::message::MessagePayload;
 use crate::message::MessageVerificationExt;
 use crate::message::PayloadSender;
+use crate::message::SessionRenewal;
+use crate::revocation::RevocationList;
+use crate::session::SessionSk;
 use crate::swarm::callback::SharedSwarmCallback;
 use crate::swarm::transport::SwarmTransport;
 
@@ -118,6 +121,34 @@ impl Swarm {
     pub async fn inspect(&self) -> SwarmInspect {
         SwarmInspect::inspect(self).await
     }
+
+    /// Get the session sk currently used to sign messages.
+    pub fn session_sk(&self) -> Result<SessionSk> {
+        self.transport.session_sk()
+    }
+
+    /// Get the local cache of session revocations. Messages signed by the revoked sessions
+    /// are rejected.
+    pub fn revocations(&self) -> &RevocationList {
+        &self.transport.revocations
+    }
+
+    /// Replace the session sk with a renewed one of the same account, then announce the new
+    /// session to connected peers by [SessionRenewal]. Connections are bound to the account did,
+    /// so they are kept as they are. Peers failed to be notified are logged and skipped.
+    pub async fn rotate_session(&self, session_sk: SessionSk) -> Result<()> {
+        let previous = self.transport.session_sk()?.session().session_id();
+        self.transport.set_session_sk(session_sk)?;
+        tracing::info!("session {} of {} is renewed", previous, self.did());
+
+        let msg = Message::SessionRenewal(SessionRenewal { previous });
+        for peer in self.transport.get_connection_ids() {
+            if let Err(e) = self.transport.send_direct_message(msg.clone(), peer).await {
+                tracing::warn!("failed to announce session renewal to {}: {:?}", peer, e);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Swarm {
@@ -133,7 +164,7 @@ impl Swarm {
         // The invoker should fix it before sending.
         let payload = MessagePayload::new_send(
             Message::ConnectNodeSend(offer_msg),
-            self.transport.session_sk(),
+            &self.transport.session_sk()?,
             self.did(),
             peer,
         )?;
@@ -147,6 +178,7 @@ impl Swarm {
         if !offer_payload.verify() {
             return Err(Error::VerifySignatureFailed);
         }
+        self.transport.check_revocation(&offer_payload)?;
 
         let Message::ConnectNodeSend(msg) = offer_payload.transaction.data()? else {
             return Err(Error::InvalidMessage(
@@ -164,7 +196,7 @@ impl Swarm {
         // The invoker should fix it before sending.
         let answer_payload = MessagePayload::new_send(
             Message::ConnectNodeReport(answer_msg),
-            self.transport.session_sk(),
+            &self.transport.session_sk()?,
             self.did(),
             self.did(),
         )?;
@@ -178,6 +210,7 @@ impl Swarm {
         if !answer_payload.verify() {
             return Err(Error::VerifySignatureFailed);
         }
+        self.transport.check_revocation(&answer_payload)?;
 
         let Message::ConnectNodeReport(ref msg) = answer_payload.transaction.data()? else {
             return Err(Error::InvalidMessage(
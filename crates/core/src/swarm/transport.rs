@@ -1,5 +1,6 @@
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::RwLock;
 
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -30,19 +31,23 @@ use crate::dht::PeerRing;
 use crate::error::Error;
 use crate::error::Result;
 use crate::measure::MeasureImpl;
+use crate::message::AggregateVerification;
 use crate::message::ConnectNodeReport;
 use crate::message::ConnectNodeSend;
 use crate::message::Message;
 use crate::message::MessagePayload;
+use crate::message::MessageVerificationExt;
 use crate::message::PayloadSender;
+use crate::revocation::RevocationList;
 use crate::session::SessionSk;
 use crate::swarm::callback::InnerSwarmCallback;
 
 pub struct SwarmTransport {
     pub(crate) network_id: u32,
     transport: Transport,
-    session_sk: SessionSk,
+    session_sk: RwLock<SessionSk>,
     pub(crate) dht: Arc<PeerRing>,
+    pub(crate) revocations: RevocationList,
     #[allow(dead_code)]
     measure: Option<MeasureImpl>,
 }
@@ -65,12 +70,57 @@ impl SwarmTransport {
         Self {
             network_id,
             transport: Transport::new(ice_servers, external_address),
-            session_sk,
+            session_sk: RwLock::new(session_sk),
             dht,
+            revocations: RevocationList::default(),
             measure,
         }
     }
 
+    /// Replace the session sk used to sign messages. The new session should be valid and
+    /// delegated by the same account, since the did of node is the account did.
+    pub fn set_session_sk(&self, session_sk: SessionSk) -> Result<()> {
+        let session = session_sk.session();
+        session.verify_self()?;
+        if self.revocations.is_revoked(&session) {
+            return Err(Error::SessionRevoked(session.session_id()));
+        }
+        if session_sk.account_did() != self.dht.did {
+            return Err(Error::SessionAccountMismatch(
+                session_sk.account_did(),
+                self.dht.did,
+            ));
+        }
+        *self
+            .session_sk
+            .write()
+            .map_err(|_| Error::SessionTryLockFailed)? = session_sk;
+        Ok(())
+    }
+
+    /// Check neither the payload nor its transaction is signed by a revoked session.
+    pub fn check_revocation(&self, payload: &MessagePayload) -> Result<()> {
+        for session in [
+            &payload.verification().session,
+            &payload.transaction.verification().session,
+        ] {
+            if self.revocations.is_revoked(session) {
+                return Err(Error::SessionRevoked(session.session_id()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check none of the co-signers signed by a revoked session.
+    pub fn check_cosigners(&self, cosignature: &AggregateVerification) -> Result<()> {
+        for session in cosignature.sessions.iter() {
+            if self.revocations.is_revoked(session) {
+                return Err(Error::SessionRevoked(session.session_id()));
+            }
+        }
+        Ok(())
+    }
+
     /// Create new connection that will be handled by swarm.
     pub async fn new_connection(&self, peer: Did, callback: InnerSwarmCallback) -> Result<()> {
         if peer == self.dht.did {
@@ -279,8 +329,12 @@ impl SwarmConnection {
 #[cfg_attr(feature = "wasm", async_trait(?Send))]
 #[cfg_attr(not(feature = "wasm"), async_trait)]
 impl PayloadSender for SwarmTransport {
-    fn session_sk(&self) -> &SessionSk {
-        &self.session_sk
+    fn session_sk(&self) -> Result<SessionSk> {
+        Ok(self
+            .session_sk
+            .read()
+            .map_err(|_| Error::SessionTryLockFailed)?
+            .clone())
     }
 
     fn dht(&self) -> Arc<PeerRing> {
@@ -314,10 +368,10 @@ impl PayloadSender for SwarmTransport {
 
         let result = if data.len() > TRANSPORT_MTU {
             let chunks = ChunkList::<TRANSPORT_MTU>::from(&data);
+            let session_sk = self.session_sk()?;
             for chunk in chunks {
-                let data =
-                    MessagePayload::new_send(Message::Chunk(chunk), &self.session_sk, did, did)?
-                        .to_bincode()?;
+                let data = MessagePayload::new_send(Message::Chunk(chunk), &session_sk, did, did)?
+                    .to_bincode()?;
                 conn.send_data(data).await?;
             }
             Ok(())
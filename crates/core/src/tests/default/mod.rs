@@ -23,6 +23,7 @@ use crate::swarm::SwarmBuilder;
 
 mod test_connection;
 mod test_message_handler;
+mod test_session;
 mod test_stabilization;
 
 pub struct Node {
@@ -0,0 +1,105 @@
+use crate::ecc::SecretKey;
+use crate::error::Error;
+use crate::message::Message;
+use crate::message::MessageVerificationExt;
+use crate::message::PayloadSender;
+use crate::revocation::SessionRevocationBuilder;
+use crate::session::SessionSk;
+use crate::session::SessionSkBuilder;
+use crate::tests::default::assert_no_more_msg;
+use crate::tests::default::prepare_node;
+use crate::tests::default::wait_for_msgs;
+use crate::tests::manually_establish_connection;
+
+#[tokio::test]
+async fn test_rotate_session() {
+    let key1 = SecretKey::random();
+    let key2 = SecretKey::random();
+    let node1 = prepare_node(key1).await;
+    let node2 = prepare_node(key2).await;
+
+    manually_establish_connection(&node1.swarm, &node2.swarm).await;
+    wait_for_msgs([&node1, &node2]).await;
+    assert_no_more_msg([&node1, &node2]).await;
+
+    // Session of other account is rejected.
+    let other = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+    assert!(matches!(
+        node1.swarm.rotate_session(other).await,
+        Err(Error::SessionAccountMismatch(_, _))
+    ));
+
+    let previous = node1.swarm.session_sk().unwrap();
+    let builder = SessionSkBuilder::renew(&previous).unwrap();
+    let sig = key1.sign(&builder.unsigned_proof());
+    let renewed = builder.set_session_sig(sig.to_vec()).build().unwrap();
+    node1.swarm.rotate_session(renewed.clone()).await.unwrap();
+    assert_eq!(node1.swarm.session_sk().unwrap(), renewed);
+
+    // The renewal is announced to connected peer.
+    let payload = node2.listen_once().await.unwrap();
+    let Message::SessionRenewal(msg) = payload.transaction.data::<Message>().unwrap() else {
+        panic!("expect SessionRenewal");
+    };
+    assert_eq!(msg.previous, previous.session().session_id());
+    assert_eq!(payload.transaction.signer(), node1.did());
+    assert_eq!(payload.transaction.verification.session, renewed.session());
+
+    // The connection survives and messages are signed by the renewed session.
+    node1
+        .swarm
+        .transport
+        .send_direct_message(Message::custom(b"hello").unwrap(), node2.did())
+        .await
+        .unwrap();
+    let payload = node2.listen_once().await.unwrap();
+    assert!(matches!(
+        payload.transaction.data::<Message>().unwrap(),
+        Message::CustomMessage(_)
+    ));
+    assert_eq!(payload.verification.session, renewed.session());
+    assert!(node2.swarm.transport.get_connection(node1.did()).is_some());
+}
+
+#[tokio::test]
+async fn test_revoked_session() {
+    let key1 = SecretKey::random();
+    let key2 = SecretKey::random();
+    let node1 = prepare_node(key1).await;
+    let node2 = prepare_node(key2).await;
+
+    manually_establish_connection(&node1.swarm, &node2.swarm).await;
+    wait_for_msgs([&node1, &node2]).await;
+    assert_no_more_msg([&node1, &node2]).await;
+
+    let session = node1.swarm.session_sk().unwrap().session();
+    let builder = SessionRevocationBuilder::new(
+        node1.did().to_string(),
+        "secp256k1".to_string(),
+        session.session_id().to_string(),
+    );
+    let sig = key1.sign(&builder.unsigned_proof().unwrap());
+    let revocation = builder.set_sig(sig.to_vec()).build().unwrap();
+    node2
+        .swarm
+        .revocations()
+        .insert(revocation.clone())
+        .unwrap();
+
+    // Messages signed by the revoked session are dropped.
+    node1
+        .swarm
+        .transport
+        .send_direct_message(Message::custom(b"hello").unwrap(), node2.did())
+        .await
+        .unwrap();
+    assert_no_more_msg([&node1, &node2]).await;
+
+    // A node cannot switch back to a revoked session.
+    node1.swarm.revocations().insert(revocation).unwrap();
+    let previous = node1.swarm.session_sk().unwrap();
+    assert!(matches!(
+        node1.swarm.rotate_session(previous).await,
+        Err(Error::SessionRevoked(_))
+    ));
+}
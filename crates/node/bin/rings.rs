@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+use std::net::SocketAddr;
 use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -14,24 +16,45 @@ use futures::StreamExt;
 use futures_timer::Delay;
 use rings_node::backend::native::BackendBehaviour;
 use rings_node::backend::native::BackendConfig;
+#[cfg(feature = "snark")]
+use rings_node::backend::snark::SupportedPrimeField;
 use rings_node::backend::Backend;
+use rings_node::consts::DEVICE_ANNOUNCE_INTERVAL;
 use rings_node::logging::init_logging;
 use rings_node::logging::LogLevel;
 use rings_node::measure::PeriodicMeasure;
 use rings_node::native::cli::Client;
 use rings_node::native::config;
 use rings_node::native::endpoint::run_external_api;
+use rings_node::native::endpoint::run_external_grpc_api;
 use rings_node::native::endpoint::run_internal_api;
-use rings_node::prelude::rings_core::dht::Did;
+use rings_node::native::endpoint::run_internal_grpc_api;
+use rings_node::native::event::EventCallback;
+use rings_node::native::event::EventHub;
+use rings_node::native::keystore;
+use rings_node::native::keystore::Keystore;
+use rings_node::native::keystore::Secrets;
+#[cfg(unix)]
+use rings_node::native::signer;
+#[cfg(feature = "snark")]
+use rings_node::native::snark;
+use rings_node::prelude::rings_core::directory::DeviceLink;
+use rings_node::prelude::rings_core::directory::DeviceLinkBuilder;
 use rings_node::prelude::rings_core::ecc::SecretKey;
+use rings_node::prelude::rings_core::revocation::SessionRevocationBuilder;
+use rings_node::prelude::rings_core::session::Account;
+use rings_node::prelude::rings_core::session::AccountSigner;
 use rings_node::prelude::rings_core::storage::sled::SledStorage;
+use rings_node::prelude::SessionSk;
 use rings_node::prelude::SessionSkBuilder;
 use rings_node::processor::Processor;
 use rings_node::processor::ProcessorBuilder;
 use rings_node::processor::ProcessorConfig;
 use rings_node::provider::Provider;
+use rings_node::provider::Signer;
 use rings_node::util::ensure_parent_dir;
 use rings_node::util::expand_home;
+use rings_rpc::auth::Credential;
 use tokio::io;
 use tokio::io::AsyncBufReadExt;
 
@@ -64,10 +87,27 @@ enum Command {
     Send(SendCommand),
     #[command(about = "Registers or looks up a service on the network.", subcommand)]
     Service(ServiceCommand),
+    #[command(about = "Manages sessions of the account.", subcommand)]
+    Session(SessionCommand),
+    #[command(about = "Manages devices of the account of user.", subcommand)]
+    Account(AccountCommand),
+    #[command(about = "Manages the encrypted keystore of node secrets.", subcommand)]
+    Keystore(KeystoreCommand),
+    #[cfg(unix)]
+    #[command(
+        about = "Runs an external signer of account on a unix socket, which signs sessions for nodes."
+    )]
+    Signer(SignerCommand),
     #[command(
         about = "Show information of swarm. Include transport table, successors, predecessor, and finger table."
     )]
     Inspect(InspectCommand),
+    #[cfg(feature = "snark")]
+    #[command(
+        about = "Inspects r1cs, checks or converts witness and proves circuits locally.",
+        subcommand
+    )]
+    Snark(SnarkCommand),
 }
 
 #[derive(Args, Debug)]
@@ -87,6 +127,21 @@ struct InitCommand {
     #[command(flatten)]
     session_args: SessionArgs,
 
+    #[arg(
+        long,
+        short = 's',
+        default_value = "~/.rings/session_sk",
+        help = "The location of session_sk file"
+    )]
+    pub session_sk: String,
+
+    #[arg(
+        long,
+        conflicts_with = "session_sk",
+        help = "Save session_sk and ecdsa_key to an encrypted keystore at this location instead of session_sk file"
+    )]
+    pub keystore: Option<String>,
+
     #[arg(
         long,
         default_value = "~/.rings/config.yaml",
@@ -99,6 +154,14 @@ struct InitCommand {
 struct NewSessionCommand {
     #[command(flatten)]
     session_args: SessionArgs,
+
+    #[arg(
+        long,
+        short = 's',
+        default_value = "~/.rings/session_sk",
+        help = "The location of session_sk file"
+    )]
+    pub session_sk: String,
 }
 
 #[derive(Args, Debug)]
@@ -110,12 +173,46 @@ struct RunCommand {
     )]
     pub external_api_addr: Option<String>,
 
+    #[arg(
+        long,
+        help = "Public URL of external api advertised in DID document, such as http://1.2.3.4:50001. If not provided, use public_endpoint_url in config file",
+        env
+    )]
+    pub public_endpoint_url: Option<String>,
+
+    #[arg(
+        long,
+        help = "Connect endpoints of loopback or private hosts advertised in DID documents of peers. If not provided, use allow_private_endpoints in config file",
+        env
+    )]
+    pub allow_private_endpoints: bool,
+
     #[arg(
         long,
         help = "Rings node internal api listen port. If not provided, use internal_api_port in config file or 50000"
     )]
     pub internal_api_port: Option<u16>,
 
+    #[arg(
+        long,
+        help = "Rings node internal api listen host. If not provided, use internal_api_host in config file or 127.0.0.1. Non-loopback host requires internal_api_auth in config file"
+    )]
+    pub internal_api_host: Option<IpAddr>,
+
+    #[arg(
+        long,
+        help = "Run gRPC internal service on this address. If not provided, use internal_grpc_api_addr in config file",
+        env
+    )]
+    pub internal_grpc_api_addr: Option<SocketAddr>,
+
+    #[arg(
+        long,
+        help = "Run gRPC external service on this address. If not provided, use external_grpc_api_addr in config file",
+        env
+    )]
+    pub external_grpc_api_addr: Option<SocketAddr>,
+
     #[arg(
         long,
         help = "ICE server list. If not provided, use ice_servers in config file or stun://stun.l.google.com:19302",
@@ -141,6 +238,13 @@ struct RunCommand {
     #[arg(long, help = "external ip address", env)]
     pub external_ip: Option<String>,
 
+    #[arg(
+        long,
+        help = "Run a SOCKS5 proxy on this address for reaching services as `<service>.<did>`. If not provided, use socks5_addr in config file",
+        env
+    )]
+    pub socks5_addr: Option<SocketAddr>,
+
     #[arg(
         long,
         help = "Storage files location. If not provided, use storage.path in config file or ~/.local/share/rings",
@@ -178,6 +282,14 @@ struct ClientArgs {
     )]
     pub ecdsa_key: Option<SecretKey>,
 
+    #[arg(
+        long,
+        short = 't',
+        env = "RINGS_ENDPOINT_TOKEN",
+        help = "Bearer token of rings-node endpoint. If not provided, use endpoint_token in config file. Requests are signed by ECDSA key if there is no token"
+    )]
+    pub token: Option<String>,
+
     #[command(flatten)]
     config_args: ConfigArgs,
 }
@@ -186,20 +298,17 @@ impl ClientArgs {
     async fn new_client(&self) -> anyhow::Result<Client> {
         let c = config::Config::read_fs(&self.config_args.config)?;
         let endpoint_url = self.endpoint_url.as_ref().unwrap_or(&c.endpoint_url);
-        Client::new(endpoint_url)
+        let credential = if let Some(token) = self.token.clone().or(c.endpoint_token) {
+            Some(Credential::Bearer(token))
+        } else {
+            self.ecdsa_key.or(c.ecdsa_key).map(Credential::Signed)
+        };
+        Client::new(endpoint_url, credential)
     }
 }
 
 #[derive(Args, Debug)]
 struct SessionArgs {
-    #[arg(
-        long,
-        short = 's',
-        default_value = "~/.rings/session_sk",
-        help = "The location of session_sk file"
-    )]
-    pub session_sk: String,
-
     #[arg(
         long,
         short = 'k',
@@ -213,34 +322,84 @@ struct SessionArgs {
         help = "The ttl of session file in seconds"
     )]
     pub ttl: u64,
+
+    #[arg(
+        long,
+        help = "Unix socket of an external signer, which signs the session instead of ecdsa_key. See `rings signer`"
+    )]
+    pub signer: Option<String>,
+
+    #[arg(
+        long = "capability",
+        help = "Restrict the session to a capability, can be repeated. One of custom_message, custom_message:<did>,<did>, read_dht, write_dht or service:<name>. The session is unrestricted if not provided"
+    )]
+    pub capabilities: Vec<String>,
+}
+
+/// Signer of account, which is the external signer listening on socket if provided,
+/// otherwise the key.
+fn account_signer(
+    socket: Option<&String>,
+    key: Option<SecretKey>,
+) -> anyhow::Result<Option<Box<dyn AccountSigner + Send + Sync>>> {
+    match socket {
+        #[cfg(unix)]
+        Some(socket) => Ok(Some(Box::new(signer::ExternalSigner::new(socket)?))),
+        #[cfg(not(unix))]
+        Some(_) => Err(anyhow::anyhow!("External signer is only supported on unix")),
+        None => Ok(key.map(|k| Box::new(k) as Box<dyn AccountSigner + Send + Sync>)),
+    }
 }
 
 impl SessionArgs {
-    fn new_session_then_write_to_fs(&self) -> anyhow::Result<&std::path::Path> {
-        let key = self.ecdsa_key.unwrap_or_else(|| {
-            let rand_key = SecretKey::random();
-            println!("Your random ecdsa key is: {}", rand_key.to_string());
-            rand_key
-        });
-        let key_did: Did = key.address().into();
+    /// Key of account, a random key is used if neither ecdsa_key nor signer is provided.
+    fn account_key(&self) -> Option<SecretKey> {
+        self.ecdsa_key
+            .or_else(|| self.signer.is_none().then(SecretKey::random))
+    }
+
+    async fn new_session(&self, key: Option<SecretKey>) -> anyhow::Result<SessionSk> {
+        let signer = account_signer(self.signer.as_ref(), key)?
+            .ok_or_else(|| anyhow::anyhow!("Signer of account is required"))?;
 
-        let ssk_builder = SessionSkBuilder::new(key_did.to_string(), "secp256k1".to_string())
+        let mut ssk_builder = SessionSkBuilder::from_signer(signer.as_ref())
+            .await?
             .set_ttl(self.ttl * 1000);
-        let unsigned_proof = ssk_builder.unsigned_proof();
+        for capability in &self.capabilities {
+            ssk_builder = ssk_builder.add_capability(capability.clone())?;
+        }
 
-        let sig = key.sign(&unsigned_proof).to_vec();
-        let ssk_builder = ssk_builder.set_session_sig(sig);
+        Ok(ssk_builder.sign_by(signer.as_ref()).await?.build()?)
+    }
 
-        let ssk = ssk_builder.build()?;
-        let ssk_dump = ssk.dump()?;
+    async fn new_session_then_write_to_fs<'a>(
+        &self,
+        session_sk: &'a str,
+    ) -> anyhow::Result<&'a std::path::Path> {
+        let key = self.account_key();
+        if let (None, Some(rand_key)) = (self.ecdsa_key, key) {
+            println!("Your random ecdsa key is: {}", rand_key.to_string());
+        }
+        let ssk_dump = self.new_session(key).await?.dump()?;
 
-        let ssk_path = std::path::Path::new(&self.session_sk);
+        let ssk_path = std::path::Path::new(session_sk);
         ensure_parent_dir(ssk_path)?;
         std::fs::write(expand_home(ssk_path)?, ssk_dump)?;
         println!("Your session_sk file has saved to: {}", ssk_path.display());
 
         Ok(ssk_path)
     }
+
+    /// Save the new session and the key of account to a new keystore.
+    /// The key is not saved if the session is signed by an external signer.
+    async fn new_session_then_write_to_keystore(&self, keystore: &str) -> anyhow::Result<String> {
+        let key = self.account_key();
+        let secrets = Secrets {
+            ecdsa_key: key,
+            session_sk: Some(self.new_session(key).await?.dump()?),
+        };
+        write_new_keystore(keystore, &secrets)
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -285,6 +444,8 @@ enum PeerCommand {
     List(PeerListCommand),
     #[command(about = "Disconnect peer")]
     Disconnect(PeerDisconnectCommand),
+    #[command(about = "Resolve DID document of peer from DHT")]
+    Resolve(PeerResolveCommand),
 }
 
 #[derive(Args, Debug)]
@@ -301,6 +462,14 @@ struct PeerDisconnectCommand {
     address: String,
 }
 
+#[derive(Args, Debug)]
+struct PeerResolveCommand {
+    #[command(flatten)]
+    client_args: ClientArgs,
+
+    did: String,
+}
+
 #[derive(Subcommand, Debug)]
 #[command(rename_all = "kebab-case")]
 enum SendCommand {
@@ -310,6 +479,8 @@ enum SendCommand {
     PlainText(SendPlainTextCommand),
     #[command(about = "Sends a custom message.")]
     Custom(SendCustomMessageCommand),
+    #[command(about = "Sends a custom message to the devices of an account.")]
+    Account(SendAccountMessageCommand),
 }
 
 #[derive(Args, Debug)]
@@ -364,6 +535,20 @@ struct SendCustomMessageCommand {
     data: String,
 }
 
+#[derive(Args, Debug)]
+struct SendAccountMessageCommand {
+    #[command(flatten)]
+    client_args: ClientArgs,
+    account: String,
+    data: String,
+
+    #[arg(
+        long,
+        help = "Send to all online devices of account, instead of one of them"
+    )]
+    fan_out: bool,
+}
+
 #[derive(Subcommand, Debug)]
 #[command(rename_all = "kebab-case")]
 enum ServiceCommand {
@@ -387,15 +572,391 @@ struct ServiceLookupCommand {
     name: String,
 }
 
+#[derive(Subcommand, Debug)]
+#[command(rename_all = "kebab-case")]
+enum SessionCommand {
+    #[command(about = "Revokes a session before it expires, and publishes the revocation to DHT.")]
+    Revoke(SessionRevokeCommand),
+}
+
+#[derive(Args, Debug)]
+struct SessionRevokeCommand {
+    #[command(flatten)]
+    client_args: ClientArgs,
+
+    #[arg(
+        long,
+        help = "Unix socket of an external signer, which signs the revocation instead of ecdsa_key. See `rings signer`"
+    )]
+    signer: Option<String>,
+
+    #[arg(help = "Did of the session to revoke")]
+    session_id: String,
+}
+
+impl SessionRevokeCommand {
+    /// Sign a revocation of the session by the external signer or the ecdsa key of account.
+    async fn sign_revocation(&self) -> anyhow::Result<String> {
+        let c = config::Config::read_fs(&self.client_args.config_args.config)?;
+        let key = self.client_args.ecdsa_key.or(c.ecdsa_key);
+        let signer = account_signer(self.signer.as_ref(), key)?.ok_or_else(|| {
+            anyhow::anyhow!("ECDSA key or signer of account is required to revoke session")
+        })?;
+
+        let builder =
+            SessionRevocationBuilder::from_signer(signer.as_ref(), self.session_id.clone()).await?;
+        Ok(builder.sign_by(signer.as_ref()).await?.build()?.dump()?)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+#[command(rename_all = "kebab-case")]
+enum AccountCommand {
+    #[command(
+        about = "Links the node to account of user as one of its devices, and saves the link to config file."
+    )]
+    Link(AccountLinkCommand),
+    #[command(about = "Lists online devices of an account from DHT.")]
+    Devices(AccountDevicesCommand),
+}
+
+#[derive(Args, Debug)]
+struct AccountLinkCommand {
+    #[command(flatten)]
+    client_args: ClientArgs,
+
+    #[arg(
+        long,
+        required_unless_present = "signer",
+        help = "ECDSA key of account of user, which is not the key of node"
+    )]
+    account_key: Option<SecretKey>,
+
+    #[arg(
+        long,
+        conflicts_with = "account_key",
+        help = "Unix socket of an external signer of account of user, which signs the link instead of account_key. See `rings signer`"
+    )]
+    signer: Option<String>,
+
+    #[arg(long, default_value = "2592000", help = "The ttl of link in seconds")]
+    ttl: u64,
+}
+
+impl AccountLinkCommand {
+    /// Sign a link of the node by the external signer or the ecdsa key of account.
+    async fn sign_link(&self, device: String) -> anyhow::Result<String> {
+        let signer = account_signer(self.signer.as_ref(), self.account_key)?
+            .ok_or_else(|| anyhow::anyhow!("ECDSA key or signer of account is required"))?;
+
+        let builder = DeviceLinkBuilder::from_signer(signer.as_ref(), device)
+            .await?
+            .set_ttl(self.ttl * 1000);
+        Ok(builder.sign_by(signer.as_ref()).await?.build()?.dump()?)
+    }
+}
+
+#[derive(Args, Debug)]
+struct AccountDevicesCommand {
+    #[command(flatten)]
+    client_args: ClientArgs,
+
+    account: String,
+}
+
+#[cfg(unix)]
+#[derive(Args, Debug)]
+struct SignerCommand {
+    #[arg(
+        long,
+        default_value = "~/.rings/signer.sock",
+        help = "The unix socket to listen on"
+    )]
+    pub socket: String,
+
+    #[arg(
+        long = "key",
+        short = 'k',
+        env = "RINGS_SIGNER_KEY",
+        required_unless_present = "keystore",
+        help = "ECDSA key of account, which is only kept in memory of signer"
+    )]
+    pub ecdsa_key: Option<SecretKey>,
+
+    #[arg(
+        long,
+        conflicts_with = "ecdsa_key",
+        help = "Load ECDSA key of account from this keystore"
+    )]
+    pub keystore: Option<String>,
+}
+
+#[cfg(unix)]
+impl SignerCommand {
+    fn ecdsa_key(&self) -> anyhow::Result<SecretKey> {
+        if let Some(key) = self.ecdsa_key {
+            return Ok(key);
+        }
+        let path = self
+            .keystore
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("ECDSA key or keystore is required"))?;
+        Keystore::read_fs(path)?
+            .decrypt(&keystore::read_password("Keystore password: ")?)?
+            .ecdsa_key
+            .ok_or_else(|| anyhow::anyhow!("Keystore {} has no ecdsa_key", path))
+    }
+}
+
+#[derive(Subcommand, Debug)]
+#[command(rename_all = "kebab-case")]
+enum KeystoreCommand {
+    #[command(
+        about = "Creates a keystore with a new session and the ecdsa key of account. The key is random if not provided."
+    )]
+    Create(KeystoreCreateCommand),
+    #[command(about = "Imports an ecdsa key and/or a session_sk file to a new keystore.")]
+    Import(KeystoreImportCommand),
+    #[command(about = "Prints the decrypted secrets of keystore in JSON.")]
+    Export(KeystoreArgs),
+    #[command(about = "Changes the password of keystore.")]
+    Passwd(KeystoreArgs),
+}
+
+#[derive(Args, Debug)]
+struct KeystoreArgs {
+    #[arg(
+        long,
+        default_value = "~/.rings/keystore.json",
+        help = "The location of keystore file"
+    )]
+    pub keystore: String,
+}
+
+#[derive(Args, Debug)]
+struct KeystoreCreateCommand {
+    #[command(flatten)]
+    keystore_args: KeystoreArgs,
+
+    #[command(flatten)]
+    session_args: SessionArgs,
+}
+
+#[derive(Args, Debug)]
+struct KeystoreImportCommand {
+    #[command(flatten)]
+    keystore_args: KeystoreArgs,
+
+    #[arg(long = "key", short = 'k', help = "The ecdsa_key to import")]
+    pub ecdsa_key: Option<SecretKey>,
+
+    #[arg(long, short = 's', help = "The location of session_sk file to import")]
+    pub session_sk: Option<String>,
+}
+
+/// Encrypt secrets by a new password, and write them to a keystore which doesn't exist yet.
+fn write_new_keystore(path: &str, secrets: &Secrets) -> anyhow::Result<String> {
+    if expand_home(path)?.exists() {
+        return Err(anyhow::anyhow!("Keystore {} already exists", path));
+    }
+    let password = keystore::read_new_password()?;
+    let path = Keystore::encrypt(secrets, &password)?.write_fs(path)?;
+    println!("Your keystore has saved to: {}", path);
+    Ok(path)
+}
+
+async fn keystore_run(command: KeystoreCommand) -> anyhow::Result<()> {
+    match command {
+        KeystoreCommand::Create(args) => {
+            args.session_args
+                .new_session_then_write_to_keystore(&args.keystore_args.keystore)
+                .await?;
+        }
+        KeystoreCommand::Import(args) => {
+            if args.ecdsa_key.is_none() && args.session_sk.is_none() {
+                return Err(anyhow::anyhow!("Nothing to import"));
+            }
+            let session_sk = match args.session_sk {
+                Some(path) => Some(std::fs::read_to_string(expand_home(path)?)?),
+                None => None,
+            };
+            let secrets = Secrets {
+                ecdsa_key: args.ecdsa_key,
+                session_sk,
+            };
+            write_new_keystore(&args.keystore_args.keystore, &secrets)?;
+        }
+        KeystoreCommand::Export(args) => {
+            let ks = Keystore::read_fs(args.keystore)?;
+            let secrets = ks.decrypt(&keystore::read_password("Keystore password: ")?)?;
+            println!("{}", serde_json::to_string_pretty(&secrets)?);
+        }
+        KeystoreCommand::Passwd(args) => {
+            let ks = Keystore::read_fs(&args.keystore)?;
+            let password = keystore::read_password("Current keystore password: ")?;
+            let new_password = keystore::prompt_new_password()?;
+            // Argon2 is costly, it's run off the async runtime.
+            let path = args.keystore.clone();
+            tokio::task::spawn_blocking(move || {
+                ks.change_password(&password, &new_password)?.write_fs(path)
+            })
+            .await??;
+            println!("Password of keystore {} is changed", args.keystore);
+        }
+    }
+    Ok(())
+}
+
 #[derive(Args, Debug)]
 struct InspectCommand {
     #[command(flatten)]
     client_args: ClientArgs,
 }
 
+#[cfg(feature = "snark")]
+#[derive(Subcommand, Debug)]
+#[command(rename_all = "kebab-case")]
+enum SnarkCommand {
+    #[command(about = "Shows constraint and variable counts of a r1cs file.")]
+    Inspect(SnarkInspectCommand),
+    #[command(about = "Checks that a witness satisfies every constraint of a r1cs file.")]
+    Check(SnarkCheckCommand),
+    #[command(
+        about = "Converts witness between json and binary formats, detected by file extensions."
+    )]
+    Convert(SnarkConvertCommand),
+    #[command(about = "Generates and verifies a recursive proof locally.")]
+    Prove(SnarkProveCommand),
+}
+
+#[cfg(feature = "snark")]
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum SnarkField {
+    Vesta,
+    Pallas,
+    Bn256,
+}
+
+#[cfg(feature = "snark")]
+impl From<SnarkField> for SupportedPrimeField {
+    fn from(field: SnarkField) -> Self {
+        match field {
+            SnarkField::Vesta => SupportedPrimeField::Vesta,
+            SnarkField::Pallas => SupportedPrimeField::Pallas,
+            SnarkField::Bn256 => SupportedPrimeField::Bn256KZG,
+        }
+    }
+}
+
+#[cfg(feature = "snark")]
+#[derive(Args, Debug)]
+struct SnarkFieldArgs {
+    #[arg(
+        long,
+        value_enum,
+        default_value = "vesta",
+        help = "Prime field of circuit"
+    )]
+    field: SnarkField,
+}
+
+#[cfg(feature = "snark")]
+#[derive(Args, Debug)]
+struct SnarkInspectCommand {
+    #[command(flatten)]
+    field_args: SnarkFieldArgs,
+
+    r1cs: String,
+}
+
+#[cfg(feature = "snark")]
+#[derive(Args, Debug)]
+struct SnarkCheckCommand {
+    #[command(flatten)]
+    field_args: SnarkFieldArgs,
+
+    r1cs: String,
+
+    #[arg(help = "Witness file, `.json` for json format and binary `.wtns` for others")]
+    witness: String,
+}
+
+#[cfg(feature = "snark")]
+#[derive(Args, Debug)]
+struct SnarkConvertCommand {
+    #[command(flatten)]
+    field_args: SnarkFieldArgs,
+
+    input: String,
+
+    output: String,
+}
+
+#[cfg(feature = "snark")]
+#[derive(Args, Debug)]
+struct SnarkProveCommand {
+    #[command(flatten)]
+    field_args: SnarkFieldArgs,
+
+    r1cs: String,
+
+    #[arg(help = "Witness calculator wasm of circuit")]
+    wasm: String,
+
+    #[arg(long = "input", short = 'i', action = ArgAction::Append, help = "Public input as name=v1,v2 in decimal")]
+    inputs: Vec<String>,
+
+    #[arg(long, default_value = "1", help = "Steps of recursion")]
+    steps: usize,
+
+    #[arg(long, short = 'o', help = "Write proof to file instead of stdout")]
+    output: Option<String>,
+}
+
+#[cfg(feature = "snark")]
+async fn snark_run(command: SnarkCommand) -> anyhow::Result<()> {
+    match command {
+        SnarkCommand::Inspect(args) => {
+            let info = snark::inspect_r1cs(&args.r1cs, args.field_args.field.into())?;
+            println!("{}", info);
+        }
+        SnarkCommand::Check(args) => {
+            let n = snark::check_witness(&args.r1cs, &args.witness, args.field_args.field.into())?;
+            println!("Witness satisfies all {} constraints", n);
+        }
+        SnarkCommand::Convert(args) => {
+            let n =
+                snark::convert_witness(&args.input, &args.output, args.field_args.field.into())?;
+            println!("Converted {} witness values to: {}", n, args.output);
+        }
+        SnarkCommand::Prove(args) => {
+            let field: SupportedPrimeField = args.field_args.field.into();
+            let input = snark::parse_input(&args.inputs, field.clone())?;
+            let proof = snark::prove(args.r1cs, args.wasm, input, args.steps, field).await?;
+            let proof = serde_json::to_string(&proof)?;
+            match args.output {
+                Some(path) => {
+                    std::fs::write(&path, proof)?;
+                    println!("Your proof has saved to: {}", path);
+                }
+                None => println!("{}", proof),
+            }
+        }
+    }
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn daemon_run(args: RunCommand) -> anyhow::Result<()> {
     let mut c = config::Config::read_fs(args.config_args.config)?;
+    let keystore_password = match c.keystore.clone() {
+        Some(path) => {
+            let password = keystore::read_password(&format!("Password of keystore {}: ", path))?;
+            c.unlock_keystore(&password)?;
+            Some(password)
+        }
+        None => None,
+    };
 
     if let Some(ice_servers) = args.ice_servers {
         c.ice_servers = ice_servers;
@@ -409,26 +970,66 @@ async fn daemon_run(args: RunCommand) -> anyhow::Result<()> {
     if let Some(external_api_addr) = args.external_api_addr {
         c.external_api_addr = external_api_addr;
     }
+    if let Some(public_endpoint_url) = args.public_endpoint_url {
+        c.public_endpoint_url = Some(public_endpoint_url);
+    }
+    if args.allow_private_endpoints {
+        c.allow_private_endpoints = true;
+    }
     if let Some(internal_api_port) = args.internal_api_port {
         c.internal_api_port = internal_api_port;
     }
+    if let Some(internal_api_host) = args.internal_api_host {
+        c.internal_api_host = Some(internal_api_host);
+    }
+    if let Some(internal_grpc_api_addr) = args.internal_grpc_api_addr {
+        c.internal_grpc_api_addr = Some(internal_grpc_api_addr);
+    }
+    if let Some(external_grpc_api_addr) = args.external_grpc_api_addr {
+        c.external_grpc_api_addr = Some(external_grpc_api_addr);
+    }
+    if let Some(socks5_addr) = args.socks5_addr {
+        c.socks5_addr = Some(socks5_addr);
+    }
 
     let pc = ProcessorConfig::try_from(c.clone())?;
     let bc = BackendConfig::from(c.clone());
 
-    let (data_storage, measure_storage) = if let Some(storage_path) = args.storage_path {
+    let (
+        data_storage,
+        measure_storage,
+        snark_storage,
+        snark_params_storage,
+        snark_ledger_storage,
+        revocation_storage,
+    ) = if let Some(storage_path) = args.storage_path {
         let storage_path = Path::new(&storage_path);
         let data_path = storage_path.join("data");
         let measure_path = storage_path.join("measure");
+        let snark_path = storage_path.join("snark");
+        let snark_params_path = storage_path.join("snark_params");
+        let snark_ledger_path = storage_path.join("snark_ledger");
+        let revocation_path = storage_path.join("revocations");
         let capacity = args
             .storage_capacity
             .unwrap_or(config::DEFAULT_STORAGE_CAPACITY);
         (
             config::StorageConfig::new(data_path.to_str().unwrap(), capacity),
             config::StorageConfig::new(measure_path.to_str().unwrap(), capacity),
+            config::StorageConfig::new(snark_path.to_str().unwrap(), capacity),
+            config::StorageConfig::new(snark_params_path.to_str().unwrap(), capacity),
+            config::StorageConfig::new(snark_ledger_path.to_str().unwrap(), capacity),
+            config::StorageConfig::new(revocation_path.to_str().unwrap(), capacity),
         )
     } else {
-        (c.data_storage, c.measure_storage)
+        (
+            c.data_storage.clone(),
+            c.measure_storage.clone(),
+            c.snark_storage.clone(),
+            c.snark_params_storage.clone(),
+            c.snark_ledger_storage.clone(),
+            c.revocation_storage.clone(),
+        )
     };
 
     let per_data_storage = Box::new(
@@ -444,22 +1045,212 @@ async fn daemon_run(args: RunCommand) -> anyhow::Result<()> {
         ProcessorBuilder::from_config(&pc)?
             .storage(per_data_storage)
             .measure(measure)
+            .allow_private_endpoints(c.allow_private_endpoints)
             .build()?,
     );
+    processor
+        .swarm
+        .revocations()
+        .set_storage(Box::new(
+            SledStorage::new_with_cap_and_path(
+                revocation_storage.capacity,
+                revocation_storage.path,
+            )
+            .await?,
+        ))
+        .await?;
+    #[cfg(feature = "snark")]
+    processor
+        .snark
+        .set_storage(Box::new(
+            SledStorage::new_with_cap_and_path(snark_storage.capacity, snark_storage.path).await?,
+        ))
+        .await?;
+    #[cfg(feature = "snark")]
+    processor.snark.set_params_storage(Box::new(
+        SledStorage::new_with_cap_and_path(
+            snark_params_storage.capacity,
+            snark_params_storage.path,
+        )
+        .await?,
+    ))?;
+    #[cfg(feature = "snark")]
+    processor
+        .snark
+        .set_ledger_storage(Box::new(
+            SledStorage::new_with_cap_and_path(
+                snark_ledger_storage.capacity,
+                snark_ledger_storage.path,
+            )
+            .await?,
+        ))
+        .await?;
+    #[cfg(feature = "snark")]
+    processor.snark.set_prover_options(c.snark_prover);
+    #[cfg(not(feature = "snark"))]
+    let _ = (snark_storage, snark_params_storage, snark_ledger_storage);
     println!("Did: {}", processor.swarm.did());
+    if let Some(link) = &c.device_link {
+        if let Err(e) = processor.link_device(DeviceLink::from_str(link)?).await {
+            tracing::error!("Failed to link device to account: {:?}", e);
+        }
+    }
     let backend_behaviour = BackendBehaviour::new(bc).await?;
     let backend_service_names = backend_behaviour.service_names();
+    let service_provider = backend_behaviour.service_provider();
     let provider = Arc::new(Provider::from_processor(processor.clone()));
-    let backend = Arc::new(Backend::new(provider, Box::new(backend_behaviour)));
-    processor.swarm.set_callback(backend).unwrap();
+    let backend = Arc::new(Backend::new(provider.clone(), Box::new(backend_behaviour)));
+    let events = EventHub::new();
+    processor
+        .swarm
+        .set_callback(Arc::new(EventCallback::new(backend, events.clone())))
+        .unwrap();
+
+    let udp_forwards = futures::future::join_all(c.udp_forwards.iter().map(|forward| {
+        let service_provider = service_provider.clone();
+        let provider = provider.clone();
+        async move {
+            if let Err(e) = service_provider.listen_udp(provider, forward).await {
+                tracing::error!("Udp forward on {} failed: {e:?}", forward.listen);
+            }
+        }
+    }));
+
+    let socks5_addr = c.socks5_addr;
+    let socks5 = async {
+        if let Some(addr) = socks5_addr {
+            if let Err(e) = service_provider
+                .clone()
+                .listen_socks5(provider.clone(), addr)
+                .await
+            {
+                tracing::error!("Socks5 proxy on {addr} failed: {e:?}");
+            }
+        }
+    };
+
+    let internal_grpc_api_addr = c.internal_grpc_api_addr;
+    let internal_api_auth = c.internal_api_auth.clone();
+    let internal_grpc = async {
+        if let Some(addr) = internal_grpc_api_addr {
+            let auth = internal_api_auth.clone();
+            if let Err(e) =
+                run_internal_grpc_api(addr, processor.clone(), events.clone(), auth).await
+            {
+                tracing::error!("gRPC internal service on {addr} failed: {e:?}");
+            }
+        }
+    };
+    let external_grpc_api_addr = c.external_grpc_api_addr;
+    let external_grpc = async {
+        if let Some(addr) = external_grpc_api_addr {
+            if let Err(e) = run_external_grpc_api(addr, processor.clone()).await {
+                tracing::error!("gRPC external service on {addr} failed: {e:?}");
+            }
+        }
+    };
+
+    let session_renewal_config = c.session_renewal.clone();
+    let session_sk_file = c.session_sk.clone();
+    let keystore_path = c.keystore.clone();
+    let secrets = c.secrets.clone().unwrap_or_default();
+    let session_renewal = async {
+        let Some(renewal) = session_renewal_config else {
+            return;
+        };
+        let key = renewal.ecdsa_key.or(secrets.ecdsa_key);
+        let signer = match account_signer(renewal.signer.as_ref(), key) {
+            Ok(Some(signer)) => signer,
+            Ok(None) => {
+                tracing::error!("session_renewal requires ecdsa_key or signer");
+                return;
+            }
+            Err(e) => {
+                tracing::error!("Failed to create signer of session_renewal: {:?}", e);
+                return;
+            }
+        };
+        let account = signer
+            .account()
+            .await
+            .and_then(Account::try_from)
+            .map(|account| account.did());
+        if account.ok() != Some(processor.did()) {
+            tracing::error!("Signer of session_renewal is not the account of session");
+            return;
+        }
+        let ssk_file = session_sk_file
+            .as_ref()
+            .and_then(|f| expand_home(f).ok())
+            .filter(|p| p.is_file());
+        provider
+            .session_renewal_daemon(
+                Signer::Account(signer),
+                Duration::from_secs(renewal.margin),
+                |session_sk| {
+                    let dump = match session_sk.dump() {
+                        Ok(dump) => dump,
+                        Err(e) => {
+                            tracing::error!("Failed to dump renewed session: {:?}", e);
+                            return;
+                        }
+                    };
+                    // Renewed session replaces the one in keystore, which is encrypted by the same password.
+                    if let (Some(path), Some(password)) = (&keystore_path, &keystore_password) {
+                        let secrets = Secrets {
+                            session_sk: Some(dump),
+                            ..secrets.clone()
+                        };
+                        let (path, password) = (path.clone(), password.clone());
+                        // Argon2 is costly, it's run off the async runtime.
+                        tokio::task::spawn_blocking(move || {
+                            let written = Keystore::read_fs(&path)
+                                .and_then(|ks| ks.reencrypt(&secrets, &password))
+                                .and_then(|ks| ks.write_fs(&path));
+                            if let Err(e) = written {
+                                tracing::error!(
+                                    "Failed to save renewed session to {}: {:?}",
+                                    path,
+                                    e
+                                );
+                            }
+                        });
+                        return;
+                    }
+                    let Some(path) = &ssk_file else {
+                        return;
+                    };
+                    if let Err(e) = std::fs::write(path, dump) {
+                        tracing::error!("Failed to save renewed session to {:?}: {}", path, e);
+                    }
+                },
+            )
+            .await
+    };
 
     let processor_clone1 = processor.clone();
     let processor_clone2 = processor.clone();
+    let processor_clone3 = processor.clone();
     let _ = futures::join!(
         processor.listen(),
-        service_loop_register(&processor, backend_service_names),
-        run_internal_api(c.internal_api_port, processor_clone2),
+        service_loop_register(
+            &processor,
+            backend_service_names,
+            c.public_endpoint_url.clone()
+        ),
+        events.watch_stabilization(processor_clone3, Duration::from_secs(c.stabilize_interval)),
+        run_internal_api(
+            c.internal_api_addr(),
+            processor_clone2,
+            events.clone(),
+            internal_api_auth.clone()
+        ),
         run_external_api(c.external_api_addr, processor_clone1),
+        internal_grpc,
+        external_grpc,
+        udp_forwards,
+        socks5,
+        session_renewal,
     );
 
     Ok(())
@@ -541,6 +1332,15 @@ async fn main() -> anyhow::Result<()> {
                 .display();
             Ok(())
         }
+        Command::Peer(PeerCommand::Resolve(args)) => {
+            args.client_args
+                .new_client()
+                .await?
+                .resolve_did(args.did.as_str())
+                .await?
+                .display();
+            Ok(())
+        }
         Command::Send(SendCommand::Http(args)) => {
             args.client_args
                 .new_client()
@@ -589,6 +1389,15 @@ async fn main() -> anyhow::Result<()> {
                 .display();
             Ok(())
         }
+        Command::Send(SendCommand::Account(args)) => {
+            args.client_args
+                .new_client()
+                .await?
+                .send_account_message(args.account.as_str(), args.data.as_str(), args.fan_out)
+                .await?
+                .display();
+            Ok(())
+        }
         Command::Service(ServiceCommand::Register(args)) => {
             args.client_args
                 .new_client()
@@ -607,17 +1416,69 @@ async fn main() -> anyhow::Result<()> {
                 .display();
             Ok(())
         }
+        Command::Session(SessionCommand::Revoke(args)) => {
+            let revocation = args.sign_revocation().await?;
+            args.client_args
+                .new_client()
+                .await?
+                .revoke_session(revocation.as_str())
+                .await?
+                .display();
+            Ok(())
+        }
+        Command::Account(AccountCommand::Link(args)) => {
+            let client = args.client_args.new_client().await?;
+            let device = client.node_did().await?.result;
+            let link = args.sign_link(device).await?;
+            client.link_device(link.as_str()).await?.display();
+
+            // The link is announced again after node restarts.
+            let config_path = &args.client_args.config_args.config;
+            let mut c = config::Config::read_fs(config_path)?;
+            c.device_link = Some(link);
+            let p = c.write_fs(config_path)?;
+            println!("Device link has saved to: {}", p);
+            Ok(())
+        }
+        Command::Account(AccountCommand::Devices(args)) => {
+            args.client_args
+                .new_client()
+                .await?
+                .resolve_account(args.account.as_str())
+                .await?
+                .display();
+            Ok(())
+        }
         Command::Init(args) => {
-            let session_sk_path = args.session_args.new_session_then_write_to_fs()?;
-            let config = config::Config::new(session_sk_path);
+            let config = if let Some(keystore) = &args.keystore {
+                let path = args
+                    .session_args
+                    .new_session_then_write_to_keystore(keystore)
+                    .await?;
+                config::Config::new_with_keystore(path)
+            } else {
+                let session_sk_path = args
+                    .session_args
+                    .new_session_then_write_to_fs(&args.session_sk)
+                    .await?;
+                config::Config::new(session_sk_path)
+            };
             let p = config.write_fs(&args.location)?;
             println!("Your config file has saved to: {}", p);
             Ok(())
         }
         Command::NewSession(args) => {
-            args.session_args.new_session_then_write_to_fs()?;
+            args.session_args
+                .new_session_then_write_to_fs(&args.session_sk)
+                .await?;
             Ok(())
         }
+        #[cfg(unix)]
+        Command::Signer(args) => {
+            signer::serve(&args.socket, Arc::new(args.ecdsa_key()?)).await?;
+            Ok(())
+        }
+        Command::Keystore(command) => keystore_run(command).await,
         Command::Inspect(args) => {
             args.client_args
                 .new_client()
@@ -627,6 +1488,8 @@ async fn main() -> anyhow::Result<()> {
                 .display();
             Ok(())
         }
+        #[cfg(feature = "snark")]
+        Command::Snark(command) => snark_run(command).await,
     }
 }
 
@@ -643,12 +1506,29 @@ async fn register_services(processor: &Processor, names: Vec<String>) -> anyhow:
     Ok(())
 }
 
-async fn service_loop_register(processor: &Processor, names: Vec<String>) {
+async fn service_loop_register(
+    processor: &Processor,
+    names: Vec<String>,
+    public_endpoint_url: Option<String>,
+) {
     loop {
-        let timeout = Delay::new(Duration::from_secs(30)).fuse();
+        let timeout = Delay::new(Duration::from_secs(DEVICE_ANNOUNCE_INTERVAL)).fuse();
         pin_mut!(timeout);
         select! {
-            _ = timeout => register_services(processor, names.clone()).await.unwrap_or_else(|e| eprintln!("Error: {}", e)),
+            _ = timeout => {
+                register_services(processor, names.clone()).await.unwrap_or_else(|e| eprintln!("Error: {}", e));
+                // DID document is published along with services, it's lost if the nodes keeping it leave.
+                if let Err(e) = processor
+                    .publish_did_document(names.clone(), public_endpoint_url.clone())
+                    .await
+                {
+                    tracing::error!("publish DID document error: {}", e);
+                }
+                // Device entry expires soon, so that offline devices drop out of account directory.
+                if let Err(e) = processor.announce_device().await {
+                    tracing::error!("announce device error: {}", e);
+                }
+            }
         }
     }
 }
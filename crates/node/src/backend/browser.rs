@@ -89,6 +89,8 @@ impl BackendBehaviour {
                     cb(self.clone(), provider.clone(), ctx, m).await?;
                 }
             }
+            // Handled by [crate::backend::Backend] before reaching here.
+            BackendMessage::ReencryptMessage(_) => {}
             #[cfg(feature = "snark")]
             BackendMessage::SNARKTaskMessage(m) => {
                 if let Some(func) = &self.get_handler("SNARKTaskMessage") {
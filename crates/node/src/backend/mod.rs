@@ -1,6 +1,7 @@
 #![warn(missing_docs)]
 //! This module provide basic mechanism.
 
+pub mod reencrypt;
 #[cfg(feature = "snark")]
 pub mod snark;
 pub mod types;
@@ -8,6 +9,8 @@ use std::result::Result;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use dashmap::DashMap;
+use rings_core::dht::Did;
 use rings_core::message::CustomMessage;
 use rings_core::message::Message;
 use rings_core::message::MessagePayload;
@@ -16,6 +19,9 @@ use rings_derive::wasm_export;
 
 use crate::backend::types::BackendMessage;
 use crate::backend::types::MessageHandler;
+use crate::backend::types::ServiceMessage;
+use crate::backend::types::TunnelId;
+use crate::consts::MAX_TUNNEL_SERVICES;
 use crate::provider::Provider;
 
 #[cfg(feature = "browser")]
@@ -36,12 +42,19 @@ type HandlerTrait = dyn MessageHandler<BackendMessage>;
 pub struct Backend {
     provider: Arc<Provider>,
     handler: Box<HandlerTrait>,
+    /// Services of tunnels opened by restricted sessions, the messages in tunnel carry no
+    /// service name.
+    tunnel_services: DashMap<(Did, TunnelId), String>,
 }
 
 impl Backend {
     /// Create a new backend instance with Provider and Handler functions
     pub fn new(provider: Arc<Provider>, handler: Box<HandlerTrait>) -> Self {
-        Self { provider, handler }
+        Self {
+            provider,
+            handler,
+            tunnel_services: DashMap::new(),
+        }
     }
 
     async fn on_backend_message(
@@ -50,6 +63,11 @@ impl Backend {
         msg: &BackendMessage,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let provider = self.provider.clone();
+        // Proxy re-encryption is handled by every node, regardless of its handler.
+        if let BackendMessage::ReencryptMessage(m) = msg {
+            let reencrypt = provider.reencrypt();
+            return reencrypt.handle_message(provider, payload, m).await;
+        }
         self.handler.handle_message(provider, payload, msg).await
     }
 }
@@ -108,11 +126,49 @@ impl SwarmCallback for Backend {
             return Ok(());
         };
 
-        let backend_msg = bincode::deserialize(&msg)?;
+        let backend_msg: BackendMessage = bincode::deserialize(&msg)?;
         tracing::debug!("backend_message received: {backend_msg:?}");
 
         self.on_backend_message(payload, &backend_msg).await?;
 
         Ok(())
     }
+
+    fn custom_message_service(
+        &self,
+        payload: &MessagePayload,
+        msg: &CustomMessage,
+    ) -> Option<String> {
+        let backend_msg: BackendMessage = bincode::deserialize(&msg.0).ok()?;
+        let session = &payload.transaction.verification.session;
+        let session_id = session.session_id();
+        let tid = backend_msg.tunnel_id();
+
+        // Remember the service of a permitted tunnel, and resolve messages in the tunnel by it.
+        if let Some(service) = backend_msg.service() {
+            if let Some(tid) = tid {
+                if session.permits_service(service)
+                    && self.tunnel_services.len() < MAX_TUNNEL_SERVICES
+                {
+                    self.tunnel_services
+                        .insert((session_id, tid), service.to_string());
+                }
+            }
+            return Some(service.to_string());
+        }
+
+        let key = (session_id, tid?);
+        if let BackendMessage::ServiceMessage(
+            ServiceMessage::TcpClose { .. } | ServiceMessage::UdpClose { .. },
+        ) = backend_msg
+        {
+            return self
+                .tunnel_services
+                .remove(&key)
+                .map(|(_, service)| service);
+        }
+        self.tunnel_services
+            .get(&key)
+            .map(|service| service.clone())
+    }
 }
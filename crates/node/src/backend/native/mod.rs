@@ -40,7 +40,7 @@ pub struct BackendConfig {
 
 /// BackendBehaviour is a Context holder of backend message handler
 pub struct BackendBehaviour {
-    server: ServiceProvider,
+    server: Arc<ServiceProvider>,
     extension: Extension,
 }
 
@@ -61,7 +61,7 @@ impl BackendBehaviour {
     /// Create a new BackendBehaviour instance with config
     pub async fn new(config: BackendConfig) -> Result<Self, Error> {
         Ok(Self {
-            server: ServiceProvider::new(config.services),
+            server: Arc::new(ServiceProvider::new(config.services)),
             extension: Extension::new(&config.extensions).await?,
         })
     }
@@ -75,6 +75,11 @@ impl BackendBehaviour {
             .collect()
     }
 
+    /// Get the service provider, which is shared with local service listeners
+    pub fn service_provider(&self) -> Arc<ServiceProvider> {
+        self.server.clone()
+    }
+
     async fn handle_backend_message(
         &self,
         provider: Arc<Provider>,
@@ -93,7 +98,13 @@ impl BackendBehaviour {
                 tracing::info!("BackendMessage from {peer_did:?} PlainText: {text:?}");
                 Ok(())
             }
-            _ => Ok(()),
+            // Handled by [crate::backend::Backend] before reaching here.
+            BackendMessage::ReencryptMessage(_) => Ok(()),
+            #[cfg(feature = "snark")]
+            BackendMessage::SNARKTaskMessage(data) => {
+                let snark = provider.snark();
+                snark.handle_message(provider, payload, data).await
+            }
         }
     }
 }
@@ -1,6 +1,6 @@
 #![warn(missing_docs)]
 //! This module provides the implementation of the Server of Rings Service.
-//! Rings Service is a TCP/IP handler that can forward messages from a DHT (Distributed Hash Table) to
+//! Rings Service is a TCP/IP and UDP/IP handler that can forward messages from a DHT (Distributed Hash Table) to
 //! a local path. This module is essential for applications requiring decentralized network communication.
 //!
 //! # Service Config
@@ -14,29 +14,54 @@
 //! A Rings Service Provider is a structure that serves Rings Service. Sometimes referred to as
 //! "hidden-services," the Rings Service Provider exclusively handles the ServiceMessage type
 //! of BackendMessage. This component is crucial for managing the flow of messages within decentralized networks.
+//!
+//! # Udp Forward
+//!
+//! An Udp Forward Config describes a local UDP listener of client side. Datagrams received by the
+//! listener are forwarded to an UDP service of a remote peer, each source address is mapped to a tunnel.
+//!
+//! # Socks5 Proxy
+//!
+//! A Service Provider can also run a SOCKS5 proxy, which resolves hostname like `<service>.<did>`
+//! and opens a tcp tunnel to the service of peer. See [socks5] for details.
+pub mod socks5;
 mod tcp_proxy;
+mod udp_proxy;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
+use rings_core::dht::Did;
 use rings_core::message::MessagePayload;
 use rings_core::message::MessageVerificationExt;
 use rings_rpc::method::Method;
 use serde::Deserialize;
 use serde::Serialize;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
 
+use crate::backend::native::service::socks5::Socks5Reply;
 use crate::backend::native::service::tcp_proxy::tcp_connect_with_timeout;
 use crate::backend::native::service::tcp_proxy::Tunnel;
+use crate::backend::native::service::udp_proxy::UdpListener;
+use crate::backend::native::service::udp_proxy::UdpSession;
+use crate::backend::native::service::udp_proxy::UdpTunnel;
 use crate::backend::native::MessageHandler;
 use crate::backend::types::BackendMessage;
 use crate::backend::types::HttpRequest;
 use crate::backend::types::HttpResponse;
 use crate::backend::types::ServiceMessage;
+use crate::backend::types::TunnelDefeat;
 use crate::backend::types::TunnelId;
+use crate::consts::SOCKS5_DIAL_TIMEOUT;
 use crate::consts::TCP_SERVER_TIMEOUT;
+use crate::consts::UDP_MAX_TUNNELS_PER_PEER;
+use crate::consts::UDP_TUNNEL_IDLE_TIMEOUT;
 use crate::error::Error;
 use crate::error::Result;
 use crate::provider::Provider;
@@ -52,6 +77,42 @@ pub struct ServiceConfig {
 
     /// target address on server
     pub addr: SocketAddr,
+
+    /// transport protocol of service, default is tcp
+    #[serde(default)]
+    pub protocol: ServiceProtocol,
+}
+
+/// Transport protocol of a service
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceProtocol {
+    /// TCP stream, also used by http requests
+    #[default]
+    Tcp,
+    /// UDP datagram
+    Udp,
+}
+
+/// Udp Forward Config for creating a local UDP listener
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct UdpForwardConfig {
+    /// local address to listen on
+    pub listen: SocketAddr,
+
+    /// did of the peer which provides the service
+    pub peer_did: Did,
+
+    /// service name on the peer
+    pub service: String,
+
+    /// seconds before an idle tunnel is closed
+    #[serde(default = "default_udp_idle_timeout")]
+    pub idle_timeout: u64,
+}
+
+fn default_udp_idle_timeout() -> u64 {
+    UDP_TUNNEL_IDLE_TIMEOUT
 }
 
 /// Service Provider, which hold tunnel and a list of service
@@ -60,21 +121,173 @@ pub struct ServiceProvider {
     pub services: Vec<ServiceConfig>,
     /// Services tunnel, which is a HashMap of tunnel Id and Tunnel instance
     pub tunnels: DashMap<TunnelId, Tunnel>,
+    /// Udp tunnels of services, which is a HashMap of tunnel Id and UdpTunnel instance
+    pub udp_tunnels: Arc<DashMap<TunnelId, UdpTunnel>>,
+    /// Udp sessions of local udp listeners, which is a HashMap of tunnel Id and UdpSession instance
+    pub udp_sessions: Arc<DashMap<TunnelId, UdpSession>>,
+    /// Udp tunnels being connected of each peer, which are counted in the limit of tunnels
+    udp_connecting: DashMap<Did, usize>,
+    /// Pending dials of socks5 requests, notified by TcpConnected or TcpClose from the target peer
+    socks5_dials: DashMap<TunnelId, (Did, Socks5DialNotifier)>,
 }
 
+type Socks5DialNotifier = oneshot::Sender<std::result::Result<(), TunnelDefeat>>;
+
 impl ServiceProvider {
     /// Create a new ServiceProvider with a config list
     pub fn new(services: Vec<ServiceConfig>) -> Self {
         Self {
             services,
             tunnels: DashMap::new(),
+            udp_tunnels: Arc::new(DashMap::new()),
+            udp_sessions: Arc::new(DashMap::new()),
+            udp_connecting: DashMap::new(),
+            socks5_dials: DashMap::new(),
         }
     }
 
-    fn service(&self, name: &str) -> Option<&ServiceConfig> {
+    fn service(&self, name: &str, protocol: ServiceProtocol) -> Option<&ServiceConfig> {
         self.services
             .iter()
-            .find(|x| x.name.eq_ignore_ascii_case(name))
+            .find(|x| x.name.eq_ignore_ascii_case(name) && x.protocol == protocol)
+    }
+
+    fn udp_tunnels_of(&self, peer_did: Did) -> usize {
+        self.udp_tunnels
+            .iter()
+            .filter(|t| t.peer_did() == peer_did)
+            .count()
+    }
+
+    /// Reserve a slot of udp tunnel for peer before connecting to service.
+    /// The check and reservation are done under the lock of peer entry, so concurrent
+    /// datagrams of a peer can't exceed [UDP_MAX_TUNNELS_PER_PEER].
+    fn reserve_udp_tunnel(&self, peer_did: Did) -> Result<()> {
+        let entry = self.udp_connecting.entry(peer_did);
+        let connecting = match &entry {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(_) => 0,
+        };
+        if self.udp_tunnels_of(peer_did) + connecting >= UDP_MAX_TUNNELS_PER_PEER {
+            return Err(Error::TunnelLimitExceeded);
+        }
+        entry.insert(connecting + 1);
+        Ok(())
+    }
+
+    /// Release a reserved slot, after the tunnel is inserted or failed to connect.
+    fn release_udp_tunnel(&self, peer_did: Did) {
+        if let Entry::Occupied(mut entry) = self.udp_connecting.entry(peer_did) {
+            *entry.get_mut() -= 1;
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+    }
+
+    /// Listen on a local UDP address, and forward datagrams to an UDP service of peer.
+    /// This function will not return unless the local socket failed.
+    pub async fn listen_udp(
+        &self,
+        provider: Arc<Provider>,
+        config: &UdpForwardConfig,
+    ) -> Result<()> {
+        let listener = UdpListener::bind(
+            config.listen,
+            config.peer_did,
+            config.service.clone(),
+            self.udp_sessions.clone(),
+        )
+        .await
+        .map_err(Error::TunnelError)?;
+        tracing::info!(
+            "Udp forward {} to {} of {} started",
+            listener.local_addr().map_err(Error::TunnelError)?,
+            config.service,
+            config.peer_did
+        );
+        listener
+            .listen(provider, Duration::from_secs(config.idle_timeout))
+            .await
+            .map_err(Error::TunnelError)
+    }
+
+    /// Run a SOCKS5 proxy on a local address, CONNECT requests to `<service>.<did>` will be
+    /// tunnelled to the tcp service of peer.
+    /// This function will not return unless the local listener failed.
+    pub async fn listen_socks5(
+        self: Arc<Self>,
+        provider: Arc<Provider>,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| Error::Socks5Error(e.to_string()))?;
+        tracing::info!("Socks5 proxy listening on {addr}");
+        loop {
+            let (stream, src) = listener
+                .accept()
+                .await
+                .map_err(|e| Error::Socks5Error(e.to_string()))?;
+            let this = self.clone();
+            let provider = provider.clone();
+            tokio::spawn(async move {
+                if let Err(e) = this.handle_socks5_stream(provider, stream).await {
+                    tracing::warn!("Socks5 request from {src} failed: {e}");
+                }
+            });
+        }
+    }
+
+    async fn handle_socks5_stream(
+        &self,
+        provider: Arc<Provider>,
+        mut stream: TcpStream,
+    ) -> Result<()> {
+        let target = socks5::handshake(&mut stream).await?;
+        let tid = uuid::Uuid::new_v4();
+        tracing::info!(
+            "Socks5 open tunnel {tid} to {} of {}",
+            target.service,
+            target.did
+        );
+
+        // Packages arrived before the local stream is ready will be buffered by tunnel
+        self.tunnels.insert(tid, Tunnel::new(tid));
+        let (dial_tx, dial_rx) = oneshot::channel();
+        self.socks5_dials.insert(tid, (target.did, dial_tx));
+        let msg = ServiceMessage::TcpDial {
+            tid,
+            service: target.service,
+        };
+        let backend_message: BackendMessage = msg.into();
+        let params = backend_message.into_send_backend_message_request(target.did)?;
+        if let Err(e) = provider.request(Method::SendBackendMessage, params).await {
+            self.tunnels.remove(&tid);
+            self.socks5_dials.remove(&tid);
+            socks5::reply(&mut stream, Socks5Reply::HostUnreachable).await?;
+            return Err(e);
+        }
+
+        // Reply the client after the peer connected to its service
+        let dialed =
+            match tokio::time::timeout(Duration::from_secs(SOCKS5_DIAL_TIMEOUT), dial_rx).await {
+                Ok(Ok(dialed)) => dialed,
+                _ => Err(TunnelDefeat::ConnectionTimeout),
+            };
+        self.socks5_dials.remove(&tid);
+        if let Err(reason) = dialed {
+            self.tunnels.remove(&tid);
+            socks5::reply(&mut stream, reason.into()).await?;
+            return Err(Error::TunnelError(reason));
+        }
+        socks5::reply(&mut stream, Socks5Reply::Succeeded).await?;
+
+        // The tunnel may be closed by peer already
+        if let Some(mut tunnel) = self.tunnels.get_mut(&tid) {
+            tunnel.listen(provider, stream, target.did);
+        }
+        Ok(())
     }
 
     async fn do_handle_message(
@@ -87,9 +300,14 @@ impl ServiceProvider {
 
         match msg {
             ServiceMessage::TcpDial { tid, service } => {
-                let service = self.service(service).ok_or(Error::InvalidService)?;
+                let service = self
+                    .service(service, ServiceProtocol::Tcp)
+                    .ok_or(Error::InvalidService)?;
+                // Packages arrived during dialing will be buffered by tunnel
+                self.tunnels.insert(*tid, Tunnel::new(*tid));
                 match tcp_connect_with_timeout(service.addr, TCP_SERVER_TIMEOUT).await {
                     Err(e) => {
+                        self.tunnels.remove(tid);
                         let msg = ServiceMessage::TcpClose {
                             tid: *tid,
                             reason: e,
@@ -101,29 +319,52 @@ impl ServiceProvider {
                     }
 
                     Ok(local_stream) => {
-                        let mut tunnel = Tunnel::new(*tid);
-                        tunnel
-                            .listen(provider.clone(), local_stream, peer_did)
-                            .await;
-                        self.tunnels.insert(*tid, tunnel);
+                        // The tunnel may be closed by peer during dialing
+                        match self.tunnels.get_mut(tid) {
+                            Some(mut tunnel) => {
+                                tunnel.listen(provider.clone(), local_stream, peer_did)
+                            }
+                            None => return Ok(()),
+                        }
+                        let msg = ServiceMessage::TcpConnected { tid: *tid };
+                        let backend_message: BackendMessage = msg.into();
+                        let params = backend_message.into_send_backend_message_request(peer_did)?;
+                        provider.request(Method::SendBackendMessage, params).await?;
                         Ok(())
                     }
                 }
             }
-            ServiceMessage::TcpClose { tid, .. } => {
+            ServiceMessage::TcpConnected { tid } => {
+                if let Some((_, (_, dial_tx))) = self
+                    .socks5_dials
+                    .remove_if(tid, |_, (did, _)| *did == peer_did)
+                {
+                    let _ = dial_tx.send(Ok(()));
+                }
+                Ok(())
+            }
+            ServiceMessage::TcpClose { tid, reason } => {
                 self.tunnels.remove(tid);
+                if let Some((_, (_, dial_tx))) = self
+                    .socks5_dials
+                    .remove_if(tid, |_, (did, _)| *did == peer_did)
+                {
+                    let _ = dial_tx.send(Err(*reason));
+                }
                 Ok(())
             }
             ServiceMessage::TcpPackage { tid, body } => {
-                self.tunnels
-                    .get(tid)
-                    .ok_or(Error::TunnelNotFound)?
-                    .send(body.clone())
-                    .await;
+                // Clone the sender, the tunnel should not be held across await
+                let tunnel_tx = self.tunnels.get(tid).ok_or(Error::TunnelNotFound)?.sender();
+                if let Err(e) = tunnel_tx.send(body.clone()).await {
+                    tracing::error!("Tunnel {tid} send to remote stream tx failed: {e:?}");
+                }
                 Ok(())
             }
             ServiceMessage::HttpRequest(req) => {
-                let service = self.service(&req.service).ok_or(Error::InvalidService)?;
+                let service = self
+                    .service(&req.service, ServiceProtocol::Tcp)
+                    .ok_or(Error::InvalidService)?;
                 let resp = handle_http_request(service.addr, req).await?;
                 let backend_message: BackendMessage = ServiceMessage::HttpResponse(resp).into();
                 let params = backend_message.into_send_backend_message_request(peer_did)?;
@@ -135,6 +376,72 @@ impl ServiceProvider {
                 tracing::info!("ServiceMessage from {peer_did:?} HttpResponse: {resp:?}");
                 Ok(())
             }
+            ServiceMessage::UdpDatagram { tid, service, body } => {
+                // Response of a local udp listener
+                let session = self.udp_sessions.get(tid).map(|s| s.value().clone());
+                if let Some(session) = session {
+                    if session.peer_did() != peer_did {
+                        return Err(Error::TunnelNotOwned);
+                    }
+                    return session.send(body).await.map_err(Error::TunnelError);
+                }
+
+                let tunnel = self.udp_tunnels.get(tid).map(|t| t.value().clone());
+                let tunnel = match tunnel {
+                    Some(tunnel) => tunnel,
+                    None => {
+                        let config = self
+                            .service(service, ServiceProtocol::Udp)
+                            .ok_or(Error::InvalidService)?;
+                        self.reserve_udp_tunnel(peer_did)?;
+                        match UdpTunnel::connect(*tid, peer_did, config.addr).await {
+                            Err(e) => {
+                                self.release_udp_tunnel(peer_did);
+                                let msg = ServiceMessage::UdpClose {
+                                    tid: *tid,
+                                    reason: e,
+                                };
+                                let backend_message: BackendMessage = msg.into();
+                                let params =
+                                    backend_message.into_send_backend_message_request(peer_did)?;
+                                provider.request(Method::SendBackendMessage, params).await?;
+                                return Err(Error::TunnelError(e));
+                            }
+                            Ok(tunnel) => {
+                                let tunnel = match self.udp_tunnels.entry(*tid) {
+                                    // The tunnel may be opened by another datagram during connecting
+                                    Entry::Occupied(entry) => entry.get().clone(),
+                                    Entry::Vacant(entry) => {
+                                        tunnel.listen(
+                                            provider.clone(),
+                                            service.clone(),
+                                            self.udp_tunnels.clone(),
+                                            Duration::from_secs(UDP_TUNNEL_IDLE_TIMEOUT),
+                                        );
+                                        entry.insert(tunnel.clone());
+                                        tunnel
+                                    }
+                                };
+                                // Released after insertion, so the tunnel is always counted.
+                                self.release_udp_tunnel(peer_did);
+                                tunnel
+                            }
+                        }
+                    }
+                };
+                if tunnel.peer_did() != peer_did {
+                    return Err(Error::TunnelNotOwned);
+                }
+                tunnel.send(body).await.map_err(Error::TunnelError)
+            }
+            ServiceMessage::UdpClose { tid, reason } => {
+                tracing::info!("UdpTunnel {tid} closed by {peer_did:?}: {reason:?}");
+                self.udp_tunnels
+                    .remove_if(tid, |_, tunnel| tunnel.peer_did() == peer_did);
+                self.udp_sessions
+                    .remove_if(tid, |_, session| session.peer_did() == peer_did);
+                Ok(())
+            }
         }
     }
 }
@@ -201,3 +508,189 @@ async fn handle_http_request(addr: SocketAddr, req: &HttpRequest) -> Result<Http
         rid: req.rid.clone(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rings_core::ecc::SecretKey;
+    use rings_core::session::SessionSk;
+    use tokio::net::UdpSocket;
+
+    use super::*;
+    use crate::tests::native::prepare_processor;
+
+    async fn prepare_provider() -> Arc<Provider> {
+        Arc::new(Provider::from_processor(Arc::new(
+            prepare_processor().await,
+        )))
+    }
+
+    fn udp_service(addr: SocketAddr) -> ServiceProvider {
+        ServiceProvider::new(vec![ServiceConfig {
+            name: "echo".to_string(),
+            register_service: None,
+            addr,
+            protocol: ServiceProtocol::Udp,
+        }])
+    }
+
+    fn datagram(tid: TunnelId, body: &'static [u8]) -> ServiceMessage {
+        ServiceMessage::UdpDatagram {
+            tid,
+            service: "echo".to_string(),
+            body: Bytes::from_static(body),
+        }
+    }
+
+    fn payload_from(key: &SecretKey, msg: &ServiceMessage) -> MessagePayload {
+        let session_sk = SessionSk::new_with_seckey(key).unwrap();
+        let did: Did = key.address().into();
+        let backend_message: BackendMessage = msg.clone().into();
+        MessagePayload::new_send(backend_message, &session_sk, did, did).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_udp_datagram_round_trip() {
+        let provider = prepare_provider().await;
+        let peer = SecretKey::random();
+        let attacker = SecretKey::random();
+        let service_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let service_provider = Arc::new(udp_service(service_socket.local_addr().unwrap()));
+        let mut buf = [0u8; 16];
+
+        // Server side, the first datagram of peer opens a tunnel to local service.
+        let tid = uuid::Uuid::new_v4();
+        let msg = datagram(tid, b"ping");
+        service_provider
+            .do_handle_message(provider.clone(), &payload_from(&peer, &msg), &msg)
+            .await
+            .unwrap();
+        let (n, _) = service_socket.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"ping");
+
+        // Datagrams and close from other peers cannot hijack the tunnel.
+        let msg = datagram(tid, b"inject");
+        assert!(service_provider
+            .do_handle_message(provider.clone(), &payload_from(&attacker, &msg), &msg)
+            .await
+            .is_err());
+        let close = ServiceMessage::UdpClose {
+            tid,
+            reason: TunnelDefeat::ConnectionClosed,
+        };
+        service_provider
+            .do_handle_message(provider.clone(), &payload_from(&attacker, &close), &close)
+            .await
+            .unwrap();
+        assert!(service_provider.udp_tunnels.contains_key(&tid));
+
+        // Client side, datagram of local application is mapped to a session,
+        // and the response from peer is sent back to the application.
+        let listener = UdpListener::bind(
+            "127.0.0.1:0".parse().unwrap(),
+            peer.address().into(),
+            "echo".to_string(),
+            service_provider.udp_sessions.clone(),
+        )
+        .await
+        .unwrap();
+        let listen_addr = listener.local_addr().unwrap();
+        let listen_provider = provider.clone();
+        tokio::spawn(async move {
+            listener
+                .listen(
+                    listen_provider,
+                    Duration::from_secs(UDP_TUNNEL_IDLE_TIMEOUT),
+                )
+                .await
+        });
+
+        let app = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        app.send_to(b"ping", listen_addr).await.unwrap();
+        let session_tid = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(s) = service_provider.udp_sessions.iter().next() {
+                    break *s.key();
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let msg = datagram(session_tid, b"inject");
+        assert!(service_provider
+            .do_handle_message(provider.clone(), &payload_from(&attacker, &msg), &msg)
+            .await
+            .is_err());
+
+        let msg = datagram(session_tid, b"pong");
+        service_provider
+            .do_handle_message(provider.clone(), &payload_from(&peer, &msg), &msg)
+            .await
+            .unwrap();
+        let (n, src) = app.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"pong");
+        assert_eq!(src, listen_addr);
+    }
+
+    #[tokio::test]
+    async fn test_udp_tunnels_limit_per_peer() {
+        let provider = prepare_provider().await;
+        let peer = SecretKey::random();
+        let other = SecretKey::random();
+        let service_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let service_provider = udp_service(service_socket.local_addr().unwrap());
+
+        for _ in 0..UDP_MAX_TUNNELS_PER_PEER {
+            let msg = datagram(uuid::Uuid::new_v4(), b"ping");
+            service_provider
+                .do_handle_message(provider.clone(), &payload_from(&peer, &msg), &msg)
+                .await
+                .unwrap();
+        }
+
+        let msg = datagram(uuid::Uuid::new_v4(), b"ping");
+        assert!(matches!(
+            service_provider
+                .do_handle_message(provider.clone(), &payload_from(&peer, &msg), &msg)
+                .await,
+            Err(Error::TunnelLimitExceeded)
+        ));
+        service_provider
+            .do_handle_message(provider.clone(), &payload_from(&other, &msg), &msg)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_udp_tunnels_limit_per_peer() {
+        let provider = prepare_provider().await;
+        let peer = SecretKey::random();
+        let service_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let service_provider = udp_service(service_socket.local_addr().unwrap());
+
+        let msgs = (0..UDP_MAX_TUNNELS_PER_PEER + 8)
+            .map(|_| datagram(uuid::Uuid::new_v4(), b"ping"))
+            .collect::<Vec<_>>();
+        let payloads = msgs
+            .iter()
+            .map(|msg| payload_from(&peer, msg))
+            .collect::<Vec<_>>();
+        let results =
+            futures::future::join_all(msgs.iter().zip(payloads.iter()).map(|(msg, payload)| {
+                service_provider.do_handle_message(provider.clone(), payload, msg)
+            }))
+            .await;
+
+        assert_eq!(
+            results.iter().filter(|r| r.is_ok()).count(),
+            UDP_MAX_TUNNELS_PER_PEER
+        );
+        assert_eq!(
+            service_provider.udp_tunnels_of(peer.address().into()),
+            UDP_MAX_TUNNELS_PER_PEER
+        );
+        assert!(service_provider.udp_connecting.is_empty());
+    }
+}
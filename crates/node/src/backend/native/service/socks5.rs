@@ -0,0 +1,253 @@
+#![warn(missing_docs)]
+//! Module socks5 provide a SOCKS5 front-end of Rings Service.
+//!
+//! Applications which are not aware of Rings Network can reach services of peers by setting
+//! a SOCKS5 proxy. The hostname of CONNECT request should be `<service>.<did>`, and an optional
+//! `.rings` suffix is allowed, such as `web.0x11e807fcc88dd319270493fb2e822e388fe36ab0.rings`.
+//! The port of request is ignored, since the target address is decided by service config of peer.
+//!
+//! Only the `NO AUTHENTICATION REQUIRED` method and `CONNECT` command of RFC 1928 are supported.
+use std::str::FromStr;
+
+use rings_core::dht::Did;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+
+use crate::backend::types::TunnelDefeat;
+use crate::error::Error;
+use crate::error::Result;
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_METHOD_NO_AUTH: u8 = 0x00;
+const SOCKS5_METHOD_NO_ACCEPTABLE: u8 = 0xFF;
+const SOCKS5_CMD_CONNECT: u8 = 0x01;
+const SOCKS5_ATYP_IPV4: u8 = 0x01;
+const SOCKS5_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS5_ATYP_IPV6: u8 = 0x04;
+const RINGS_HOST_SUFFIX: &str = ".rings";
+
+/// Reply field of SOCKS5 response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Socks5Reply {
+    /// succeeded
+    Succeeded = 0x00,
+    /// general SOCKS server failure
+    GeneralFailure = 0x01,
+    /// host unreachable
+    HostUnreachable = 0x04,
+    /// connection refused
+    ConnectionRefused = 0x05,
+    /// command not supported
+    CommandNotSupported = 0x07,
+    /// address type not supported
+    AddressTypeNotSupported = 0x08,
+}
+
+impl From<TunnelDefeat> for Socks5Reply {
+    fn from(defeat: TunnelDefeat) -> Self {
+        match defeat {
+            TunnelDefeat::ConnectionRefused => Socks5Reply::ConnectionRefused,
+            TunnelDefeat::ConnectionTimeout | TunnelDefeat::WebrtcDatachannelSendFailed => {
+                Socks5Reply::HostUnreachable
+            }
+            _ => Socks5Reply::GeneralFailure,
+        }
+    }
+}
+
+/// Target of a SOCKS5 CONNECT request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Socks5Target {
+    /// service name on peer
+    pub service: String,
+    /// did of peer
+    pub did: Did,
+}
+
+impl FromStr for Socks5Target {
+    type Err = Error;
+
+    fn from_str(host: &str) -> Result<Self> {
+        let host = host.trim_end_matches('.');
+        let host = host.strip_suffix(RINGS_HOST_SUFFIX).unwrap_or(host);
+        let (service, did) = host.rsplit_once('.').ok_or(Error::InvalidAddress)?;
+        if service.is_empty() {
+            return Err(Error::InvalidService);
+        }
+        let did = Did::from_str(did).map_err(|_| Error::InvalidDid(did.to_string()))?;
+        Ok(Self {
+            service: service.to_string(),
+            did,
+        })
+    }
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::Socks5Error(e.to_string())
+}
+
+/// Negotiate with a SOCKS5 client, and read the target of CONNECT request.
+/// Requests which cannot be served are replied here, otherwise the caller
+/// should send a reply by [reply] after the tunnel is opened.
+pub async fn handshake<S>(stream: &mut S) -> Result<Socks5Target>
+where S: AsyncRead + AsyncWrite + Unpin {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await.map_err(io_error)?;
+    if header[0] != SOCKS5_VERSION {
+        return Err(Error::Socks5Error(format!(
+            "unsupported version {}",
+            header[0]
+        )));
+    }
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods).await.map_err(io_error)?;
+    if !methods.contains(&SOCKS5_METHOD_NO_AUTH) {
+        stream
+            .write_all(&[SOCKS5_VERSION, SOCKS5_METHOD_NO_ACCEPTABLE])
+            .await
+            .map_err(io_error)?;
+        return Err(Error::Socks5Error("no acceptable methods".to_string()));
+    }
+    stream
+        .write_all(&[SOCKS5_VERSION, SOCKS5_METHOD_NO_AUTH])
+        .await
+        .map_err(io_error)?;
+
+    // VER | CMD | RSV | ATYP
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await.map_err(io_error)?;
+    if request[0] != SOCKS5_VERSION {
+        return Err(Error::Socks5Error(format!(
+            "unsupported version {}",
+            request[0]
+        )));
+    }
+    if request[1] != SOCKS5_CMD_CONNECT {
+        reply(stream, Socks5Reply::CommandNotSupported).await?;
+        return Err(Error::Socks5Error(format!(
+            "unsupported command {}",
+            request[1]
+        )));
+    }
+    let host = match request[3] {
+        SOCKS5_ATYP_DOMAIN => {
+            let len = stream.read_u8().await.map_err(io_error)?;
+            let mut host = vec![0u8; len as usize];
+            stream.read_exact(&mut host).await.map_err(io_error)?;
+            String::from_utf8(host).map_err(|e| Error::Socks5Error(e.to_string()))?
+        }
+        SOCKS5_ATYP_IPV4 | SOCKS5_ATYP_IPV6 => {
+            reply(stream, Socks5Reply::AddressTypeNotSupported).await?;
+            return Err(Error::Socks5Error(
+                "ip address is not supported, use `<service>.<did>` as hostname".to_string(),
+            ));
+        }
+        atyp => {
+            reply(stream, Socks5Reply::AddressTypeNotSupported).await?;
+            return Err(Error::Socks5Error(format!(
+                "unsupported address type {}",
+                atyp
+            )));
+        }
+    };
+    // The port is ignored, address of service is decided by peer
+    let _port = stream.read_u16().await.map_err(io_error)?;
+
+    match Socks5Target::from_str(&host) {
+        Ok(target) => Ok(target),
+        Err(e) => {
+            reply(stream, Socks5Reply::HostUnreachable).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Reply a SOCKS5 request, the bound address is always `0.0.0.0:0`.
+pub async fn reply<S>(stream: &mut S, rep: Socks5Reply) -> Result<()>
+where S: AsyncWrite + Unpin {
+    stream
+        .write_all(&[
+            SOCKS5_VERSION,
+            rep as u8,
+            0x00,
+            SOCKS5_ATYP_IPV4,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ])
+        .await
+        .map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DID: &str = "0x11e807fcc88dd319270493fb2e822e388fe36ab0";
+
+    #[test]
+    fn test_parse_target() {
+        let did = Did::from_str(DID).unwrap();
+
+        let target = Socks5Target::from_str(&format!("web.{DID}")).unwrap();
+        assert_eq!(target.service, "web");
+        assert_eq!(target.did, did);
+
+        let target = Socks5Target::from_str(&format!("ipfs.gateway.{DID}.rings")).unwrap();
+        assert_eq!(target.service, "ipfs.gateway");
+        assert_eq!(target.did, did);
+
+        assert!(Socks5Target::from_str(DID).is_err());
+        assert!(Socks5Target::from_str(&format!(".{DID}")).is_err());
+        assert!(Socks5Target::from_str("web.example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handshake() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let host = format!("web.{DID}.rings");
+
+        let mut request = vec![SOCKS5_VERSION, 1, SOCKS5_METHOD_NO_AUTH];
+        request.extend([SOCKS5_VERSION, SOCKS5_CMD_CONNECT, 0, SOCKS5_ATYP_DOMAIN]);
+        request.push(host.len() as u8);
+        request.extend(host.as_bytes());
+        request.extend(80u16.to_be_bytes());
+        client.write_all(&request).await.unwrap();
+
+        let target = handshake(&mut server).await.unwrap();
+        assert_eq!(target.service, "web");
+        assert_eq!(target.did, Did::from_str(DID).unwrap());
+        reply(&mut server, Socks5Reply::Succeeded).await.unwrap();
+
+        let mut resp = [0u8; 12];
+        client.read_exact(&mut resp).await.unwrap();
+        assert_eq!(resp[..2], [SOCKS5_VERSION, SOCKS5_METHOD_NO_AUTH]);
+        assert_eq!(resp[2..4], [SOCKS5_VERSION, Socks5Reply::Succeeded as u8]);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_reject_ip_address() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        let mut request = vec![SOCKS5_VERSION, 1, SOCKS5_METHOD_NO_AUTH];
+        request.extend([SOCKS5_VERSION, SOCKS5_CMD_CONNECT, 0, SOCKS5_ATYP_IPV4]);
+        request.extend([127, 0, 0, 1]);
+        request.extend(80u16.to_be_bytes());
+        client.write_all(&request).await.unwrap();
+
+        assert!(handshake(&mut server).await.is_err());
+
+        let mut resp = [0u8; 4];
+        client.read_exact(&mut resp).await.unwrap();
+        assert_eq!(resp[2..4], [
+            SOCKS5_VERSION,
+            Socks5Reply::AddressTypeNotSupported as u8
+        ]);
+    }
+}
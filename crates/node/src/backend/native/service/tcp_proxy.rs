@@ -23,7 +23,8 @@ use crate::provider::Provider;
 /// Abstract Tcp Tunnel
 pub struct Tunnel {
     tid: TunnelId,
-    remote_stream_tx: Option<mpsc::Sender<Bytes>>,
+    remote_stream_tx: mpsc::Sender<Bytes>,
+    remote_stream_rx: Option<mpsc::Receiver<Bytes>>,
     listener_cancel_token: Option<CancellationToken>,
     listener: Option<tokio::task::JoinHandle<()>>,
 }
@@ -32,7 +33,6 @@ pub struct Tunnel {
 pub struct TunnelListener {
     tid: TunnelId,
     local_stream: TcpStream,
-    remote_stream_tx: mpsc::Sender<Bytes>,
     remote_stream_rx: mpsc::Receiver<Bytes>,
     peer_did: Did,
     cancel_token: CancellationToken,
@@ -56,57 +56,53 @@ impl Drop for Tunnel {
 }
 
 impl Tunnel {
-    /// Create a new tunnel with a given tunnel Id
+    /// Create a new tunnel with a given tunnel Id.
+    /// Bytes sent to the tunnel before listening are buffered in channel.
     pub fn new(tid: TunnelId) -> Self {
+        let (remote_stream_tx, remote_stream_rx) = mpsc::channel(1024);
         Self {
             tid,
-            remote_stream_tx: None,
+            remote_stream_tx,
+            remote_stream_rx: Some(remote_stream_rx),
             listener: None,
             listener_cancel_token: None,
         }
     }
 
-    /// Send bytes to tunnel via channel
-    pub async fn send(&self, bytes: Bytes) {
-        if let Some(ref tx) = self.remote_stream_tx {
-            let _ = tx.send(bytes).await;
-        } else {
-            tracing::error!("Tunnel {} remote stream tx is none", self.tid);
-        }
+    /// Get sender of the channel, bytes sent to it will be written to local stream.
+    /// It's cloned so that the tunnel needs not to be held during sending.
+    pub fn sender(&self) -> mpsc::Sender<Bytes> {
+        self.remote_stream_tx.clone()
     }
 
     /// Start listen a local stream, this function will spawn a thread which
     /// listening the inbound messages
-    pub async fn listen(
-        &mut self,
-        provider: Arc<Provider>,
-        local_stream: TcpStream,
-        peer_did: Did,
-    ) {
-        if self.listener.is_some() {
+    pub fn listen(&mut self, provider: Arc<Provider>, local_stream: TcpStream, peer_did: Did) {
+        let Some(remote_stream_rx) = self.remote_stream_rx.take() else {
             return;
-        }
+        };
         let provider = provider.clone();
-        let mut listener = TunnelListener::new(self.tid, local_stream, peer_did).await;
+        let mut listener = TunnelListener::new(self.tid, local_stream, remote_stream_rx, peer_did);
         let listener_cancel_token = listener.cancel_token();
-        let remote_stream_tx = listener.remote_stream_tx.clone();
         let listener_handler =
             tokio::spawn(Box::pin(async move { listener.listen(provider).await }));
 
-        self.remote_stream_tx = Some(remote_stream_tx);
         self.listener = Some(listener_handler);
         self.listener_cancel_token = Some(listener_cancel_token);
     }
 }
 
 impl TunnelListener {
-    /// Create a new listener instance with TcpStream, tunnel id, and did of a target peer
-    async fn new(tid: TunnelId, local_stream: TcpStream, peer_did: Did) -> Self {
-        let (remote_stream_tx, remote_stream_rx) = mpsc::channel(1024);
+    /// Create a new listener instance with TcpStream, tunnel id, receiver of tunnel, and did of a target peer
+    fn new(
+        tid: TunnelId,
+        local_stream: TcpStream,
+        remote_stream_rx: mpsc::Receiver<Bytes>,
+        peer_did: Did,
+    ) -> Self {
         Self {
             tid,
             local_stream,
-            remote_stream_tx,
             remote_stream_rx,
             peer_did,
             cancel_token: CancellationToken::new(),
@@ -160,11 +156,12 @@ impl TunnelListener {
                     break TunnelDefeat::ConnectionClosed;
                 }
 
-                if let Some(body) = self.remote_stream_rx.recv().await {
-                    if let Err(e) = local_write.write_all(&body).await {
-                        tracing::error!("Write to local stream failed: {e:?}");
-                        break e.kind().into();
-                    }
+                let Some(body) = self.remote_stream_rx.recv().await else {
+                    break TunnelDefeat::ConnectionClosed;
+                };
+                if let Err(e) = local_write.write_all(&body).await {
+                    tracing::error!("Write to local stream failed: {e:?}");
+                    break e.kind().into();
                 }
             }
         };
@@ -0,0 +1,318 @@
+#![warn(missing_docs)]
+//! Module udp_proxy provide implementation of UDP/IP based services
+//!
+//! Unlike TCP, UDP has no dial and close handshake. A tunnel is opened by the first
+//! [ServiceMessage::UdpDatagram] of a tunnel id, and closed by [ServiceMessage::UdpClose]
+//! or when no datagram is transferred during the idle timeout.
+//!
+//! A tunnel belongs to the peer who opened it, datagrams and close of the same tunnel id from
+//! other peers are rejected. Each peer can open at most
+//! [UDP_MAX_TUNNELS_PER_PEER](crate::consts::UDP_MAX_TUNNELS_PER_PEER) tunnels.
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use rings_core::dht::Did;
+use rings_rpc::method::Method;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tokio_util::sync::DropGuard;
+
+use crate::backend::types::BackendMessage;
+use crate::backend::types::ServiceMessage;
+use crate::backend::types::TunnelDefeat;
+use crate::backend::types::TunnelId;
+use crate::consts::UDP_MAX_DATAGRAM_SIZE;
+use crate::error::Result;
+use crate::provider::Provider;
+
+/// Last active time of a tunnel, shared between the tunnel and its listener
+#[derive(Clone)]
+struct Activity(Arc<Mutex<Instant>>);
+
+impl Activity {
+    fn new() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+
+    fn touch(&self) {
+        if let Ok(mut t) = self.0.lock() {
+            *t = Instant::now();
+        }
+    }
+
+    fn is_idle(&self, idle_timeout: Duration) -> bool {
+        self.0
+            .lock()
+            .map(|t| t.elapsed() >= idle_timeout)
+            .unwrap_or(true)
+    }
+}
+
+/// Server side of an Udp Tunnel, holding a socket connected to the local service.
+/// The listener of tunnel will be cancelled when the last clone of tunnel dropped.
+#[derive(Clone)]
+pub struct UdpTunnel {
+    tid: TunnelId,
+    peer_did: Did,
+    socket: Arc<UdpSocket>,
+    activity: Activity,
+    cancel_token: CancellationToken,
+    _cancel_guard: Arc<DropGuard>,
+}
+
+/// Client side session of an Udp Tunnel, which remember the source address
+/// of local application, response datagrams will be sent back to it.
+#[derive(Clone)]
+pub struct UdpSession {
+    socket: Arc<UdpSocket>,
+    peer_did: Did,
+    src: SocketAddr,
+    activity: Activity,
+}
+
+/// Client side of Udp Tunnels, listening on a local address.
+/// Each source address is mapped to a tunnel towards service of peer.
+pub struct UdpListener {
+    socket: Arc<UdpSocket>,
+    peer_did: Did,
+    service: String,
+    sessions: Arc<DashMap<TunnelId, UdpSession>>,
+}
+
+impl UdpTunnel {
+    /// Bind an ephemeral local socket, and connect it to address of service.
+    /// The tunnel belongs to the peer who opened it.
+    pub async fn connect(
+        tid: TunnelId,
+        peer_did: Did,
+        addr: SocketAddr,
+    ) -> std::result::Result<Self, TunnelDefeat> {
+        let bind_addr: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(bind_addr)
+            .await
+            .map_err(|e| TunnelDefeat::from(e.kind()))?;
+        socket
+            .connect(addr)
+            .await
+            .map_err(|e| TunnelDefeat::from(e.kind()))?;
+
+        let cancel_token = CancellationToken::new();
+        Ok(Self {
+            tid,
+            peer_did,
+            socket: Arc::new(socket),
+            activity: Activity::new(),
+            _cancel_guard: Arc::new(cancel_token.clone().drop_guard()),
+            cancel_token,
+        })
+    }
+
+    /// Did of the peer who opened the tunnel
+    pub fn peer_did(&self) -> Did {
+        self.peer_did
+    }
+
+    /// Send a datagram to local service
+    pub async fn send(&self, body: &[u8]) -> std::result::Result<(), TunnelDefeat> {
+        self.activity.touch();
+        self.socket
+            .send(body)
+            .await
+            .map(|_| ())
+            .map_err(|e| TunnelDefeat::from(e.kind()))
+    }
+
+    /// Start listen datagrams from local service, this function will spawn a task which
+    /// forward datagrams to peer. When the tunnel is idle, it will remove itself from tunnels
+    /// and notify the peer.
+    pub fn listen(
+        &self,
+        provider: Arc<Provider>,
+        service: String,
+        tunnels: Arc<DashMap<TunnelId, UdpTunnel>>,
+        idle_timeout: Duration,
+    ) {
+        let tid = self.tid;
+        let peer_did = self.peer_did;
+        let socket = self.socket.clone();
+        let activity = self.activity.clone();
+        let cancel_token = self.cancel_token.clone();
+
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; UDP_MAX_DATAGRAM_SIZE];
+            let mut ticker = tokio::time::interval(idle_timeout);
+
+            let defeat = loop {
+                tokio::select! {
+                    _ = cancel_token.cancelled() => {
+                        break None;
+                    }
+                    _ = ticker.tick() => {
+                        if activity.is_idle(idle_timeout) {
+                            break Some(TunnelDefeat::IdleTimeout);
+                        }
+                    }
+                    r = socket.recv(&mut buf) => match r {
+                        Err(e) => {
+                            break Some(e.kind().into());
+                        }
+                        Ok(n) => {
+                            activity.touch();
+                            let msg = ServiceMessage::UdpDatagram {
+                                tid,
+                                service: service.clone(),
+                                body: Bytes::copy_from_slice(&buf[..n]),
+                            };
+                            if let Err(e) = send_service_message(&provider, peer_did, msg).await {
+                                tracing::error!("Send UdpDatagram message failed: {e:?}");
+                                break Some(TunnelDefeat::WebrtcDatachannelSendFailed);
+                            }
+                        }
+                    }
+                }
+            };
+
+            if let Some(reason) = defeat {
+                tracing::info!("UdpTunnel {tid} closed: {reason:?}");
+                tunnels.remove(&tid);
+                let msg = ServiceMessage::UdpClose { tid, reason };
+                if let Err(e) = send_service_message(&provider, peer_did, msg).await {
+                    tracing::error!("Send UdpClose message failed: {e:?}");
+                }
+            }
+        });
+    }
+}
+
+impl UdpSession {
+    /// Did of the peer which provides the service
+    pub fn peer_did(&self) -> Did {
+        self.peer_did
+    }
+
+    /// Send a datagram back to local application
+    pub async fn send(&self, body: &[u8]) -> std::result::Result<(), TunnelDefeat> {
+        self.activity.touch();
+        self.socket
+            .send_to(body, self.src)
+            .await
+            .map(|_| ())
+            .map_err(|e| TunnelDefeat::from(e.kind()))
+    }
+}
+
+impl UdpListener {
+    /// Bind a local address for tunnels towards service of peer
+    pub async fn bind(
+        addr: SocketAddr,
+        peer_did: Did,
+        service: String,
+        sessions: Arc<DashMap<TunnelId, UdpSession>>,
+    ) -> std::result::Result<Self, TunnelDefeat> {
+        let socket = UdpSocket::bind(addr)
+            .await
+            .map_err(|e| TunnelDefeat::from(e.kind()))?;
+        Ok(Self {
+            socket: Arc::new(socket),
+            peer_did,
+            service,
+            sessions,
+        })
+    }
+
+    /// Local address of listener
+    pub fn local_addr(&self) -> std::result::Result<SocketAddr, TunnelDefeat> {
+        self.socket
+            .local_addr()
+            .map_err(|e| TunnelDefeat::from(e.kind()))
+    }
+
+    /// Listen datagrams from local applications and forward them to peer.
+    /// Sessions that are idle will be closed and the peer will be notified.
+    pub async fn listen(
+        &self,
+        provider: Arc<Provider>,
+        idle_timeout: Duration,
+    ) -> std::result::Result<(), TunnelDefeat> {
+        let mut tids: HashMap<SocketAddr, TunnelId> = HashMap::new();
+        let mut buf = vec![0u8; UDP_MAX_DATAGRAM_SIZE];
+        let mut ticker = tokio::time::interval(idle_timeout);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let mut expired = vec![];
+                    tids.retain(|_, tid| match self.sessions.get(tid) {
+                        // closed by peer
+                        None => false,
+                        Some(s) if s.activity.is_idle(idle_timeout) => {
+                            expired.push(*tid);
+                            false
+                        }
+                        Some(_) => true,
+                    });
+                    for tid in expired {
+                        tracing::info!("UdpSession {tid} closed: idle");
+                        self.sessions.remove(&tid);
+                        let msg = ServiceMessage::UdpClose {
+                            tid,
+                            reason: TunnelDefeat::IdleTimeout,
+                        };
+                        if let Err(e) = send_service_message(&provider, self.peer_did, msg).await {
+                            tracing::error!("Send UdpClose message failed: {e:?}");
+                        }
+                    }
+                }
+                r = self.socket.recv_from(&mut buf) => {
+                    let (n, src) = r.map_err(|e| TunnelDefeat::from(e.kind()))?;
+                    let tid = match tids.get(&src) {
+                        Some(tid) if self.sessions.contains_key(tid) => *tid,
+                        _ => {
+                            let tid = uuid::Uuid::new_v4();
+                            self.sessions.insert(tid, UdpSession {
+                                socket: self.socket.clone(),
+                                peer_did: self.peer_did,
+                                src,
+                                activity: Activity::new(),
+                            });
+                            tids.insert(src, tid);
+                            tid
+                        }
+                    };
+                    if let Some(session) = self.sessions.get(&tid) {
+                        session.activity.touch();
+                    }
+                    let msg = ServiceMessage::UdpDatagram {
+                        tid,
+                        service: self.service.clone(),
+                        body: Bytes::copy_from_slice(&buf[..n]),
+                    };
+                    if let Err(e) = send_service_message(&provider, self.peer_did, msg).await {
+                        tracing::error!("Send UdpDatagram message failed: {e:?}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn send_service_message(
+    provider: &Provider,
+    peer_did: Did,
+    msg: ServiceMessage,
+) -> Result<()> {
+    let backend_message: BackendMessage = msg.into();
+    let params = backend_message.into_send_backend_message_request(peer_did)?;
+    provider.request(Method::SendBackendMessage, params).await?;
+    Ok(())
+}
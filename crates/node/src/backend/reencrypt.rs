@@ -0,0 +1,546 @@
+//! Proxy re-encryption Backend
+//! ================
+//! A node is both a proxy, which keeps key fragments granted by owners of sealed data and
+//! re-encrypts capsules for members, and a member, which collects capsule fragments from proxies.
+//! See [rings_core::ecc::pre] for the scheme.
+//!
+//! Key fragments are only kept in memory, owners should grant them again after proxies restart.
+//! With threshold `t` of `n` proxies, data stays readable while `t` proxies keep theirs.
+//!
+//! Grants and revocations carry epochs of owner, a proxy keeps the latest epoch of each grant
+//! and a tombstone after revocation, so replayed grants can't restore a revoked member.
+//! Key fragments are sealed to session key of proxy, and each sender can only take
+//! [REENCRYPT_MAX_GRANTS_PER_SENDER] of the grants kept by a proxy.
+//! A member only accepts capsule fragments of its pending requests from the designated
+//! proxies, and each fragment is verified by [CapsuleFrag::verify].
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use rings_core::dht::Did;
+use rings_core::ecc::pre;
+use rings_core::ecc::pre::Capsule;
+use rings_core::ecc::pre::CapsuleFrag;
+use rings_core::ecc::pre::KeyFrag;
+use rings_core::ecc::pre::SealedData;
+use rings_core::ecc::PublicKey;
+use rings_core::ecc::SecretKey;
+use rings_core::message::MessagePayload;
+use rings_core::message::MessageVerificationExt;
+use rings_derive::wasm_export;
+
+use super::types::reencrypt::ReencryptGrant;
+use super::types::reencrypt::ReencryptMessage;
+use super::types::reencrypt::ReencryptRevocation;
+use crate::backend::types::MessageHandler;
+use crate::consts::REENCRYPT_MAX_GRANTS;
+use crate::consts::REENCRYPT_MAX_GRANTS_PER_SENDER;
+use crate::consts::REENCRYPT_MAX_PENDING;
+use crate::error::Error;
+use crate::error::Result;
+use crate::provider::Provider;
+
+/// Delegating and receiving key of key fragments
+type GrantKey = ([u8; 33], [u8; 33]);
+
+/// Key fragments granted at the latest epoch, or the tombstone of a revocation.
+struct GrantState {
+    epoch: u64,
+    revoked: bool,
+    kfrags: Vec<KeyFrag>,
+}
+
+/// Re-encryption request of a member, waiting for capsule fragments from its proxies.
+struct PendingRequest {
+    capsule: Capsule,
+    delegating: PublicKey<33>,
+    receiving: PublicKey<33>,
+    proxies: Vec<Did>,
+    cfrags: Vec<CapsuleFrag>,
+}
+
+/// Key fragments and capsule fragments of proxy re-encryption
+#[derive(Default)]
+pub struct ReencryptManager {
+    /// grants to this node as proxy, by delegating and receiving key
+    grants: DashMap<GrantKey, GrantState>,
+    /// number of grants taken by each sender
+    senders: DashMap<Did, usize>,
+    /// requests of this node as member, by digest of capsule
+    pending: DashMap<[u8; 32], PendingRequest>,
+}
+
+/// Proxy re-encryption message handler
+#[wasm_export]
+#[derive(Default, Clone)]
+pub struct ReencryptBehaviour {
+    inner: Arc<ReencryptManager>,
+}
+
+impl std::ops::Deref for ReencryptBehaviour {
+    type Target = Arc<ReencryptManager>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl ReencryptManager {
+    /// Count a new grant key against the capacity of proxy and the quota of sender.
+    fn check_grants_capacity(&self, sender: Did, key: &GrantKey) -> Result<()> {
+        if self.grants.contains_key(key) {
+            return Ok(());
+        }
+        if self.grants.len() >= REENCRYPT_MAX_GRANTS {
+            return Err(Error::ReencryptRejected("too many grants".to_string()));
+        }
+        let mut count = self.senders.entry(sender).or_insert(0);
+        if *count >= REENCRYPT_MAX_GRANTS_PER_SENDER {
+            return Err(Error::ReencryptRejected(format!(
+                "too many grants from {}",
+                sender
+            )));
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// Keep a key fragment from sender, signed by its delegating key.
+    /// A grant of earlier epoch, or of the epoch of a revocation, is rejected.
+    pub fn grant(&self, sender: Did, grant: ReencryptGrant) -> Result<()> {
+        if !grant.verify() {
+            return Err(Error::InvalidMessage);
+        }
+        let kfrag = grant.kfrag;
+        let key = (kfrag.delegating.0, kfrag.receiving.0);
+        self.check_grants_capacity(sender, &key)?;
+
+        let mut state = self.grants.entry(key).or_insert_with(|| GrantState {
+            epoch: grant.epoch,
+            revoked: false,
+            kfrags: vec![],
+        });
+        if grant.epoch < state.epoch || (grant.epoch == state.epoch && state.revoked) {
+            return Err(Error::ReencryptOutdated(grant.epoch));
+        }
+        if grant.epoch > state.epoch {
+            *state = GrantState {
+                epoch: grant.epoch,
+                revoked: false,
+                kfrags: vec![],
+            };
+        }
+        state.kfrags.retain(|k| k.id != kfrag.id);
+        state.kfrags.push(kfrag);
+        Ok(())
+    }
+
+    /// Drop key fragments of a member, if the revocation from sender is signed by delegating
+    /// key. A tombstone of its epoch is kept, so that earlier grants are not accepted again.
+    pub fn revoke(&self, sender: Did, revocation: &ReencryptRevocation) -> Result<()> {
+        if !revocation.verify() {
+            return Err(Error::InvalidMessage);
+        }
+        let key = (revocation.delegating.0, revocation.receiving.0);
+        self.check_grants_capacity(sender, &key)?;
+
+        let tombstone = || GrantState {
+            epoch: revocation.epoch,
+            revoked: true,
+            kfrags: vec![],
+        };
+        let mut state = self.grants.entry(key).or_insert_with(tombstone);
+        if revocation.epoch < state.epoch {
+            return Err(Error::ReencryptOutdated(revocation.epoch));
+        }
+        *state = tombstone();
+        Ok(())
+    }
+
+    /// Re-encrypt capsule with key fragments granted from delegating key to receiving key.
+    pub fn reencrypt(
+        &self,
+        capsule: &pre::Capsule,
+        delegating: PublicKey<33>,
+        receiving: PublicKey<33>,
+    ) -> Option<Result<Vec<CapsuleFrag>>> {
+        let state = self.grants.get(&(delegating.0, receiving.0))?;
+        if state.revoked {
+            return None;
+        }
+        Some(
+            state
+                .kfrags
+                .iter()
+                .map(|k| pre::reencrypt(capsule, k).map_err(Error::InternalError))
+                .collect(),
+        )
+    }
+
+    /// Wait for capsule fragments of sealed data for receiving key from proxies.
+    pub fn request(
+        &self,
+        sealed: &SealedData,
+        receiving: PublicKey<33>,
+        proxies: &[Did],
+    ) -> Result<()> {
+        let digest = sealed.capsule.digest();
+        if !self.pending.contains_key(&digest) && self.pending.len() >= REENCRYPT_MAX_PENDING {
+            return Err(Error::ReencryptRejected(
+                "too many pending requests".to_string(),
+            ));
+        }
+        let mut request = self
+            .pending
+            .entry(digest)
+            .or_insert_with(|| PendingRequest {
+                capsule: sealed.capsule,
+                delegating: sealed.delegating,
+                receiving,
+                proxies: vec![],
+                cfrags: vec![],
+            });
+        for proxy in proxies {
+            if !request.proxies.contains(proxy) {
+                request.proxies.push(*proxy);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep a capsule fragment from proxy, if it's requested from the proxy and verified.
+    pub fn receive(&self, proxy: Did, capsule: [u8; 32], cfrag: CapsuleFrag) -> Result<()> {
+        let mut request = self
+            .pending
+            .get_mut(&capsule)
+            .ok_or_else(|| Error::ReencryptRejected("capsule is not requested".to_string()))?;
+        if !request.proxies.contains(&proxy) {
+            return Err(Error::ReencryptRejected(format!(
+                "{} is not a requested proxy",
+                proxy
+            )));
+        }
+        if !cfrag.verify(&request.capsule, &request.delegating, &request.receiving) {
+            return Err(Error::ReencryptRejected(
+                "capsule fragment is not verified".to_string(),
+            ));
+        }
+        if !request.cfrags.iter().any(|c| c.id == cfrag.id) {
+            request.cfrags.push(cfrag);
+        }
+        Ok(())
+    }
+
+    /// Verified capsule fragments of sealed data received from proxies.
+    pub fn cfrags(&self, sealed: &SealedData) -> Vec<CapsuleFrag> {
+        self.pending
+            .get(&sealed.capsule.digest())
+            .map(|r| r.cfrags.clone())
+            .unwrap_or_default()
+    }
+
+    /// Open sealed data by receiving key with capsule fragments received from proxies.
+    /// Returns None if not enough fragments of a grant are received yet.
+    pub fn open(&self, sealed: &SealedData, receiving: &SecretKey) -> Result<Option<Vec<u8>>> {
+        let cfrags = self.cfrags(sealed);
+        let enough = cfrags
+            .iter()
+            .any(|c| cfrags.iter().filter(|o| o.precursor == c.precursor).count() >= c.threshold);
+        if !enough {
+            return Ok(None);
+        }
+        sealed
+            .open_reencrypted(receiving, &cfrags)
+            .map(Some)
+            .map_err(Error::InternalError)
+    }
+
+    /// Drop the request of sealed data, once it is opened.
+    pub fn forget(&self, sealed: &SealedData) {
+        self.pending.remove(&sealed.capsule.digest());
+    }
+}
+
+#[cfg_attr(feature = "browser", async_trait::async_trait(?Send))]
+#[cfg_attr(not(feature = "browser"), async_trait::async_trait)]
+impl MessageHandler<ReencryptMessage> for ReencryptBehaviour {
+    async fn handle_message(
+        &self,
+        provider: Arc<Provider>,
+        ctx: &MessagePayload,
+        msg: &ReencryptMessage,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let sender = ctx.transaction.signer();
+        match msg {
+            ReencryptMessage::Grant(sealed) => {
+                let grant = ReencryptGrant::open(sealed, &provider.session_sk()?)?;
+                self.grant(sender, grant)?;
+                tracing::info!("Granted key fragment from {}", sender);
+            }
+            ReencryptMessage::Revoke(revocation) => {
+                self.revoke(sender, revocation)?;
+                tracing::info!("Revoked key fragments from {}", sender);
+            }
+            ReencryptMessage::Request {
+                capsule,
+                delegating,
+                receiving,
+            } => {
+                let digest = capsule.digest();
+                match self.reencrypt(capsule, *delegating, *receiving) {
+                    Some(cfrags) => {
+                        for cfrag in cfrags? {
+                            let resp = ReencryptMessage::Response {
+                                capsule: digest,
+                                cfrag,
+                            };
+                            provider.send_backend_message(sender, resp.into()).await?;
+                        }
+                    }
+                    None => {
+                        let resp = ReencryptMessage::Denied { capsule: digest };
+                        provider.send_backend_message(sender, resp.into()).await?;
+                    }
+                }
+            }
+            ReencryptMessage::Response { capsule, cfrag } => {
+                self.receive(sender, *capsule, cfrag.clone())?;
+            }
+            ReencryptMessage::Denied { .. } => {
+                tracing::debug!("Re-encryption request denied by {}", sender);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn did(key: &SecretKey) -> Did {
+        key.address().into()
+    }
+
+    #[test]
+    fn test_grant_reencrypt_and_revoke() {
+        let group = SecretKey::random();
+        let member = SecretKey::random();
+        let sealed = SealedData::seal(&group.pubkey(), b"hello member").unwrap();
+        let kfrags = pre::generate_kfrags(&group, &member.pubkey(), 2, 3).unwrap();
+
+        let owner = did(&group);
+        let proxy_keys: Vec<SecretKey> = (0..3).map(|_| SecretKey::random()).collect();
+        let proxies: Vec<ReencryptBehaviour> = (0..3).map(|_| Default::default()).collect();
+        for (proxy, kfrag) in proxies.iter().zip(kfrags.clone()) {
+            proxy
+                .grant(owner, ReencryptGrant::new(&group, kfrag, 1))
+                .unwrap();
+        }
+
+        let receiver = ReencryptBehaviour::default();
+        let respond = |i: usize| -> Result<()> {
+            for cfrag in proxies[i]
+                .reencrypt(&sealed.capsule, group.pubkey(), member.pubkey())
+                .unwrap()
+                .unwrap()
+            {
+                receiver.receive(did(&proxy_keys[i]), sealed.capsule.digest(), cfrag)?;
+            }
+            Ok(())
+        };
+
+        // Fragments are only accepted for requests, from the requested proxies.
+        assert!(respond(0).is_err());
+        receiver
+            .request(&sealed, member.pubkey(), &[
+                did(&proxy_keys[0]),
+                did(&proxy_keys[2]),
+            ])
+            .unwrap();
+        respond(0).unwrap();
+        respond(0).unwrap();
+        assert!(respond(1).is_err());
+        assert_eq!(receiver.open(&sealed, &member).unwrap(), None);
+        respond(2).unwrap();
+        assert_eq!(
+            receiver.open(&sealed, &member).unwrap().unwrap(),
+            b"hello member"
+        );
+        receiver.forget(&sealed);
+        assert!(receiver.cfrags(&sealed).is_empty());
+
+        // Only the delegating key can revoke.
+        let mut forged = ReencryptRevocation::new(&SecretKey::random(), &member.pubkey(), 2);
+        forged.delegating = group.pubkey();
+        assert!(proxies[1].revoke(owner, &forged).is_err());
+        let revocation = ReencryptRevocation::new(&group, &member.pubkey(), 2);
+        proxies[1].revoke(owner, &revocation).unwrap();
+        assert!(proxies[1]
+            .reencrypt(&sealed.capsule, group.pubkey(), member.pubkey())
+            .is_none());
+
+        // Replayed grant of earlier epoch can't restore the revoked member, nor an earlier
+        // revocation drops a later grant.
+        let replayed = ReencryptGrant::new(&group, kfrags[1].clone(), 1);
+        assert!(matches!(
+            proxies[1].grant(owner, replayed),
+            Err(Error::ReencryptOutdated(1))
+        ));
+        let regrant = ReencryptGrant::new(&group, kfrags[1].clone(), 2);
+        assert!(proxies[1].grant(owner, regrant).is_err());
+        proxies[1]
+            .grant(owner, ReencryptGrant::new(&group, kfrags[1].clone(), 3))
+            .unwrap();
+        assert!(matches!(
+            proxies[1].revoke(owner, &revocation),
+            Err(Error::ReencryptOutdated(2))
+        ));
+        assert!(proxies[1]
+            .reencrypt(&sealed.capsule, group.pubkey(), member.pubkey())
+            .is_some());
+
+        // Grant with a tampered epoch is rejected.
+        let mut forged = ReencryptGrant::new(&group, kfrags[2].clone(), 4);
+        forged.epoch = 5;
+        assert!(proxies[2].grant(owner, forged).is_err());
+    }
+
+    #[test]
+    fn test_grants_per_sender() {
+        let proxy = ReencryptBehaviour::default();
+        let sender = did(&SecretKey::random());
+        let member = SecretKey::random();
+        let grant = |group: &SecretKey, epoch: u64| {
+            let kfrag = pre::generate_kfrags(group, &member.pubkey(), 1, 1)
+                .unwrap()
+                .remove(0);
+            ReencryptGrant::new(group, kfrag, epoch)
+        };
+
+        let groups: Vec<SecretKey> = (0..REENCRYPT_MAX_GRANTS_PER_SENDER)
+            .map(|_| SecretKey::random())
+            .collect();
+        for group in groups.iter() {
+            proxy.grant(sender, grant(group, 1)).unwrap();
+        }
+
+        // Sender can't take more grants or tombstones, but can still update its own.
+        let other = SecretKey::random();
+        assert!(matches!(
+            proxy.grant(sender, grant(&other, 1)),
+            Err(Error::ReencryptRejected(_))
+        ));
+        let revocation = ReencryptRevocation::new(&other, &member.pubkey(), 2);
+        assert!(matches!(
+            proxy.revoke(sender, &revocation),
+            Err(Error::ReencryptRejected(_))
+        ));
+        proxy.grant(sender, grant(&groups[0], 2)).unwrap();
+        let revocation = ReencryptRevocation::new(&groups[1], &member.pubkey(), 2);
+        proxy.revoke(sender, &revocation).unwrap();
+
+        // Other senders are not affected.
+        proxy
+            .grant(did(&SecretKey::random()), grant(&other, 1))
+            .unwrap();
+    }
+
+    #[cfg(feature = "node")]
+    #[tokio::test]
+    async fn test_handle_message() {
+        use rings_core::session::SessionSk;
+
+        use crate::backend::BackendMessage;
+        use crate::tests::native::prepare_processor;
+
+        let provider = Arc::new(Provider::from_processor(Arc::new(
+            prepare_processor().await,
+        )));
+        let payload = |key: &SecretKey, msg: &ReencryptMessage| {
+            let session_sk = SessionSk::new_with_seckey(key).unwrap();
+            let did = session_sk.account_did();
+            let data = BackendMessage::from(msg.clone());
+            MessagePayload::new_send(data, &session_sk, did, did).unwrap()
+        };
+        let handle = |behaviour: ReencryptBehaviour, key: SecretKey, msg: ReencryptMessage| {
+            let provider = provider.clone();
+            async move {
+                behaviour
+                    .handle_message(provider, &payload(&key, &msg), &msg)
+                    .await
+            }
+        };
+
+        let group = SecretKey::random();
+        let member = SecretKey::random();
+        let proxy_key = SecretKey::random();
+        let sealed = SealedData::seal(&group.pubkey(), b"hello member").unwrap();
+        let kfrag = pre::generate_kfrags(&group, &member.pubkey(), 1, 1)
+            .unwrap()
+            .remove(0);
+
+        // Proxy only accepts grants sealed to its session key.
+        let proxy = ReencryptBehaviour::default();
+        let plain = ReencryptGrant::new(&group, kfrag, 1);
+        let unsealed = serde_json::json!({ "Grant": plain });
+        assert!(serde_json::from_value::<ReencryptMessage>(unsealed).is_err());
+        let misdirected = ReencryptMessage::Grant(plain.seal(&proxy_key.pubkey()).unwrap());
+        assert!(handle(proxy.clone(), group, misdirected).await.is_err());
+
+        // Proxy keeps the granted key fragment, and rejects a replayed grant after revocation.
+        let session_pk = provider.session_sk().unwrap().pubkey();
+        let grant = ReencryptMessage::Grant(plain.seal(&session_pk).unwrap());
+        handle(proxy.clone(), group, grant.clone()).await.unwrap();
+        let cfrag = proxy
+            .reencrypt(&sealed.capsule, group.pubkey(), member.pubkey())
+            .unwrap()
+            .unwrap()
+            .remove(0);
+        let revoke =
+            ReencryptMessage::Revoke(ReencryptRevocation::new(&group, &member.pubkey(), 2));
+        handle(proxy.clone(), group, revoke).await.unwrap();
+        assert!(handle(proxy.clone(), group, grant).await.is_err());
+        assert!(proxy
+            .reencrypt(&sealed.capsule, group.pubkey(), member.pubkey())
+            .is_none());
+
+        // Member only accepts verified fragments of its request from the designated proxy.
+        let receiver = ReencryptBehaviour::default();
+        let response = ReencryptMessage::Response {
+            capsule: sealed.capsule.digest(),
+            cfrag: cfrag.clone(),
+        };
+        assert!(handle(receiver.clone(), proxy_key, response.clone())
+            .await
+            .is_err());
+        receiver
+            .request(&sealed, member.pubkey(), &[did(&proxy_key)])
+            .unwrap();
+        assert!(
+            handle(receiver.clone(), SecretKey::random(), response.clone())
+                .await
+                .is_err()
+        );
+        let other = SealedData::seal(&group.pubkey(), b"other").unwrap();
+        let mismatched = ReencryptMessage::Response {
+            capsule: sealed.capsule.digest(),
+            cfrag: pre::reencrypt(
+                &other.capsule,
+                &pre::generate_kfrags(&group, &member.pubkey(), 1, 1)
+                    .unwrap()
+                    .remove(0),
+            )
+            .unwrap(),
+        };
+        assert!(handle(receiver.clone(), proxy_key, mismatched)
+            .await
+            .is_err());
+        assert_eq!(receiver.open(&sealed, &member).unwrap(), None);
+
+        handle(receiver.clone(), proxy_key, response).await.unwrap();
+        assert_eq!(
+            receiver.open(&sealed, &member).unwrap().unwrap(),
+            b"hello member"
+        );
+    }
+}
@@ -8,6 +8,8 @@ use wasm_bindgen::JsError;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::future_to_promise;
 
+use super::coordinator::DistributeOptions;
+use super::coordinator::DistributedTaskProgress;
 use super::*;
 use crate::backend::types::snark::SNARKProofTask;
 use crate::backend::types::snark::SNARKVerifyTask;
@@ -153,6 +155,33 @@ impl SNARKBehaviour {
         SNARKBehaviour::gen_proof_task(circuits).map(|t| t.into())
     }
 
+    /// gen proof task with circuits, reuse cached public params of the circuits if there are,
+    /// resolve [SNARKProofTaskRef]
+    pub fn gen_cached_proof_task_ref(&self, circuits: Vec<Circuit>) -> js_sys::Promise {
+        let ins = self.clone();
+        future_to_promise(async move {
+            let task: SNARKProofTaskRef = ins
+                .gen_cached_proof_task(circuits)
+                .await
+                .map_err(JsError::from)?
+                .into();
+            Ok(JsValue::from(task))
+        })
+    }
+
+    /// Generate groth16 proof task of a bn256 circuit
+    pub fn gen_groth16_proof_task_ref(circuit: Circuit) -> Result<SNARKProofTaskRef> {
+        SNARKBehaviour::gen_groth16_proof_task(circuit).map(|t| t.into())
+    }
+
+    /// Generate groth16 proof task of a bn256 circuit, with prover key in bellman format
+    pub fn gen_groth16_proof_task_with_pk_ref(
+        circuit: Circuit,
+        pk: Vec<u8>,
+    ) -> Result<SNARKProofTaskRef> {
+        SNARKBehaviour::gen_groth16_proof_task_with_pk(circuit, &pk).map(|t| t.into())
+    }
+
     /// handle snark proof task ref, this function is helpful for js_sys
     pub fn handle_snark_proof_task_ref(data: SNARKProofTaskRef) -> Result<SNARKVerifyTaskRef> {
         Self::handle_snark_proof_task(data).map(|x| x.into())
@@ -166,6 +195,17 @@ impl SNARKBehaviour {
         Self::handle_snark_verify_task(data, snark)
     }
 
+    /// Export solidity verifier contract and calldata of a groth16 proof,
+    /// resolve object with `contract` and `calldata`
+    pub fn export_solidity_verifier_js(
+        data: SNARKVerifyTaskRef,
+        snark: SNARKProofTaskRef,
+    ) -> Result<JsValue> {
+        Ok(js_value::serialize(&Self::export_solidity_verifier(
+            data, snark,
+        )?)?)
+    }
+
     /// send proof task to did
     pub fn send_proof_task_to(
         &self,
@@ -204,6 +244,103 @@ impl SNARKBehaviour {
         })
     }
 
+    /// List snark tasks, resolve array of task info with status
+    pub fn list_task_infos(&self) -> Result<JsValue> {
+        Ok(js_value::serialize(&self.list_tasks())?)
+    }
+
+    /// Get proof of task, if it's received
+    pub fn get_task_proof_ref(&self, task_id: String) -> Result<Option<SNARKVerifyTaskRef>> {
+        Ok(self.get_task_proof(task_id)?.map(|p| p.into()))
+    }
+
+    /// Cancel a pending task, its prover is notified
+    pub fn cancel_task_ref(&self, provider: ProviderRef, task_id: String) -> js_sys::Promise {
+        let ins = self.clone();
+        future_to_promise(async move {
+            ins.cancel_task(provider.inner(), task_id)
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::NULL)
+        })
+    }
+
+    /// Send a pending or timed out task to another prover, from its latest checkpoint
+    pub fn resume_task_ref(
+        &self,
+        provider: ProviderRef,
+        task_id: String,
+        did: String,
+    ) -> js_sys::Promise {
+        let ins = self.clone();
+        future_to_promise(async move {
+            ins.resume_task(provider.inner(), task_id, Did::from_str(&did)?)
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::NULL)
+        })
+    }
+
+    /// Get queue depth and running jobs of prover
+    pub fn prover_info_js(&self) -> Result<JsValue> {
+        Ok(js_value::serialize(&self.prover_info())?)
+    }
+
+    /// Register this node as a snark prover
+    pub fn register_as_prover(provider: ProviderRef) -> js_sys::Promise {
+        future_to_promise(async move {
+            SNARKBehaviour::register_prover(provider.inner())
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::NULL)
+        })
+    }
+
+    /// Split proof task and send sub tasks to registered provers,
+    /// resolve task_id of distributed task
+    pub fn distribute_proof_task_ref(
+        &self,
+        provider: ProviderRef,
+        task: SNARKProofTaskRef,
+        options: DistributeOptions,
+    ) -> js_sys::Promise {
+        let ins = self.clone();
+        future_to_promise(async move {
+            let ret = ins
+                .distribute_proof_task_to_provers(provider.inner(), task.as_ref(), options)
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::from(ret))
+        })
+    }
+
+    /// Resend timed out sub tasks, resolve [DistributedTaskProgress]
+    pub fn check_distributed_task_ref(
+        &self,
+        provider: ProviderRef,
+        task_id: String,
+    ) -> js_sys::Promise {
+        let ins = self.clone();
+        future_to_promise(async move {
+            let ret = ins
+                .check_distributed_task(provider.inner(), task_id)
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::from(ret))
+        })
+    }
+
+    /// Get progress of distributed task
+    pub fn distributed_task_progress(&self, task_id: String) -> Result<DistributedTaskProgress> {
+        self.get_distributed_task_progress(task_id)
+    }
+
+    /// Get proofs of finished distributed task, in order of sub tasks
+    pub fn distributed_task_proofs(&self, task_id: String) -> Result<Vec<SNARKVerifyTaskRef>> {
+        self.get_distributed_task_proofs(task_id)
+            .map(|v| v.into_iter().map(|t| t.into()).collect())
+    }
+
     /// create new instance for browser
     /// which support syntax `new SNARKBehaviour` in browser env
     #[wasm_bindgen(constructor)]
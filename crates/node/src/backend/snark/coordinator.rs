@@ -0,0 +1,495 @@
+//! Distributed proving
+//! ===========
+//! A [SNARKProofTask] can be split into sub tasks with [SNARKProofTask::split], the coordinator
+//! dispatches the sub tasks to provers in parallel, and collects their proofs.
+//!
+//! Provers announce themselves by registering service [SNARK_PROVER_SERVICE], see
+//! [SNARKBehaviour::register_prover]. A sub task is sent to another prover if the proof is not
+//! received within timeout, or the received proof cannot be verified. The task fails once a sub
+//! task used up its attempts.
+//!
+//! Each sub task is proved and compressed separately, compressed proofs cannot be folded again,
+//! so the result of a distributed task is the list of sub proofs, in order of sub tasks.
+//!
+//! The sub proofs are chained by their public io, see [SNARKProofTask::io]. When splitting, the
+//! input of each sub task must equal the output of the previous one. When a sub proof is
+//! verified, the output it proves must equal the output expected by the sub task, so a chain of
+//! verified sub proofs proves the whole task.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rings_core::dht::Did;
+use rings_core::utils::get_epoch_ms;
+use rings_derive::wasm_export;
+use rings_rpc::method::Method;
+use rings_rpc::protos::rings_node::LookupServiceRequest;
+use rings_rpc::protos::rings_node::LookupServiceResponse;
+use rings_rpc::protos::rings_node::RegisterServiceRequest;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::record::SNARKTaskRecord;
+use super::record::SNARKTaskStatus;
+use super::SNARKBehaviour;
+use super::TaskId;
+use crate::backend::types::snark::SNARKProofTask;
+use crate::backend::types::snark::SNARKTask;
+use crate::backend::types::snark::SNARKVerifyTask;
+use crate::error::Error;
+use crate::error::Result;
+use crate::provider::Provider;
+
+/// Name of service registered by snark provers
+pub const SNARK_PROVER_SERVICE: &str = "snark-prover";
+
+/// Options of distributed proof task
+#[wasm_export]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DistributeOptions {
+    /// count of sub tasks
+    pub parts: usize,
+    /// seconds to wait for a proof before sending sub task to another prover
+    pub timeout: u64,
+    /// max times a sub task can be sent
+    pub max_attempts: usize,
+}
+
+impl Default for DistributeOptions {
+    fn default() -> Self {
+        Self {
+            parts: 4,
+            timeout: 600,
+            max_attempts: 3,
+        }
+    }
+}
+
+/// State of sub task
+#[derive(Debug, Clone)]
+pub enum SubTaskState {
+    /// waiting to be sent
+    Pending,
+    /// sent to prover at timestamp in milliseconds
+    Proving {
+        /// prover of sub task
+        prover: Did,
+        /// timestamp of sending
+        sent_at: u128,
+    },
+    /// proof received and verified
+    Done(SNARKVerifyTask),
+    /// all attempts failed
+    Failed,
+}
+
+/// A split part of distributed proof task
+#[derive(Debug, Clone)]
+pub struct SubTask {
+    /// task_id of sub task message
+    pub task_id: TaskId,
+    /// the split task
+    pub task: SNARKProofTask,
+    /// public outputs of the last step, encoded as json, which is the input of next sub task
+    pub output: String,
+    /// current state
+    pub state: SubTaskState,
+    /// provers the sub task has been sent to
+    pub tried: Vec<Did>,
+}
+
+/// A proof task distributed to multiple provers
+#[derive(Debug, Clone)]
+pub struct DistributedTask {
+    /// sub tasks, in order of split
+    pub parts: Vec<SubTask>,
+    /// candidate provers
+    pub provers: Vec<Did>,
+    /// options of task
+    pub options: DistributeOptions,
+}
+
+/// Progress of distributed proof task
+#[wasm_export]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DistributedTaskProgress {
+    /// count of sub tasks
+    pub total: usize,
+    /// sub tasks waiting to be sent
+    pub pending: usize,
+    /// sub tasks being proved
+    pub proving: usize,
+    /// sub tasks proved and verified
+    pub done: usize,
+    /// sub tasks failed
+    pub failed: usize,
+}
+
+#[wasm_export]
+impl DistributedTaskProgress {
+    /// all sub tasks are proved
+    pub fn is_finished(&self) -> bool {
+        self.done == self.total
+    }
+
+    /// any sub task failed
+    pub fn is_failed(&self) -> bool {
+        self.failed > 0
+    }
+}
+
+impl DistributedTask {
+    /// Split task into sub tasks, and check that each sub task starts from the output of the
+    /// previous one.
+    pub fn new(
+        task: &SNARKProofTask,
+        provers: Vec<Did>,
+        options: DistributeOptions,
+    ) -> Result<Self> {
+        let mut parts: Vec<SubTask> = vec![];
+        for (index, task) in task.split(options.parts.max(1)).into_iter().enumerate() {
+            let (input, output) = task.io()?;
+            if parts.last().is_some_and(|prev| prev.output != input) {
+                return Err(Error::SNARKSubTaskNotLinked(index));
+            }
+            parts.push(SubTask {
+                task_id: uuid::Uuid::new_v4(),
+                task,
+                output,
+                state: SubTaskState::Pending,
+                tried: vec![],
+            });
+        }
+        Ok(Self {
+            parts,
+            provers,
+            options,
+        })
+    }
+
+    /// Get progress of task
+    pub fn progress(&self) -> DistributedTaskProgress {
+        let mut progress = DistributedTaskProgress {
+            total: self.parts.len(),
+            ..Default::default()
+        };
+        for part in self.parts.iter() {
+            match part.state {
+                SubTaskState::Pending => progress.pending += 1,
+                SubTaskState::Proving { .. } => progress.proving += 1,
+                SubTaskState::Done(_) => progress.done += 1,
+                SubTaskState::Failed => progress.failed += 1,
+            }
+        }
+        progress
+    }
+
+    /// Pick a prover for the sub task at index.
+    /// Provers never tried by the sub task are preferred, then the least busy one.
+    fn pick_prover(&self, index: usize) -> Option<Did> {
+        let mut load: HashMap<Did, usize> = HashMap::new();
+        for part in self.parts.iter() {
+            if let SubTaskState::Proving { prover, .. } = part.state {
+                *load.entry(prover).or_default() += 1;
+            }
+        }
+        let tried = &self.parts[index].tried;
+        self.provers
+            .iter()
+            .min_by_key(|p| (tried.contains(p), load.get(p).copied().unwrap_or(0)))
+            .copied()
+    }
+
+    /// Mark timed out sub tasks as pending, and sub tasks out of attempts as failed.
+    /// Return (index, prover) of sub tasks which should be sent, the states are updated to
+    /// [SubTaskState::Proving] as if they are sent.
+    fn schedule(&mut self, now: u128) -> Vec<(usize, Did)> {
+        let timeout = self.options.timeout as u128 * 1000;
+        let max_attempts = self.options.max_attempts.max(1);
+
+        let mut dispatch = vec![];
+        for index in 0..self.parts.len() {
+            let part = &mut self.parts[index];
+            if let SubTaskState::Proving { prover, sent_at } = part.state {
+                if now.saturating_sub(sent_at) < timeout {
+                    continue;
+                }
+                tracing::warn!("sub task {} timed out on prover {}", part.task_id, prover);
+                part.state = SubTaskState::Pending;
+            }
+            if !matches!(part.state, SubTaskState::Pending) {
+                continue;
+            }
+            if part.tried.len() >= max_attempts {
+                part.state = SubTaskState::Failed;
+                continue;
+            }
+            let Some(prover) = self.pick_prover(index) else {
+                continue;
+            };
+            let part = &mut self.parts[index];
+            part.tried.push(prover);
+            part.state = SubTaskState::Proving {
+                prover,
+                sent_at: now,
+            };
+            dispatch.push((index, prover));
+        }
+        dispatch
+    }
+}
+
+impl SNARKBehaviour {
+    /// Register this node as a snark prover
+    pub async fn register_prover(provider: Arc<Provider>) -> Result<()> {
+        let params = serde_json::to_value(RegisterServiceRequest {
+            name: SNARK_PROVER_SERVICE.to_string(),
+        })?;
+        provider
+            .request_internal(Method::RegisterService.to_string(), params)
+            .await?;
+        Ok(())
+    }
+
+    /// Lookup provers which registered [SNARK_PROVER_SERVICE]
+    pub async fn lookup_provers(provider: Arc<Provider>) -> Result<Vec<Did>> {
+        let params = serde_json::to_value(LookupServiceRequest {
+            name: SNARK_PROVER_SERVICE.to_string(),
+        })?;
+        let resp = provider
+            .request_internal(Method::LookupService.to_string(), params)
+            .await?;
+        let resp: LookupServiceResponse = serde_json::from_value(resp)?;
+        Ok(resp
+            .dids
+            .iter()
+            .filter_map(|did| did.parse().ok())
+            .collect())
+    }
+
+    /// Split proof task and send sub tasks to provers, return task_id of distributed task.
+    /// Call [SNARKBehaviour::check_distributed_task] periodically to resend timed out
+    /// sub tasks, or just [SNARKBehaviour::wait_distributed_task].
+    pub async fn distribute_proof_task(
+        &self,
+        provider: Arc<Provider>,
+        task_ref: impl AsRef<SNARKProofTask>,
+        provers: Vec<Did>,
+        options: DistributeOptions,
+    ) -> Result<String> {
+        if provers.is_empty() {
+            return Err(Error::SNARKNoProverAvailable());
+        }
+        self.cache_task_params(&provider, task_ref.as_ref()).await?;
+        let task_id = self
+            .new_distributed_task(task_ref.as_ref(), provers, options)
+            .await?;
+        self.update_status(task_id, SNARKTaskStatus::Sent, None)
+            .await?;
+        self.check_distributed_task(provider, task_id.to_string())
+            .await?;
+        Ok(task_id.to_string())
+    }
+
+    /// Split proof task and send sub tasks to provers found by [SNARKBehaviour::lookup_provers]
+    pub async fn distribute_proof_task_to_provers(
+        &self,
+        provider: Arc<Provider>,
+        task_ref: impl AsRef<SNARKProofTask>,
+        options: DistributeOptions,
+    ) -> Result<String> {
+        let provers = Self::lookup_provers(provider.clone()).await?;
+        self.distribute_proof_task(provider, task_ref, provers, options)
+            .await
+    }
+
+    /// Create a distributed task without sending it
+    pub(crate) async fn new_distributed_task(
+        &self,
+        task: &SNARKProofTask,
+        provers: Vec<Did>,
+        options: DistributeOptions,
+    ) -> Result<TaskId> {
+        let task_id = uuid::Uuid::new_v4();
+        let task = DistributedTask::new(task, provers, options)?;
+        for part in task.parts.iter() {
+            self.sub_tasks.insert(part.task_id, task_id);
+        }
+        self.distributed.insert(task_id, task);
+        self.save_record(task_id, SNARKTaskRecord::new(None, None))
+            .await?;
+        Ok(task_id)
+    }
+
+    /// Resend timed out or rejected sub tasks, return progress of distributed task
+    pub async fn check_distributed_task(
+        &self,
+        provider: Arc<Provider>,
+        task_id: String,
+    ) -> Result<DistributedTaskProgress> {
+        let task_id = uuid::Uuid::parse_str(&task_id)?;
+        let dispatch = {
+            let mut task = self
+                .distributed
+                .get_mut(&task_id)
+                .ok_or_else(|| Error::SNARKTaskNotFound(task_id.to_string()))?;
+            task.schedule(get_epoch_ms())
+                .into_iter()
+                .map(|(i, prover)| (task.parts[i].task_id, task.parts[i].task.clone(), prover))
+                .collect::<Vec<_>>()
+        };
+
+        let sending = dispatch.into_iter().map(|(sub_task_id, task, prover)| {
+            let provider = provider.clone();
+            async move {
+                let msg = SNARKTask::SNARKProof(task);
+                let ret = Self::send_task_message(&provider, sub_task_id, msg, prover).await;
+                (sub_task_id, prover, ret)
+            }
+        });
+        for (sub_task_id, prover, ret) in futures::future::join_all(sending).await {
+            match ret {
+                Ok(()) => tracing::info!("sent sub task {} to {}", sub_task_id, prover),
+                Err(e) => {
+                    tracing::warn!(
+                        "failed to send sub task {} to {}: {}",
+                        sub_task_id,
+                        prover,
+                        e
+                    );
+                    self.reset_sub_task(sub_task_id);
+                }
+            }
+        }
+        let progress = self.get_distributed_task_progress(task_id.to_string())?;
+        let pending = self
+            .records
+            .get(&task_id)
+            .map(|r| r.status.is_pending())
+            .unwrap_or(false);
+        if progress.is_failed() && pending {
+            self.update_status(
+                task_id,
+                SNARKTaskStatus::Failed,
+                Some(format!("{} sub tasks failed", progress.failed)),
+            )
+            .await?;
+        }
+        Ok(progress)
+    }
+
+    /// Check distributed task every `interval` seconds, until it's finished or failed
+    pub async fn wait_distributed_task(
+        &self,
+        provider: Arc<Provider>,
+        task_id: String,
+        interval: u64,
+    ) -> Result<DistributedTaskProgress> {
+        loop {
+            let progress = self
+                .check_distributed_task(provider.clone(), task_id.clone())
+                .await?;
+            tracing::debug!("distributed task {} progress: {:?}", task_id, progress);
+            if progress.is_finished() || progress.is_failed() {
+                return Ok(progress);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            futures_timer::Delay::new(std::time::Duration::from_secs(interval)).await;
+            #[cfg(target_arch = "wasm32")]
+            rings_core::utils::js_utils::window_sleep(interval as i32 * 1000)
+                .await
+                .map_err(|e| Error::JsError(format!("Failed to sleep: {:?}", e)))?;
+        }
+    }
+
+    /// Get progress of distributed task
+    pub fn get_distributed_task_progress(
+        &self,
+        task_id: String,
+    ) -> Result<DistributedTaskProgress> {
+        let task_id = uuid::Uuid::parse_str(&task_id)?;
+        self.distributed
+            .get(&task_id)
+            .map(|t| t.progress())
+            .ok_or_else(|| Error::SNARKTaskNotFound(task_id.to_string()))
+    }
+
+    /// Get proofs of a finished distributed task, in order of sub tasks
+    pub fn get_distributed_task_proofs(&self, task_id: String) -> Result<Vec<SNARKVerifyTask>> {
+        let task_id = uuid::Uuid::parse_str(&task_id)?;
+        let task = self
+            .distributed
+            .get(&task_id)
+            .ok_or_else(|| Error::SNARKTaskNotFound(task_id.to_string()))?;
+        task.parts
+            .iter()
+            .map(|part| match &part.state {
+                SubTaskState::Done(proof) => Ok(proof.clone()),
+                _ => Err(Error::SNARKHandleMessage(format!(
+                    "sub task {} is not proved",
+                    part.task_id
+                ))),
+            })
+            .collect()
+    }
+
+    /// Set a sent sub task back to pending, it will be resent by next check
+    pub(crate) fn reset_sub_task(&self, sub_task_id: TaskId) {
+        let Some(task_id) = self.sub_tasks.get(&sub_task_id).map(|v| *v.value()) else {
+            return;
+        };
+        if let Some(mut task) = self.distributed.get_mut(&task_id) {
+            if let Some(part) = task.parts.iter_mut().find(|p| p.task_id == sub_task_id) {
+                if matches!(part.state, SubTaskState::Proving { .. }) {
+                    part.state = SubTaskState::Pending;
+                }
+            }
+        }
+    }
+
+    /// Verify proof of sub task, proofs which cannot be verified, or don't prove the expected
+    /// output of sub task, are rejected and the sub task will be resent. Once all sub tasks are
+    /// proved, the distributed task is marked verified, see [SNARKBehaviour::get_task_result].
+    pub(crate) async fn on_sub_task_proof(
+        &self,
+        sub_task_id: TaskId,
+        proof: &SNARKVerifyTask,
+    ) -> Result<()> {
+        let Some(task_id) = self.sub_tasks.get(&sub_task_id).map(|v| *v.value()) else {
+            return Ok(());
+        };
+        let Some((sub_task, expected)) = self.distributed.get(&task_id).and_then(|t| {
+            t.parts
+                .iter()
+                .find(|p| p.task_id == sub_task_id)
+                .filter(|p| !matches!(p.state, SubTaskState::Done(_) | SubTaskState::Failed))
+                .map(|p| (p.task.clone(), p.output.clone()))
+        }) else {
+            return Ok(());
+        };
+
+        let verified = matches!(
+            Self::verify_snark_proof_output(proof, &sub_task),
+            Ok(Some(output)) if output == expected
+        );
+        let finished = {
+            let Some(mut task) = self.distributed.get_mut(&task_id) else {
+                return Ok(());
+            };
+            if let Some(part) = task.parts.iter_mut().find(|p| p.task_id == sub_task_id) {
+                if verified {
+                    part.state = SubTaskState::Done(proof.clone());
+                } else {
+                    tracing::warn!("proof of sub task {} is rejected", sub_task_id);
+                    part.state = SubTaskState::Pending;
+                }
+            }
+            task.progress().is_finished()
+        };
+        if finished {
+            self.update_status(task_id, SNARKTaskStatus::Verified, None)
+                .await?;
+        }
+        Ok(())
+    }
+}
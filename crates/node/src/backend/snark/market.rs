@@ -0,0 +1,499 @@
+//! Marketplace of proving
+//! ===========
+//! A requester asks provers for quotes before sending a proof task, and pays for proofs it
+//! verified:
+//!
+//! requester -> [SNARKTask::SNARKQuoteRequest] -> provers
+//! provers -> [SNARKTask::SNARKQuote] -> requester
+//! requester -> [SNARKTask::SNARKProof] -> prover of the accepted quote
+//! prover -> [SNARKTask::SNARKVerify] -> requester
+//! requester -> [SNARKTask::SNARKReceipt] -> prover
+//!
+//! Quotes are signed by provers, and receipts are signed by requesters once the proof is
+//! verified. A receipt binds requester, prover, circuit hash and proof hash, and carries the
+//! accepted quote, so each side can show the other agreed on the work and its price. Receipts
+//! of both sides are kept in a ledger, in memory and in a [SNARKLedgerStorage] if set.
+//!
+//! A prover with [ProverOptions::require_quote] only accepts tasks it quoted.
+
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use rings_core::dht::Did;
+use rings_core::ecc::keccak256;
+use rings_core::message::MessageVerification;
+use rings_core::storage::KvStorageInterface;
+use rings_core::utils::get_epoch_ms;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::prover::ProverOptions;
+use super::record::SNARKTaskRecord;
+use super::SNARKBehaviour;
+use super::TaskId;
+use crate::backend::types::snark::SNARKProofTask;
+use crate::backend::types::snark::SNARKTask;
+use crate::backend::types::snark::SNARKVerifyTask;
+use crate::error::Error;
+use crate::error::Result;
+use crate::provider::Provider;
+
+/// Seconds a quote is valid for after signing
+pub const SNARK_QUOTE_TTL: u64 = 10 * 60;
+
+/// Max number of unexpired quotes a prover keeps
+pub const SNARK_MAX_QUOTED: usize = 1024;
+
+/// `SNARKLedgerStorage` is the type accepted by [SNARKBehaviour::set_ledger_storage].
+#[cfg(feature = "browser")]
+pub type SNARKLedgerStorage = Box<dyn KvStorageInterface<SignedReceipt>>;
+
+/// `SNARKLedgerStorage` is the type accepted by [SNARKBehaviour::set_ledger_storage].
+#[cfg(not(feature = "browser"))]
+pub type SNARKLedgerStorage = Box<dyn KvStorageInterface<SignedReceipt> + Send + Sync>;
+
+/// Description of proof task, provers quote tasks by it without receiving the circuits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SNARKTaskDescription {
+    /// hash of step circuit, see [SNARKProofTask::circuit_hash]
+    pub circuit: String,
+    /// number of steps
+    pub steps: usize,
+    /// max number of constraints of a step circuit
+    pub constraints: usize,
+}
+
+/// Quote of prover for a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SNARKQuote {
+    /// task_id given by requester
+    pub task_id: TaskId,
+    /// did of requester
+    pub requester: Did,
+    /// did of prover
+    pub prover: Did,
+    /// the quoted task
+    pub description: SNARKTaskDescription,
+    /// price of task, in the unit agreed by requester and prover
+    pub price: u64,
+    /// estimated seconds from accepting the task to sending its proof
+    pub eta: u64,
+    /// timestamp in milliseconds, the quote can't be accepted after it
+    pub expires_at: u64,
+}
+
+/// Receipt of a verified proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SNARKReceipt {
+    /// task_id given by requester
+    pub task_id: TaskId,
+    /// did of requester
+    pub requester: Did,
+    /// did of prover
+    pub prover: Did,
+    /// hash of step circuit, see [SNARKProofTask::circuit_hash]
+    pub circuit: String,
+    /// hash of proof, see [proof_hash]
+    pub proof_hash: String,
+    /// the accepted quote, signed by prover
+    pub quote: SignedQuote,
+    /// timestamp of verifying the proof in milliseconds
+    pub verified_at: u64,
+}
+
+/// Data signed by session of its signer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signed<T> {
+    /// signed data
+    pub data: T,
+    /// signature and session of signer
+    pub verification: MessageVerification,
+}
+
+/// Quote signed by prover
+pub type SignedQuote = Signed<SNARKQuote>;
+
+/// Receipt signed by requester
+pub type SignedReceipt = Signed<SNARKReceipt>;
+
+impl<T: Serialize> Signed<T> {
+    /// Sign data with session of this node
+    pub fn sign(provider: &Provider, data: T) -> Result<Self> {
+        let verification = provider.sign_data(&serde_json::to_vec(&data)?)?;
+        Ok(Self { data, verification })
+    }
+
+    /// Did of signer
+    pub fn signer(&self) -> Did {
+        self.verification.session.account_did()
+    }
+
+    /// Verify the data is signed by `signer`. Only the session is checked for expiry, a signed
+    /// receipt stays valid after the message ttl.
+    pub fn verify(&self, signer: Did) -> bool {
+        self.signer() == signer
+            && serde_json::to_vec(&self.data)
+                .map(|data| self.verification.verify(&data))
+                .unwrap_or(false)
+    }
+}
+
+/// Keccak hash of proof in hex
+pub fn proof_hash(proof: &SNARKVerifyTask) -> Result<String> {
+    let hash = keccak256(&serde_json::to_vec(proof)?);
+    Ok(hash.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Quotes received for a task of this node
+#[derive(Debug, Clone)]
+pub struct QuoteRequest {
+    /// the task waiting for quotes
+    pub task: SNARKProofTask,
+    /// quotes of provers
+    pub quotes: Vec<SignedQuote>,
+}
+
+/// Quotes and receipts of task manager
+#[derive(Default)]
+pub struct Market {
+    /// map of task_id and quotes asked by this node as requester
+    requests: DashMap<TaskId, QuoteRequest>,
+    /// map of requester and task_id to quotes given by this node as prover
+    quoted: DashMap<(Did, TaskId), SNARKQuote>,
+    /// map of task_id and receipts, of both requester and prover
+    receipts: DashMap<TaskId, SignedReceipt>,
+    /// persistent storage of receipts
+    storage: RwLock<Option<Arc<SNARKLedgerStorage>>>,
+}
+
+impl SNARKProofTask {
+    /// Hash of step circuit, with proof system, such as `VastaPallas:<shape digest>`
+    pub fn circuit_hash(&self) -> Result<String> {
+        match self {
+            SNARKProofTask::Groth16Bn256(t) => {
+                Ok(format!("Groth16Bn256:{}", t.circuit.shape_digest()?))
+            }
+            _ => self
+                .params_ref()
+                .map(|r| r.key.clone())
+                .ok_or_else(|| Error::SNARKHandleMessage("no public params".to_string())),
+        }
+    }
+
+    /// Description of task for quoting
+    pub fn description(&self) -> Result<SNARKTaskDescription> {
+        let (steps, constraints) = self.size();
+        Ok(SNARKTaskDescription {
+            circuit: self.circuit_hash()?,
+            steps,
+            constraints,
+        })
+    }
+}
+
+impl SNARKQuote {
+    fn is_expired(&self) -> bool {
+        get_epoch_ms() as u64 > self.expires_at
+    }
+}
+
+impl SNARKBehaviour {
+    /// Ask provers for quotes of a task, return task_id of the task.
+    /// Quotes are listed by [SNARKBehaviour::list_quotes], and one of them is accepted by
+    /// [SNARKBehaviour::accept_quote].
+    pub async fn request_quotes(
+        &self,
+        provider: Arc<Provider>,
+        task_ref: impl AsRef<SNARKProofTask>,
+        provers: Vec<Did>,
+    ) -> Result<String> {
+        if provers.is_empty() {
+            return Err(Error::SNARKNoProverAvailable());
+        }
+        let task = task_ref.as_ref().clone();
+        let description = task.description()?;
+        let task_id = uuid::Uuid::new_v4();
+        self.market.requests.insert(task_id, QuoteRequest {
+            task,
+            quotes: vec![],
+        });
+        for prover in provers {
+            let msg = SNARKTask::SNARKQuoteRequest(description.clone());
+            if let Err(e) = Self::send_task_message(&provider, task_id, msg, prover).await {
+                tracing::warn!("failed to ask {} for quote: {}", prover, e);
+            }
+        }
+        Ok(task_id.to_string())
+    }
+
+    /// Quotes received for a task, ordered by price, expired quotes are dropped
+    pub fn list_quotes(&self, task_id: String) -> Result<Vec<SignedQuote>> {
+        let task_id = uuid::Uuid::parse_str(&task_id)?;
+        let request = self
+            .market
+            .requests
+            .get(&task_id)
+            .ok_or_else(|| Error::SNARKQuoteNotFound(task_id.to_string()))?;
+        let mut quotes = request
+            .quotes
+            .iter()
+            .filter(|q| !q.data.is_expired())
+            .cloned()
+            .collect::<Vec<_>>();
+        quotes.sort_by_key(|q| q.data.price);
+        Ok(quotes)
+    }
+
+    /// Task is waiting for quotes
+    pub(crate) fn is_quote_request(&self, task_id: &TaskId) -> bool {
+        self.market.requests.contains_key(task_id)
+    }
+
+    /// Accept quote of prover, the task is sent to the prover, and tracked as other tasks
+    pub async fn accept_quote(
+        &self,
+        provider: Arc<Provider>,
+        task_id: String,
+        prover: Did,
+    ) -> Result<()> {
+        let quote = self
+            .list_quotes(task_id.clone())?
+            .into_iter()
+            .find(|q| q.data.prover == prover)
+            .ok_or_else(|| Error::SNARKQuoteNotFound(format!("{} of {}", task_id, prover)))?;
+        let (task_id, request) = self
+            .market
+            .requests
+            .remove(&quote.data.task_id)
+            .ok_or_else(|| Error::SNARKQuoteNotFound(task_id))?;
+        let mut record = SNARKTaskRecord::new(Some(request.task.clone()), Some(prover));
+        record.quote = Some(quote);
+        self.dispatch_proof_task(&provider, task_id, request.task, record)
+            .await
+    }
+
+    /// Quote a task for requester, or reject it if it's beyond limits of prover
+    pub(crate) async fn on_quote_request(
+        &self,
+        provider: &Provider,
+        task_id: TaskId,
+        description: &SNARKTaskDescription,
+        requester: Did,
+    ) -> Result<()> {
+        let msg = match self
+            .quote_task(provider.did(), task_id, description, requester)
+            .and_then(|quote| Signed::sign(provider, quote))
+        {
+            Ok(quote) => SNARKTask::SNARKQuote(quote),
+            Err(e) => SNARKTask::SNARKRejected(e.to_string()),
+        };
+        Self::send_task_message(provider, task_id, msg, requester).await
+    }
+
+    /// Price a task by [ProverOptions], the quote is kept for checking the task once it's sent.
+    /// A task can only be quoted once per requester until the quote is used or expired, and
+    /// at most [SNARK_MAX_QUOTED] quotes are kept.
+    pub(crate) fn quote_task(
+        &self,
+        prover: Did,
+        task_id: TaskId,
+        description: &SNARKTaskDescription,
+        requester: Did,
+    ) -> Result<SNARKQuote> {
+        let options: ProverOptions = self.prover_options();
+        self.check_task_size(description.steps, description.constraints)?;
+        self.market.quoted.retain(|_, q| !q.is_expired());
+        if self.market.quoted.len() >= SNARK_MAX_QUOTED {
+            return Err(Error::SNARKTaskRejected("too many quotes".to_string()));
+        }
+        let steps = description.steps as u64;
+        let waiting = self.prover_info();
+        let rounds =
+            (waiting.queued + waiting.running) as u64 / options.max_concurrency.max(1) as u64;
+        let quote = SNARKQuote {
+            task_id,
+            requester,
+            prover,
+            description: description.clone(),
+            price: steps.saturating_mul(options.price_per_step),
+            eta: (rounds + 1).saturating_mul(steps.saturating_mul(options.secs_per_step)),
+            expires_at: get_epoch_ms() as u64 + SNARK_QUOTE_TTL * 1000,
+        };
+        match self.market.quoted.entry((requester, task_id)) {
+            Entry::Occupied(_) => Err(Error::SNARKTaskRejected(
+                "task is already quoted".to_string(),
+            )),
+            Entry::Vacant(e) => {
+                e.insert(quote.clone());
+                Ok(quote)
+            }
+        }
+    }
+
+    /// Check a proof task against the quote given to requester. Without a quote, the task is
+    /// rejected if [ProverOptions::require_quote] is set.
+    pub(crate) fn check_quote(
+        &self,
+        requester: Did,
+        task_id: TaskId,
+        task: &SNARKProofTask,
+    ) -> Result<()> {
+        let Some((_, quote)) = self.market.quoted.remove(&(requester, task_id)) else {
+            if self.prover_options().require_quote {
+                return Err(Error::SNARKTaskRejected("task is not quoted".to_string()));
+            }
+            return Ok(());
+        };
+        if quote.is_expired() {
+            return Err(Error::SNARKTaskRejected("quote expired".to_string()));
+        }
+        if quote.description != task.description()? {
+            return Err(Error::SNARKTaskRejected(
+                "task doesn't match the quote".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Store quote from prover, quotes of unknown tasks or with invalid signature are dropped
+    pub(crate) fn on_quote(&self, quote: &SignedQuote, prover: Did) -> Result<()> {
+        if !quote.verify(prover) || quote.data.prover != prover {
+            return Err(Error::SNARKInvalidSignature(format!(
+                "quote of {} is not signed by {}",
+                quote.data.task_id, prover
+            )));
+        }
+        let Some(mut request) = self.market.requests.get_mut(&quote.data.task_id) else {
+            return Ok(());
+        };
+        if quote.data.description != request.task.description()? {
+            tracing::warn!("quote of {} doesn't match the task", quote.data.task_id);
+            return Ok(());
+        }
+        request.quotes.retain(|q| q.data.prover != prover);
+        request.quotes.push(quote.clone());
+        Ok(())
+    }
+
+    /// Sign receipt of a verified proof of quoted task, and send it to prover
+    pub(crate) async fn issue_receipt(
+        &self,
+        provider: &Provider,
+        task_id: TaskId,
+        record: &SNARKTaskRecord,
+    ) -> Result<()> {
+        let (Some(quote), Some(proof)) = (&record.quote, &record.proof) else {
+            return Ok(());
+        };
+        let receipt = SNARKReceipt {
+            task_id,
+            requester: provider.did(),
+            prover: quote.data.prover,
+            circuit: quote.data.description.circuit.clone(),
+            proof_hash: proof_hash(proof)?,
+            quote: quote.clone(),
+            verified_at: get_epoch_ms() as u64,
+        };
+        let receipt = Signed::sign(provider, receipt)?;
+        self.save_receipt(task_id, receipt.clone()).await?;
+        Self::send_task_message(
+            provider,
+            task_id,
+            SNARKTask::SNARKReceipt(receipt),
+            quote.data.prover,
+        )
+        .await
+    }
+
+    /// Store receipt from requester, the receipt should carry a quote signed by this node
+    pub(crate) async fn on_receipt(
+        &self,
+        provider: &Provider,
+        receipt: &SignedReceipt,
+        requester: Did,
+    ) -> Result<()> {
+        let data = &receipt.data;
+        let quote = &data.quote;
+        if !receipt.verify(requester)
+            || data.requester != requester
+            || quote.data.requester != requester
+        {
+            return Err(Error::SNARKInvalidSignature(format!(
+                "receipt of {} is not signed by {}",
+                data.task_id, requester
+            )));
+        }
+        let did = provider.did();
+        if !quote.verify(did)
+            || data.prover != did
+            || data.task_id != quote.data.task_id
+            || data.circuit != quote.data.description.circuit
+        {
+            return Err(Error::SNARKInvalidSignature(format!(
+                "receipt of {} doesn't carry a quote of this node",
+                data.task_id
+            )));
+        }
+        self.save_receipt(data.task_id, receipt.clone()).await
+    }
+
+    /// Set storage of receipts, receipts already in storage are loaded
+    pub async fn set_ledger_storage(&self, storage: SNARKLedgerStorage) -> Result<()> {
+        let receipts = storage
+            .get_all()
+            .await
+            .map_err(Error::SNARKTaskStorageError)?;
+        for (key, receipt) in receipts {
+            match uuid::Uuid::parse_str(&key) {
+                Ok(task_id) => {
+                    self.market.receipts.insert(task_id, receipt);
+                }
+                Err(e) => tracing::warn!("invalid snark task id {} in ledger: {:?}", key, e),
+            }
+        }
+        *self
+            .market
+            .storage
+            .write()
+            .map_err(|_| Error::SNARKHandleMessage("storage lock poisoned".to_string()))? =
+            Some(Arc::new(storage));
+        Ok(())
+    }
+
+    /// Save receipt to memory and storage
+    async fn save_receipt(&self, task_id: TaskId, receipt: SignedReceipt) -> Result<()> {
+        let storage = self.market.storage.read().ok().and_then(|s| s.clone());
+        if let Some(storage) = storage {
+            storage
+                .put(&task_id.to_string(), &receipt)
+                .await
+                .map_err(Error::SNARKTaskStorageError)?;
+        }
+        self.market.receipts.insert(task_id, receipt);
+        Ok(())
+    }
+
+    /// Get receipt of task
+    pub fn get_receipt(&self, task_id: String) -> Result<Option<SignedReceipt>> {
+        let task_id = uuid::Uuid::parse_str(&task_id)?;
+        Ok(self
+            .market
+            .receipts
+            .get(&task_id)
+            .map(|r| r.value().clone()))
+    }
+
+    /// List receipts in ledger, ordered by verifying time
+    pub fn list_receipts(&self) -> Vec<SignedReceipt> {
+        let mut receipts = self
+            .market
+            .receipts
+            .iter()
+            .map(|r| r.value().clone())
+            .collect::<Vec<_>>();
+        receipts.sort_by_key(|r| r.data.verified_at);
+        receipts
+    }
+}
@@ -9,12 +9,19 @@ use rings_core::message::MessagePayload;
 use rings_derive::wasm_export;
 use rings_rpc::method::Method;
 use rings_snark::circuit;
+use rings_snark::prelude::bellman::groth16;
+use rings_snark::prelude::bellman::pairing::bn256::Bn256;
+use rings_snark::prelude::ff::PrimeField;
 use rings_snark::prelude::nova::provider;
 use rings_snark::prelude::nova::provider::hyperkzg;
 use rings_snark::prelude::nova::provider::ipa_pc;
 use rings_snark::prelude::nova::spartan;
 use rings_snark::prelude::nova::traits::snark::RelaxedR1CSSNARKTrait;
 use rings_snark::prelude::nova::traits::Engine;
+use rings_snark::proof_system::groth16::Groth16;
+use rings_snark::proof_system::groth16::Groth16Codec;
+use rings_snark::proof_system::solidity;
+use rings_snark::proof_system::ProofSystem;
 use rings_snark::r1cs;
 use rings_snark::snark::CompressedSNARK;
 use rings_snark::snark::ProverKey;
@@ -24,6 +31,11 @@ use rings_snark::snark::SNARK;
 use serde::Deserialize;
 use serde::Serialize;
 
+use self::params::PublicParamsRef;
+use self::params::SNARKPublicParams;
+use self::record::SNARKTaskRecord;
+use self::record::SNARKTaskStatus;
+use self::record::SNARKTaskStorageRef;
 use super::types::snark::SNARKProofTask;
 use super::types::snark::SNARKTask;
 use super::types::snark::SNARKTaskMessage;
@@ -38,14 +50,30 @@ type TaskId = uuid::Uuid;
 
 #[cfg(feature = "browser")]
 pub mod browser;
+pub mod coordinator;
+pub mod market;
+pub mod params;
+pub mod progress;
+pub mod prover;
+pub mod record;
 
 /// Task Manageer of SNARK provier and verifier
-#[derive(Default, Clone)]
+#[derive(Default)]
 pub struct SNARKTaskManager {
-    /// map of task_id and task
-    task: DashMap<TaskId, SNARKProofTask>,
-    /// map of task_id and result
-    verified: DashMap<TaskId, bool>,
+    /// map of task_id and task record, see [record]
+    records: DashMap<TaskId, SNARKTaskRecord>,
+    /// persistent storage of task records
+    storage: SNARKTaskStorageRef,
+    /// map of task_id and distributed task, see [coordinator]
+    pub(crate) distributed: DashMap<TaskId, coordinator::DistributedTask>,
+    /// map of sub task_id and the distributed task_id it belongs to
+    sub_tasks: DashMap<TaskId, TaskId>,
+    /// queue of proof jobs received from requesters, see [prover]
+    prover: prover::ProverQueue,
+    /// cached public params, see [params]
+    params: params::PublicParamsCache,
+    /// quotes and receipts, see [market]
+    market: market::Market,
 }
 
 /// SNARK message handler
@@ -80,6 +108,33 @@ impl SNARKBehaviour {
         SNARKTaskBuilder::gen_proof_task(circuits)
     }
 
+    /// Generate groth16 proof task of a bn256 circuit
+    pub fn gen_groth16_proof_task(circuit: Circuit) -> Result<SNARKProofTask> {
+        SNARKTaskBuilder::gen_groth16_proof_task(circuit)
+    }
+
+    /// Generate groth16 proof task of a bn256 circuit, with prover key in bellman format
+    pub fn gen_groth16_proof_task_with_pk(circuit: Circuit, pk: &[u8]) -> Result<SNARKProofTask> {
+        SNARKTaskBuilder::gen_groth16_proof_task_with_pk(circuit, pk)
+    }
+
+    /// Generate proof task, reuse cached public params of the circuits if there are
+    pub async fn gen_cached_proof_task(&self, circuits: Vec<Circuit>) -> Result<SNARKProofTask> {
+        let key = circuits
+            .first()
+            .ok_or_else(|| Error::SNARKHandleMessage("no circuit".to_string()))?
+            .params_key()?;
+        let params = self.get_public_params(&key).await?;
+        let cached = params.is_some();
+        let task = SNARKTaskBuilder::gen_proof_task_with_params(circuits, params)?;
+        if !cached {
+            if let Some(params) = task.public_params() {
+                self.put_public_params(&key, params).await?;
+            }
+        }
+        Ok(task)
+    }
+
     /// Generate a proof task and send it to did
     pub async fn gen_and_send_proof_task(
         &self,
@@ -87,7 +142,7 @@ impl SNARKBehaviour {
         circuits: Vec<Circuit>,
         did: Did,
     ) -> Result<String> {
-        let task = Self::gen_proof_task(circuits)?;
+        let task = self.gen_cached_proof_task(circuits).await?;
         self.send_proof_task(provider.clone(), &task, did).await
     }
 
@@ -100,11 +155,43 @@ impl SNARKBehaviour {
     ) -> Result<String> {
         let task_id = uuid::Uuid::new_v4();
         let task = task_ref.as_ref().clone();
-        let msg: BackendMessage = SNARKTaskMessage {
-            task_id,
-            task: SNARKTask::SNARKProof(task.clone()),
+        let record = SNARKTaskRecord::new(Some(task.clone()), Some(did));
+        self.dispatch_proof_task(&provider, task_id, task, record)
+            .await?;
+        Ok(task_id.to_string())
+    }
+
+    /// Save record of task and send the task to prover of record
+    async fn dispatch_proof_task(
+        &self,
+        provider: &Provider,
+        task_id: TaskId,
+        task: SNARKProofTask,
+        record: SNARKTaskRecord,
+    ) -> Result<()> {
+        let did = record.prover.ok_or_else(Error::SNARKNoProverAvailable)?;
+        self.cache_task_params(provider, &task).await?;
+        self.save_record(task_id, record).await?;
+        let msg = SNARKTask::SNARKProof(task);
+        if let Err(e) = Self::send_task_message(provider, task_id, msg, did).await {
+            self.update_status(task_id, SNARKTaskStatus::Failed, Some(e.to_string()))
+                .await?;
+            return Err(e);
         }
-        .into();
+        self.update_status(task_id, SNARKTaskStatus::Sent, None)
+            .await?;
+        tracing::info!("sent proof request");
+        Ok(())
+    }
+
+    /// send task message with given task_id to did
+    async fn send_task_message(
+        provider: &Provider,
+        task_id: TaskId,
+        task: SNARKTask,
+        did: Did,
+    ) -> Result<()> {
+        let msg: BackendMessage = SNARKTaskMessage { task_id, task }.into();
         let params = msg.into_send_backend_message_request(did)?;
         #[cfg(not(target_arch = "wasm32"))]
         provider.request(Method::SendBackendMessage, params).await?;
@@ -116,23 +203,68 @@ impl SNARKBehaviour {
                 .await
                 .map_err(|e| Error::JsError(format!("Failed to send backend messate: {:?}", e)))?;
         }
-        self.task.insert(task_id, task);
-        tracing::info!("sent proof request");
-        Ok(task_id.to_string())
+        Ok(())
+    }
+
+    /// Store and verify proof of task, a proof of finished task is ignored.
+    /// A receipt is sent to prover if the task is quoted, see [market].
+    async fn on_task_proof(
+        &self,
+        provider: &Provider,
+        task_id: TaskId,
+        proof: &SNARKVerifyTask,
+    ) -> Result<()> {
+        let Some(mut record) = self.records.get(&task_id).map(|r| r.value().clone()) else {
+            return Ok(());
+        };
+        let Some(task) = record.task.clone() else {
+            return Ok(());
+        };
+        record.proof = Some(proof.clone());
+        record.set_status(SNARKTaskStatus::ProofReceived, None);
+        self.save_record(task_id, record.clone()).await?;
+
+        match Self::handle_snark_verify_task(proof, &task) {
+            Ok(true) => {
+                self.update_status(task_id, SNARKTaskStatus::Verified, None)
+                    .await?;
+                if let Err(e) = self.issue_receipt(provider, task_id, &record).await {
+                    tracing::warn!("failed to issue receipt of task {}: {}", task_id, e);
+                }
+                Ok(())
+            }
+            Ok(false) => {
+                self.update_status(
+                    task_id,
+                    SNARKTaskStatus::Failed,
+                    Some("proof verification failed".to_string()),
+                )
+                .await
+            }
+            Err(e) => {
+                self.update_status(task_id, SNARKTaskStatus::Failed, Some(e.to_string()))
+                    .await
+            }
+        }
     }
 }
 
 #[wasm_export]
 impl SNARKBehaviour {
-    /// Get task result
+    /// Get task result, return true if the proof is verified
     pub fn get_task_result(&self, task_id: String) -> Result<bool> {
         let task_id = uuid::Uuid::parse_str(&task_id)?;
-        if let Some(v) = self.inner.verified.get(&task_id) {
-            Ok(*v.value())
+        if let Some(v) = self.inner.records.get(&task_id) {
+            Ok(v.status == SNARKTaskStatus::Verified)
         } else {
             Ok(false)
         }
     }
+
+    /// Get task status, such as `proving` and `verified`, see [SNARKTaskStatus]
+    pub fn get_task_status(&self, task_id: String) -> Result<String> {
+        Ok(self.get_task_record(task_id)?.status.as_str().to_string())
+    }
 }
 
 /// Types for circuit
@@ -234,6 +366,15 @@ impl Circuit {
     pub fn from_json(s: String) -> Result<Circuit> {
         Ok(serde_json::from_str(&s)?)
     }
+
+    /// Key of public params of circuit, see [params::params_key]
+    pub fn params_key(&self) -> Result<String> {
+        match &self.inner {
+            CircuitEnum::Vesta(c) => params::params_key("VastaPallas", c),
+            CircuitEnum::Pallas(c) => params::params_key("PallasVasta", c),
+            CircuitEnum::Bn256KZG(c) => params::params_key("Bn256KZGGrumpkin", c),
+        }
+    }
 }
 
 /// Field type
@@ -270,6 +411,19 @@ impl Field {
             },
         }
     }
+
+    /// create field from decimal string
+    pub fn from_decimal(v: String, ty: SupportedPrimeField) -> Result<Field> {
+        fn parse<F: PrimeField>(v: &str) -> Result<F> {
+            F::from_str_vartime(v).ok_or(Error::FailedToLoadFF())
+        }
+        let value = match ty {
+            SupportedPrimeField::Vesta => FieldEnum::Vesta(parse(&v)?),
+            SupportedPrimeField::Pallas => FieldEnum::Pallas(parse(&v)?),
+            SupportedPrimeField::Bn256KZG => FieldEnum::Bn256KZG(parse(&v)?),
+        };
+        Ok(Self { value })
+    }
 }
 
 /// Snark builder
@@ -552,6 +706,19 @@ impl SNARKTaskBuilder {
 impl SNARKTaskBuilder {
     /// Generate proof task
     pub fn gen_proof_task(circuits: Vec<Circuit>) -> Result<SNARKProofTask> {
+        Self::gen_proof_task_with_params(circuits, None)
+    }
+
+    /// Generate proof task with public params, they are generated if not given or the curve
+    /// doesn't match
+    pub fn gen_proof_task_with_params(
+        circuits: Vec<Circuit>,
+        params: Option<SNARKPublicParams>,
+    ) -> Result<SNARKProofTask> {
+        let key = circuits
+            .first()
+            .ok_or_else(|| Error::SNARKHandleMessage("no circuit".to_string()))?
+            .params_key()?;
         let task = match &circuits[0].inner {
             CircuitEnum::Vesta(_) => {
                 type E1 = provider::VestaEngine;
@@ -571,16 +738,17 @@ impl SNARKTaskBuilder {
                     })
                     .collect();
                 let inputs = circuits[0].get_public_inputs();
-                let pp = SNARK::<E1, E2>::gen_pp::<S1, S2>(circuits[0].clone())?;
-                let snark = SNARK::<E1, E2>::new(&circuits[0], &pp, &inputs, &vec![
+                let pp = match params {
+                    Some(SNARKPublicParams::VastaPallas(pp)) => pp,
+                    _ => Arc::new(SNARK::<E1, E2>::gen_pp::<S1, S2>(circuits[0].clone())?),
+                };
+                let snark = SNARK::<E1, E2>::new(&circuits[0], &*pp, &inputs, &vec![
                     <E2 as Engine>::Scalar::from(0),
                 ])?;
 
-                SNARKProofTask::VastaPallas(SNARKGenerator {
-                    pp: pp.into(),
-                    snark,
-                    circuits,
-                })
+                SNARKProofTask::VastaPallas(SNARKGenerator::new::<S1, S2>(
+                    snark, circuits, pp, key,
+                )?)
             }
             CircuitEnum::Pallas(_) => {
                 type E1 = provider::PallasEngine;
@@ -600,15 +768,16 @@ impl SNARKTaskBuilder {
                     })
                     .collect();
                 let inputs = circuits[0].get_public_inputs();
-                let pp = SNARK::<E1, E2>::gen_pp::<S1, S2>(circuits[0].clone())?;
-                let snark = SNARK::<E1, E2>::new(&circuits[0], &pp, &inputs, &vec![
+                let pp = match params {
+                    Some(SNARKPublicParams::PallasVasta(pp)) => pp,
+                    _ => Arc::new(SNARK::<E1, E2>::gen_pp::<S1, S2>(circuits[0].clone())?),
+                };
+                let snark = SNARK::<E1, E2>::new(&circuits[0], &*pp, &inputs, &vec![
                     <E2 as Engine>::Scalar::from(0),
                 ])?;
-                SNARKProofTask::PallasVasta(SNARKGenerator {
-                    pp: pp.into(),
-                    snark,
-                    circuits,
-                })
+                SNARKProofTask::PallasVasta(SNARKGenerator::new::<S1, S2>(
+                    snark, circuits, pp, key,
+                )?)
             }
             CircuitEnum::Bn256KZG(_) => {
                 type E1 = provider::Bn256EngineKZG;
@@ -628,19 +797,51 @@ impl SNARKTaskBuilder {
                     })
                     .collect();
                 let inputs = circuits[0].get_public_inputs();
-                let pp = SNARK::<E1, E2>::gen_pp::<S1, S2>(circuits[0].clone())?;
-                let snark = SNARK::<E1, E2>::new(&circuits[0], &pp, &inputs, &vec![
+                let pp = match params {
+                    Some(SNARKPublicParams::Bn256KZGGrumpkin(pp)) => pp,
+                    _ => Arc::new(SNARK::<E1, E2>::gen_pp::<S1, S2>(circuits[0].clone())?),
+                };
+                let snark = SNARK::<E1, E2>::new(&circuits[0], &*pp, &inputs, &vec![
                     <E2 as Engine>::Scalar::from(0),
                 ])?;
-                SNARKProofTask::Bn256KZGGrumpkin(SNARKGenerator {
-                    pp: pp.into(),
-                    snark,
-                    circuits,
-                })
+                SNARKProofTask::Bn256KZGGrumpkin(SNARKGenerator::new::<S1, S2>(
+                    snark, circuits, pp, key,
+                )?)
             }
         };
         Ok(task)
     }
+
+    /// Generate groth16 proof task, the circuit should be based on bn256.
+    /// Keys of the circuit are generated locally, so that only the requester can verify the
+    /// proofs, use [SNARKTaskBuilder::gen_groth16_proof_task_with_pk] with keys of a trusted
+    /// setup ceremony for proofs verified by others, such as on-chain verifiers.
+    pub fn gen_groth16_proof_task(circuit: Circuit) -> Result<SNARKProofTask> {
+        type F = <provider::Bn256EngineKZG as Engine>::Scalar;
+        type G = Groth16<Bn256>;
+        match circuit.inner {
+            CircuitEnum::Bn256KZG(circuit) => {
+                let (pk, _) = <G as ProofSystem<F>>::setup(&circuit)?;
+                Ok(SNARKProofTask::Groth16Bn256(Groth16Task::new(
+                    circuit, &pk,
+                )?))
+            }
+            _ => Err(Error::SNARKWrongField("Bn256KZG".to_string())),
+        }
+    }
+
+    /// Generate groth16 proof task with prover key of the circuit, in bellman format
+    pub fn gen_groth16_proof_task_with_pk(circuit: Circuit, pk: &[u8]) -> Result<SNARKProofTask> {
+        match circuit.inner {
+            CircuitEnum::Bn256KZG(circuit) => {
+                let pk = groth16::Parameters::<Bn256>::from_bytes(pk)?;
+                Ok(SNARKProofTask::Groth16Bn256(Groth16Task::new(
+                    circuit, &pk,
+                )?))
+            }
+            _ => Err(Error::SNARKWrongField("Bn256KZG".to_string())),
+        }
+    }
 }
 
 /// SNARK Proof
@@ -666,6 +867,96 @@ where
     pub proof: CompressedSNARK<E1, E2, S1, S2>,
 }
 
+/// Groth16 proof task of a single bn256 circuit.
+/// The circuit specific keys are owned by requester, the prover key is sent with the task, and
+/// the verifier key is pinned by its hash. Proofs with other verifier keys are rejected, so a
+/// prover cannot forge proofs with keys of its own setup.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Groth16Task {
+    circuit: circuit::Circuit<<provider::Bn256EngineKZG as Engine>::Scalar>,
+    /// prover key, in base64 encoded bellman format
+    pk: String,
+    /// hex encoded keccak256 hash of verifier key
+    vk_hash: String,
+}
+
+impl Groth16Task {
+    fn new(
+        circuit: circuit::Circuit<<provider::Bn256EngineKZG as Engine>::Scalar>,
+        pk: &groth16::Parameters<Bn256>,
+    ) -> Result<Self> {
+        Ok(Self {
+            circuit,
+            pk: base64::encode(pk.to_bytes()?),
+            vk_hash: groth16_vk_hash(&pk.vk)?,
+        })
+    }
+
+    /// Hash of verifier key which proofs of task are verified with
+    pub fn vk_hash(&self) -> &str {
+        &self.vk_hash
+    }
+
+    /// Decode prover key, which should match the pinned verifier key
+    fn pk(&self) -> Result<groth16::Parameters<Bn256>> {
+        let pk = base64::decode(&self.pk).map_err(|e| Error::SNARKHandleMessage(e.to_string()))?;
+        let pk = groth16::Parameters::<Bn256>::from_bytes(&pk)?;
+        self.check_vk(&pk.vk)?;
+        Ok(pk)
+    }
+
+    /// Check verifier key against the pinned hash
+    fn check_vk(&self, vk: &groth16::VerifyingKey<Bn256>) -> Result<()> {
+        if groth16_vk_hash(vk)? != self.vk_hash {
+            return Err(Error::SNARKVerifierKeyMismatch(self.vk_hash.clone()));
+        }
+        Ok(())
+    }
+}
+
+fn groth16_vk_hash(vk: &groth16::VerifyingKey<Bn256>) -> Result<String> {
+    Ok(hex_keccak256(&vk.to_bytes()?))
+}
+
+fn nova_vk_hash<T: Serialize>(vk: &T) -> Result<String> {
+    Ok(hex_keccak256(&serde_json::to_vec(vk)?))
+}
+
+fn hex_keccak256(data: &[u8]) -> String {
+    rings_core::ecc::keccak256(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Groth16 proof with its verifier key, in base64 encoded bellman format
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Groth16Proof {
+    /// verifier key
+    pub vk: String,
+    /// proof
+    pub proof: String,
+}
+
+/// Solidity verifier contract of a groth16 proof, and calldata of the proof
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Groth16SolidityExport {
+    /// source of verifier contract
+    pub contract: String,
+    /// hex encoded calldata of `verifyProof`, with `0x` prefix
+    pub calldata: String,
+}
+
+impl Groth16Proof {
+    fn decode(&self) -> Result<(groth16::VerifyingKey<Bn256>, groth16::Proof<Bn256>)> {
+        let decode =
+            |s: &str| base64::decode(s).map_err(|e| Error::SNARKHandleMessage(e.to_string()));
+        let vk = groth16::VerifyingKey::<Bn256>::from_bytes(&decode(&self.vk)?)?;
+        let proof = groth16::Proof::<Bn256>::from_bytes(&decode(&self.proof)?)?;
+        Ok((vk, proof))
+    }
+}
+
 /// SNARK proof generator, including setup, proof and verify
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SNARKGenerator<E1, E2>
@@ -675,7 +966,18 @@ where
 {
     snark: SNARK<E1, E2>,
     circuits: Vec<circuit::Circuit<<E1 as Engine>::Scalar>>,
-    pp: Arc<PublicParams<E1, E2>>,
+    /// reference of public params, provers resolve the params by it, see [params]
+    params: PublicParamsRef,
+    /// public params, they're not sent with task
+    #[serde(skip)]
+    pp: Option<Arc<PublicParams<E1, E2>>>,
+    /// hex encoded keccak256 hash of verifier key, proofs with other verifier keys are rejected
+    #[serde(default)]
+    vk_hash: String,
+    /// count of circuits folded into snark, a partially folded generator is a checkpoint
+    /// of task, see [progress]
+    #[serde(default)]
+    folded: usize,
 }
 
 impl SNARKProofTask {
@@ -697,8 +999,91 @@ impl SNARKProofTask {
                 .into_iter()
                 .map(SNARKProofTask::Bn256KZGGrumpkin)
                 .collect(),
+            SNARKProofTask::Groth16Bn256(_) => vec![self.clone()],
+        }
+    }
+
+    /// Regenerate public params of task if they're not resolved yet, see
+    /// [SNARKGenerator::ensure_public_params]. Params with KZG commitment keys are not
+    /// regenerated, error is returned if they're not resolved.
+    pub fn ensure_public_params(&mut self) -> Result<()> {
+        match self {
+            SNARKProofTask::VastaPallas(g) => {
+                type E1 = provider::VestaEngine;
+                type E2 = provider::PallasEngine;
+                type S1 = spartan::snark::RelaxedR1CSSNARK<E1, ipa_pc::EvaluationEngine<E1>>;
+                type S2 = spartan::snark::RelaxedR1CSSNARK<E2, ipa_pc::EvaluationEngine<E2>>;
+                g.ensure_public_params::<S1, S2>()
+            }
+            SNARKProofTask::PallasVasta(g) => {
+                type E1 = provider::PallasEngine;
+                type E2 = provider::VestaEngine;
+                type S1 = spartan::snark::RelaxedR1CSSNARK<E1, ipa_pc::EvaluationEngine<E1>>;
+                type S2 = spartan::snark::RelaxedR1CSSNARK<E2, ipa_pc::EvaluationEngine<E2>>;
+                g.ensure_public_params::<S1, S2>()
+            }
+            // params with KZG commitment keys are not deterministic, they cannot be regenerated
+            SNARKProofTask::Bn256KZGGrumpkin(g) => g.pp().map(|_| ()),
+            SNARKProofTask::Groth16Bn256(_) => Ok(()),
+        }
+    }
+
+    /// Fold at most `n` steps of task, return count of folded steps.
+    /// Groth16 tasks are not folded, they're proved in one step.
+    pub fn fold_next(&mut self, n: usize) -> Result<usize> {
+        match self {
+            SNARKProofTask::PallasVasta(g) => g.fold_next(n),
+            SNARKProofTask::VastaPallas(g) => g.fold_next(n),
+            SNARKProofTask::Bn256KZGGrumpkin(g) => g.fold_next(n),
+            SNARKProofTask::Groth16Bn256(_) => Ok(1),
+        }
+    }
+
+    /// Count of folded steps of task
+    pub fn folded(&self) -> usize {
+        match self {
+            SNARKProofTask::PallasVasta(g) => g.folded(),
+            SNARKProofTask::VastaPallas(g) => g.folded(),
+            SNARKProofTask::Bn256KZGGrumpkin(g) => g.folded(),
+            SNARKProofTask::Groth16Bn256(_) => 0,
+        }
+    }
+
+    /// Hash of verifier key which proofs of task are verified with
+    pub fn vk_hash(&self) -> &str {
+        match self {
+            SNARKProofTask::PallasVasta(g) => g.vk_hash(),
+            SNARKProofTask::VastaPallas(g) => g.vk_hash(),
+            SNARKProofTask::Bn256KZGGrumpkin(g) => g.vk_hash(),
+            SNARKProofTask::Groth16Bn256(t) => t.vk_hash(),
+        }
+    }
+
+    /// Size of task, as number of steps and max number of constraints of a step circuit
+    pub fn size(&self) -> (usize, usize) {
+        match self {
+            SNARKProofTask::PallasVasta(g) => g.size(),
+            SNARKProofTask::VastaPallas(g) => g.size(),
+            SNARKProofTask::Bn256KZGGrumpkin(g) => g.size(),
+            SNARKProofTask::Groth16Bn256(t) => (1, t.circuit.num_constraints()),
         }
     }
+
+    /// Public inputs of the first step and public outputs of the last step, encoded as json.
+    /// Sub tasks split from a task are linked by them, the output of a sub task is the input of
+    /// the next one.
+    pub fn io(&self) -> Result<(String, String)> {
+        let (input, output) = match self {
+            SNARKProofTask::PallasVasta(g) => encode_io(g.io()),
+            SNARKProofTask::VastaPallas(g) => encode_io(g.io()),
+            SNARKProofTask::Bn256KZGGrumpkin(g) => encode_io(g.io()),
+            SNARKProofTask::Groth16Bn256(t) => encode_io((
+                t.circuit.get_public_inputs(),
+                t.circuit.get_public_outputs(),
+            )),
+        };
+        Ok((input?, output?))
+    }
 }
 
 impl<E1, E2> SNARKGenerator<E1, E2>
@@ -706,24 +1091,135 @@ where
     E1: Engine<Base = <E2 as Engine>::Scalar>,
     E2: Engine<Base = <E1 as Engine>::Scalar>,
 {
+    /// Create generator with public params of circuits, `key` is the key of public params,
+    /// see [params::params_key]. The verifier key derived from public params is pinned by its
+    /// hash.
+    pub fn new<S1: RelaxedR1CSSNARKTrait<E1>, S2: RelaxedR1CSSNARKTrait<E2>>(
+        snark: SNARK<E1, E2>,
+        circuits: Vec<circuit::Circuit<<E1 as Engine>::Scalar>>,
+        pp: Arc<PublicParams<E1, E2>>,
+        key: String,
+    ) -> Result<Self> {
+        let params = PublicParamsRef {
+            key,
+            digest: pp.digest(),
+        };
+        let (_, vk) = SNARK::<E1, E2>::compress_setup::<S1, S2>(&pp)?;
+        Ok(Self {
+            snark,
+            circuits,
+            params,
+            pp: Some(pp),
+            vk_hash: nova_vk_hash(&vk)?,
+            folded: 0,
+        })
+    }
+
+    /// Reference of public params
+    pub fn params_ref(&self) -> &PublicParamsRef {
+        &self.params
+    }
+
+    /// Public params, None if they're not resolved yet
+    pub fn public_params(&self) -> Option<Arc<PublicParams<E1, E2>>> {
+        self.pp.clone()
+    }
+
+    /// Set public params, return error if the digest doesn't match
+    pub fn set_public_params(&mut self, pp: Arc<PublicParams<E1, E2>>) -> Result<()> {
+        if pp.digest() != self.params.digest {
+            return Err(Error::SNARKPublicParamsMismatch(self.params.key.clone()));
+        }
+        self.pp = Some(pp);
+        Ok(())
+    }
+
+    /// Regenerate public params if they're not resolved yet, return error if regenerated params
+    /// don't match. Params with KZG commitment keys never match, don't regenerate them.
+    pub fn ensure_public_params<S1: RelaxedR1CSSNARKTrait<E1>, S2: RelaxedR1CSSNARKTrait<E2>>(
+        &mut self,
+    ) -> Result<()> {
+        if self.pp.is_some() {
+            return Ok(());
+        }
+        let circuit = self
+            .circuits
+            .first()
+            .ok_or_else(|| Error::SNARKPublicParamsNotFound(self.params.key.clone()))?;
+        tracing::info!("regenerating public params {}", self.params.key);
+        let pp = SNARK::<E1, E2>::gen_pp::<S1, S2>(circuit.clone())?;
+        self.set_public_params(Arc::new(pp))
+    }
+
+    fn pp(&self) -> Result<&Arc<PublicParams<E1, E2>>> {
+        self.pp
+            .as_ref()
+            .ok_or_else(|| Error::SNARKPublicParamsNotFound(self.params.key.clone()))
+    }
+
     /// Setup snark, get pk and vk, if check set to true, it will check the folding is working correct
     pub fn fold(&mut self, check: bool) -> Result<()> {
-        self.snark.fold_all(&self.pp, &self.circuits)?;
+        self.fold_next(self.circuits.len())?;
         if check {
             let steps = self.circuits.len();
             let first_input = self.circuits.first().unwrap().get_public_inputs();
             self.snark
-                .verify(&self.pp, steps, first_input, vec![E2::Scalar::from(0)])?;
+                .verify(self.pp()?, steps, first_input, vec![E2::Scalar::from(0)])?;
         }
         Ok(())
     }
 
+    /// Fold at most `n` circuits which are not folded yet, return count of folded circuits
+    pub fn fold_next(&mut self, n: usize) -> Result<usize> {
+        let pp = self.pp()?.clone();
+        let end = self.circuits.len().min(self.folded.saturating_add(n));
+        for c in &self.circuits[self.folded.min(end)..end] {
+            self.snark.foldr(&pp, c)?;
+            self.folded += 1;
+        }
+        Ok(self.folded)
+    }
+
+    /// Count of circuits folded into snark
+    pub fn folded(&self) -> usize {
+        self.folded
+    }
+
+    /// Number of steps and max number of constraints of a step circuit
+    pub fn size(&self) -> (usize, usize) {
+        let constraints = self
+            .circuits
+            .iter()
+            .map(|c| c.num_constraints())
+            .max()
+            .unwrap_or(0);
+        (self.circuits.len(), constraints)
+    }
+
+    /// Public inputs of the first step and public outputs of the last step
+    pub fn io(&self) -> (Vec<E1::Scalar>, Vec<E1::Scalar>) {
+        let input = self
+            .circuits
+            .first()
+            .map(|c| c.get_public_inputs())
+            .unwrap_or_default();
+        let output = self
+            .circuits
+            .last()
+            .map(|c| c.get_public_outputs())
+            .unwrap_or_default();
+        (input, output)
+    }
+
     /// Split a SNARKGenerator task to multiple, by split circuits into multiple
     pub fn split(&self, n: usize) -> Vec<Self> {
         let SNARKGenerator {
             snark,
             circuits,
+            params,
             pp,
+            vk_hash,
+            ..
         } = self;
 
         let mut split = Vec::new();
@@ -733,7 +1229,10 @@ where
             let new_generator = SNARKGenerator {
                 snark: snark.clone(),
                 circuits: circuit_chunk.to_vec(),
-                pp: Arc::clone(pp),
+                params: params.clone(),
+                pp: pp.clone(),
+                vk_hash: vk_hash.clone(),
+                folded: 0,
             };
             split.push(new_generator);
         }
@@ -745,7 +1244,7 @@ where
     pub fn setup<S1: RelaxedR1CSSNARKTrait<E1>, S2: RelaxedR1CSSNARKTrait<E2>>(
         &self,
     ) -> Result<(ProverKey<E1, E2, S1, S2>, VerifierKey<E1, E2, S1, S2>)> {
-        Ok(SNARK::<E1, E2>::compress_setup(&self.pp)?)
+        Ok(SNARK::<E1, E2>::compress_setup(self.pp()?)?)
     }
 
     /// gen proof for compressed snark
@@ -753,16 +1252,34 @@ where
         &self,
         pk: impl AsRef<ProverKey<E1, E2, S1, S2>>,
     ) -> Result<CompressedSNARK<E1, E2, S1, S2>> {
-        Ok(self.snark.compress_prove(&self.pp, pk)?)
+        Ok(self.snark.compress_prove(self.pp()?, pk)?)
     }
 
-    /// verify a proof
+    /// Hash of verifier key which proofs of task are verified with
+    pub fn vk_hash(&self) -> &str {
+        &self.vk_hash
+    }
+
+    /// Check verifier key against the pinned hash
+    pub fn check_vk<S1: RelaxedR1CSSNARKTrait<E1>, S2: RelaxedR1CSSNARKTrait<E2>>(
+        &self,
+        vk: &VerifierKey<E1, E2, S1, S2>,
+    ) -> Result<()> {
+        if nova_vk_hash(vk)? != self.vk_hash {
+            return Err(Error::SNARKVerifierKeyMismatch(self.vk_hash.clone()));
+        }
+        Ok(())
+    }
+
+    /// verify a proof, proofs with verifier key not matching the pinned one are rejected,
+    /// see [Self::check_vk]
     #[allow(clippy::type_complexity)]
     pub fn verify<S1: RelaxedR1CSSNARKTrait<E1>, S2: RelaxedR1CSSNARKTrait<E2>>(
         &self,
         proof: impl AsRef<CompressedSNARK<E1, E2, S1, S2>>,
         vk: impl AsRef<VerifierKey<E1, E2, S1, S2>>,
     ) -> Result<(Vec<E1::Scalar>, Vec<E2::Scalar>)> {
+        self.check_vk(vk.as_ref())?;
         let steps = self.circuits.len();
         let first_input = self.circuits.first().unwrap().get_public_inputs();
         Ok(SNARK::<E1, E2>::compress_verify(
@@ -787,6 +1304,7 @@ impl SNARKBehaviour {
                 type S1 = spartan::snark::RelaxedR1CSSNARK<E1, EE1>;
                 type S2 = spartan::snark::RelaxedR1CSSNARK<E2, EE2>;
                 let mut snark = s.clone();
+                snark.ensure_public_params::<S1, S2>()?;
                 snark.fold(false)?;
                 let (pk, vk) = snark.setup()?;
                 let compressed_proof = snark.prove::<S1, S2>(&pk)?;
@@ -804,6 +1322,7 @@ impl SNARKBehaviour {
                 type S1 = spartan::snark::RelaxedR1CSSNARK<E1, EE1>;
                 type S2 = spartan::snark::RelaxedR1CSSNARK<E2, EE2>;
                 let mut snark = s.clone();
+                snark.ensure_public_params::<S1, S2>()?;
                 snark.fold(false)?;
                 let (pk, vk) = snark.setup()?;
                 let compressed_proof = snark.prove::<S1, S2>(&pk)?;
@@ -821,6 +1340,7 @@ impl SNARKBehaviour {
                 type S1 = spartan::snark::RelaxedR1CSSNARK<E1, EE1>; // non-preprocessing SNARK
                 type S2 = spartan::snark::RelaxedR1CSSNARK<E2, EE2>; // non-preprocessing SNARK
                 let mut snark = s.clone();
+                snark.ensure_public_params::<S1, S2>()?;
                 snark.fold(false)?;
                 let (pk, vk) = snark.setup()?;
                 let compressed_proof = snark.prove::<S1, S2>(&pk)?;
@@ -832,6 +1352,19 @@ impl SNARKBehaviour {
                     &proof,
                 )?))
             }
+            SNARKProofTask::Groth16Bn256(t) => {
+                type F = <provider::Bn256EngineKZG as Engine>::Scalar;
+                type G = Groth16<Bn256>;
+                let pk = t.pk()?;
+                let proof = <G as ProofSystem<F>>::prove(&pk, std::slice::from_ref(&t.circuit))?;
+                let proof = Groth16Proof {
+                    vk: base64::encode(pk.vk.to_bytes()?),
+                    proof: base64::encode(proof.to_bytes()?),
+                };
+                Ok(SNARKVerifyTask::Groth16Bn256(serde_json::to_string(
+                    &proof,
+                )?))
+            }
         };
         tracing::debug!("SNARK proof success");
         ret
@@ -842,6 +1375,15 @@ impl SNARKBehaviour {
         data: T,
         snark: F,
     ) -> Result<bool> {
+        Ok(Self::verify_snark_proof_output(data, snark)?.is_some())
+    }
+
+    /// Verify proof of task, return the public outputs of the last step proved, encoded as
+    /// json like [SNARKProofTask::io]. Returns None if the proof is invalid.
+    pub fn verify_snark_proof_output<T: AsRef<SNARKVerifyTask>, F: AsRef<SNARKProofTask>>(
+        data: T,
+        snark: F,
+    ) -> Result<Option<String>> {
         tracing::debug!("SNARK verify start");
         let snark = snark.as_ref();
         let ret = match data.as_ref() {
@@ -855,7 +1397,7 @@ impl SNARKBehaviour {
                 let proof = serde_json::from_str::<SNARKProof<E1, E2, S1, S2>>(p)?;
                 if let SNARKProofTask::PallasVasta(t) = snark {
                     let ret = t.verify::<S1, S2>(proof.proof, proof.vk);
-                    Ok(ret.is_ok())
+                    ret.ok().map(|(z, _)| encode_scalars(z)).transpose()
                 } else {
                     Err(Error::SNARKCurveNotMatch())
                 }
@@ -870,7 +1412,7 @@ impl SNARKBehaviour {
                 let proof = serde_json::from_str::<SNARKProof<E1, E2, S1, S2>>(p)?;
                 if let SNARKProofTask::VastaPallas(t) = snark {
                     let ret = t.verify::<S1, S2>(proof.proof, proof.vk);
-                    Ok(ret.is_ok())
+                    ret.ok().map(|(z, _)| encode_scalars(z)).transpose()
                 } else {
                     Err(Error::SNARKCurveNotMatch())
                 }
@@ -885,7 +1427,26 @@ impl SNARKBehaviour {
                 let proof = serde_json::from_str::<SNARKProof<E1, E2, S1, S2>>(p)?;
                 if let SNARKProofTask::Bn256KZGGrumpkin(t) = snark {
                     let ret = t.verify::<S1, S2>(proof.proof, proof.vk);
-                    Ok(ret.is_ok())
+                    ret.ok().map(|(z, _)| encode_scalars(z)).transpose()
+                } else {
+                    Err(Error::SNARKCurveNotMatch())
+                }
+            }
+            SNARKVerifyTask::Groth16Bn256(p) => {
+                type F = <provider::Bn256EngineKZG as Engine>::Scalar;
+                type G = Groth16<Bn256>;
+                let proof = serde_json::from_str::<Groth16Proof>(p)?;
+                if let SNARKProofTask::Groth16Bn256(t) = snark {
+                    let (vk, proof) = proof.decode()?;
+                    if let Err(e) = t.check_vk(&vk) {
+                        tracing::warn!("groth16 proof rejected: {}", e);
+                        return Ok(None);
+                    }
+                    let public_signals = t.circuit.get_public_signals();
+                    let verified = <G as ProofSystem<F>>::verify(&vk, &proof, &public_signals)?;
+                    verified
+                        .then(|| encode_scalars(t.circuit.get_public_outputs()))
+                        .transpose()
                 } else {
                     Err(Error::SNARKCurveNotMatch())
                 }
@@ -894,6 +1455,47 @@ impl SNARKBehaviour {
         tracing::debug!("SNARK verify success");
         ret
     }
+
+    /// Export solidity verifier contract and calldata of a groth16 proof, for settling the
+    /// proof on-chain. Only [SNARKVerifyTask::Groth16Bn256] is supported.
+    /// The contract embeds the verifier key of task, instead of the one sent with the proof,
+    /// proofs with other verifier keys are rejected.
+    pub fn export_solidity_verifier<T: AsRef<SNARKVerifyTask>, F: AsRef<SNARKProofTask>>(
+        data: T,
+        snark: F,
+    ) -> Result<Groth16SolidityExport> {
+        match (data.as_ref(), snark.as_ref()) {
+            (SNARKVerifyTask::Groth16Bn256(p), SNARKProofTask::Groth16Bn256(t)) => {
+                let (proof_vk, proof) = serde_json::from_str::<Groth16Proof>(p)?.decode()?;
+                t.check_vk(&proof_vk)?;
+                let vk = t.pk()?.vk;
+                let public_signals = t.circuit.get_public_signals();
+                let calldata = solidity::proof_calldata(&proof, &public_signals)?;
+                Ok(Groth16SolidityExport {
+                    contract: solidity::verifier_contract(&vk)?,
+                    calldata: format!(
+                        "0x{}",
+                        calldata
+                            .iter()
+                            .map(|b| format!("{:02x}", b))
+                            .collect::<String>()
+                    ),
+                })
+            }
+            (SNARKVerifyTask::Groth16Bn256(_), _) => Err(Error::SNARKCurveNotMatch()),
+            _ => Err(Error::SNARKHandleMessage(
+                "solidity verifier is only supported for groth16 proofs".to_string(),
+            )),
+        }
+    }
+}
+
+fn encode_scalars<F: Serialize>(z: Vec<F>) -> Result<String> {
+    Ok(serde_json::to_string(&z)?)
+}
+
+fn encode_io<F: Serialize>((input, output): (Vec<F>, Vec<F>)) -> (Result<String>, Result<String>) {
+    (encode_scalars(input), encode_scalars(output))
 }
 
 impl From<SNARKGenerator<provider::PallasEngine, provider::VestaEngine>> for SNARKProofTask {
@@ -926,25 +1528,111 @@ impl MessageHandler<SNARKTaskMessage> for SNARKBehaviour {
         let verifier = ctx.relay.origin_sender();
         match &msg.task {
             SNARKTask::SNARKProof(t) => {
-                let proof = Self::handle_snark_proof_task(t)?;
-                let resp: BackendMessage = SNARKTaskMessage {
-                    task_id: msg.task_id,
-                    task: SNARKTask::SNARKVerify(proof),
+                if let Err(e) = self.enqueue_proof_job(verifier, msg.task_id, t.clone()) {
+                    tracing::warn!("rejected task {} from {}: {}", msg.task_id, verifier, e);
+                    let rejected = SNARKTask::SNARKRejected(e.to_string());
+                    Self::send_task_message(&provider, msg.task_id, rejected, verifier).await?;
+                    return Ok(());
+                }
+                self.run_proof_jobs(provider.clone());
+                Ok(())
+            }
+            SNARKTask::SNARKProving => {
+                if !self.is_task_prover(msg.task_id, verifier) {
+                    tracing::warn!(
+                        "ignore proving notice of task {} from {}, which is not its prover",
+                        msg.task_id,
+                        verifier
+                    );
+                    return Ok(());
                 }
-                .into();
-                let params = resp.into_send_backend_message_request(verifier)?;
-                provider
-                    .request_internal(
-                        Method::SendBackendMessage.to_string(),
-                        serde_json::to_value(params)?,
-                    )
+                let pending = self
+                    .records
+                    .get(&msg.task_id)
+                    .map(|r| r.status == SNARKTaskStatus::Sent)
+                    .unwrap_or(false);
+                if pending {
+                    self.update_status(msg.task_id, SNARKTaskStatus::Proving, None)
+                        .await?;
+                }
+                Ok(())
+            }
+            SNARKTask::SNARKProgress(progress) => {
+                self.on_progress(msg.task_id, progress, verifier).await?;
+                Ok(())
+            }
+            SNARKTask::SNARKCheckpoint(checkpoint) => {
+                self.on_checkpoint(msg.task_id, checkpoint, verifier)
                     .await?;
                 Ok(())
             }
             SNARKTask::SNARKVerify(t) => {
-                if let Some(task) = self.task.get(&msg.task_id) {
-                    let verified = Self::handle_snark_verify_task(t, task.value())?;
-                    self.verified.insert(msg.task_id, verified);
+                if !self.is_task_prover(msg.task_id, verifier) {
+                    tracing::warn!(
+                        "ignore proof of task {} from {}, which is not its prover",
+                        msg.task_id,
+                        verifier
+                    );
+                    return Ok(());
+                }
+                if self.sub_tasks.contains_key(&msg.task_id) {
+                    self.on_sub_task_proof(msg.task_id, t).await?;
+                } else {
+                    self.on_task_proof(&provider, msg.task_id, t).await?;
+                }
+                Ok(())
+            }
+            SNARKTask::SNARKCancel => {
+                self.cancel_proof_job(verifier, msg.task_id);
+                Ok(())
+            }
+            SNARKTask::SNARKParamsRequest(params_ref) => {
+                self.on_public_params_request(&provider, msg.task_id, params_ref, verifier)
+                    .await?;
+                Ok(())
+            }
+            SNARKTask::SNARKParams(key, params) => {
+                self.on_public_params(key, params).await?;
+                Ok(())
+            }
+            SNARKTask::SNARKQuoteRequest(description) => {
+                self.on_quote_request(&provider, msg.task_id, description, verifier)
+                    .await?;
+                Ok(())
+            }
+            SNARKTask::SNARKQuote(quote) => {
+                self.on_quote(quote, verifier)?;
+                Ok(())
+            }
+            SNARKTask::SNARKReceipt(receipt) => {
+                self.on_receipt(&provider, receipt, verifier).await?;
+                Ok(())
+            }
+            SNARKTask::SNARKRejected(reason) => {
+                if self.is_quote_request(&msg.task_id) {
+                    tracing::info!(
+                        "quote of {} refused by {}: {}",
+                        msg.task_id,
+                        verifier,
+                        reason
+                    );
+                } else if !self.is_task_prover(msg.task_id, verifier) {
+                    tracing::warn!(
+                        "ignore rejection of task {} from {}, which is not its prover",
+                        msg.task_id,
+                        verifier
+                    );
+                } else if self.sub_tasks.contains_key(&msg.task_id) {
+                    tracing::warn!("sub task {} rejected: {}", msg.task_id, reason);
+                    self.reset_sub_task(msg.task_id);
+                } else if self
+                    .records
+                    .get(&msg.task_id)
+                    .map(|r| r.status.is_pending())
+                    .unwrap_or(false)
+                {
+                    self.update_status(msg.task_id, SNARKTaskStatus::Failed, Some(reason.clone()))
+                        .await?;
                 }
                 Ok(())
             }
@@ -0,0 +1,383 @@
+//! Public params cache
+//! ===========
+//! Public params of nova only depend on the shape of step circuit, but generating them dominates
+//! the latency of proving, and they are much larger than the task itself. So a [SNARKProofTask]
+//! only carries a [PublicParamsRef], the key and digest of its public params, and the params are
+//! cached by key, in memory and in a [PublicParamsStorage] if set, which is sled on native and
+//! IndexedDB in browser.
+//!
+//! The key of public params is the curve cycle and the digest of circuit shape, see
+//! [params_key]. Public params with KZG commitment keys are not deterministic, so params of the
+//! same key may differ, they are told apart by their digest, and params are accepted only if
+//! their digest matches the digest in [PublicParamsRef], received or regenerated.
+//!
+//! Nodes holding public params register service `snark-params:<key>:<digest>` on DHT. A prover
+//! missing the public params of a task asks the requester and other holders with
+//! [SNARKTask::SNARKParamsRequest] before queueing the task, and regenerates them if no one
+//! answers in time. Params with KZG commitment keys are never regenerated, as regenerated ones
+//! don't match, the task is rejected instead.
+
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use dashmap::DashMap;
+use rings_core::dht::Did;
+use rings_core::storage::KvStorageInterface;
+use rings_rpc::method::Method;
+use rings_rpc::protos::rings_node::LookupServiceRequest;
+use rings_rpc::protos::rings_node::LookupServiceResponse;
+use rings_rpc::protos::rings_node::RegisterServiceRequest;
+use rings_snark::circuit;
+use rings_snark::prelude::ff::PrimeField;
+use rings_snark::prelude::nova::provider::Bn256EngineKZG;
+use rings_snark::prelude::nova::provider::GrumpkinEngine;
+use rings_snark::prelude::nova::provider::PallasEngine;
+use rings_snark::prelude::nova::provider::VestaEngine;
+use rings_snark::snark::PublicParams;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::SNARKBehaviour;
+use super::TaskId;
+use crate::backend::types::snark::SNARKProofTask;
+use crate::backend::types::snark::SNARKTask;
+use crate::error::Error;
+use crate::error::Result;
+use crate::provider::Provider;
+
+/// Prefix of service registered by holders of public params
+pub const SNARK_PARAMS_SERVICE: &str = "snark-params";
+
+/// Seconds to wait for public params from a holder
+pub const PARAMS_FETCH_TIMEOUT: u64 = 30;
+
+/// Max count of holders asked for public params of a task
+pub const PARAMS_FETCH_HOLDERS: usize = 3;
+
+/// `PublicParamsStorage` is the type accepted by [SNARKBehaviour::set_params_storage].
+#[cfg(feature = "browser")]
+pub type PublicParamsStorage = Box<dyn KvStorageInterface<SNARKPublicParams>>;
+
+/// `PublicParamsStorage` is the type accepted by [SNARKBehaviour::set_params_storage].
+#[cfg(not(feature = "browser"))]
+pub type PublicParamsStorage = Box<dyn KvStorageInterface<SNARKPublicParams> + Send + Sync>;
+
+/// Key of public params, with curve cycle, such as `VastaPallas`, and digest of circuit shape
+pub fn params_key<F: PrimeField>(curve: &str, circuit: &circuit::Circuit<F>) -> Result<String> {
+    Ok(format!("{}:{}", curve, circuit.shape_digest()?))
+}
+
+/// Reference of public params, carried by proof task instead of the params
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PublicParamsRef {
+    /// key of public params in cache, see [params_key]
+    pub key: String,
+    /// digest of public params, see [PublicParams::digest]
+    pub digest: String,
+}
+
+/// Public params of supported curve cycles
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum SNARKPublicParams {
+    /// public params with curve pallas and vesta
+    PallasVasta(Arc<PublicParams<PallasEngine, VestaEngine>>),
+    /// public params with curve vesta and pallas
+    VastaPallas(Arc<PublicParams<VestaEngine, PallasEngine>>),
+    /// public params with curve bn256 whth KZG multi linear commitment and grumpkin
+    Bn256KZGGrumpkin(Arc<PublicParams<Bn256EngineKZG, GrumpkinEngine>>),
+}
+
+impl SNARKPublicParams {
+    /// Digest of public params, see [PublicParams::digest]
+    pub fn digest(&self) -> String {
+        match self {
+            SNARKPublicParams::PallasVasta(pp) => pp.digest(),
+            SNARKPublicParams::VastaPallas(pp) => pp.digest(),
+            SNARKPublicParams::Bn256KZGGrumpkin(pp) => pp.digest(),
+        }
+    }
+}
+
+impl PublicParamsRef {
+    /// Name of service registered by holders of the public params
+    fn service_name(&self) -> String {
+        format!("{}:{}:{}", SNARK_PARAMS_SERVICE, self.key, self.digest)
+    }
+}
+
+/// Cached public params of task manager
+#[derive(Default)]
+pub struct PublicParamsCache {
+    memory: DashMap<String, SNARKPublicParams>,
+    storage: RwLock<Option<Arc<PublicParamsStorage>>>,
+    /// map of key and digest of public params requested from holders, others are dropped
+    requested: DashMap<String, String>,
+}
+
+impl SNARKProofTask {
+    /// Reference of public params of task, groth16 task has no public params
+    pub fn params_ref(&self) -> Option<&PublicParamsRef> {
+        match self {
+            SNARKProofTask::PallasVasta(g) => Some(g.params_ref()),
+            SNARKProofTask::VastaPallas(g) => Some(g.params_ref()),
+            SNARKProofTask::Bn256KZGGrumpkin(g) => Some(g.params_ref()),
+            SNARKProofTask::Groth16Bn256(_) => None,
+        }
+    }
+
+    /// If public params of task can be regenerated from its circuits, params with KZG
+    /// commitment keys are not deterministic, so they can only be fetched from holders
+    pub fn params_regenerable(&self) -> bool {
+        !matches!(self, SNARKProofTask::Bn256KZGGrumpkin(_))
+    }
+
+    /// Public params of task, None if they're not resolved yet
+    pub fn public_params(&self) -> Option<SNARKPublicParams> {
+        match self {
+            SNARKProofTask::PallasVasta(g) => g.public_params().map(SNARKPublicParams::PallasVasta),
+            SNARKProofTask::VastaPallas(g) => g.public_params().map(SNARKPublicParams::VastaPallas),
+            SNARKProofTask::Bn256KZGGrumpkin(g) => {
+                g.public_params().map(SNARKPublicParams::Bn256KZGGrumpkin)
+            }
+            SNARKProofTask::Groth16Bn256(_) => None,
+        }
+    }
+
+    /// Set public params of task, return error if curve or digest doesn't match
+    pub fn set_public_params(&mut self, params: SNARKPublicParams) -> Result<()> {
+        match (self, params) {
+            (SNARKProofTask::PallasVasta(g), SNARKPublicParams::PallasVasta(pp)) => {
+                g.set_public_params(pp)
+            }
+            (SNARKProofTask::VastaPallas(g), SNARKPublicParams::VastaPallas(pp)) => {
+                g.set_public_params(pp)
+            }
+            (SNARKProofTask::Bn256KZGGrumpkin(g), SNARKPublicParams::Bn256KZGGrumpkin(pp)) => {
+                g.set_public_params(pp)
+            }
+            _ => Err(Error::SNARKCurveNotMatch()),
+        }
+    }
+}
+
+impl SNARKBehaviour {
+    /// Set storage of public params, params in storage are loaded on demand
+    pub fn set_params_storage(&self, storage: PublicParamsStorage) -> Result<()> {
+        *self
+            .params
+            .storage
+            .write()
+            .map_err(|_| Error::SNARKHandleMessage("storage lock poisoned".to_string()))? =
+            Some(Arc::new(storage));
+        Ok(())
+    }
+
+    /// Get cached public params by key, from memory or storage
+    pub async fn get_public_params(&self, key: &str) -> Result<Option<SNARKPublicParams>> {
+        if let Some(params) = self.params.memory.get(key) {
+            return Ok(Some(params.value().clone()));
+        }
+        let storage = self.params.storage.read().ok().and_then(|s| s.clone());
+        let Some(storage) = storage else {
+            return Ok(None);
+        };
+        let params = storage
+            .get(key)
+            .await
+            .map_err(Error::SNARKTaskStorageError)?;
+        if let Some(params) = &params {
+            self.params.memory.insert(key.to_string(), params.clone());
+        }
+        Ok(params)
+    }
+
+    /// Get cached public params matching the reference
+    pub async fn get_matched_params(
+        &self,
+        params_ref: &PublicParamsRef,
+    ) -> Result<Option<SNARKPublicParams>> {
+        Ok(self
+            .get_public_params(&params_ref.key)
+            .await?
+            .filter(|params| params.digest() == params_ref.digest))
+    }
+
+    /// Put public params to memory and storage
+    pub(crate) async fn put_public_params(
+        &self,
+        key: &str,
+        params: SNARKPublicParams,
+    ) -> Result<()> {
+        let storage = self.params.storage.read().ok().and_then(|s| s.clone());
+        if let Some(storage) = storage {
+            storage
+                .put(key, &params)
+                .await
+                .map_err(Error::SNARKTaskStorageError)?;
+        }
+        self.params.memory.insert(key.to_string(), params);
+        Ok(())
+    }
+
+    /// Cache public params of task and announce this node as a holder,
+    /// do nothing if they're already cached or not resolved
+    pub(crate) async fn cache_task_params(
+        &self,
+        provider: &Provider,
+        task: &SNARKProofTask,
+    ) -> Result<()> {
+        let (Some(params_ref), Some(params)) = (task.params_ref(), task.public_params()) else {
+            return Ok(());
+        };
+        if self.get_matched_params(params_ref).await?.is_some() {
+            return Ok(());
+        }
+        self.put_public_params(&params_ref.key, params).await?;
+        if let Err(e) = Self::announce_public_params(provider, params_ref).await {
+            tracing::warn!("failed to announce public params {}: {}", params_ref.key, e);
+        }
+        Ok(())
+    }
+
+    /// Register this node as a holder of public params on DHT
+    pub async fn announce_public_params(
+        provider: &Provider,
+        params_ref: &PublicParamsRef,
+    ) -> Result<()> {
+        let params = serde_json::to_value(RegisterServiceRequest {
+            name: params_ref.service_name(),
+        })?;
+        provider
+            .request_internal(Method::RegisterService.to_string(), params)
+            .await?;
+        Ok(())
+    }
+
+    /// Lookup holders of public params on DHT
+    pub async fn lookup_public_params_holders(
+        provider: &Provider,
+        params_ref: &PublicParamsRef,
+    ) -> Result<Vec<Did>> {
+        let params = serde_json::to_value(LookupServiceRequest {
+            name: params_ref.service_name(),
+        })?;
+        let resp = provider
+            .request_internal(Method::LookupService.to_string(), params)
+            .await?;
+        let resp: LookupServiceResponse = serde_json::from_value(resp)?;
+        Ok(resp
+            .dids
+            .iter()
+            .filter_map(|did| did.parse().ok())
+            .collect())
+    }
+
+    /// Ask holders for public params one by one, until one of them answers
+    pub(crate) async fn fetch_public_params(
+        &self,
+        provider: &Provider,
+        params_ref: &PublicParamsRef,
+        holders: Vec<Did>,
+    ) -> Result<Option<SNARKPublicParams>> {
+        let key = params_ref.key.as_str();
+        self.params
+            .requested
+            .insert(key.to_string(), params_ref.digest.clone());
+        for holder in holders {
+            let request = SNARKTask::SNARKParamsRequest(params_ref.clone());
+            let task_id = uuid::Uuid::new_v4();
+            if let Err(e) = Self::send_task_message(provider, task_id, request, holder).await {
+                tracing::warn!("failed to request public params from {}: {}", holder, e);
+                continue;
+            }
+            for _ in 0..PARAMS_FETCH_TIMEOUT {
+                #[cfg(not(target_arch = "wasm32"))]
+                futures_timer::Delay::new(std::time::Duration::from_secs(1)).await;
+                #[cfg(target_arch = "wasm32")]
+                rings_core::utils::js_utils::window_sleep(1000)
+                    .await
+                    .map_err(|e| Error::JsError(format!("Failed to sleep: {:?}", e)))?;
+                let received = self
+                    .params
+                    .memory
+                    .get(key)
+                    .map(|p| p.value().clone())
+                    .filter(|p| p.digest() == params_ref.digest);
+                if received.is_some() {
+                    self.params.requested.remove(key);
+                    return Ok(received);
+                }
+            }
+            tracing::debug!("no public params {} received from {}", key, holder);
+        }
+        self.params.requested.remove(key);
+        Ok(None)
+    }
+
+    /// Resolve public params of task received from requester, from cache, or from the
+    /// requester and other holders. The task is left unresolved if no one has them.
+    pub(crate) async fn resolve_task_params(
+        &self,
+        provider: &Provider,
+        requester: Did,
+        task: &mut SNARKProofTask,
+    ) -> Result<()> {
+        let Some(params_ref) = task.params_ref().cloned() else {
+            return Ok(());
+        };
+        if task.public_params().is_some() {
+            return Ok(());
+        }
+        if let Some(params) = self.get_matched_params(&params_ref).await? {
+            return task.set_public_params(params);
+        }
+
+        let mut holders = vec![requester];
+        match Self::lookup_public_params_holders(provider, &params_ref).await {
+            Ok(dids) => holders.extend(dids.into_iter().filter(|did| *did != requester)),
+            Err(e) => tracing::warn!("failed to lookup holders of {}: {}", params_ref.key, e),
+        }
+        holders.truncate(PARAMS_FETCH_HOLDERS);
+        if let Some(params) = self
+            .fetch_public_params(provider, &params_ref, holders)
+            .await?
+        {
+            task.set_public_params(params)?;
+        }
+        Ok(())
+    }
+
+    /// Answer request of public params, the request is ignored if they're not cached
+    pub(crate) async fn on_public_params_request(
+        &self,
+        provider: &Provider,
+        task_id: TaskId,
+        params_ref: &PublicParamsRef,
+        requester: Did,
+    ) -> Result<()> {
+        let Some(params) = self.get_matched_params(params_ref).await? else {
+            tracing::debug!(
+                "public params {} requested by {} not found",
+                params_ref.key,
+                requester
+            );
+            return Ok(());
+        };
+        let msg = SNARKTask::SNARKParams(params_ref.key.clone(), params);
+        Self::send_task_message(provider, task_id, msg, requester).await
+    }
+
+    /// Cache public params received from holder, only requested params are accepted
+    pub(crate) async fn on_public_params(
+        &self,
+        key: &str,
+        params: &SNARKPublicParams,
+    ) -> Result<()> {
+        let requested = self.params.requested.get(key).map(|d| d.value().clone());
+        if requested != Some(params.digest()) {
+            tracing::debug!("dropped public params {} which is not requested", key);
+            return Ok(());
+        }
+        self.put_public_params(key, params.clone()).await
+    }
+}
@@ -0,0 +1,181 @@
+//! Progress and checkpoints of proving
+//! ===========
+//! Recursive tasks are folded step by step, the prover reports progress to the requester while
+//! folding:
+//!
+//! prover -> [SNARKTask::SNARKProgress] -> requester, every [ProverOptions::progress_interval] steps
+//! prover -> [SNARKTask::SNARKCheckpoint] -> requester, every [ProverOptions::checkpoint_interval] steps
+//!
+//! A checkpoint is the proof task with its recursive snark folded so far. If the prover
+//! disconnects halfway, the requester can send the latest checkpoint to another prover with
+//! [SNARKBehaviour::resume_task], which only folds the remaining steps. The final proof is
+//! verified against the original task and its pinned verifier key, so a broken checkpoint only
+//! makes the resumed task fail.
+//!
+//! Checkpoints carry every step circuit of the task, they're disabled by default.
+
+use std::sync::Arc;
+
+use rings_core::dht::Did;
+use rings_core::utils::get_epoch_ms;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::prover::ProverOptions;
+use super::record::SNARKTaskStatus;
+use super::SNARKBehaviour;
+use super::TaskId;
+use crate::backend::types::snark::SNARKProofTask;
+use crate::backend::types::snark::SNARKTask;
+use crate::error::Error;
+use crate::error::Result;
+use crate::provider::Provider;
+
+/// Progress of proving, reported by prover
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SNARKProgress {
+    /// count of folded steps
+    pub folded: usize,
+    /// count of all steps
+    pub steps: usize,
+    /// estimated seconds to fold the remaining steps
+    pub eta: u64,
+}
+
+/// Fold all steps of task, progress and checkpoint messages are passed to `report`
+/// by intervals of [ProverOptions]
+pub(crate) fn fold_with_progress(
+    task: &mut SNARKProofTask,
+    options: &ProverOptions,
+    mut report: impl FnMut(SNARKTask),
+) -> Result<()> {
+    let (steps, _) = task.size();
+    let started_at = get_epoch_ms();
+    let start = task.folded();
+    let mut folded = start;
+    while folded < steps {
+        folded = task.fold_next(1)?;
+        if options.progress_interval > 0
+            && (folded % options.progress_interval == 0 || folded == steps)
+        {
+            let elapsed = get_epoch_ms().saturating_sub(started_at);
+            let done = (folded - start).max(1) as u128;
+            let eta = elapsed * (steps - folded) as u128 / done / 1000;
+            report(SNARKTask::SNARKProgress(SNARKProgress {
+                folded,
+                steps,
+                eta: eta as u64,
+            }));
+        }
+        if options.checkpoint_interval > 0
+            && folded % options.checkpoint_interval == 0
+            && folded < steps
+        {
+            report(SNARKTask::SNARKCheckpoint(task.clone()));
+        }
+    }
+    Ok(())
+}
+
+impl SNARKBehaviour {
+    /// Record progress of task, progress from others than prover of task is ignored
+    pub(crate) async fn on_progress(
+        &self,
+        task_id: TaskId,
+        progress: &SNARKProgress,
+        prover: Did,
+    ) -> Result<()> {
+        let Some(mut record) = self.records.get(&task_id).map(|r| r.value().clone()) else {
+            return Ok(());
+        };
+        if record.prover != Some(prover) || !record.status.is_pending() {
+            return Ok(());
+        }
+        tracing::debug!(
+            "task {} folded {}/{} steps",
+            task_id,
+            progress.folded,
+            progress.steps
+        );
+        record.progress = Some(*progress);
+        if record.status == SNARKTaskStatus::Sent {
+            record.set_status(SNARKTaskStatus::Proving, None);
+        }
+        self.save_record(task_id, record).await
+    }
+
+    /// Keep the latest checkpoint of task, checkpoints from others than prover of task or
+    /// not matching the task are ignored
+    pub(crate) async fn on_checkpoint(
+        &self,
+        task_id: TaskId,
+        checkpoint: &SNARKProofTask,
+        prover: Did,
+    ) -> Result<()> {
+        let Some(mut record) = self.records.get(&task_id).map(|r| r.value().clone()) else {
+            return Ok(());
+        };
+        if record.prover != Some(prover) || !record.status.is_pending() {
+            return Ok(());
+        }
+        let Some(task) = &record.task else {
+            return Ok(());
+        };
+        if std::mem::discriminant(task) != std::mem::discriminant(checkpoint)
+            || task.size() != checkpoint.size()
+            || task.params_ref() != checkpoint.params_ref()
+            || task.vk_hash() != checkpoint.vk_hash()
+        {
+            tracing::warn!("checkpoint of {} doesn't match the task", task_id);
+            return Ok(());
+        }
+        let latest = record.checkpoint.as_ref().map(|c| c.folded()).unwrap_or(0);
+        if checkpoint.folded() <= latest {
+            return Ok(());
+        }
+        record.checkpoint = Some(checkpoint.clone());
+        self.save_record(task_id, record).await
+    }
+
+    /// Send a pending or timed out task to another prover, the task is resumed from its latest
+    /// checkpoint if there is one. The former prover is asked to cancel the task.
+    pub async fn resume_task(
+        &self,
+        provider: Arc<Provider>,
+        task_id: String,
+        did: Did,
+    ) -> Result<()> {
+        let task_id = uuid::Uuid::parse_str(&task_id)?;
+        let mut record = self.get_task_record(task_id.to_string())?;
+        if !(record.status.is_pending() || record.status == SNARKTaskStatus::TimedOut) {
+            return Err(Error::SNARKHandleMessage(format!(
+                "task {} is {}",
+                task_id,
+                record.status.as_str()
+            )));
+        }
+        let task = record
+            .checkpoint
+            .clone()
+            .or_else(|| record.task.clone())
+            .ok_or_else(|| Error::SNARKTaskNotFound(task_id.to_string()))?;
+        if let Some(former) = record.prover.filter(|p| *p != did) {
+            if let Err(e) =
+                Self::send_task_message(&provider, task_id, SNARKTask::SNARKCancel, former).await
+            {
+                tracing::warn!("failed to cancel task {} on {}: {}", task_id, former, e);
+            }
+        }
+        tracing::info!(
+            "resume task {} on {} from step {}",
+            task_id,
+            did,
+            task.folded()
+        );
+        record.prover = Some(did);
+        record.quote = None;
+        record.set_status(SNARKTaskStatus::Queued, None);
+        self.dispatch_proof_task(&provider, task_id, task, record)
+            .await
+    }
+}
@@ -0,0 +1,440 @@
+//! Prover job queue
+//! ===========
+//! Proof tasks received from requesters are not proved inside the message handler, they are
+//! queued and proved by at most [ProverOptions::max_concurrency] workers, so a single requester
+//! cannot occupy every core of a prover node, or block the connection it sends tasks through.
+//!
+//! A task is rejected with [SNARKTask::SNARKRejected] if it's larger than the limits of
+//! [ProverOptions], the queue is full, the requester already has too many queued or running
+//! tasks, or the task doesn't match the quote given to requester, see [super::market].
+//! Requesters can cancel their tasks with [SNARKTask::SNARKCancel], see
+//! [SNARKBehaviour::cancel_task].
+//!
+//! Proving can't be interrupted, so a cancelled or timed out task keeps its worker until the
+//! proof is generated, then the proof is dropped.
+//!
+//! Public params of a task are resolved before the task is queued, see [super::params]. Fetching
+//! them from holders may take a while, so it runs in background instead of occupying a worker,
+//! and tasks waiting for their params are counted by the limits of queue. A task with KZG params
+//! which cannot be fetched is rejected, as they cannot be regenerated.
+//!
+//! While folding, the worker reports progress to requester, see [super::progress].
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+
+use dashmap::DashMap;
+use dashmap::DashSet;
+use futures::channel::mpsc::UnboundedSender;
+use futures::StreamExt;
+use rings_core::dht::Did;
+use rings_core::utils::get_epoch_ms;
+use rings_derive::wasm_export;
+use rings_rpc::protos::rings_node::SnarkProverInfo;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::progress::fold_with_progress;
+use super::SNARKBehaviour;
+use super::TaskId;
+use crate::backend::types::snark::SNARKProofTask;
+use crate::backend::types::snark::SNARKTask;
+use crate::backend::types::snark::SNARKVerifyTask;
+use crate::error::Error;
+use crate::error::Result;
+use crate::provider::Provider;
+
+/// Limits of prover job queue
+#[wasm_export]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProverOptions {
+    /// count of tasks proved at the same time, proving is already parallel inside a task
+    pub max_concurrency: usize,
+    /// count of tasks waiting in queue
+    pub max_queue: usize,
+    /// count of queued and running tasks of each requester
+    pub max_per_requester: usize,
+    /// max steps of a task
+    pub max_steps: usize,
+    /// max constraints of a step circuit
+    pub max_constraints: usize,
+    /// seconds from receiving a task to sending its proof, the task is dropped after that
+    pub timeout: u64,
+    /// price of a step in quotes, see [super::market]
+    pub price_per_step: u64,
+    /// estimated seconds of proving a step, for eta in quotes
+    pub secs_per_step: u64,
+    /// only accept tasks quoted by this node
+    pub require_quote: bool,
+    /// steps between progress reports to requester, 0 to disable, see [super::progress]
+    pub progress_interval: usize,
+    /// steps between checkpoints sent to requester, 0 to disable
+    pub checkpoint_interval: usize,
+}
+
+impl Default for ProverOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 1,
+            max_queue: 16,
+            max_per_requester: 2,
+            max_steps: 1024,
+            max_constraints: 1 << 20,
+            timeout: 600,
+            price_per_step: 0,
+            secs_per_step: 1,
+            require_quote: false,
+            progress_interval: 16,
+            checkpoint_interval: 0,
+        }
+    }
+}
+
+/// A proof task received from requester
+#[derive(Debug, Clone)]
+pub struct ProverJob {
+    /// task_id given by requester
+    pub task_id: TaskId,
+    /// did of requester
+    pub requester: Did,
+    /// the proof task
+    pub task: SNARKProofTask,
+    /// timestamp of receiving in milliseconds
+    pub received_at: u128,
+}
+
+impl ProverJob {
+    fn is_expired(&self, timeout: u64, now: u128) -> bool {
+        now.saturating_sub(self.received_at) > timeout as u128 * 1000
+    }
+}
+
+/// Queued and running jobs of prover
+#[derive(Default)]
+pub struct ProverQueue {
+    options: RwLock<ProverOptions>,
+    queue: Mutex<VecDeque<ProverJob>>,
+    /// jobs accepted but waiting for their public params to be resolved
+    unresolved: Mutex<Vec<ProverJob>>,
+    /// map of task_id and requester of jobs whose public params are being resolved
+    resolving: DashMap<TaskId, Did>,
+    /// map of running task_id and its requester
+    running: DashMap<TaskId, Did>,
+    /// running tasks cancelled by requester
+    cancelled: DashSet<TaskId>,
+}
+
+impl ProverQueue {
+    fn options(&self) -> ProverOptions {
+        self.options.read().map(|o| *o).unwrap_or_default()
+    }
+
+    fn lock_queue(&self) -> Result<std::sync::MutexGuard<VecDeque<ProverJob>>> {
+        self.queue
+            .lock()
+            .map_err(|_| Error::SNARKHandleMessage("prover queue lock poisoned".to_string()))
+    }
+
+    fn take_unresolved(&self) -> Vec<ProverJob> {
+        self.unresolved
+            .lock()
+            .map(|mut jobs| std::mem::take(&mut *jobs))
+            .unwrap_or_default()
+    }
+}
+
+#[wasm_export]
+impl SNARKBehaviour {
+    /// Set limits of prover job queue, queued jobs are not affected
+    pub fn set_prover_options(&self, options: ProverOptions) {
+        if let Ok(mut o) = self.prover.options.write() {
+            *o = options;
+        }
+    }
+}
+
+impl SNARKBehaviour {
+    /// Get limits of prover job queue
+    pub(crate) fn prover_options(&self) -> ProverOptions {
+        self.prover.options()
+    }
+
+    /// Check size of a task against limits of prover
+    pub(crate) fn check_task_size(&self, steps: usize, constraints: usize) -> Result<()> {
+        let options = self.prover.options();
+        if steps > options.max_steps {
+            return Err(Error::SNARKTaskRejected(format!(
+                "{} steps exceed limit {}",
+                steps, options.max_steps
+            )));
+        }
+        if constraints > options.max_constraints {
+            return Err(Error::SNARKTaskRejected(format!(
+                "{} constraints exceed limit {}",
+                constraints, options.max_constraints
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get queue depth and running jobs of prover
+    pub fn prover_info(&self) -> SnarkProverInfo {
+        let options = self.prover.options();
+        let queued =
+            self.prover.lock_queue().map(|q| q.len()).unwrap_or(0) + self.prover.resolving.len();
+        SnarkProverInfo {
+            queued: queued as u32,
+            running: self.prover.running.len() as u32,
+            max_concurrency: options.max_concurrency as u32,
+            max_queue: options.max_queue as u32,
+        }
+    }
+
+    /// Queue a proof task from requester, return error if the task is rejected.
+    /// A task without public params waits for them before queued, see [Self::run_proof_jobs].
+    pub(crate) fn enqueue_proof_job(
+        &self,
+        requester: Did,
+        task_id: TaskId,
+        task: SNARKProofTask,
+    ) -> Result<()> {
+        let options = self.prover.options();
+        let (steps, constraints) = task.size();
+        self.check_task_size(steps, constraints)?;
+
+        let mut queue = self.prover.lock_queue()?;
+        if self.prover.running.contains_key(&task_id)
+            || self.prover.resolving.contains_key(&task_id)
+            || queue.iter().any(|j| j.task_id == task_id)
+        {
+            return Ok(());
+        }
+        if queue.len() + self.prover.resolving.len() >= options.max_queue {
+            return Err(Error::SNARKTaskRejected("prover queue is full".to_string()));
+        }
+        let owned = queue.iter().filter(|j| j.requester == requester).count()
+            + self
+                .prover
+                .resolving
+                .iter()
+                .filter(|r| *r.value() == requester)
+                .count()
+            + self
+                .prover
+                .running
+                .iter()
+                .filter(|r| *r.value() == requester)
+                .count();
+        if owned >= options.max_per_requester {
+            return Err(Error::SNARKTaskRejected(format!(
+                "requester already has {} tasks",
+                owned
+            )));
+        }
+        self.check_quote(requester, task_id, &task)?;
+        let job = ProverJob {
+            task_id,
+            requester,
+            task,
+            received_at: get_epoch_ms(),
+        };
+        if job.task.params_ref().is_some() && job.task.public_params().is_none() {
+            self.prover.resolving.insert(task_id, requester);
+            self.prover
+                .unresolved
+                .lock()
+                .map_err(|_| Error::SNARKHandleMessage("prover queue lock poisoned".to_string()))?
+                .push(job);
+        } else {
+            queue.push_back(job);
+        }
+        Ok(())
+    }
+
+    /// Cancel a queued or running task, only the requester of the task can cancel it
+    pub(crate) fn cancel_proof_job(&self, requester: Did, task_id: TaskId) -> bool {
+        if let Ok(mut queue) = self.prover.lock_queue() {
+            let len = queue.len();
+            queue.retain(|j| !(j.task_id == task_id && j.requester == requester));
+            if queue.len() != len {
+                tracing::info!("cancelled queued task {}", task_id);
+                return true;
+            }
+        }
+        if self.prover.running.get(&task_id).map(|r| *r.value()) == Some(requester) {
+            tracing::info!("cancelled running task {}", task_id);
+            self.prover.cancelled.insert(task_id);
+            return true;
+        }
+        if self.prover.resolving.get(&task_id).map(|r| *r.value()) == Some(requester) {
+            tracing::info!("cancelled task {} waiting for public params", task_id);
+            self.prover.cancelled.insert(task_id);
+            return true;
+        }
+        false
+    }
+
+    /// Take next job if there is a free worker, expired jobs are dropped
+    fn next_proof_job(&self) -> Option<ProverJob> {
+        let options = self.prover.options();
+        let mut queue = self.prover.lock_queue().ok()?;
+        if self.prover.running.len() >= options.max_concurrency {
+            return None;
+        }
+        let now = get_epoch_ms();
+        while let Some(job) = queue.pop_front() {
+            if job.is_expired(options.timeout, now) {
+                tracing::warn!("dropped expired task {} in queue", job.task_id);
+                continue;
+            }
+            self.prover.running.insert(job.task_id, job.requester);
+            return Some(job);
+        }
+        None
+    }
+
+    /// Start resolving public params of new jobs, and workers for queued jobs, up to
+    /// [ProverOptions::max_concurrency]
+    pub(crate) fn run_proof_jobs(&self, provider: Arc<Provider>) {
+        for job in self.prover.take_unresolved() {
+            let resolve = self.clone().resolve_proof_job(provider.clone(), job);
+            #[cfg(feature = "node")]
+            tokio::spawn(resolve);
+            #[cfg(feature = "browser")]
+            wasm_bindgen_futures::spawn_local(resolve);
+        }
+        while let Some(job) = self.next_proof_job() {
+            let worker = self.clone().run_proof_worker(provider.clone(), job);
+            #[cfg(feature = "node")]
+            tokio::spawn(worker);
+            #[cfg(feature = "browser")]
+            wasm_bindgen_futures::spawn_local(worker);
+        }
+    }
+
+    /// Resolve public params of the job from cache or holders, then queue it. The job is
+    /// rejected if its params can't be resolved or regenerated, see
+    /// [SNARKProofTask::ensure_public_params].
+    async fn resolve_proof_job(self, provider: Arc<Provider>, mut job: ProverJob) {
+        let (task_id, requester) = (job.task_id, job.requester);
+        let ret = self
+            .resolve_task_params(&provider, requester, &mut job.task)
+            .await
+            .and_then(|_| match job.task.params_ref() {
+                Some(params_ref)
+                    if job.task.public_params().is_none() && !job.task.params_regenerable() =>
+                {
+                    Err(Error::SNARKPublicParamsNotFound(params_ref.key.clone()))
+                }
+                _ => Ok(()),
+            });
+        self.prover.resolving.remove(&task_id);
+        if self.prover.cancelled.remove(&task_id).is_some() {
+            tracing::debug!("dropped cancelled task {} after resolving params", task_id);
+            return;
+        }
+        let ret = ret.and_then(|_| self.prover.lock_queue().map(|mut q| q.push_back(job)));
+        match ret {
+            Ok(()) => self.run_proof_jobs(provider),
+            Err(e) => {
+                tracing::warn!("failed to resolve public params of task {}: {}", task_id, e);
+                let rejected = SNARKTask::SNARKRejected(e.to_string());
+                if let Err(e) =
+                    Self::send_task_message(&provider, task_id, rejected, requester).await
+                {
+                    tracing::warn!("failed to reject task {}: {}", task_id, e);
+                }
+            }
+        }
+    }
+
+    /// Prove the job, then keep taking jobs from queue until it's empty
+    async fn run_proof_worker(self, provider: Arc<Provider>, mut job: ProverJob) {
+        loop {
+            self.prove_job(&provider, &job).await;
+            self.prover.running.remove(&job.task_id);
+            self.prover.cancelled.remove(&job.task_id);
+            match self.next_proof_job() {
+                Some(next) => job = next,
+                None => break,
+            }
+        }
+    }
+
+    async fn prove_job(&self, provider: &Provider, job: &ProverJob) {
+        let (task_id, requester) = (job.task_id, job.requester);
+        if let Err(e) =
+            Self::send_task_message(provider, task_id, SNARKTask::SNARKProving, requester).await
+        {
+            tracing::warn!("failed to notify proving of task {}: {}", task_id, e);
+        }
+
+        let task = job.task.clone();
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+        let forward = async {
+            while let Some(msg) = rx.next().await {
+                if self.prover.cancelled.contains(&task_id) {
+                    continue;
+                }
+                if let Err(e) = Self::send_task_message(provider, task_id, msg, requester).await {
+                    tracing::warn!("failed to send progress of task {}: {}", task_id, e);
+                }
+            }
+        };
+        let options = self.prover.options();
+        let (ret, _) = futures::join!(Self::prove_in_worker(task, options, tx), forward);
+        if let Ok((_, task)) = &ret {
+            if let Err(e) = self.cache_task_params(provider, task).await {
+                tracing::warn!("failed to cache public params of task {}: {}", task_id, e);
+            }
+        }
+        if self.prover.cancelled.contains(&task_id) {
+            tracing::debug!("dropped proof of cancelled task {}", task_id);
+            return;
+        }
+        let timeout = self.prover.options().timeout;
+        let msg = match ret {
+            _ if job.is_expired(timeout, get_epoch_ms()) => {
+                SNARKTask::SNARKRejected("proving timed out".to_string())
+            }
+            Ok((proof, _)) => SNARKTask::SNARKVerify(proof),
+            Err(e) => SNARKTask::SNARKRejected(e.to_string()),
+        };
+        if let Err(e) = Self::send_task_message(provider, task_id, msg, requester).await {
+            tracing::warn!("failed to send proof of task {}: {}", task_id, e);
+        }
+    }
+
+    /// Prove task without blocking async runtime, public params of task are regenerated if
+    /// they're not resolved, the task is returned with its public params.
+    /// Progress and checkpoints are sent to `report` while folding, see [super::progress].
+    async fn prove_in_worker(
+        task: SNARKProofTask,
+        options: ProverOptions,
+        report: UnboundedSender<SNARKTask>,
+    ) -> Result<(SNARKVerifyTask, SNARKProofTask)> {
+        let prove = move || -> Result<(SNARKVerifyTask, SNARKProofTask)> {
+            let mut task = task;
+            task.ensure_public_params()?;
+            fold_with_progress(&mut task, &options, |msg| {
+                let _ = report.unbounded_send(msg);
+            })?;
+            drop(report);
+            let proof = Self::handle_snark_proof_task(&task)?;
+            Ok((proof, task))
+        };
+        #[cfg(feature = "node")]
+        {
+            tokio::task::spawn_blocking(prove)
+                .await
+                .map_err(|e| Error::SNARKHandleMessage(e.to_string()))?
+        }
+        #[cfg(not(feature = "node"))]
+        {
+            prove()
+        }
+    }
+}
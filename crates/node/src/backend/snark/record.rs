@@ -0,0 +1,345 @@
+//! Lifecycle of SNARK task
+//! ===========
+//! Each proof task sent by this node is tracked by a [SNARKTaskRecord]:
+//!
+//! queued -> sent -> proving -> proof_received -> verified | failed
+//!
+//! A task which doesn't receive its proof within timeout is marked as timed_out, but a proof
+//! arriving later is still accepted. Progress and checkpoints reported by prover are kept in the
+//! record, see [super::progress]. A pending task can be cancelled by
+//! [SNARKBehaviour::cancel_task], the prover is notified and drops the task.
+//!
+//! Records are kept in memory and written through to a [SNARKTaskStorage] if set, so in-flight
+//! tasks survive restarting. Messages of a task, such as proving notices, rejections and proofs,
+//! are only accepted from the prover the task is sent to.
+
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use rings_core::dht::Did;
+use rings_core::storage::KvStorageInterface;
+use rings_core::utils::get_epoch_ms;
+use rings_rpc::protos::rings_node::GetSnarkProofResponse;
+use rings_rpc::protos::rings_node::SnarkTaskInfo;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::coordinator::SubTaskState;
+use super::market::SignedQuote;
+use super::progress::SNARKProgress;
+use super::SNARKBehaviour;
+use super::TaskId;
+use crate::backend::types::snark::SNARKProofTask;
+use crate::backend::types::snark::SNARKTask;
+use crate::backend::types::snark::SNARKVerifyTask;
+use crate::error::Error;
+use crate::error::Result;
+use crate::provider::Provider;
+
+/// Seconds to wait for the proof of a task before marking it as timed out
+pub const SNARK_TASK_TIMEOUT: u64 = 60 * 60;
+
+/// `SNARKTaskStorage` is the type accepted by [SNARKBehaviour::set_storage].
+#[cfg(feature = "browser")]
+pub type SNARKTaskStorage = Box<dyn KvStorageInterface<SNARKTaskRecord>>;
+
+/// `SNARKTaskStorage` is the type accepted by [SNARKBehaviour::set_storage].
+#[cfg(not(feature = "browser"))]
+pub type SNARKTaskStorage = Box<dyn KvStorageInterface<SNARKTaskRecord> + Send + Sync>;
+
+/// Storage holder of task manager, the storage can be set after the manager is shared
+pub(crate) type SNARKTaskStorageRef = RwLock<Option<Arc<SNARKTaskStorage>>>;
+
+/// Status of SNARK task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SNARKTaskStatus {
+    /// created, not sent yet
+    Queued,
+    /// sent to prover
+    Sent,
+    /// prover started proving
+    Proving,
+    /// proof received, verifying
+    ProofReceived,
+    /// proof verified
+    Verified,
+    /// failed to send task or verify proof
+    Failed,
+    /// no proof received within timeout
+    TimedOut,
+    /// cancelled by requester
+    Cancelled,
+}
+
+impl SNARKTaskStatus {
+    /// Return status as `&str`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Queued => "queued",
+            Self::Sent => "sent",
+            Self::Proving => "proving",
+            Self::ProofReceived => "proof_received",
+            Self::Verified => "verified",
+            Self::Failed => "failed",
+            Self::TimedOut => "timed_out",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Task is waiting for its proof
+    pub fn is_pending(&self) -> bool {
+        matches!(self, Self::Queued | Self::Sent | Self::Proving)
+    }
+}
+
+/// Record of SNARK task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SNARKTaskRecord {
+    /// current status
+    pub status: SNARKTaskStatus,
+    /// did of prover
+    pub prover: Option<Did>,
+    /// timestamp of creation in milliseconds
+    pub created_at: u64,
+    /// timestamp of last status change in milliseconds
+    pub updated_at: u64,
+    /// reason of failure
+    pub error: Option<String>,
+    /// proof received from prover
+    pub proof: Option<SNARKVerifyTask>,
+    /// the proof task, it's dropped once the task is finished
+    #[serde(
+        serialize_with = "crate::util::serialize_gzip",
+        deserialize_with = "crate::util::deserialize_gzip"
+    )]
+    pub task: Option<SNARKProofTask>,
+    /// accepted quote of prover, see [super::market]
+    #[serde(default)]
+    pub quote: Option<SignedQuote>,
+    /// latest progress reported by prover, see [super::progress]
+    #[serde(default)]
+    pub progress: Option<SNARKProgress>,
+    /// latest checkpoint sent by prover, it's dropped once the task is finished
+    #[serde(
+        default,
+        serialize_with = "crate::util::serialize_gzip",
+        deserialize_with = "crate::util::deserialize_gzip"
+    )]
+    pub checkpoint: Option<SNARKProofTask>,
+}
+
+impl SNARKTaskRecord {
+    /// Create a queued record
+    pub fn new(task: Option<SNARKProofTask>, prover: Option<Did>) -> Self {
+        let now = get_epoch_ms() as u64;
+        Self {
+            status: SNARKTaskStatus::Queued,
+            prover,
+            created_at: now,
+            updated_at: now,
+            error: None,
+            proof: None,
+            task,
+            quote: None,
+            progress: None,
+            checkpoint: None,
+        }
+    }
+
+    /// Update status, the proof task is dropped if the task is finished
+    pub fn set_status(&mut self, status: SNARKTaskStatus, error: Option<String>) {
+        self.status = status;
+        self.updated_at = get_epoch_ms() as u64;
+        if error.is_some() {
+            self.error = error;
+        }
+        if matches!(
+            status,
+            SNARKTaskStatus::Verified | SNARKTaskStatus::Failed | SNARKTaskStatus::Cancelled
+        ) {
+            self.task = None;
+            self.checkpoint = None;
+        }
+    }
+
+    /// Convert to rpc message
+    pub fn to_info(&self, task_id: &TaskId) -> SnarkTaskInfo {
+        SnarkTaskInfo {
+            task_id: task_id.to_string(),
+            status: self.status.as_str().to_string(),
+            prover: self.prover.map(|did| did.to_string()),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            error: self.error.clone(),
+            folded: self.progress.map(|p| p.folded as u64).unwrap_or(0),
+            steps: self
+                .task
+                .as_ref()
+                .map(|t| t.size().0)
+                .or(self.progress.map(|p| p.steps))
+                .unwrap_or(0) as u64,
+            eta: self.progress.map(|p| p.eta),
+            resumable: self.checkpoint.is_some(),
+        }
+    }
+}
+
+impl From<&SNARKVerifyTask> for GetSnarkProofResponse {
+    fn from(proof: &SNARKVerifyTask) -> Self {
+        let (curve, proof) = match proof {
+            SNARKVerifyTask::PallasVasta(p) => ("PallasVasta", p),
+            SNARKVerifyTask::VastaPallas(p) => ("VastaPallas", p),
+            SNARKVerifyTask::Bn256KZGGrumpkin(p) => ("Bn256KZGGrumpkin", p),
+            SNARKVerifyTask::Groth16Bn256(p) => ("Groth16Bn256", p),
+        };
+        Self {
+            curve: curve.to_string(),
+            proof: proof.clone(),
+        }
+    }
+}
+
+impl SNARKBehaviour {
+    /// Set storage of task records, records already in storage are loaded
+    pub async fn set_storage(&self, storage: SNARKTaskStorage) -> Result<()> {
+        let records = storage
+            .get_all()
+            .await
+            .map_err(Error::SNARKTaskStorageError)?;
+        for (key, record) in records {
+            match uuid::Uuid::parse_str(&key) {
+                Ok(task_id) => {
+                    self.records.insert(task_id, record);
+                }
+                Err(e) => tracing::warn!("invalid snark task id {} in storage: {:?}", key, e),
+            }
+        }
+        *self
+            .storage
+            .write()
+            .map_err(|_| Error::SNARKHandleMessage("storage lock poisoned".to_string()))? =
+            Some(Arc::new(storage));
+        Ok(())
+    }
+
+    /// Save record to memory and storage
+    pub(crate) async fn save_record(&self, task_id: TaskId, record: SNARKTaskRecord) -> Result<()> {
+        let storage = self.storage.read().ok().and_then(|s| s.clone());
+        if let Some(storage) = storage {
+            storage
+                .put(&task_id.to_string(), &record)
+                .await
+                .map_err(Error::SNARKTaskStorageError)?;
+        }
+        self.records.insert(task_id, record);
+        Ok(())
+    }
+
+    /// Update status of record, do nothing if the task is not found
+    pub(crate) async fn update_status(
+        &self,
+        task_id: TaskId,
+        status: SNARKTaskStatus,
+        error: Option<String>,
+    ) -> Result<()> {
+        let Some(mut record) = self.records.get(&task_id).map(|r| r.value().clone()) else {
+            return Ok(());
+        };
+        record.set_status(status, error);
+        self.save_record(task_id, record).await
+    }
+
+    /// Check if `did` is the prover a task, or a sub task of distributed task, is currently sent to
+    pub(crate) fn is_task_prover(&self, task_id: TaskId, did: Did) -> bool {
+        if let Some(parent) = self.sub_tasks.get(&task_id).map(|v| *v.value()) {
+            return self.distributed.get(&parent).is_some_and(|task| {
+                task.parts.iter().any(|p| {
+                    p.task_id == task_id
+                        && matches!(p.state, SubTaskState::Proving { prover, .. } if prover == did)
+                })
+            });
+        }
+        self.records
+            .get(&task_id)
+            .is_some_and(|r| r.prover == Some(did))
+    }
+
+    /// Mark pending tasks which are not updated within `timeout` seconds as timed out.
+    /// Distributed tasks are skipped, their sub tasks have their own timeouts.
+    pub async fn expire_tasks(&self, timeout: u64) -> Result<()> {
+        let now = get_epoch_ms() as u64;
+        let expired = self
+            .records
+            .iter()
+            .filter(|r| r.status.is_pending())
+            .filter(|r| now.saturating_sub(r.updated_at) > timeout * 1000)
+            .filter(|r| !self.distributed.contains_key(r.key()))
+            .map(|r| *r.key())
+            .collect::<Vec<_>>();
+        for task_id in expired {
+            self.update_status(task_id, SNARKTaskStatus::TimedOut, None)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Cancel a pending task, provers of the task and its sub tasks are notified
+    pub async fn cancel_task(&self, provider: Arc<Provider>, task_id: String) -> Result<()> {
+        let task_id = uuid::Uuid::parse_str(&task_id)?;
+        let record = self.get_task_record(task_id.to_string())?;
+        if !record.status.is_pending() {
+            return Ok(());
+        }
+        let mut provers = record
+            .prover
+            .map(|did| (task_id, did))
+            .into_iter()
+            .collect::<Vec<_>>();
+        if let Some(mut task) = self.distributed.get_mut(&task_id) {
+            for part in task.parts.iter_mut() {
+                if let SubTaskState::Proving { prover, .. } = part.state {
+                    provers.push((part.task_id, prover));
+                }
+                if !matches!(part.state, SubTaskState::Done(_)) {
+                    part.state = SubTaskState::Failed;
+                }
+            }
+        }
+        self.update_status(task_id, SNARKTaskStatus::Cancelled, None)
+            .await?;
+        for (id, prover) in provers {
+            if let Err(e) =
+                Self::send_task_message(&provider, id, SNARKTask::SNARKCancel, prover).await
+            {
+                tracing::warn!("failed to cancel task {} on {}: {}", id, prover, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get record of task
+    pub fn get_task_record(&self, task_id: String) -> Result<SNARKTaskRecord> {
+        let task_id = uuid::Uuid::parse_str(&task_id)?;
+        self.records
+            .get(&task_id)
+            .map(|r| r.value().clone())
+            .ok_or_else(|| Error::SNARKTaskNotFound(task_id.to_string()))
+    }
+
+    /// List tasks, ordered by creation time
+    pub fn list_tasks(&self) -> Vec<SnarkTaskInfo> {
+        let mut tasks = self
+            .records
+            .iter()
+            .map(|r| r.value().to_info(r.key()))
+            .collect::<Vec<_>>();
+        tasks.sort_by_key(|t| t.created_at);
+        tasks
+    }
+
+    /// Get proof of task, the proof is available once it's received
+    pub fn get_task_proof(&self, task_id: String) -> Result<Option<SNARKVerifyTask>> {
+        Ok(self.get_task_record(task_id)?.proof)
+    }
+}
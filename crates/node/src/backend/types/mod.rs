@@ -13,6 +13,7 @@ use serde::Serialize;
 use crate::error::Error;
 use crate::provider::Provider;
 
+pub mod reencrypt;
 #[cfg(feature = "snark")]
 pub mod snark;
 
@@ -29,6 +30,8 @@ pub enum BackendMessage {
     ServiceMessage(ServiceMessage),
     /// Plain text
     PlainText(String),
+    /// Proxy re-encryption of sealed data
+    ReencryptMessage(Box<reencrypt::ReencryptMessage>),
     /// SNARK with curve pallas and vesta
     #[cfg(feature = "snark")]
     SNARKTaskMessage(snark::SNARKTaskMessage),
@@ -62,6 +65,27 @@ pub enum ServiceMessage {
     HttpRequest(HttpRequest),
     /// Http Response
     HttpResponse(HttpResponse),
+    /// Send Udp Datagram, the remote side will open an udp tunnel to service on the first datagram of tid
+    UdpDatagram {
+        /// Tunnel Id
+        tid: TunnelId,
+        /// service name
+        service: String,
+        /// Udp Datagram
+        body: Bytes,
+    },
+    /// Udp Tunnel Close
+    UdpClose {
+        /// Tunnel Id
+        tid: TunnelId,
+        /// The reason of close
+        reason: TunnelDefeat,
+    },
+    /// Tunnel Connected, replied by service side when [ServiceMessage::TcpDial] succeeded
+    TcpConnected {
+        /// Tunnel Id
+        tid: TunnelId,
+    },
 }
 
 /// A list specifying general categories of Tunnel error like [std::io::ErrorKind].
@@ -83,6 +107,8 @@ pub enum TunnelDefeat {
     NotConnected = 6,
     /// The connection is closed by peer.
     ConnectionClosed = 7,
+    /// The tunnel is closed since no data transferred for a while.
+    IdleTimeout = 8,
     /// Unknown [std::io::ErrorKind] error.
     Unknown = u8::MAX,
 }
@@ -238,4 +264,30 @@ impl BackendMessage {
             data: serde_json::to_string(&self)?,
         })
     }
+
+    /// Name of the service that the message accesses, only messages opening a tunnel or
+    /// requesting a service carry it.
+    pub fn service(&self) -> Option<&str> {
+        match self {
+            BackendMessage::ServiceMessage(ServiceMessage::TcpDial { service, .. })
+            | BackendMessage::ServiceMessage(ServiceMessage::UdpDatagram { service, .. }) => {
+                Some(service)
+            }
+            BackendMessage::ServiceMessage(ServiceMessage::HttpRequest(req)) => Some(&req.service),
+            _ => None,
+        }
+    }
+
+    /// Tunnel that the message belongs to.
+    pub fn tunnel_id(&self) -> Option<TunnelId> {
+        match self {
+            BackendMessage::ServiceMessage(ServiceMessage::TcpDial { tid, .. })
+            | BackendMessage::ServiceMessage(ServiceMessage::TcpClose { tid, .. })
+            | BackendMessage::ServiceMessage(ServiceMessage::TcpPackage { tid, .. })
+            | BackendMessage::ServiceMessage(ServiceMessage::TcpConnected { tid })
+            | BackendMessage::ServiceMessage(ServiceMessage::UdpDatagram { tid, .. })
+            | BackendMessage::ServiceMessage(ServiceMessage::UdpClose { tid, .. }) => Some(*tid),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,163 @@
+//! Backend message types for proxy re-encryption
+//! ==============================
+use rings_core::ecc::pre::Capsule;
+use rings_core::ecc::pre::CapsuleFrag;
+use rings_core::ecc::pre::KeyFrag;
+use rings_core::ecc::pre::SealedData;
+use rings_core::ecc::PublicKey;
+use rings_core::ecc::SecretKey;
+use rings_core::session::SessionSk;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::backend::BackendMessage;
+use crate::error::Error;
+use crate::error::Result;
+
+/// Message between owner, proxies and members of data sealed under a delegating key.
+/// See [rings_core::ecc::pre].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum ReencryptMessage {
+    /// Owner asks proxy to keep a key fragment, the [ReencryptGrant] is sealed to session key
+    /// of proxy, so that only the proxy can read it.
+    Grant(SealedData),
+    /// Owner asks proxy to drop key fragments of a member.
+    Revoke(ReencryptRevocation),
+    /// Member asks proxy to re-encrypt a capsule.
+    Request {
+        /// Capsule of sealed data.
+        capsule: Capsule,
+        /// Public key which data is sealed under.
+        delegating: PublicKey<33>,
+        /// Public key of member.
+        receiving: PublicKey<33>,
+    },
+    /// Proxy responds with a re-encrypted capsule.
+    Response {
+        /// Digest of capsule.
+        capsule: [u8; 32],
+        /// Capsule fragment.
+        cfrag: CapsuleFrag,
+    },
+    /// Proxy has no key fragment of the request.
+    Denied {
+        /// Digest of capsule.
+        capsule: [u8; 32],
+    },
+}
+
+/// Key fragment granted at an epoch, signed by delegating key.
+///
+/// Epochs of grants and revocations from a delegating key to a receiving key must increase,
+/// so that proxies reject replayed ones which are older than what they have seen.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReencryptGrant {
+    /// Key fragment for proxy.
+    pub kfrag: KeyFrag,
+    /// Epoch of grant.
+    pub epoch: u64,
+    /// Signature of delegating key.
+    pub sig: Vec<u8>,
+}
+
+/// Revocation of key fragments from delegating key to receiving key at an epoch, signed by
+/// delegating key. It drops grants of the same or earlier epochs.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReencryptRevocation {
+    /// Public key which data is sealed under.
+    pub delegating: PublicKey<33>,
+    /// Public key of revoked member.
+    pub receiving: PublicKey<33>,
+    /// Epoch of revocation.
+    pub epoch: u64,
+    /// Signature of delegating key.
+    pub sig: Vec<u8>,
+}
+
+fn signed_by(digest: &[u8], sig: &[u8], key: &PublicKey<33>) -> bool {
+    rings_core::ecc::recover(digest, sig)
+        .map(|pk| pk == *key)
+        .unwrap_or(false)
+}
+
+impl ReencryptGrant {
+    fn digest(kfrag: &KeyFrag, epoch: u64) -> Vec<u8> {
+        [
+            &b"grant"[..],
+            &kfrag.id[..],
+            &kfrag.delegating.0[..],
+            &kfrag.receiving.0[..],
+            &epoch.to_be_bytes()[..],
+        ]
+        .concat()
+    }
+
+    /// Create a grant of key fragment signed by delegating key.
+    pub fn new(delegating: &SecretKey, kfrag: KeyFrag, epoch: u64) -> Self {
+        let sig = delegating.sign_raw(&Self::digest(&kfrag, epoch));
+        Self {
+            kfrag,
+            epoch,
+            sig: sig.to_vec(),
+        }
+    }
+
+    /// Check that grant and its key fragment are signed by delegating key.
+    pub fn verify(&self) -> bool {
+        let digest = Self::digest(&self.kfrag, self.epoch);
+        self.kfrag.verify() && signed_by(&digest, &self.sig, &self.kfrag.delegating)
+    }
+
+    /// Seal grant to session key of proxy.
+    pub fn seal(&self, proxy: &PublicKey<33>) -> Result<SealedData> {
+        let data = serde_json::to_vec(self)?;
+        SealedData::seal(proxy, &data).map_err(Error::InternalError)
+    }
+
+    /// Open grant sealed to session key of this proxy.
+    pub fn open(sealed: &SealedData, session_sk: &SessionSk) -> Result<Self> {
+        if sealed.delegating != session_sk.pubkey() {
+            return Err(Error::ReencryptRejected(
+                "grant is not sealed to this proxy".to_string(),
+            ));
+        }
+        let data = session_sk.unseal(sealed).map_err(Error::InternalError)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+}
+
+impl ReencryptRevocation {
+    fn digest(delegating: &PublicKey<33>, receiving: &PublicKey<33>, epoch: u64) -> Vec<u8> {
+        [
+            &b"revoke"[..],
+            &delegating.0[..],
+            &receiving.0[..],
+            &epoch.to_be_bytes()[..],
+        ]
+        .concat()
+    }
+
+    /// Create a revocation signed by delegating key.
+    pub fn new(delegating: &SecretKey, receiving: &PublicKey<33>, epoch: u64) -> Self {
+        let delegating_pk = delegating.pubkey();
+        let sig = delegating.sign_raw(&Self::digest(&delegating_pk, receiving, epoch));
+        Self {
+            delegating: delegating_pk,
+            receiving: *receiving,
+            epoch,
+            sig: sig.to_vec(),
+        }
+    }
+
+    /// Check that revocation is signed by delegating key.
+    pub fn verify(&self) -> bool {
+        let digest = Self::digest(&self.delegating, &self.receiving, self.epoch);
+        signed_by(&digest, &self.sig, &self.delegating)
+    }
+}
+
+impl From<ReencryptMessage> for BackendMessage {
+    fn from(val: ReencryptMessage) -> Self {
+        BackendMessage::ReencryptMessage(Box::new(val))
+    }
+}
@@ -7,6 +7,13 @@ use rings_snark::prelude::nova::provider::VestaEngine;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::backend::snark::market::SNARKTaskDescription;
+use crate::backend::snark::market::SignedQuote;
+use crate::backend::snark::market::SignedReceipt;
+use crate::backend::snark::params::PublicParamsRef;
+use crate::backend::snark::params::SNARKPublicParams;
+use crate::backend::snark::progress::SNARKProgress;
+use crate::backend::snark::Groth16Task;
 use crate::backend::snark::SNARKGenerator;
 use crate::backend::BackendMessage;
 
@@ -31,6 +38,26 @@ pub enum SNARKTask {
     SNARKProof(SNARKProofTask),
     /// Verify task
     SNARKVerify(SNARKVerifyTask),
+    /// Prover started proving the task
+    SNARKProving,
+    /// Requester cancelled the task
+    SNARKCancel,
+    /// Prover rejected the task or failed to prove it, with the reason
+    SNARKRejected(String),
+    /// Ask for public params, see [crate::backend::snark::params]
+    SNARKParamsRequest(PublicParamsRef),
+    /// Public params with its key, answer of [SNARKTask::SNARKParamsRequest]
+    SNARKParams(String, SNARKPublicParams),
+    /// Ask for quote of task, see [crate::backend::snark::market]
+    SNARKQuoteRequest(SNARKTaskDescription),
+    /// Quote signed by prover, answer of [SNARKTask::SNARKQuoteRequest]
+    SNARKQuote(SignedQuote),
+    /// Receipt signed by requester, once the proof of quoted task is verified
+    SNARKReceipt(SignedReceipt),
+    /// Progress of proving, see [crate::backend::snark::progress]
+    SNARKProgress(SNARKProgress),
+    /// Task folded so far, another prover can resume the task from it
+    SNARKCheckpoint(SNARKProofTask),
 }
 
 /// Message type of snark proof
@@ -42,6 +69,8 @@ pub enum SNARKProofTask {
     VastaPallas(SNARKGenerator<VestaEngine, PallasEngine>),
     /// SNARK with curve bn256 whth KZG multi linear commitment and grumpkin
     Bn256KZGGrumpkin(SNARKGenerator<Bn256EngineKZG, GrumpkinEngine>),
+    /// Groth16 with curve bn256
+    Groth16Bn256(Groth16Task),
 }
 
 /// Message type of snark proof
@@ -53,6 +82,8 @@ pub enum SNARKVerifyTask {
     VastaPallas(String),
     /// SNARK with curve bn256 whth KZG multi linear commitment and grumpkin
     Bn256KZGGrumpkin(String),
+    /// Groth16 with curve bn256, see [crate::backend::snark::Groth16Proof]
+    Groth16Bn256(String),
 }
 
 impl From<SNARKTaskMessage> for BackendMessage {
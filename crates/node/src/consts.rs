@@ -11,3 +11,35 @@ pub const MSG_SEND_FAILED_LIMIT: i64 = 10;
 pub const MSG_RECV_FAILED_LIMIT: i64 = 10;
 /// Timeout for proxied TCP connections
 pub const TCP_SERVER_TIMEOUT: u64 = 30;
+/// Timeout in seconds of waiting peer to connect the service for a SOCKS5 request
+pub const SOCKS5_DIAL_TIMEOUT: u64 = 60;
+/// Idle timeout of proxied UDP tunnels
+pub const UDP_TUNNEL_IDLE_TIMEOUT: u64 = 60;
+/// Max size of a proxied UDP datagram
+pub const UDP_MAX_DATAGRAM_SIZE: usize = 65535;
+/// Max number of UDP tunnels opened by a single peer
+pub const UDP_MAX_TUNNELS_PER_PEER: usize = 64;
+/// Max number of tunnels whose service is remembered to check capabilities of restricted sessions
+pub const MAX_TUNNEL_SERVICES: usize = 4096;
+/// Capacity of node event channel, slow websocket subscribers will lag behind and skip events
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+/// Interval of polling topic messages for websocket subscribers
+pub const TOPIC_POLL_INTERVAL: u64 = 5;
+/// Renew session this many seconds before it expires
+pub const SESSION_RENEWAL_MARGIN: u64 = 24 * 3600;
+/// Retry interval in seconds of failed session renewal
+pub const SESSION_RENEWAL_RETRY_INTERVAL: u64 = 60;
+/// Interval in seconds of refreshing session revocations from DHT
+pub const REVOCATION_REFRESH_INTERVAL: u64 = 60;
+/// Timeout in seconds of resolving DID document or account directory from DHT
+pub const DHT_RESOLVE_TIMEOUT: u64 = 3;
+/// Device entry in account directory expires after this many seconds, unless it's announced again
+pub const DEVICE_ENTRY_TTL: u64 = 90;
+/// Interval in seconds of announcing device and republishing DID document and services
+pub const DEVICE_ANNOUNCE_INTERVAL: u64 = 30;
+/// Max number of grants and revocation tombstones kept by a proxy of re-encryption
+pub const REENCRYPT_MAX_GRANTS: usize = 4096;
+/// Max number of grants and revocation tombstones kept by a proxy of re-encryption for one sender
+pub const REENCRYPT_MAX_GRANTS_PER_SENDER: usize = 256;
+/// Max number of pending re-encryption requests of a member
+pub const REENCRYPT_MAX_PENDING: usize = 1024;
@@ -58,6 +58,8 @@ pub enum Error {
     InvalidMethod = 501,
     #[error("Internal error: {0}.")]
     InternalError(rings_core::error::Error) = 502,
+    #[error("No other device of account {0} is online")]
+    NoOtherDeviceOnline(rings_core::dht::Did) = 503,
     #[error("No Permission")]
     NoPermission = 504,
     #[error("Connect error, {0}")]
@@ -100,6 +102,10 @@ pub enum Error {
     HomeDirError = 903,
     #[error("Cannot find parent directory")]
     ParentDirError = 904,
+    #[error("Keystore error: {0}")]
+    KeystoreError(String) = 905,
+    #[error("Wrong keystore password")]
+    KeystorePassword = 906,
     #[error("Serde json error: {0}")]
     SerdeJsonError(#[from] serde_json::Error) = 1000,
     #[error("Serde yaml error: {0}")]
@@ -122,6 +128,12 @@ pub enum Error {
     TunnelNotFound = 1303,
     #[error("Tunnel error: {0:?}")]
     TunnelError(TunnelDefeat) = 1304,
+    #[error("Socks5 error: {0}")]
+    Socks5Error(String) = 1305,
+    #[error("Tunnel is not owned by peer")]
+    TunnelNotOwned = 1306,
+    #[error("Too many tunnels of peer")]
+    TunnelLimitExceeded = 1307,
     #[cfg(feature = "snark")]
     #[error("Snark error: {0}")]
     RingsSNARKError(#[from] rings_snark::error::Error) = 1400,
@@ -139,8 +151,32 @@ pub enum Error {
     SNARKBigIntValueEmpty() = 1405,
     #[error("Failed to load string to PrimeField")]
     FailedToLoadFF() = 1406,
+    #[error("No snark prover available")]
+    SNARKNoProverAvailable() = 1407,
+    #[error("Snark task not found: {0}")]
+    SNARKTaskNotFound(String) = 1408,
+    #[error("Snark task storage error: {0}")]
+    SNARKTaskStorageError(rings_core::error::Error) = 1409,
+    #[error("Snark task rejected: {0}")]
+    SNARKTaskRejected(String) = 1410,
+    #[error("Snark public params not found: {0}")]
+    SNARKPublicParamsNotFound(String) = 1411,
+    #[error("Snark public params mismatch: {0}")]
+    SNARKPublicParamsMismatch(String) = 1412,
+    #[error("Snark quote not found: {0}")]
+    SNARKQuoteNotFound(String) = 1413,
+    #[error("Snark invalid signature: {0}")]
+    SNARKInvalidSignature(String) = 1414,
+    #[error("Snark sub task {0} is not linked to the previous one")]
+    SNARKSubTaskNotLinked(usize) = 1415,
+    #[error("Snark verifier key mismatch, expected hash {0}")]
+    SNARKVerifierKeyMismatch(String) = 1416,
     #[error("Extend Backend Error {0}")]
     BackendError(String) = 1501,
+    #[error("Re-encryption grant or revocation of epoch {0} is outdated")]
+    ReencryptOutdated(u64) = 1600,
+    #[error("Re-encryption rejected: {0}")]
+    ReencryptRejected(String) = 1601,
 }
 
 impl Error {
@@ -178,6 +214,7 @@ impl From<rings_rpc::error::Error> for Error {
             rings_rpc::error::Error::InvalidMethod => Error::InvalidMethod,
             rings_rpc::error::Error::RpcError(v) => Error::RemoteRpcError(v.to_string()),
             rings_rpc::error::Error::InvalidSignature => Error::InvalidData,
+            rings_rpc::error::Error::ReplayedRequest => Error::InvalidData,
             rings_rpc::error::Error::InvalidHeaders => Error::InvalidHeaders,
             _ => Error::UnknownRpcError,
         }
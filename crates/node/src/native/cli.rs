@@ -24,6 +24,7 @@ use futures::select;
 use futures::FutureExt;
 use futures::Stream;
 use futures_timer::Delay;
+use rings_rpc::auth::Credential;
 use rings_rpc::jsonrpc::Client as RpcClient;
 use rings_rpc::protos::rings_node::*;
 
@@ -49,9 +50,12 @@ pub struct ClientOutput<T> {
 }
 
 impl Client {
-    /// Creates a new Client instance with the specified endpoint URL and signature.
-    pub fn new(endpoint_url: &str) -> anyhow::Result<Self> {
-        let rpc_client = RpcClient::new(endpoint_url);
+    /// Creates a new Client instance with the specified endpoint URL and credential.
+    pub fn new(endpoint_url: &str, credential: Option<Credential>) -> anyhow::Result<Self> {
+        let mut rpc_client = RpcClient::new(endpoint_url);
+        if let Some(credential) = credential {
+            rpc_client = rpc_client.with_credential(credential);
+        }
         Ok(Self { client: rpc_client })
     }
 
@@ -198,6 +202,102 @@ impl Client {
         ClientOutput::ok("Done.".into(), ())
     }
 
+    /// Publishes a dumped session revocation signed by account of the session.
+    pub async fn revoke_session(&self, revocation: &str) -> Output<()> {
+        self.client
+            .revoke_session(&RevokeSessionRequest {
+                revocation: revocation.to_string(),
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        ClientOutput::ok("Done.".into(), ())
+    }
+
+    /// Resolves the DID document of a node from DHT.
+    pub async fn resolve_did(&self, did: &str) -> Output<Option<DidDocument>> {
+        let document = self
+            .client
+            .resolve_did(&ResolveDidRequest {
+                did: did.to_string(),
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .document;
+
+        let display = match &document {
+            Some(document) => {
+                serde_json::to_string_pretty(document).map_err(|e| anyhow::anyhow!("{}", e))?
+            }
+            None => format!("DID document of {} is not found.", did),
+        };
+
+        ClientOutput::ok(display, document)
+    }
+
+    /// Retrieves the DID of node.
+    pub async fn node_did(&self) -> Output<String> {
+        let did = self
+            .client
+            .node_did(&NodeDidRequest {})
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .did;
+        ClientOutput::ok(did.clone(), did)
+    }
+
+    /// Links the node to account of user as one of its devices, with a dumped link signed by the account.
+    pub async fn link_device(&self, link: &str) -> Output<String> {
+        let account = self
+            .client
+            .link_device(&LinkDeviceRequest {
+                link: link.to_string(),
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .account;
+        ClientOutput::ok(format!("Linked to account {}.", account), account)
+    }
+
+    /// Resolves the online devices of an account from DHT.
+    pub async fn resolve_account(&self, account: &str) -> Output<Vec<AccountDevice>> {
+        let devices = self
+            .client
+            .resolve_account(&ResolveAccountRequest {
+                account: account.to_string(),
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .devices;
+
+        let display = if devices.is_empty() {
+            format!("No online device of {} is found.", account)
+        } else {
+            serde_json::to_string_pretty(&devices).map_err(|e| anyhow::anyhow!("{}", e))?
+        };
+
+        ClientOutput::ok(display, devices)
+    }
+
+    /// Sends a custom message to the devices of an account, to all online devices if fan_out.
+    pub async fn send_account_message(
+        &self,
+        account: &str,
+        data: &str,
+        fan_out: bool,
+    ) -> Output<Vec<String>> {
+        let devices = self
+            .client
+            .send_account_message(&SendAccountMessageRequest {
+                account: account.to_string(),
+                data: data.to_string(),
+                fan_out,
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .devices;
+        ClientOutput::ok(devices.join("\n"), devices)
+    }
+
     /// Registers a new service with the given name.
     pub async fn register_service(&self, name: &str) -> Output<()> {
         self.client
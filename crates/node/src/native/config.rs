@@ -1,6 +1,9 @@
 use std::env;
 use std::fs;
 use std::io;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 use serde::Deserialize;
@@ -8,9 +11,16 @@ use serde::Serialize;
 
 use crate::backend::native::extension::ExtensionConfig;
 use crate::backend::native::service::ServiceConfig;
+use crate::backend::native::service::UdpForwardConfig;
 use crate::backend::native::BackendConfig;
+#[cfg(feature = "snark")]
+use crate::backend::snark::prover::ProverOptions;
+use crate::consts::SESSION_RENEWAL_MARGIN;
 use crate::error::Error;
 use crate::error::Result;
+use crate::native::endpoint::auth::ApiAuthConfig;
+use crate::native::keystore::Keystore;
+use crate::native::keystore::Secrets;
 use crate::prelude::rings_core::ecc::SecretKey;
 use crate::prelude::SessionSk;
 use crate::processor::ProcessorConfig;
@@ -27,6 +37,22 @@ lazy_static::lazy_static! {
     path: get_storage_location(".rings", "measure"),
     capacity: DEFAULT_STORAGE_CAPACITY,
   };
+  static ref DEFAULT_SNARK_STORAGE_CONFIG: StorageConfig = StorageConfig {
+    path: get_storage_location(".rings", "snark"),
+    capacity: DEFAULT_STORAGE_CAPACITY,
+  };
+  static ref DEFAULT_SNARK_PARAMS_STORAGE_CONFIG: StorageConfig = StorageConfig {
+    path: get_storage_location(".rings", "snark_params"),
+    capacity: DEFAULT_STORAGE_CAPACITY,
+  };
+  static ref DEFAULT_SNARK_LEDGER_STORAGE_CONFIG: StorageConfig = StorageConfig {
+    path: get_storage_location(".rings", "snark_ledger"),
+    capacity: DEFAULT_STORAGE_CAPACITY,
+  };
+  static ref DEFAULT_REVOCATION_STORAGE_CONFIG: StorageConfig = StorageConfig {
+    path: get_storage_location(".rings", "revocations"),
+    capacity: DEFAULT_STORAGE_CAPACITY,
+  };
 }
 
 pub const DEFAULT_NETWORK_ID: u32 = 1;
@@ -55,9 +81,41 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub session_manager: Option<String>,
     pub session_sk: Option<String>,
+    /// Encrypted keystore of `ecdsa_key` and `session_sk`, which is used instead of the
+    /// plaintext fields if provided. See [crate::native::keystore].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keystore: Option<String>,
+    /// Secrets decrypted from `keystore` by [Config::unlock_keystore].
+    #[serde(skip)]
+    pub secrets: Option<Secrets>,
+    /// Renew session before it expires if provided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_renewal: Option<SessionRenewalConfig>,
     pub internal_api_port: u16,
+    /// Host of internal api, 127.0.0.1 if not provided.
+    /// Non-loopback host is only allowed when `internal_api_auth` is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal_api_host: Option<IpAddr>,
+    /// Credentials accepted by internal api, authentication is disabled if empty.
+    #[serde(default)]
+    pub internal_api_auth: ApiAuthConfig,
     pub external_api_addr: String,
+    /// Public URL of external api, which is advertised in DID document of node,
+    /// so that peers can connect to this node by http with it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_endpoint_url: Option<String>,
+    /// Connect endpoints of loopback, private or link-local hosts advertised in DID documents
+    /// of peers. Only enable it in a trusted network.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_private_endpoints: bool,
+    /// Dumped link of this node to account of user, the node announces itself as a device of
+    /// the account if provided. See `rings account link`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_link: Option<String>,
     pub endpoint_url: String,
+    /// Bearer token used by client to call internal api.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint_token: Option<String>,
     pub ice_servers: String,
     pub stabilize_interval: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -66,8 +124,39 @@ pub struct Config {
     /// its deserialization is equivalent to `vec![]` in Rust.
     #[serde(default)]
     pub services: Vec<ServiceConfig>,
+    /// Local UDP listeners which forward datagrams to UDP services of peers.
+    /// When there is no configuration in the YAML file,
+    /// its deserialization is equivalent to `vec![]` in Rust.
+    #[serde(default)]
+    pub udp_forwards: Vec<UdpForwardConfig>,
+    /// Run gRPC internal service on this address if provided.
+    /// It shares `internal_api_auth` with internal api.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal_grpc_api_addr: Option<SocketAddr>,
+    /// Run gRPC external service on this address if provided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_grpc_api_addr: Option<SocketAddr>,
+    /// Run a SOCKS5 proxy on this address if provided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socks5_addr: Option<SocketAddr>,
     pub data_storage: StorageConfig,
     pub measure_storage: StorageConfig,
+    /// Storage of snark task records.
+    #[serde(default = "default_snark_storage")]
+    pub snark_storage: StorageConfig,
+    /// Storage of cached snark public params.
+    #[serde(default = "default_snark_params_storage")]
+    pub snark_params_storage: StorageConfig,
+    /// Storage of snark receipts.
+    #[serde(default = "default_snark_ledger_storage")]
+    pub snark_ledger_storage: StorageConfig,
+    /// Storage of session revocations.
+    #[serde(default = "default_revocation_storage")]
+    pub revocation_storage: StorageConfig,
+    /// Limits of proof tasks accepted from other nodes.
+    #[cfg(feature = "snark")]
+    #[serde(default)]
+    pub snark_prover: ProverOptions,
     /// When there is no configuration in the YAML file,
     /// its deserialization is equivalent to `ExtensionConfig(vec![])` in Rust.
     #[serde(default)]
@@ -77,8 +166,22 @@ pub struct Config {
 impl TryFrom<Config> for ProcessorConfigSerialized {
     type Error = Error;
     fn try_from(config: Config) -> Result<Self> {
-        // Support old version
-        let session_sk: String = if let Some(sk) = config.ecdsa_key {
+        if config.keystore.is_some() && config.secrets.is_none() {
+            return Err(Error::KeystoreError("keystore is locked".to_string()));
+        }
+
+        let session_sk: String = if let Some(secrets) = config.secrets {
+            match (secrets.session_sk, secrets.ecdsa_key) {
+                (Some(ssk), _) => ssk,
+                (None, Some(sk)) => SessionSk::new_with_seckey(&sk)?.dump()?,
+                (None, None) => {
+                    return Err(Error::KeystoreError(
+                        "keystore has neither session_sk nor ecdsa_key".to_string(),
+                    ))
+                }
+            }
+        } else if let Some(sk) = config.ecdsa_key {
+            // Support old version
             tracing::warn!("Field `ecdsa_key` is deprecated, use `session_sk` instead.");
             SessionSk::new_with_seckey(&sk)
                 .expect("create session sk failed")
@@ -138,19 +241,64 @@ impl Config {
             ecdsa_key: None,
             session_manager: None,
             session_sk: Some(session_sk),
+            keystore: None,
+            secrets: None,
+            session_renewal: None,
             internal_api_port: DEFAULT_INTERNAL_API_PORT,
+            internal_api_host: None,
+            internal_api_auth: ApiAuthConfig::default(),
             external_api_addr: DEFAULT_EXTERNAL_API_ADDR.to_string(),
+            public_endpoint_url: None,
+            allow_private_endpoints: false,
+            device_link: None,
             endpoint_url: DEFAULT_ENDPOINT_URL.to_string(),
+            endpoint_token: None,
             ice_servers: DEFAULT_ICE_SERVERS.to_string(),
             stabilize_interval: DEFAULT_STABILIZE_INTERVAL,
             external_ip: None,
             services: vec![],
+            udp_forwards: vec![],
+            internal_grpc_api_addr: None,
+            external_grpc_api_addr: None,
+            socks5_addr: None,
             data_storage: DEFAULT_DATA_STORAGE_CONFIG.clone(),
             measure_storage: DEFAULT_MEASURE_STORAGE_CONFIG.clone(),
+            snark_storage: DEFAULT_SNARK_STORAGE_CONFIG.clone(),
+            snark_params_storage: DEFAULT_SNARK_PARAMS_STORAGE_CONFIG.clone(),
+            snark_ledger_storage: DEFAULT_SNARK_LEDGER_STORAGE_CONFIG.clone(),
+            revocation_storage: DEFAULT_REVOCATION_STORAGE_CONFIG.clone(),
+            #[cfg(feature = "snark")]
+            snark_prover: ProverOptions::default(),
             extension: ExtensionConfig::default(),
         }
     }
 
+    /// Create a config whose secrets are in the keystore.
+    pub fn new_with_keystore<P>(keystore: P) -> Self
+    where P: AsRef<std::path::Path> {
+        Self {
+            session_sk: None,
+            keystore: Some(keystore.as_ref().to_string_lossy().to_string()),
+            ..Self::new("")
+        }
+    }
+
+    /// Decrypt secrets of `keystore` by password, nothing happens if there is no keystore.
+    pub fn unlock_keystore(&mut self, password: &str) -> Result<()> {
+        if let Some(path) = &self.keystore {
+            self.secrets = Some(Keystore::read_fs(path)?.decrypt(password)?);
+        }
+        Ok(())
+    }
+
+    /// Binding address of internal api
+    pub fn internal_api_addr(&self) -> SocketAddr {
+        let host = self
+            .internal_api_host
+            .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        SocketAddr::new(host, self.internal_api_port)
+    }
+
     pub fn write_fs<P>(&self, path: P) -> Result<String>
     where P: AsRef<std::path::Path> {
         let path = expand_home(path)?;
@@ -172,6 +320,26 @@ impl Config {
     }
 }
 
+/// Renew session with the secp256k1 key of account, which is the `ecdsa_key` used to
+/// create the session file, or with an external signer of account listening on `signer`.
+/// The renewed session is written back to the session file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SessionRenewalConfig {
+    /// Secret key of account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecdsa_key: Option<SecretKey>,
+    /// Unix socket of external signer, used if `ecdsa_key` is not provided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    /// Renew session this many seconds before it expires.
+    #[serde(default = "default_session_renewal_margin")]
+    pub margin: u64,
+}
+
+fn default_session_renewal_margin() -> u64 {
+    SESSION_RENEWAL_MARGIN
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StorageConfig {
     pub path: String,
@@ -187,9 +355,28 @@ impl StorageConfig {
     }
 }
 
+fn default_snark_storage() -> StorageConfig {
+    DEFAULT_SNARK_STORAGE_CONFIG.clone()
+}
+
+fn default_snark_params_storage() -> StorageConfig {
+    DEFAULT_SNARK_PARAMS_STORAGE_CONFIG.clone()
+}
+
+fn default_snark_ledger_storage() -> StorageConfig {
+    DEFAULT_SNARK_LEDGER_STORAGE_CONFIG.clone()
+}
+
+fn default_revocation_storage() -> StorageConfig {
+    DEFAULT_REVOCATION_STORAGE_CONFIG.clone()
+}
+
 #[cfg(test)]
 mod tests {
+    use rings_rpc::auth::Scope;
+
     use super::*;
+    use crate::backend::native::service::ServiceProtocol;
 
     #[test]
     fn test_deserialization_with_missed_field() {
@@ -212,5 +399,134 @@ measure_storage:
         let cfg: Config = serde_yaml::from_str(yaml).unwrap();
         assert_eq!(cfg.extension, ExtensionConfig::default());
         assert_eq!(cfg.services, vec![]);
+        assert_eq!(cfg.udp_forwards, vec![]);
+        assert_eq!(cfg.socks5_addr, None);
+        assert_eq!(cfg.public_endpoint_url, None);
+        assert!(!cfg.allow_private_endpoints);
+        assert_eq!(cfg.device_link, None);
+        assert_eq!(cfg.internal_grpc_api_addr, None);
+        assert!(!cfg.internal_api_auth.is_enabled());
+        assert_eq!(cfg.internal_api_addr(), "127.0.0.1:50000".parse().unwrap());
+        assert_eq!(
+            cfg.snark_storage.path,
+            DEFAULT_SNARK_STORAGE_CONFIG.path.clone()
+        );
+        assert_eq!(
+            cfg.snark_params_storage.path,
+            DEFAULT_SNARK_PARAMS_STORAGE_CONFIG.path.clone()
+        );
+        assert_eq!(
+            cfg.snark_ledger_storage.path,
+            DEFAULT_SNARK_LEDGER_STORAGE_CONFIG.path.clone()
+        );
+        assert_eq!(
+            cfg.revocation_storage.path,
+            DEFAULT_REVOCATION_STORAGE_CONFIG.path.clone()
+        );
+    }
+
+    #[test]
+    fn test_deserialization_of_udp_service() {
+        let yaml = r#"
+network_id: 1
+session_sk: session_sk
+internal_api_port: 50000
+external_api_addr: 127.0.0.1:50001
+endpoint_url: http://127.0.0.1:50000
+ice_servers: stun://stun.l.google.com:19302
+stabilize_interval: 3
+external_ip: null
+services:
+  - name: web
+    register_service: web
+    addr: 127.0.0.1:8080
+  - name: dns
+    register_service: dns
+    addr: 127.0.0.1:53
+    protocol: udp
+udp_forwards:
+  - listen: 127.0.0.1:5353
+    peer_did: "0x11e807fcc88dd319270493fb2e822e388fe36ab0"
+    service: dns
+data_storage:
+  path: /Users/foo/.rings/data
+  capacity: 200000000
+measure_storage:
+  path: /Users/foo/.rings/measure
+  capacity: 200000000
+"#;
+        let cfg: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(cfg.services[0].protocol, ServiceProtocol::Tcp);
+        assert_eq!(cfg.services[1].protocol, ServiceProtocol::Udp);
+        assert_eq!(cfg.udp_forwards[0].service, "dns");
+        assert_eq!(
+            cfg.udp_forwards[0].idle_timeout,
+            crate::consts::UDP_TUNNEL_IDLE_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn test_deserialization_of_internal_api_auth() {
+        let yaml = r#"
+network_id: 1
+session_sk: session_sk
+internal_api_port: 50000
+internal_api_host: 0.0.0.0
+internal_api_auth:
+  tokens:
+    - token: secret
+      scopes: [read, messaging]
+  signers:
+    - did: "0x11e807fcc88dd319270493fb2e822e388fe36ab0"
+      scopes: [read, messaging, admin]
+external_api_addr: 127.0.0.1:50001
+endpoint_url: http://127.0.0.1:50000
+endpoint_token: secret
+ice_servers: stun://stun.l.google.com:19302
+stabilize_interval: 3
+external_ip: null
+data_storage:
+  path: /Users/foo/.rings/data
+  capacity: 200000000
+measure_storage:
+  path: /Users/foo/.rings/measure
+  capacity: 200000000
+"#;
+        let cfg: Config = serde_yaml::from_str(yaml).unwrap();
+        assert!(cfg.internal_api_auth.is_enabled());
+        assert_eq!(cfg.internal_api_auth.tokens[0].scopes, vec![
+            Scope::Read,
+            Scope::Messaging
+        ]);
+        assert_eq!(cfg.internal_api_auth.signers[0].scopes.len(), 3);
+        assert_eq!(cfg.internal_api_addr(), "0.0.0.0:50000".parse().unwrap());
+        assert_eq!(cfg.endpoint_token, Some("secret".to_string()));
+    }
+
+    #[test]
+    fn test_unlock_keystore() {
+        let key = SecretKey::random();
+        let secrets = Secrets {
+            ecdsa_key: Some(key),
+            session_sk: None,
+        };
+        let path = std::env::temp_dir().join(format!("rings-keystore-{}.json", key.address()));
+        Keystore::encrypt(&secrets, "password")
+            .unwrap()
+            .write_fs(&path)
+            .unwrap();
+
+        let mut cfg = Config::new_with_keystore(&path);
+        let yaml = serde_yaml::to_string(&cfg).unwrap();
+        assert!(yaml.contains("keystore: "));
+        assert!(!yaml.contains("secrets"));
+        assert!(ProcessorConfigSerialized::try_from(cfg.clone()).is_err());
+
+        assert!(cfg.unlock_keystore("wrong").is_err());
+        cfg.unlock_keystore("password").unwrap();
+        assert_eq!(cfg.secrets, Some(secrets));
+        assert!(ProcessorConfigSerialized::try_from(cfg).is_ok());
+
+        std::fs::remove_file(&path).unwrap();
     }
 }
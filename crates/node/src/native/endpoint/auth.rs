@@ -0,0 +1,403 @@
+//! Authentication of internal api, the credentials are described in [rings_rpc::auth].
+//!
+//! When neither token nor signer is configured, authentication is disabled, and the
+//! internal api can only be bound to a loopback address. Requests which may be sent by web
+//! pages of other sites are always rejected, unless the origin is listed in [ApiAuthConfig::origins].
+//!
+//! [ApiAuth] holds the [ApiAuthConfig] and the nonces of accepted signed requests, it's
+//! shared by the endpoints of a server.
+use std::collections::HashSet;
+
+use axum::http::header::CONTENT_TYPE;
+use axum::http::header::ORIGIN;
+use axum::http::HeaderMap;
+use rings_core::dht::Did;
+use rings_rpc::auth::parse_bearer_token;
+use rings_rpc::auth::verify_signed_request;
+use rings_rpc::auth::NonceCache;
+use rings_rpc::auth::Scope;
+use rings_rpc::auth::SignedRequest;
+use rings_rpc::auth::HEADER_AUTHORIZATION;
+use rings_rpc::auth::HEADER_NONCE;
+use rings_rpc::auth::HEADER_SIGNATURE;
+use rings_rpc::auth::HEADER_SIGNER;
+use rings_rpc::auth::HEADER_TIMESTAMP;
+use rings_rpc::method::Method;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::http_error::HttpError;
+
+/// Credentials accepted by internal api
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiAuthConfig {
+    /// Bearer tokens
+    #[serde(default)]
+    pub tokens: Vec<ApiToken>,
+    /// Dids of keys which are allowed to sign requests
+    #[serde(default)]
+    pub signers: Vec<ApiSigner>,
+    /// Origins of web pages which are allowed to access internal api, like `http://localhost:3000`.
+    /// Requests from other web pages are rejected, whether authentication is enabled or not.
+    #[serde(default)]
+    pub origins: Vec<String>,
+}
+
+/// A bearer token and its scopes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// Token sent in `Authorization: Bearer <token>` header
+    pub token: String,
+    /// Scopes granted to the token
+    pub scopes: Vec<Scope>,
+}
+
+/// A signer of signed requests and its scopes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiSigner {
+    /// Did of signer
+    pub did: Did,
+    /// Scopes granted to the signer
+    pub scopes: Vec<Scope>,
+}
+
+/// Http method, path and body of a request, which are covered by signature of signed request
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RequestParts<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    pub body: &'a str,
+}
+
+/// Authenticator of internal api
+#[derive(Debug, Default)]
+pub struct ApiAuth {
+    config: ApiAuthConfig,
+    nonces: NonceCache,
+}
+
+impl ApiAuthConfig {
+    /// Authentication is enabled if any credential is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty() || !self.signers.is_empty()
+    }
+}
+
+impl From<ApiAuthConfig> for ApiAuth {
+    fn from(config: ApiAuthConfig) -> Self {
+        Self {
+            config,
+            nonces: NonceCache::default(),
+        }
+    }
+}
+
+impl ApiAuth {
+    /// Authentication is enabled if any credential is configured
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_enabled()
+    }
+
+    /// Reject requests sent by web pages of origins which are not allowed.
+    /// Requests without `Origin` header are not sent by browsers, so they are not checked.
+    pub(crate) fn check_origin(&self, headers: &HeaderMap) -> Result<(), HttpError> {
+        let Some(origin) = headers.get(ORIGIN) else {
+            return Ok(());
+        };
+        if self
+            .config
+            .origins
+            .iter()
+            .any(|o| o.as_bytes() == origin.as_bytes())
+        {
+            Ok(())
+        } else {
+            tracing::debug!("reject request from origin {:?}", origin);
+            Err(HttpError::Forbidden)
+        }
+    }
+
+    /// Reject requests which can be sent by web pages of other sites, that are the requests
+    /// of origins not allowed, and the posts which are not json, since browsers send them
+    /// without asking permission of server by preflight.
+    pub(crate) fn check_cross_site(
+        &self,
+        method: &http::Method,
+        headers: &HeaderMap,
+    ) -> Result<(), HttpError> {
+        self.check_origin(headers)?;
+        if method != http::Method::POST {
+            return Ok(());
+        }
+        let is_json = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(';').next())
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("application/json"));
+        if is_json {
+            Ok(())
+        } else {
+            Err(HttpError::UnsupportedMediaType)
+        }
+    }
+
+    /// Return the scopes granted to credential of request.
+    /// Signed requests are rejected if request is None.
+    pub(crate) fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        request: Option<RequestParts>,
+    ) -> Result<HashSet<Scope>, HttpError> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+        if let Some(token) = header(HEADER_AUTHORIZATION).and_then(parse_bearer_token) {
+            return self
+                .config
+                .tokens
+                .iter()
+                .find(|t| constant_time_eq(t.token.as_bytes(), token.as_bytes()))
+                .map(|t| t.scopes.iter().copied().collect())
+                .ok_or(HttpError::Unauthorized);
+        }
+
+        if let (Some(request), Some(signer), Some(timestamp), Some(nonce), Some(signature)) = (
+            request,
+            header(HEADER_SIGNER),
+            header(HEADER_TIMESTAMP),
+            header(HEADER_NONCE),
+            header(HEADER_SIGNATURE),
+        ) {
+            let signed = SignedRequest {
+                signer,
+                timestamp,
+                nonce,
+                signature,
+                method: request.method,
+                path: request.path,
+                body: request.body,
+            };
+            let did = verify_signed_request(&signed, &self.nonces).map_err(|e| {
+                tracing::debug!("verify signed request failed: {:?}", e);
+                HttpError::Unauthorized
+            })?;
+            return self
+                .config
+                .signers
+                .iter()
+                .find(|s| s.did == did)
+                .map(|s| s.scopes.iter().copied().collect())
+                .ok_or(HttpError::Unauthorized);
+        }
+
+        Err(HttpError::Unauthorized)
+    }
+
+    /// Check if the request is granted all the required scopes.
+    /// Always pass if authentication is disabled.
+    pub(crate) fn authorize(
+        &self,
+        headers: &HeaderMap,
+        request: Option<RequestParts>,
+        required: &[Scope],
+    ) -> Result<(), HttpError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let granted = self.authenticate(headers, request)?;
+        if required.iter().all(|s| granted.contains(s)) {
+            Ok(())
+        } else {
+            Err(HttpError::Forbidden)
+        }
+    }
+}
+
+/// Scopes required by the calls of a jsonrpc request.
+/// Unknown methods require [Scope::Admin].
+pub(crate) fn required_scopes(body: &str) -> Result<Vec<Scope>, HttpError> {
+    use jsonrpc_core::Call;
+    use jsonrpc_core::Request;
+
+    let request: Request = serde_json::from_str(body).map_err(|_| HttpError::BadRequest)?;
+    let calls = match request {
+        Request::Single(call) => vec![call],
+        Request::Batch(calls) => calls,
+    };
+    Ok(calls
+        .iter()
+        .map(|call| {
+            let method = match call {
+                Call::MethodCall(c) => c.method.as_str(),
+                Call::Notification(n) => n.method.as_str(),
+                Call::Invalid { .. } => "",
+            };
+            Method::try_from(method)
+                .map(|m| m.scope())
+                .unwrap_or(Scope::Admin)
+        })
+        .collect())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use rings_rpc::auth::Credential;
+
+    use super::*;
+    use crate::prelude::rings_core::ecc::SecretKey;
+
+    fn headers(credential: &Credential, request: RequestParts) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in credential.headers(request.method, request.path, request.body) {
+            headers.insert(name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn post(body: &str) -> RequestParts {
+        RequestParts {
+            method: "POST",
+            path: "/",
+            body,
+        }
+    }
+
+    #[test]
+    fn test_authorize() {
+        let key = SecretKey::random();
+        let auth = ApiAuth::from(ApiAuthConfig {
+            tokens: vec![ApiToken {
+                token: "reader".to_string(),
+                scopes: vec![Scope::Read],
+            }],
+            signers: vec![ApiSigner {
+                did: key.address().into(),
+                scopes: vec![Scope::Read, Scope::Messaging, Scope::Admin],
+            }],
+            ..Default::default()
+        });
+        let body = r#"{"jsonrpc":"2.0","method":"disconnect","params":{"did":"0x0"},"id":1}"#;
+        let required = required_scopes(body).unwrap();
+        assert_eq!(required, vec![Scope::Admin]);
+
+        let reader = headers(&Credential::Bearer("reader".to_string()), post(body));
+        assert!(auth
+            .authorize(&reader, Some(post(body)), &[Scope::Read])
+            .is_ok());
+        assert!(matches!(
+            auth.authorize(&reader, Some(post(body)), &required),
+            Err(HttpError::Forbidden)
+        ));
+
+        let unknown = headers(&Credential::Bearer("unknown".to_string()), post(body));
+        assert!(matches!(
+            auth.authorize(&unknown, Some(post(body)), &[Scope::Read]),
+            Err(HttpError::Unauthorized)
+        ));
+        assert!(matches!(
+            auth.authorize(&HeaderMap::new(), Some(post(body)), &[Scope::Read]),
+            Err(HttpError::Unauthorized)
+        ));
+
+        let signed = headers(&Credential::Signed(key), post(body));
+        assert!(auth.authorize(&signed, None, &required).is_err());
+        assert!(auth
+            .authorize(&signed, Some(post("tampered")), &required)
+            .is_err());
+        assert!(auth.authorize(&signed, Some(post(body)), &required).is_ok());
+        // Replayed request is rejected
+        assert!(matches!(
+            auth.authorize(&signed, Some(post(body)), &required),
+            Err(HttpError::Unauthorized)
+        ));
+
+        // Signature of a bodyless request is bound to its path
+        let status = RequestParts {
+            method: "GET",
+            path: "/status",
+            body: "",
+        };
+        let signed = headers(&Credential::Signed(key), status);
+        let ws = RequestParts {
+            path: "/ws",
+            ..status
+        };
+        assert!(auth.authorize(&signed, Some(ws), &[Scope::Read]).is_err());
+        assert!(auth
+            .authorize(&signed, Some(status), &[Scope::Read])
+            .is_ok());
+
+        let other = headers(&Credential::Signed(SecretKey::random()), post(body));
+        assert!(auth
+            .authorize(&other, Some(post(body)), &[Scope::Read])
+            .is_err());
+
+        let disabled = ApiAuth::default();
+        assert!(disabled
+            .authorize(&HeaderMap::new(), None, &required)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_check_origin() {
+        let auth = ApiAuth::from(ApiAuthConfig {
+            origins: vec!["http://localhost:3000".to_string()],
+            ..Default::default()
+        });
+        let origin = |origin: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ORIGIN, origin.parse().unwrap());
+            headers
+        };
+        assert!(auth.check_origin(&HeaderMap::new()).is_ok());
+        assert!(auth.check_origin(&origin("http://localhost:3000")).is_ok());
+        assert!(matches!(
+            auth.check_origin(&origin("https://evil.example")),
+            Err(HttpError::Forbidden)
+        ));
+        assert!(ApiAuth::default()
+            .check_origin(&origin("http://localhost:3000"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_check_cross_site() {
+        let auth = ApiAuth::default();
+        let headers = |content_type: &str, origin: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, content_type.parse().unwrap());
+            if let Some(origin) = origin {
+                headers.insert(ORIGIN, origin.parse().unwrap());
+            }
+            headers
+        };
+        let post = http::Method::POST;
+        assert!(auth
+            .check_cross_site(&post, &headers("application/json", None))
+            .is_ok());
+        assert!(auth
+            .check_cross_site(&post, &headers("application/json; charset=utf-8", None))
+            .is_ok());
+        assert!(matches!(
+            auth.check_cross_site(&post, &headers("text/plain", None)),
+            Err(HttpError::UnsupportedMediaType)
+        ));
+        assert!(matches!(
+            auth.check_cross_site(&post, &HeaderMap::new()),
+            Err(HttpError::UnsupportedMediaType)
+        ));
+        assert!(matches!(
+            auth.check_cross_site(
+                &post,
+                &headers("application/json", Some("https://evil.example"))
+            ),
+            Err(HttpError::Forbidden)
+        ));
+        assert!(auth
+            .check_cross_site(&http::Method::GET, &HeaderMap::new())
+            .is_ok());
+    }
+}
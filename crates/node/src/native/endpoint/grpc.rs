@@ -0,0 +1,569 @@
+//! gRPC services of rings node, see `rings_node.proto` of rings-rpc.
+//!
+//! The internal service shares [ApiAuthConfig] with json-rpc, but only bearer tokens are
+//! accepted, since there is no request body to sign.
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::Stream;
+use jsonrpc_core::ErrorCode;
+use rings_rpc::auth::Scope;
+use rings_rpc::grpc::ExternalService;
+use rings_rpc::grpc::ExternalServiceServer;
+use rings_rpc::grpc::InternalService;
+use rings_rpc::grpc::InternalServiceServer;
+use rings_rpc::method::Method;
+use rings_rpc::protos::rings_node;
+use rings_rpc::protos::rings_node::*;
+use rings_rpc::protos::rings_node_handler::HandleRpc;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::Request;
+use tonic::Response;
+use tonic::Status;
+
+use super::auth::ApiAuth;
+use super::auth::ApiAuthConfig;
+use super::http_error::HttpError;
+use crate::consts::TOPIC_POLL_INTERVAL;
+use crate::native::event;
+use crate::native::event::fetch_topic_messages;
+use crate::native::event::EventFilter;
+use crate::native::event::EventHub;
+use crate::processor::Processor;
+
+type GrpcStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Implementation of gRPC InternalService
+#[derive(Clone)]
+pub struct InternalGrpcService {
+    processor: Arc<Processor>,
+    events: EventHub,
+    auth: Arc<ApiAuth>,
+}
+
+/// Implementation of gRPC ExternalService
+#[derive(Clone)]
+pub struct ExternalGrpcService {
+    processor: Arc<Processor>,
+}
+
+/// Run a gRPC server of internal service.
+/// Binding to a non-loopback address is refused if authentication is disabled.
+pub async fn run_internal_grpc_api(
+    binding_addr: SocketAddr,
+    processor: Arc<Processor>,
+    events: EventHub,
+    auth: ApiAuthConfig,
+) -> anyhow::Result<()> {
+    if !binding_addr.ip().is_loopback() && !auth.is_enabled() {
+        anyhow::bail!(
+            "Refuse to bind internal grpc api to non-loopback address {} without authentication",
+            binding_addr
+        );
+    }
+    let service = InternalGrpcService {
+        processor,
+        events,
+        auth: Arc::new(auth.into()),
+    };
+
+    println!("gRPC endpoint: http://{}", binding_addr);
+    tonic::transport::Server::builder()
+        .add_service(InternalServiceServer::new(service))
+        .serve(binding_addr)
+        .await?;
+    Ok(())
+}
+
+/// Run a gRPC server of external service
+pub async fn run_external_grpc_api(
+    binding_addr: SocketAddr,
+    processor: Arc<Processor>,
+) -> anyhow::Result<()> {
+    let service = ExternalGrpcService { processor };
+
+    println!("External gRPC endpoint: http://{}", binding_addr);
+    tonic::transport::Server::builder()
+        .add_service(ExternalServiceServer::new(service))
+        .serve(binding_addr)
+        .await?;
+    Ok(())
+}
+
+impl InternalGrpcService {
+    fn authorize<T>(&self, req: &Request<T>, scope: Scope) -> Result<(), Status> {
+        let headers = req.metadata().clone().into_headers();
+        self.auth
+            .authorize(&headers, None, &[scope])
+            .map_err(|e| match e {
+                HttpError::Unauthorized => Status::unauthenticated("Unauthorized"),
+                HttpError::Forbidden => Status::permission_denied("Forbidden"),
+                HttpError::BadRequest | HttpError::UnsupportedMediaType => {
+                    Status::invalid_argument("Bad Request")
+                }
+                HttpError::Internal => Status::internal("Internal error"),
+            })
+    }
+
+    async fn handle<Req, Resp>(
+        &self,
+        req: Request<Req>,
+        method: Method,
+    ) -> Result<Response<Resp>, Status>
+    where
+        Req: Send + 'static,
+        Resp: Send + 'static,
+        Processor: HandleRpc<Req, Resp>,
+    {
+        self.authorize(&req, method.scope())?;
+        handle_rpc(&self.processor, req).await
+    }
+}
+
+async fn handle_rpc<Req, Resp>(
+    processor: &Processor,
+    req: Request<Req>,
+) -> Result<Response<Resp>, Status>
+where
+    Req: Send + 'static,
+    Resp: Send + 'static,
+    Processor: HandleRpc<Req, Resp>,
+{
+    HandleRpc::<Req, Resp>::handle_rpc(processor, req.into_inner())
+        .await
+        .map(Response::new)
+        .map_err(into_status)
+}
+
+fn into_status(e: jsonrpc_core::Error) -> Status {
+    match e.code {
+        ErrorCode::InvalidParams | ErrorCode::ParseError | ErrorCode::InvalidRequest => {
+            Status::invalid_argument(e.message)
+        }
+        ErrorCode::MethodNotFound => Status::unimplemented(e.message),
+        _ => Status::internal(e.message),
+    }
+}
+
+impl From<event::NodeEvent> for rings_node::NodeEvent {
+    fn from(e: event::NodeEvent) -> Self {
+        use rings_node::node_event::Event;
+
+        let event = match e {
+            event::NodeEvent::ConnectionStateChange { peer, state } => {
+                Event::ConnectionStateChange(ConnectionStateChangeEvent { peer, state })
+            }
+            event::NodeEvent::CustomMessage { from, data } => {
+                Event::CustomMessage(CustomMessageEvent { from, data })
+            }
+            event::NodeEvent::BackendMessage { from, message } => {
+                Event::BackendMessage(BackendMessageEvent {
+                    from,
+                    data: serde_json::to_string(&message).unwrap_or_default(),
+                })
+            }
+            event::NodeEvent::TopicMessage { topic, data } => {
+                Event::TopicMessage(TopicMessageEvent { topic, data })
+            }
+            event::NodeEvent::Stabilization {
+                successors,
+                predecessor,
+            } => Event::Stabilization(StabilizationEvent {
+                successors,
+                predecessor,
+            }),
+            event::NodeEvent::SessionRenewed {
+                peer,
+                session,
+                expires_at,
+            } => Event::SessionRenewed(SessionRenewedEvent {
+                peer,
+                session,
+                expires_at,
+            }),
+        };
+        Self { event: Some(event) }
+    }
+}
+
+#[tonic::async_trait]
+impl InternalService for InternalGrpcService {
+    type SubscribeEventsStream = GrpcStream<rings_node::NodeEvent>;
+    type SubscribeTopicStream = GrpcStream<TopicMessageEvent>;
+
+    async fn connect_peer_via_http(
+        &self,
+        req: Request<ConnectPeerViaHttpRequest>,
+    ) -> Result<Response<ConnectPeerViaHttpResponse>, Status> {
+        self.handle(req, Method::ConnectPeerViaHttp).await
+    }
+
+    async fn connect_with_did(
+        &self,
+        req: Request<ConnectWithDidRequest>,
+    ) -> Result<Response<ConnectWithDidResponse>, Status> {
+        self.handle(req, Method::ConnectWithDid).await
+    }
+
+    async fn connect_with_seed(
+        &self,
+        req: Request<ConnectWithSeedRequest>,
+    ) -> Result<Response<ConnectWithSeedResponse>, Status> {
+        self.handle(req, Method::ConnectWithSeed).await
+    }
+
+    async fn list_peers(
+        &self,
+        req: Request<ListPeersRequest>,
+    ) -> Result<Response<ListPeersResponse>, Status> {
+        self.handle(req, Method::ListPeers).await
+    }
+
+    async fn create_offer(
+        &self,
+        req: Request<CreateOfferRequest>,
+    ) -> Result<Response<CreateOfferResponse>, Status> {
+        self.handle(req, Method::CreateOffer).await
+    }
+
+    async fn answer_offer(
+        &self,
+        req: Request<AnswerOfferRequest>,
+    ) -> Result<Response<AnswerOfferResponse>, Status> {
+        self.handle(req, Method::AnswerOffer).await
+    }
+
+    async fn accept_answer(
+        &self,
+        req: Request<AcceptAnswerRequest>,
+    ) -> Result<Response<AcceptAnswerResponse>, Status> {
+        self.handle(req, Method::AcceptAnswer).await
+    }
+
+    async fn disconnect(
+        &self,
+        req: Request<DisconnectRequest>,
+    ) -> Result<Response<DisconnectResponse>, Status> {
+        self.handle(req, Method::Disconnect).await
+    }
+
+    async fn send_custom_message(
+        &self,
+        req: Request<SendCustomMessageRequest>,
+    ) -> Result<Response<SendCustomMessageResponse>, Status> {
+        self.handle(req, Method::SendCustomMessage).await
+    }
+
+    async fn send_backend_message(
+        &self,
+        req: Request<SendBackendMessageRequest>,
+    ) -> Result<Response<SendBackendMessageResponse>, Status> {
+        self.handle(req, Method::SendBackendMessage).await
+    }
+
+    async fn publish_message_to_topic(
+        &self,
+        req: Request<PublishMessageToTopicRequest>,
+    ) -> Result<Response<PublishMessageToTopicResponse>, Status> {
+        self.handle(req, Method::PublishMessageToTopic).await
+    }
+
+    async fn fetch_topic_messages(
+        &self,
+        req: Request<FetchTopicMessagesRequest>,
+    ) -> Result<Response<FetchTopicMessagesResponse>, Status> {
+        self.handle(req, Method::FetchTopicMessages).await
+    }
+
+    async fn register_service(
+        &self,
+        req: Request<RegisterServiceRequest>,
+    ) -> Result<Response<RegisterServiceResponse>, Status> {
+        self.handle(req, Method::RegisterService).await
+    }
+
+    async fn lookup_service(
+        &self,
+        req: Request<LookupServiceRequest>,
+    ) -> Result<Response<LookupServiceResponse>, Status> {
+        self.handle(req, Method::LookupService).await
+    }
+
+    async fn node_info(
+        &self,
+        req: Request<NodeInfoRequest>,
+    ) -> Result<Response<NodeInfoResponse>, Status> {
+        self.handle(req, Method::NodeInfo).await
+    }
+
+    async fn node_did(
+        &self,
+        req: Request<NodeDidRequest>,
+    ) -> Result<Response<NodeDidResponse>, Status> {
+        self.handle(req, Method::NodeDid).await
+    }
+
+    async fn list_snark_tasks(
+        &self,
+        req: Request<ListSnarkTasksRequest>,
+    ) -> Result<Response<ListSnarkTasksResponse>, Status> {
+        self.handle(req, Method::ListSnarkTasks).await
+    }
+
+    async fn get_snark_task(
+        &self,
+        req: Request<GetSnarkTaskRequest>,
+    ) -> Result<Response<GetSnarkTaskResponse>, Status> {
+        self.handle(req, Method::GetSnarkTask).await
+    }
+
+    async fn get_snark_proof(
+        &self,
+        req: Request<GetSnarkProofRequest>,
+    ) -> Result<Response<GetSnarkProofResponse>, Status> {
+        self.handle(req, Method::GetSnarkProof).await
+    }
+
+    async fn revoke_session(
+        &self,
+        req: Request<RevokeSessionRequest>,
+    ) -> Result<Response<RevokeSessionResponse>, Status> {
+        self.handle(req, Method::RevokeSession).await
+    }
+
+    async fn resolve_did(
+        &self,
+        req: Request<ResolveDidRequest>,
+    ) -> Result<Response<ResolveDidResponse>, Status> {
+        self.handle(req, Method::ResolveDid).await
+    }
+
+    async fn link_device(
+        &self,
+        req: Request<LinkDeviceRequest>,
+    ) -> Result<Response<LinkDeviceResponse>, Status> {
+        self.handle(req, Method::LinkDevice).await
+    }
+
+    async fn resolve_account(
+        &self,
+        req: Request<ResolveAccountRequest>,
+    ) -> Result<Response<ResolveAccountResponse>, Status> {
+        self.handle(req, Method::ResolveAccount).await
+    }
+
+    async fn send_account_message(
+        &self,
+        req: Request<SendAccountMessageRequest>,
+    ) -> Result<Response<SendAccountMessageResponse>, Status> {
+        self.handle(req, Method::SendAccountMessage).await
+    }
+
+    async fn subscribe_events(
+        &self,
+        req: Request<SubscribeEventsRequest>,
+    ) -> Result<Response<Self::SubscribeEventsStream>, Status> {
+        self.authorize(&req, Scope::Read)?;
+        let req = req.into_inner();
+        let events = req
+            .events
+            .into_iter()
+            .map(|e| {
+                serde_json::from_value(serde_json::Value::String(e.clone()))
+                    .map_err(|_| Status::invalid_argument(format!("unknown event {}", e)))
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+        let filter = EventFilter {
+            events,
+            peers: req.peers,
+            topics: vec![],
+        };
+
+        let mut rx = self.events.subscribe();
+        let stream = async_stream::stream! {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        if filter.matches(&event) {
+                            yield Ok(event.into());
+                        }
+                    }
+                    Err(RecvError::Lagged(n)) => {
+                        tracing::warn!("grpc subscriber lagged, {} events skipped", n);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        };
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn subscribe_topic(
+        &self,
+        req: Request<SubscribeTopicRequest>,
+    ) -> Result<Response<Self::SubscribeTopicStream>, Status> {
+        self.authorize(&req, Scope::Read)?;
+        let SubscribeTopicRequest { topic, mut skip } = req.into_inner();
+
+        let processor = self.processor.clone();
+        let stream = async_stream::stream! {
+            let mut ticker = tokio::time::interval(Duration::from_secs(TOPIC_POLL_INTERVAL));
+            loop {
+                ticker.tick().await;
+                let data = match fetch_topic_messages(&processor, &topic, skip).await {
+                    Ok(data) => data,
+                    Err(e) => {
+                        yield Err(into_status(e));
+                        break;
+                    }
+                };
+                skip += data.len() as i64;
+                for data in data {
+                    yield Ok(TopicMessageEvent {
+                        topic: topic.clone(),
+                        data,
+                    });
+                }
+            }
+        };
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+#[tonic::async_trait]
+impl ExternalService for ExternalGrpcService {
+    async fn answer_offer(
+        &self,
+        req: Request<AnswerOfferRequest>,
+    ) -> Result<Response<AnswerOfferResponse>, Status> {
+        handle_rpc(&self.processor, req).await
+    }
+
+    async fn node_info(
+        &self,
+        req: Request<NodeInfoRequest>,
+    ) -> Result<Response<NodeInfoResponse>, Status> {
+        handle_rpc(&self.processor, req).await
+    }
+
+    async fn node_did(
+        &self,
+        req: Request<NodeDidRequest>,
+    ) -> Result<Response<NodeDidResponse>, Status> {
+        handle_rpc(&self.processor, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rings_rpc::grpc::connect_internal;
+    use rings_rpc::grpc::InternalClient;
+    use rings_rpc::protos::rings_node::node_event::Event;
+
+    use super::*;
+    use crate::native::endpoint::auth::ApiToken;
+    use crate::tests::native::prepare_processor;
+
+    const PEER: &str = "0x11e807fcc88dd319270493fb2e822e388fe36ab0";
+
+    async fn serve(events: EventHub) -> (Arc<Processor>, String) {
+        let processor = Arc::new(prepare_processor().await);
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let auth = ApiAuthConfig {
+            tokens: vec![ApiToken {
+                token: "reader".to_string(),
+                scopes: vec![Scope::Read],
+            }],
+            signers: vec![],
+            origins: vec![],
+        };
+        tokio::spawn(run_internal_grpc_api(addr, processor.clone(), events, auth));
+        (processor, format!("http://{}", addr))
+    }
+
+    async fn connect(url: &str, token: Option<&str>) -> InternalClient {
+        for _ in 0..50 {
+            if let Ok(client) = connect_internal(url, token.map(|t| t.to_string())).await {
+                return client;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("failed to connect grpc server {}", url);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_request() {
+        let (processor, url) = serve(EventHub::new()).await;
+
+        let mut client = connect(&url, Some("reader")).await;
+        let resp = client
+            .node_did(NodeDidRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(resp.did, processor.did().to_string());
+
+        let status = client
+            .disconnect(DisconnectRequest { did: resp.did })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        let mut anonymous = connect(&url, None).await;
+        let status = anonymous.node_did(NodeDidRequest {}).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_subscribe_events() {
+        let events = EventHub::new();
+        let (_, url) = serve(events.clone()).await;
+
+        let mut client = connect(&url, Some("reader")).await;
+        let mut stream = client
+            .subscribe_events(SubscribeEventsRequest {
+                events: vec![
+                    "connection_state_change".to_string(),
+                    "session_renewed".to_string(),
+                ],
+                peers: vec![],
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        events.publish(event::NodeEvent::Stabilization {
+            successors: vec![PEER.to_string()],
+            predecessor: None,
+        });
+        events.publish(event::NodeEvent::ConnectionStateChange {
+            peer: PEER.to_string(),
+            state: "Connected".to_string(),
+        });
+        events.publish(event::NodeEvent::SessionRenewed {
+            peer: PEER.to_string(),
+            session: PEER.to_string(),
+            expires_at: 42,
+        });
+
+        let event = stream.message().await.unwrap().unwrap().event;
+        assert!(matches!(
+            event,
+            Some(Event::ConnectionStateChange(ConnectionStateChangeEvent { peer, state }))
+                if peer == PEER && state == "Connected"
+        ));
+        let event = stream.message().await.unwrap().unwrap().event;
+        assert!(matches!(
+            event,
+            Some(Event::SessionRenewed(SessionRenewedEvent {
+                expires_at: 42,
+                ..
+            }))
+        ));
+    }
+}
@@ -5,6 +5,9 @@ use axum::response::Response;
 #[derive(Debug)]
 pub enum HttpError {
     BadRequest,
+    Unauthorized,
+    Forbidden,
+    UnsupportedMediaType,
     Internal,
 }
 
@@ -12,6 +15,11 @@ impl IntoResponse for HttpError {
     fn into_response(self) -> Response {
         let (code, msg) = match self {
             HttpError::BadRequest => (StatusCode::BAD_REQUEST, "Bad Request"),
+            HttpError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
+            HttpError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden"),
+            HttpError::UnsupportedMediaType => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, "Unsupported Media Type")
+            }
             HttpError::Internal => (StatusCode::INTERNAL_SERVER_ERROR, "Internal error"),
         };
 
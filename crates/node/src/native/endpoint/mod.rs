@@ -1,5 +1,7 @@
 //! rings-node service run with `Swarm` and chord stabilization.
 #![warn(missing_docs)]
+pub mod auth;
+mod grpc;
 mod http_error;
 mod ws;
 
@@ -7,17 +9,38 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use axum::extract::ConnectInfo;
+use axum::extract::Query;
 use axum::extract::State;
 use axum::extract::WebSocketUpgrade;
+use axum::http::header::AUTHORIZATION;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::HeaderMap;
+use axum::http::HeaderName;
+use axum::http::HeaderValue;
+use axum::http::Uri;
 use axum::response::IntoResponse;
 use axum::routing::get;
 use axum::routing::post;
 use axum::Router;
 use jsonrpc_core::MetaIoHandler;
+use rings_rpc::auth::Scope;
+use rings_rpc::auth::HEADER_NONCE;
+use rings_rpc::auth::HEADER_SIGNATURE;
+use rings_rpc::auth::HEADER_SIGNER;
+use rings_rpc::auth::HEADER_TIMESTAMP;
 use rings_rpc::protos::rings_node::NodeInfoResponse;
+use serde::Deserialize;
+use tower_http::cors::AllowOrigin;
 use tower_http::cors::CorsLayer;
 
+use self::auth::required_scopes;
+use self::auth::ApiAuth;
+use self::auth::ApiAuthConfig;
+use self::auth::RequestParts;
+pub use self::grpc::run_external_grpc_api;
+pub use self::grpc::run_internal_grpc_api;
 use self::http_error::HttpError;
+use crate::native::event::EventHub;
 use crate::processor::Processor;
 
 /// JSON-RPC state
@@ -27,39 +50,58 @@ where M: jsonrpc_core::Middleware<Arc<Processor>>
 {
     processor: Arc<Processor>,
     io_handler: MetaIoHandler<Arc<Processor>, M>,
+    auth: Arc<ApiAuth>,
 }
 
 /// websocket state
 #[derive(Clone)]
-#[allow(dead_code)]
 pub struct WsState {
     processor: Arc<Processor>,
+    events: EventHub,
+    auth: Arc<ApiAuth>,
 }
 
 /// Status state
 #[derive(Clone)]
 pub struct StatusState {
     processor: Arc<Processor>,
+    auth: Arc<ApiAuth>,
 }
 
 struct ExternalRpcMiddleware;
 struct InternalRpcMiddleware;
 
-/// Run a web server to handle jsonrpc request locally
-pub async fn run_internal_api(port: u16, processor: Arc<Processor>) -> anyhow::Result<()> {
-    let binding_addr = SocketAddr::from(([127, 0, 0, 1], port));
+/// Run a web server to handle jsonrpc request locally.
+/// Binding to a non-loopback address is refused if authentication is disabled.
+pub async fn run_internal_api(
+    binding_addr: SocketAddr,
+    processor: Arc<Processor>,
+    events: EventHub,
+    auth: ApiAuthConfig,
+) -> anyhow::Result<()> {
+    if !binding_addr.ip().is_loopback() && !auth.is_enabled() {
+        anyhow::bail!(
+            "Refuse to bind internal api to non-loopback address {} without authentication",
+            binding_addr
+        );
+    }
+    let cors = internal_cors(&auth.origins);
+    let auth = Arc::new(ApiAuth::from(auth));
 
     let jsonrpc_handler = MetaIoHandler::with_middleware(InternalRpcMiddleware);
     let jsonrpc_state = Arc::new(JsonRpcState {
         processor: processor.clone(),
         io_handler: jsonrpc_handler,
+        auth: auth.clone(),
     });
 
     let ws_state = Arc::new(WsState {
         processor: processor.clone(),
+        events,
+        auth: auth.clone(),
     });
 
-    let status_state = Arc::new(StatusState { processor });
+    let status_state = Arc::new(StatusState { processor, auth });
 
     let axum_make_service = Router::new()
         .route(
@@ -67,8 +109,15 @@ pub async fn run_internal_api(port: u16, processor: Arc<Processor>) -> anyhow::R
             post(jsonrpc_io_handler).with_state(jsonrpc_state.clone()),
         )
         .route("/ws", get(ws_handler).with_state(ws_state))
-        .route("/status", get(status_handler).with_state(status_state))
-        .layer(CorsLayer::permissive())
+        .route(
+            "/status",
+            get(status_handler).with_state(status_state.clone()),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            status_state.auth.clone(),
+            reject_cross_site,
+        ))
+        .layer(cors)
         .layer(axum::middleware::from_fn(node_info_header))
         .into_make_service_with_connect_info::<SocketAddr>();
 
@@ -88,9 +137,13 @@ pub async fn run_external_api(addr: String, processor: Arc<Processor>) -> anyhow
     let jsonrpc_state = Arc::new(JsonRpcState {
         processor: processor.clone(),
         io_handler: jsonrpc_handler,
+        auth: Default::default(),
     });
 
-    let status_state = Arc::new(StatusState { processor });
+    let status_state = Arc::new(StatusState {
+        processor,
+        auth: Default::default(),
+    });
 
     let axum_make_service = Router::new()
         .route(
@@ -111,11 +164,22 @@ pub async fn run_external_api(addr: String, processor: Arc<Processor>) -> anyhow
 
 async fn jsonrpc_io_handler<M>(
     State(state): State<Arc<JsonRpcState<M>>>,
+    uri: Uri,
+    headers: HeaderMap,
     body: String,
 ) -> Result<JsonResponse, HttpError>
 where
     M: jsonrpc_core::Middleware<Arc<Processor>>,
 {
+    if state.auth.is_enabled() {
+        let required = required_scopes(&body)?;
+        let request = RequestParts {
+            method: "POST",
+            path: uri.path(),
+            body: &body,
+        };
+        state.auth.authorize(&headers, Some(request), &required)?;
+    }
     let r = state
         .io_handler
         .handle_request(&body, state.processor.clone())
@@ -124,6 +188,42 @@ where
     Ok(JsonResponse(r))
 }
 
+/// Cors of internal api, only the configured origins are allowed to read responses
+/// and to send requests which need preflight.
+fn internal_cors(origins: &[String]) -> CorsLayer {
+    let origins = origins
+        .iter()
+        .filter_map(|o| {
+            HeaderValue::from_str(o)
+                .map_err(|_| tracing::warn!("Ignore invalid origin {:?}", o))
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([http::Method::GET, http::Method::POST])
+        .allow_headers([
+            CONTENT_TYPE,
+            AUTHORIZATION,
+            HeaderName::from_static(HEADER_SIGNER),
+            HeaderName::from_static(HEADER_TIMESTAMP),
+            HeaderName::from_static(HEADER_NONCE),
+            HeaderName::from_static(HEADER_SIGNATURE),
+        ])
+}
+
+/// Requests of internal api can change the node, they are rejected if they may be sent by
+/// web pages of other sites, whether authentication is enabled or not.
+/// Websocket is also checked here, since it's not restricted by CORS.
+async fn reject_cross_site<B>(
+    State(auth): State<Arc<ApiAuth>>,
+    req: http::Request<B>,
+    next: axum::middleware::Next<B>,
+) -> Result<axum::response::Response, HttpError> {
+    auth.check_cross_site(req.method(), req.headers())?;
+    Ok(next.run(req).await)
+}
+
 async fn node_info_header<B>(
     req: http::Request<B>,
     next: axum::middleware::Next<B>,
@@ -139,7 +239,17 @@ async fn node_info_header<B>(
 
 async fn status_handler(
     State(state): State<Arc<StatusState>>,
+    uri: Uri,
+    headers: HeaderMap,
 ) -> Result<axum::Json<NodeInfoResponse>, HttpError> {
+    let request = RequestParts {
+        method: "GET",
+        path: uri.path(),
+        body: "",
+    };
+    state
+        .auth
+        .authorize(&headers, Some(request), &[Scope::Read])?;
     let info = state
         .processor
         .get_node_info()
@@ -158,13 +268,36 @@ impl IntoResponse for JsonResponse {
     }
 }
 
+/// Query of websocket request
+#[derive(Debug, Deserialize)]
+struct WsQuery {
+    /// Bearer token, browsers can't set `Authorization` header of websocket
+    token: Option<String>,
+}
+
 async fn ws_handler(
     State(state): State<Arc<WsState>>,
+    uri: Uri,
+    mut headers: HeaderMap,
+    Query(query): Query<WsQuery>,
     ws: WebSocketUpgrade,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, HttpError> {
+    if let Some(token) = query.token {
+        let bearer = HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(|_| HttpError::BadRequest)?;
+        headers.entry(AUTHORIZATION).or_insert(bearer);
+    }
+    let request = RequestParts {
+        method: "GET",
+        path: uri.path(),
+        body: "",
+    };
+    state
+        .auth
+        .authorize(&headers, Some(request), &[Scope::Read])?;
     tracing::info!("ws connected, remote: {}", addr);
-    ws.on_upgrade(move |socket| self::ws::handle_socket(state, socket))
+    Ok(ws.on_upgrade(move |socket| self::ws::handle_socket(state, socket)))
 }
 
 mod jsonrpc_middleware_impl {
@@ -1,25 +1,137 @@
+//! Websocket event stream.
+//!
+//! Client subscribes events by sending a text frame like
+//! `{"type": "subscribe", "events": ["connection_state_change"], "peers": [], "topics": ["news"]}`,
+//! a later subscribe request replaces the previous one. Each matched [NodeEvent] is sent as a
+//! json text frame. Nothing is sent before the first subscribe request.
+//!
+//! Browsers can't set `Authorization` header of websocket, so the bearer token can also be sent
+//! as query like `/ws?token=<token>`.
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
+use axum::extract::ws::Message;
 use axum::extract::ws::WebSocket;
+use futures::stream::SplitSink;
+use futures::SinkExt;
 use futures::StreamExt;
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
 
 use super::WsState;
+use crate::consts::TOPIC_POLL_INTERVAL;
+use crate::native::event::fetch_topic_messages;
+use crate::native::event::EventFilter;
+use crate::native::event::NodeEvent;
+
+/// Request sent by websocket client
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsRequest {
+    Subscribe(EventFilter),
+}
 
 /// Actual websocket statemachine (one will be spawned per connection)
-pub async fn handle_socket(_ws_state: Arc<WsState>, socket: WebSocket) {
-    let (_, mut receiver) = socket.split();
+pub async fn handle_socket(ws_state: Arc<WsState>, socket: WebSocket) {
+    let (mut sender, mut receiver) = socket.split();
+    let (filter_tx, mut filter_rx) = watch::channel::<Option<EventFilter>>(None);
+    let mut events = ws_state.events.subscribe();
+    let processor = ws_state.processor.clone();
 
     let mut send_task = tokio::spawn(async move {
+        let mut cnt = 0;
+        // topic -> count of messages already seen
+        let mut topics: HashMap<String, i64> = HashMap::new();
+        let mut ticker = tokio::time::interval(Duration::from_secs(TOPIC_POLL_INTERVAL));
+
         loop {
-            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            let poll_topics = tokio::select! {
+                r = filter_rx.changed() => {
+                    if r.is_err() {
+                        break;
+                    }
+                    true
+                }
+                _ = ticker.tick() => true,
+                r = events.recv() => match r {
+                    Ok(event) => {
+                        let matched = filter_rx
+                            .borrow()
+                            .as_ref()
+                            .map(|f| f.matches(&event))
+                            .unwrap_or(false);
+                        if matched {
+                            if !send_event(&mut sender, &event).await {
+                                break;
+                            }
+                            cnt += 1;
+                        }
+                        false
+                    }
+                    Err(RecvError::Lagged(n)) => {
+                        tracing::warn!("ws subscriber lagged, {} events skipped", n);
+                        false
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            };
+            if !poll_topics {
+                continue;
+            }
+
+            let Some(filter) = filter_rx.borrow().clone() else {
+                continue;
+            };
+            topics.retain(|t, _| filter.topics.contains(t));
+            for topic in filter.topics.iter() {
+                let skip = topics.get(topic).copied();
+                let data = match fetch_topic_messages(&processor, topic, skip.unwrap_or(0)).await {
+                    Ok(data) => data,
+                    Err(e) => {
+                        tracing::warn!("fetch messages of topic {} failed: {:?}", topic, e);
+                        continue;
+                    }
+                };
+                topics.insert(topic.clone(), skip.unwrap_or(0) + data.len() as i64);
+                // Messages published before subscribing are not sent
+                if skip.is_none() {
+                    continue;
+                }
+                for data in data {
+                    let event = NodeEvent::TopicMessage {
+                        topic: topic.clone(),
+                        data,
+                    };
+                    if !filter.matches(&event) {
+                        continue;
+                    }
+                    if !send_event(&mut sender, &event).await {
+                        return cnt;
+                    }
+                    cnt += 1;
+                }
+            }
         }
+        cnt
     });
     let mut recv_task = tokio::spawn(async move {
         let mut cnt = 0;
         while let Some(Ok(msg)) = receiver.next().await {
             cnt += 1;
-            // print message and break if instructed to do so
             tracing::debug!("recv message: {:?}", msg);
+            let Message::Text(text) = msg else {
+                continue;
+            };
+            match serde_json::from_str::<WsRequest>(&text) {
+                Ok(WsRequest::Subscribe(filter)) => {
+                    if filter_tx.send(Some(filter)).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => tracing::warn!("invalid ws request: {:?}", e),
+            }
         }
         cnt
     });
@@ -42,3 +154,15 @@ pub async fn handle_socket(_ws_state: Arc<WsState>, socket: WebSocket) {
     }
     tracing::info!("WS over");
 }
+
+/// Send an event as json text frame, return false if the socket is closed
+async fn send_event(sender: &mut SplitSink<WebSocket, Message>, event: &NodeEvent) -> bool {
+    let text = match serde_json::to_string(event) {
+        Ok(text) => text,
+        Err(e) => {
+            tracing::error!("serialize event failed: {:?}", e);
+            return true;
+        }
+    };
+    sender.send(Message::Text(text)).await.is_ok()
+}
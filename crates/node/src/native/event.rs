@@ -0,0 +1,328 @@
+//! Events of a native node, which are streamed to websocket clients.
+//!
+//! [EventCallback] wraps the [SwarmCallback] of node, and publishes connection state changes
+//! and inbound messages to an [EventHub]. [EventHub::watch_stabilization] publishes the changes of
+//! successors and predecessor after stabilization. Topic messages are polled per subscriber, see
+//! [crate::native::endpoint].
+#![warn(missing_docs)]
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rings_core::inspect::DHTInspect;
+use rings_core::message::CustomMessage;
+use rings_core::message::Message;
+use rings_core::message::MessagePayload;
+use rings_core::message::MessageVerificationExt;
+use rings_core::swarm::callback::SharedSwarmCallback;
+use rings_core::swarm::callback::SwarmCallback;
+use rings_core::swarm::callback::SwarmEvent;
+use rings_rpc::protos::rings_node::FetchTopicMessagesRequest;
+use rings_rpc::protos::rings_node_handler::HandleRpc;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::backend::types::BackendMessage;
+use crate::consts::EVENT_CHANNEL_CAPACITY;
+use crate::processor::Processor;
+
+type CallbackError = Box<dyn std::error::Error>;
+
+/// Event of node
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum NodeEvent {
+    /// Connection state of a peer changed
+    ConnectionStateChange {
+        /// did of peer
+        peer: String,
+        /// state of connection
+        state: String,
+    },
+    /// A custom message which is not a backend message received
+    CustomMessage {
+        /// did of sender
+        from: String,
+        /// base64 encoded message
+        data: String,
+    },
+    /// A backend message received
+    BackendMessage {
+        /// did of sender
+        from: String,
+        /// message
+        message: BackendMessage,
+    },
+    /// New message of a subscribed topic
+    TopicMessage {
+        /// topic name
+        topic: String,
+        /// message
+        data: String,
+    },
+    /// Successors or predecessor changed after stabilization
+    Stabilization {
+        /// successors of node
+        successors: Vec<String>,
+        /// predecessor of node
+        predecessor: Option<String>,
+    },
+    /// A connected peer renewed its session
+    SessionRenewed {
+        /// did of peer
+        peer: String,
+        /// did of the new session
+        session: String,
+        /// timestamp in milliseconds when the new session expires
+        expires_at: u64,
+    },
+}
+
+/// Kind of [NodeEvent], used by subscribe filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// [NodeEvent::ConnectionStateChange]
+    ConnectionStateChange,
+    /// [NodeEvent::CustomMessage]
+    CustomMessage,
+    /// [NodeEvent::BackendMessage]
+    BackendMessage,
+    /// [NodeEvent::TopicMessage]
+    TopicMessage,
+    /// [NodeEvent::Stabilization]
+    Stabilization,
+    /// [NodeEvent::SessionRenewed]
+    SessionRenewed,
+}
+
+/// Filter of events, sent by subscriber
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventFilter {
+    /// kinds of event to receive, all kinds are received if empty
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+    /// only receive events of these peers if not empty
+    #[serde(default)]
+    pub peers: Vec<String>,
+    /// topics to subscribe
+    #[serde(default)]
+    pub topics: Vec<String>,
+}
+
+impl NodeEvent {
+    /// Kind of event
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::ConnectionStateChange { .. } => EventKind::ConnectionStateChange,
+            Self::CustomMessage { .. } => EventKind::CustomMessage,
+            Self::BackendMessage { .. } => EventKind::BackendMessage,
+            Self::TopicMessage { .. } => EventKind::TopicMessage,
+            Self::Stabilization { .. } => EventKind::Stabilization,
+            Self::SessionRenewed { .. } => EventKind::SessionRenewed,
+        }
+    }
+
+    /// Did of remote peer which the event related to
+    pub fn peer(&self) -> Option<&str> {
+        match self {
+            Self::ConnectionStateChange { peer, .. } => Some(peer),
+            Self::CustomMessage { from, .. } => Some(from),
+            Self::BackendMessage { from, .. } => Some(from),
+            Self::SessionRenewed { peer, .. } => Some(peer),
+            _ => None,
+        }
+    }
+}
+
+impl EventFilter {
+    /// Check if the event should be sent to subscriber
+    pub fn matches(&self, event: &NodeEvent) -> bool {
+        if !self.events.is_empty() && !self.events.contains(&event.kind()) {
+            return false;
+        }
+        if let NodeEvent::TopicMessage { topic, .. } = event {
+            return self.topics.contains(topic);
+        }
+        match event.peer() {
+            Some(peer) if !self.peers.is_empty() => {
+                self.peers.iter().any(|p| p.eq_ignore_ascii_case(peer))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Hub of node events, which is a broadcast channel
+#[derive(Clone)]
+pub struct EventHub {
+    sender: broadcast::Sender<NodeEvent>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventHub {
+    /// Create a new event hub
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// Publish an event, it's dropped if there is no subscriber
+    pub fn publish(&self, event: NodeEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribe events published after this call
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Inspect dht of node periodically, and publish [NodeEvent::Stabilization]
+    /// when successors or predecessor changed.
+    pub async fn watch_stabilization(&self, processor: Arc<Processor>, interval: Duration) {
+        let mut last: Option<(Vec<String>, Option<String>)> = None;
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let dht = DHTInspect::inspect(&processor.swarm.dht());
+            let current = (dht.successors, dht.predecessor);
+            if last.as_ref() != Some(&current) {
+                self.publish(NodeEvent::Stabilization {
+                    successors: current.0.clone(),
+                    predecessor: current.1.clone(),
+                });
+                last = Some(current);
+            }
+        }
+    }
+}
+
+/// A [SwarmCallback] which publishes events to [EventHub], then calls the inner callback.
+pub struct EventCallback {
+    inner: SharedSwarmCallback,
+    events: EventHub,
+}
+
+impl EventCallback {
+    /// Wrap a callback with event hub
+    pub fn new(inner: SharedSwarmCallback, events: EventHub) -> Self {
+        Self { inner, events }
+    }
+}
+
+#[async_trait]
+impl SwarmCallback for EventCallback {
+    async fn on_validate(&self, payload: &MessagePayload) -> Result<(), CallbackError> {
+        self.inner.on_validate(payload).await
+    }
+
+    async fn on_inbound(&self, payload: &MessagePayload) -> Result<(), CallbackError> {
+        if let Ok(Message::CustomMessage(CustomMessage(msg))) = payload.transaction.data() {
+            let from = payload.transaction.signer().to_string();
+            let event = match bincode::deserialize::<BackendMessage>(&msg) {
+                Ok(message) => NodeEvent::BackendMessage { from, message },
+                Err(_) => NodeEvent::CustomMessage {
+                    from,
+                    data: base64::encode(&msg),
+                },
+            };
+            self.events.publish(event);
+        }
+        self.inner.on_inbound(payload).await
+    }
+
+    async fn on_event(&self, event: &SwarmEvent) -> Result<(), CallbackError> {
+        match event {
+            SwarmEvent::ConnectionStateChange { peer, state } => {
+                self.events.publish(NodeEvent::ConnectionStateChange {
+                    peer: peer.to_string(),
+                    state: format!("{:?}", state),
+                })
+            }
+            SwarmEvent::SessionRenewed { peer, session } => {
+                self.events.publish(NodeEvent::SessionRenewed {
+                    peer: peer.to_string(),
+                    session: session.session_id().to_string(),
+                    expires_at: session.expires_at().try_into().unwrap_or(u64::MAX),
+                })
+            }
+            _ => {}
+        }
+        self.inner.on_event(event).await
+    }
+}
+
+/// Fetch messages of topic, skipping the first `skip` messages
+pub async fn fetch_topic_messages(
+    processor: &Processor,
+    topic: &str,
+    skip: i64,
+) -> jsonrpc_core::Result<Vec<String>> {
+    let resp = processor
+        .handle_rpc(FetchTopicMessagesRequest {
+            topic: topic.to_string(),
+            skip,
+        })
+        .await?;
+    Ok(resp.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_filter() {
+        let peer = "0x11e807fcc88dd319270493fb2e822e388fe36ab0".to_string();
+        let connected = NodeEvent::ConnectionStateChange {
+            peer: peer.clone(),
+            state: "Connected".to_string(),
+        };
+        let topic = NodeEvent::TopicMessage {
+            topic: "news".to_string(),
+            data: "hello".to_string(),
+        };
+        let stabilization = NodeEvent::Stabilization {
+            successors: vec![],
+            predecessor: None,
+        };
+
+        let filter = EventFilter::default();
+        assert!(filter.matches(&connected));
+        assert!(filter.matches(&stabilization));
+        assert!(!filter.matches(&topic));
+
+        let filter: EventFilter = serde_json::from_str(
+            r#"{"events": ["connection_state_change", "topic_message"], "topics": ["news"]}"#,
+        )
+        .unwrap();
+        assert!(filter.matches(&connected));
+        assert!(filter.matches(&topic));
+        assert!(!filter.matches(&stabilization));
+
+        let filter = EventFilter {
+            peers: vec!["0x0000000000000000000000000000000000000001".to_string()],
+            ..Default::default()
+        };
+        assert!(!filter.matches(&connected));
+        assert!(filter.matches(&stabilization));
+    }
+
+    #[test]
+    fn test_event_serialize() {
+        let event = NodeEvent::TopicMessage {
+            topic: "news".to_string(),
+            data: "hello".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"topic_message","topic":"news","data":"hello"}"#
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! Encrypted keystore of the secrets of node.
+//!
+//! The keystore is a JSON file similar to Ethereum keystores. Secrets are encrypted by
+//! AES-256-GCM with a key derived from password by Argon2id:
+//! ```json
+//! {
+//!   "version": 1,
+//!   "id": "5e0c0c8e-...",
+//!   "crypto": {
+//!     "cipher": "aes-256-gcm",
+//!     "ciphertext": "<base64>",
+//!     "nonce": "<base64>",
+//!     "kdf": "argon2id",
+//!     "kdfparams": { "m_cost": 19456, "t_cost": 2, "p_cost": 1, "salt": "<base64>" }
+//!   }
+//! }
+//! ```
+//!
+//! A config refers to the keystore by `keystore` field. The password is read from
+//! [KEYSTORE_PASSWORD_ENV], or prompted if the variable is not set.
+#![warn(missing_docs)]
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::Aead;
+use aes_gcm::aead::KeyInit;
+use aes_gcm::aead::OsRng;
+use aes_gcm::Aes256Gcm;
+use aes_gcm::Nonce;
+use argon2::Algorithm;
+use argon2::Argon2;
+use argon2::Block;
+use argon2::Params;
+use argon2::Version;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::error::Result;
+use crate::prelude::rings_core::ecc::SecretKey;
+use crate::util::ensure_parent_dir;
+use crate::util::expand_home;
+
+/// Environment variable of keystore password.
+pub const KEYSTORE_PASSWORD_ENV: &str = "RINGS_KEYSTORE_PASSWORD";
+
+const KEYSTORE_VERSION: u32 = 1;
+const CIPHER: &str = "aes-256-gcm";
+const KDF: &str = "argon2id";
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Max memory size of Argon2id in KiB, so that a crafted keystore can't exhaust memory.
+const MAX_M_COST: u32 = 1 << 20;
+/// Max number of iterations of Argon2id, so that a crafted keystore can't hang the node.
+const MAX_T_COST: u32 = 16;
+
+/// Secrets of node stored in keystore.
+#[derive(Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Secrets {
+    /// Secret key of account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecdsa_key: Option<SecretKey>,
+    /// Dump of session sk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_sk: Option<String>,
+}
+
+// Secrets should never be printed to log.
+impl std::fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Secrets")
+            .field("ecdsa_key", &self.ecdsa_key.map(|_| "<redacted>"))
+            .field(
+                "session_sk",
+                &self.session_sk.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+/// Parameters of Argon2id.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KdfParams {
+    /// Memory size in KiB.
+    pub m_cost: u32,
+    /// Number of iterations.
+    pub t_cost: u32,
+    /// Degree of parallelism.
+    pub p_cost: u32,
+    /// Base64 encoded salt.
+    pub salt: String,
+}
+
+/// Encrypted secrets and the parameters to decrypt them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CryptoParams {
+    /// Cipher of secrets, only "aes-256-gcm" is supported.
+    pub cipher: String,
+    /// Base64 encoded ciphertext of secrets in JSON.
+    pub ciphertext: String,
+    /// Base64 encoded nonce of cipher.
+    pub nonce: String,
+    /// Key derivation function of password, only "argon2id" is supported.
+    pub kdf: String,
+    /// Parameters of key derivation function.
+    pub kdfparams: KdfParams,
+}
+
+/// Keystore file of [Secrets] encrypted by password.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Keystore {
+    /// Version of keystore format.
+    pub version: u32,
+    /// Id of keystore.
+    pub id: uuid::Uuid,
+    /// Encrypted secrets.
+    pub crypto: CryptoParams,
+}
+
+fn derive_key(password: &str, kdfparams: &KdfParams) -> Result<[u8; KEY_LEN]> {
+    let salt = base64::decode(&kdfparams.salt).map_err(|_| Error::InvalidData)?;
+    if kdfparams.m_cost > MAX_M_COST || kdfparams.t_cost > MAX_T_COST {
+        return Err(Error::KeystoreError(format!(
+            "kdfparams exceed m_cost {} or t_cost {}",
+            MAX_M_COST, MAX_T_COST
+        )));
+    }
+    let params = Params::new(
+        kdfparams.m_cost,
+        kdfparams.t_cost,
+        kdfparams.p_cost,
+        Some(KEY_LEN),
+    )
+    .map_err(|e| Error::KeystoreError(e.to_string()))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut key = [0u8; KEY_LEN];
+    let mut memory = vec![Block::default(); argon2.params().block_count()];
+    argon2
+        .hash_password_into_with_memory(password.as_bytes(), &salt, &mut key, &mut memory)
+        .map_err(|e| Error::KeystoreError(e.to_string()))?;
+    Ok(key)
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+impl Keystore {
+    /// Encrypt secrets by password.
+    pub fn encrypt(secrets: &Secrets, password: &str) -> Result<Self> {
+        let kdfparams = KdfParams {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+            salt: base64::encode(random_bytes::<SALT_LEN>()),
+        };
+        let key = derive_key(password, &kdfparams)?;
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| Error::InvalidData)?;
+
+        let nonce = random_bytes::<NONCE_LEN>();
+        let plaintext = serde_json::to_vec(secrets)?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|e| Error::KeystoreError(e.to_string()))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            id: uuid::Uuid::new_v4(),
+            crypto: CryptoParams {
+                cipher: CIPHER.to_string(),
+                ciphertext: base64::encode(ciphertext),
+                nonce: base64::encode(nonce),
+                kdf: KDF.to_string(),
+                kdfparams,
+            },
+        })
+    }
+
+    /// Decrypt secrets by password.
+    pub fn decrypt(&self, password: &str) -> Result<Secrets> {
+        let crypto = &self.crypto;
+        if self.version != KEYSTORE_VERSION || crypto.cipher != CIPHER || crypto.kdf != KDF {
+            return Err(Error::KeystoreError(format!(
+                "unsupported keystore version {} with {} and {}",
+                self.version, crypto.cipher, crypto.kdf
+            )));
+        }
+        let key = derive_key(password, &crypto.kdfparams)?;
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| Error::InvalidData)?;
+
+        let nonce = base64::decode(&crypto.nonce).map_err(|_| Error::InvalidData)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(Error::InvalidData);
+        }
+        let ciphertext = base64::decode(&crypto.ciphertext).map_err(|_| Error::InvalidData)?;
+        // Authentication of AES-GCM fails if the password is wrong.
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| Error::KeystorePassword)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Replace the secrets of keystore, the id of keystore is kept.
+    pub fn reencrypt(&self, secrets: &Secrets, password: &str) -> Result<Self> {
+        Ok(Self {
+            id: self.id,
+            ..Self::encrypt(secrets, password)?
+        })
+    }
+
+    /// Encrypt the secrets again by new password.
+    pub fn change_password(&self, password: &str, new_password: &str) -> Result<Self> {
+        let secrets = self.decrypt(password)?;
+        self.reencrypt(&secrets, new_password)
+    }
+
+    /// Read keystore from file.
+    pub fn read_fs<P>(path: P) -> Result<Self>
+    where P: AsRef<Path> {
+        let path = expand_home(path)?;
+        let data = fs::read(path).map_err(|e| Error::OpenFileError(e.to_string()))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Write keystore to file, which is only accessible by its owner.
+    /// The keystore is written to a temporary file in the same directory, which is then renamed
+    /// to the path, so that the existing keystore is never left half written.
+    pub fn write_fs<P>(&self, path: P) -> Result<String>
+    where P: AsRef<Path> {
+        let path = expand_home(path)?;
+        ensure_parent_dir(&path)?;
+        let data = serde_json::to_vec_pretty(self)?;
+
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| Error::CreateFileError(format!("{} is not a file", path.display())))?;
+        let tmp = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            file_name.to_string_lossy(),
+            uuid::Uuid::new_v4()
+        ));
+        let written = write_private_file(&tmp, &data)
+            .and_then(|_| fs::rename(&tmp, &path).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&tmp);
+            return Err(Error::CreateFileError(e));
+        }
+        Ok(path.to_string_lossy().to_string())
+    }
+}
+
+/// Create a new file only accessible by its owner, write data and sync it to disk.
+fn write_private_file(path: &Path, data: &[u8]) -> std::result::Result<(), String> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| e.to_string())?;
+    file.write_all(data).map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())
+}
+
+/// Read password of keystore from [KEYSTORE_PASSWORD_ENV], or prompt for it.
+pub fn read_password(prompt: &str) -> Result<String> {
+    if let Ok(password) = std::env::var(KEYSTORE_PASSWORD_ENV) {
+        return Ok(password);
+    }
+    rpassword::prompt_password(prompt).map_err(|e| Error::KeystoreError(e.to_string()))
+}
+
+/// Read a new password of keystore from [KEYSTORE_PASSWORD_ENV], or prompt for it.
+pub fn read_new_password() -> Result<String> {
+    if let Ok(password) = std::env::var(KEYSTORE_PASSWORD_ENV) {
+        return Ok(password);
+    }
+    prompt_new_password()
+}
+
+/// Prompt for a new password of keystore twice.
+pub fn prompt_new_password() -> Result<String> {
+    let password = rpassword::prompt_password("New keystore password: ")
+        .map_err(|e| Error::KeystoreError(e.to_string()))?;
+    let confirm = rpassword::prompt_password("Repeat keystore password: ")
+        .map_err(|e| Error::KeystoreError(e.to_string()))?;
+    if password != confirm {
+        return Err(Error::KeystoreError("passwords do not match".to_string()));
+    }
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keystore_encrypt_and_decrypt() {
+        let secrets = Secrets {
+            ecdsa_key: Some(SecretKey::random()),
+            session_sk: Some("dump of session sk".to_string()),
+        };
+        let keystore = Keystore::encrypt(&secrets, "password").unwrap();
+        assert_eq!(keystore.decrypt("password").unwrap(), secrets);
+        assert!(matches!(
+            keystore.decrypt("wrong"),
+            Err(Error::KeystorePassword)
+        ));
+
+        // Secrets are not in plaintext.
+        let json = serde_json::to_string(&keystore).unwrap();
+        assert!(!json.contains("dump of session sk"));
+        assert!(!format!("{:?}", secrets).contains("dump of session sk"));
+
+        let restored: Keystore = serde_json::from_str(&json).unwrap();
+        let changed = restored.change_password("password", "new").unwrap();
+        assert_eq!(changed.id, keystore.id);
+        assert!(changed.decrypt("password").is_err());
+        assert_eq!(changed.decrypt("new").unwrap(), secrets);
+
+        // Costly kdfparams are rejected before deriving key.
+        let mut crafted = keystore.clone();
+        crafted.crypto.kdfparams.m_cost = u32::MAX;
+        assert!(matches!(
+            crafted.decrypt("password"),
+            Err(Error::KeystoreError(_))
+        ));
+        let mut crafted = keystore.clone();
+        crafted.crypto.kdfparams.t_cost = u32::MAX;
+        assert!(matches!(
+            crafted.decrypt("password"),
+            Err(Error::KeystoreError(_))
+        ));
+
+        // Keystore file is only accessible by its owner.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let name = format!("rings-keystore-{}.json", keystore.id);
+            let path = std::env::temp_dir().join(&name);
+            keystore.write_fs(&path).unwrap();
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            assert_eq!(Keystore::read_fs(&path).unwrap().id, keystore.id);
+
+            // An existing keystore is replaced as a whole, and no temporary file is left.
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            changed.write_fs(&path).unwrap();
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            let restored = Keystore::read_fs(&path).unwrap();
+            assert_eq!(restored.decrypt("new").unwrap(), secrets);
+            assert!(!fs::read_dir(std::env::temp_dir()).unwrap().any(|e| e
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(&format!(".{}", name))));
+            fs::remove_file(&path).unwrap();
+        }
+    }
+}
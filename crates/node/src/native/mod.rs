@@ -1,3 +1,9 @@
 pub mod cli;
 pub mod config;
 pub mod endpoint;
+pub mod event;
+pub mod keystore;
+#[cfg(unix)]
+pub mod signer;
+#[cfg(feature = "snark")]
+pub mod snark;
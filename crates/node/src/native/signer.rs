@@ -0,0 +1,322 @@
+//! External signer of account, which keeps the account key out of the node.
+//!
+//! [ExternalSigner] implements [AccountSigner] by asking a signer process over a local unix
+//! socket, so that `rings init`, `rings new-session`, session renewal and revocation don't need
+//! the secret key of account. [serve] is a reference implementation of the signer process,
+//! which signs with a key in its memory. A daemon backed by PKCS#11 or a hardware token can
+//! speak the same protocol.
+//!
+//! The protocol is line delimited JSON, one request and one response per connection:
+//! ```text
+//! -> {"method":"account"}
+//! <- {"result":"account","account_entity":"0x...","account_type":"secp256k1"}
+//! -> {"method":"sign","proof":"..."}
+//! <- {"result":"signature","sig":"<base64>"}
+//! <- {"result":"error","message":"..."}
+//! ```
+//!
+//! The daemon signs whatever is asked by a process that can open its socket, so the socket is
+//! only accessible by its owner.
+#![warn(missing_docs)]
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rings_core::error::Error as CoreError;
+use rings_core::error::Result as CoreResult;
+use rings_core::session::AccountSigner;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::UnixListener;
+use tokio::net::UnixStream;
+
+use crate::error::Error;
+use crate::error::Result;
+use crate::util::ensure_parent_dir;
+use crate::util::expand_home;
+
+/// Request to signer process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SignerRequest {
+    /// Ask for the account of signer.
+    Account,
+    /// Ask for the signature of proof.
+    Sign {
+        /// Unsigned proof of session or revocation.
+        proof: String,
+    },
+}
+
+/// Response of signer process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum SignerResponse {
+    /// Account of signer.
+    Account {
+        /// Entity of account.
+        account_entity: String,
+        /// Type of account.
+        account_type: String,
+    },
+    /// Base64 encoded signature.
+    Signature {
+        /// Signature of account.
+        sig: String,
+    },
+    /// Signer failed to handle the request.
+    Error {
+        /// Reason of failure.
+        message: String,
+    },
+}
+
+/// Client of a signer process listening on unix socket.
+#[derive(Debug, Clone)]
+pub struct ExternalSigner {
+    socket: PathBuf,
+}
+
+impl ExternalSigner {
+    /// Create a client of signer listening on socket, `~` is expanded.
+    pub fn new(socket: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            socket: expand_home(socket)?,
+        })
+    }
+
+    async fn request(&self, req: &SignerRequest) -> Result<SignerResponse> {
+        let stream = UnixStream::connect(&self.socket).await.map_err(|e| {
+            Error::ExternalError(format!("connect {}: {}", self.socket.display(), e))
+        })?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut line = serde_json::to_string(req)?;
+        line.push('\n');
+        writer
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| Error::ExternalError(e.to_string()))?;
+
+        let mut resp = String::new();
+        BufReader::new(reader)
+            .read_line(&mut resp)
+            .await
+            .map_err(|e| Error::ExternalError(e.to_string()))?;
+        match serde_json::from_str(&resp)? {
+            SignerResponse::Error { message } => Err(Error::ExternalError(message)),
+            resp => Ok(resp),
+        }
+    }
+}
+
+#[async_trait]
+impl AccountSigner for ExternalSigner {
+    async fn account(&self) -> CoreResult<(String, String)> {
+        match self.request(&SignerRequest::Account).await {
+            Ok(SignerResponse::Account {
+                account_entity,
+                account_type,
+            }) => Ok((account_entity, account_type)),
+            Ok(resp) => Err(CoreError::AccountSigner(format!(
+                "unexpected response {:?}",
+                resp
+            ))),
+            Err(e) => Err(CoreError::AccountSigner(e.to_string())),
+        }
+    }
+
+    async fn sign(&self, proof: &str) -> CoreResult<Vec<u8>> {
+        let req = SignerRequest::Sign {
+            proof: proof.to_string(),
+        };
+        match self.request(&req).await {
+            Ok(SignerResponse::Signature { sig }) => {
+                base64::decode(sig).map_err(|e| CoreError::AccountSigner(e.to_string()))
+            }
+            Ok(resp) => Err(CoreError::AccountSigner(format!(
+                "unexpected response {:?}",
+                resp
+            ))),
+            Err(e) => Err(CoreError::AccountSigner(e.to_string())),
+        }
+    }
+}
+
+async fn handle_request(
+    signer: &(dyn AccountSigner + Send + Sync),
+    req: SignerRequest,
+) -> SignerResponse {
+    let resp = match req {
+        SignerRequest::Account => signer
+            .account()
+            .await
+            .map(|(account_entity, account_type)| SignerResponse::Account {
+                account_entity,
+                account_type,
+            }),
+        SignerRequest::Sign { proof } => {
+            tracing::info!("Signing proof: {:?}", proof);
+            signer
+                .sign(&proof)
+                .await
+                .map(|sig| SignerResponse::Signature {
+                    sig: base64::encode(sig),
+                })
+        }
+    };
+    resp.unwrap_or_else(|e| SignerResponse::Error {
+        message: e.to_string(),
+    })
+}
+
+async fn handle_connection(
+    signer: &(dyn AccountSigner + Send + Sync),
+    stream: UnixStream,
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(reader)
+        .read_line(&mut line)
+        .await
+        .map_err(|e| Error::ExternalError(e.to_string()))?;
+
+    let resp = match serde_json::from_str(&line) {
+        Ok(req) => handle_request(signer, req).await,
+        Err(e) => SignerResponse::Error {
+            message: format!("invalid request: {}", e),
+        },
+    };
+
+    let mut line = serde_json::to_string(&resp)?;
+    line.push('\n');
+    writer
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| Error::ExternalError(e.to_string()))
+}
+
+/// Bind a listener on socket which is only accessible by its owner.
+/// The socket is bound in a private directory and moved to its path after restricted, so
+/// that it's never accessible by others, even for a moment.
+fn bind_private(socket: &Path) -> Result<UnixListener> {
+    let name = socket
+        .file_name()
+        .ok_or(Error::ParentDirError)?
+        .to_string_lossy();
+    let dir = socket.with_file_name(format!(".{}.{}", name, std::process::id()));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).map_err(|e| Error::CreateFileError(e.to_string()))?;
+    }
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&dir)
+        .map_err(|e| Error::CreateFileError(e.to_string()))?;
+
+    let tmp = dir.join("signer.sock");
+    let listener = UnixListener::bind(&tmp)
+        .and_then(|listener| {
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+            std::fs::rename(&tmp, socket)?;
+            Ok(listener)
+        })
+        .map_err(|e| Error::CreateFileError(e.to_string()));
+    std::fs::remove_dir_all(&dir).map_err(|e| Error::CreateFileError(e.to_string()))?;
+    listener
+}
+
+/// Run a signer process on socket, which signs requests by signer.
+/// A stale socket file is replaced, and the new one is only accessible by its owner.
+pub async fn serve(
+    socket: impl AsRef<Path>,
+    signer: Arc<dyn AccountSigner + Send + Sync>,
+) -> Result<()> {
+    let socket = expand_home(socket)?;
+    ensure_parent_dir(&socket)?;
+    // only a stale socket is replaced, other files at the path are never removed
+    if let Ok(metadata) = std::fs::symlink_metadata(&socket) {
+        if !metadata.file_type().is_socket() {
+            return Err(Error::CreateFileError(format!(
+                "{} exists and is not a socket",
+                socket.display()
+            )));
+        }
+        std::fs::remove_file(&socket).map_err(|e| Error::CreateFileError(e.to_string()))?;
+    }
+    let listener = bind_private(&socket)?;
+    tracing::info!("Signer listening on {}", socket.display());
+
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| Error::ExternalError(e.to_string()))?;
+        let signer = signer.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(signer.as_ref(), stream).await {
+                tracing::warn!("Signer failed to handle connection: {:?}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rings_core::dht::Did;
+    use rings_core::ecc::SecretKey;
+    use rings_core::session::SessionSkBuilder;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_external_signer() {
+        let key = SecretKey::random();
+        let socket = std::env::temp_dir().join(format!("rings-signer-{}.sock", key.address()));
+        let daemon = tokio::spawn(serve(socket.clone(), Arc::new(key)));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let signer = ExternalSigner::new(&socket).unwrap();
+        let session_sk = SessionSkBuilder::from_signer(&signer)
+            .await
+            .unwrap()
+            .sign_by(&signer)
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(session_sk.account_did(), Did::from(key.address()));
+
+        let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let dir = socket.with_file_name(format!(
+            ".{}.{}",
+            socket.file_name().unwrap().to_string_lossy(),
+            std::process::id()
+        ));
+        assert!(!dir.exists());
+
+        daemon.abort();
+        std::fs::remove_file(&socket).unwrap();
+
+        // Signer is unreachable after daemon stopped.
+        assert!(signer.account().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_serve_keeps_other_files() {
+        let key = SecretKey::random();
+        let path = std::env::temp_dir().join(format!("rings-signer-{}.txt", key.address()));
+        std::fs::write(&path, "not a socket").unwrap();
+
+        assert!(serve(path.clone(), Arc::new(key)).await.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+//! Offline SNARK tools of `rings snark` command.
+//!
+//! Inspect circom r1cs files, check that a witness satisfies every constraint of a r1cs,
+//! convert witness between json and binary `.wtns` formats, and generate proofs locally,
+//! without running a node.
+#![warn(missing_docs)]
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use rings_snark::prelude::ff::PrimeField;
+use rings_snark::prelude::nova::provider;
+use rings_snark::prelude::nova::traits::Engine;
+use rings_snark::r1cs;
+use rings_snark::r1cs::reader;
+use rings_snark::r1cs::writer;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::backend::snark::Field;
+use crate::backend::snark::Input;
+use crate::backend::snark::SNARKBehaviour;
+use crate::backend::snark::SNARKTaskBuilder;
+use crate::backend::snark::SupportedPrimeField;
+use crate::backend::types::snark::SNARKVerifyTask;
+use crate::error::Error;
+use crate::error::Result;
+
+/// Run `$body` with type alias `$F` bound to the scalar field of `$field`.
+macro_rules! with_field {
+    ($field:expr, $F:ident => $body:expr) => {
+        match $field {
+            SupportedPrimeField::Vesta => {
+                type $F = <provider::VestaEngine as Engine>::Scalar;
+                $body
+            }
+            SupportedPrimeField::Pallas => {
+                type $F = <provider::PallasEngine as Engine>::Scalar;
+                $body
+            }
+            SupportedPrimeField::Bn256KZG => {
+                type $F = <provider::Bn256EngineKZG as Engine>::Scalar;
+                $body
+            }
+        }
+    };
+}
+
+/// Encoding of witness file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WitnessFormat {
+    /// Json array of decimal strings
+    Json,
+    /// Binary `.wtns` format of circom
+    Bin,
+}
+
+impl WitnessFormat {
+    /// Detect format by extension of path, `.json` for json and binary for others
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Bin,
+        }
+    }
+}
+
+/// Sizes of a r1cs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct R1CSInfo {
+    /// number of constraints
+    pub constraints: usize,
+    /// number of variables, including the constant one
+    pub variables: usize,
+    /// number of public inputs and outputs
+    pub public: usize,
+    /// number of private inputs and intermediate variables
+    pub private: usize,
+}
+
+impl std::fmt::Display for R1CSInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "constraints: {}", self.constraints)?;
+        writeln!(f, "variables: {}", self.variables)?;
+        writeln!(f, "public: {}", self.public)?;
+        write!(f, "private: {}", self.private)
+    }
+}
+
+fn load_r1cs<F: PrimeField>(path: impl AsRef<Path>) -> Result<r1cs::R1CS<F>> {
+    Ok(r1cs::load_r1cs_local::<F>(path, r1cs::Format::Bin)?)
+}
+
+fn load_witness<F: PrimeField>(path: impl AsRef<Path>) -> Result<Vec<F>> {
+    let file = File::open(path.as_ref()).map_err(|e| Error::OpenFileError(e.to_string()))?;
+    let reader = BufReader::new(file);
+    match WitnessFormat::from_path(path) {
+        WitnessFormat::Json => {
+            let witness: Vec<String> = serde_json::from_reader(reader)?;
+            witness
+                .iter()
+                .map(|x| F::from_str_vartime(x).ok_or(Error::FailedToLoadFF()))
+                .collect()
+        }
+        WitnessFormat::Bin => Ok(reader::load_witness_from_bin_reader::<F, _>(reader)?),
+    }
+}
+
+fn write_witness<F: PrimeField>(witness: &[F], path: impl AsRef<Path>) -> Result<()> {
+    match WitnessFormat::from_path(path.as_ref()) {
+        WitnessFormat::Json => writer::write_witness_to_json_file(witness, path)?,
+        WitnessFormat::Bin => writer::write_witness_to_bin_file(witness, path)?,
+    }
+    Ok(())
+}
+
+/// Inspect sizes of a binary r1cs file
+pub fn inspect_r1cs(path: impl AsRef<Path>, field: SupportedPrimeField) -> Result<R1CSInfo> {
+    with_field!(field, F => {
+        let r1cs = load_r1cs::<F>(path)?;
+        Ok(R1CSInfo {
+            constraints: r1cs.constraints.len(),
+            variables: r1cs.num_variables,
+            public: r1cs.num_inputs - 1,
+            private: r1cs.num_aux,
+        })
+    })
+}
+
+/// Check that witness satisfies every constraint of r1cs, returns number of checked constraints.
+/// The first unsatisfied constraint is reported as error.
+pub fn check_witness(
+    r1cs_path: impl AsRef<Path>,
+    witness_path: impl AsRef<Path>,
+    field: SupportedPrimeField,
+) -> Result<usize> {
+    with_field!(field, F => {
+        let r1cs = load_r1cs::<F>(r1cs_path)?;
+        let witness = load_witness::<F>(witness_path)?;
+        r1cs.check_witness(&witness)?;
+        Ok(r1cs.constraints.len())
+    })
+}
+
+/// Convert witness file, formats are detected by extensions, see [WitnessFormat::from_path].
+/// Returns length of witness.
+pub fn convert_witness(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    field: SupportedPrimeField,
+) -> Result<usize> {
+    with_field!(field, F => {
+        let witness = load_witness::<F>(input)?;
+        write_witness(&witness, output)?;
+        Ok(witness.len())
+    })
+}
+
+/// Parse public input from `name=v1,v2` pairs, values are decimal strings.
+pub fn parse_input(pairs: &[String], field: SupportedPrimeField) -> Result<Input> {
+    pairs
+        .iter()
+        .map(|pair| {
+            let (name, values) = pair.split_once('=').ok_or_else(|| {
+                Error::SNARKHandleMessage(format!("invalid input {pair}, expect name=v1,v2"))
+            })?;
+            let values = values
+                .split(',')
+                .map(|v| Field::from_decimal(v.trim().to_string(), field.clone()))
+                .collect::<Result<Vec<_>>>()?;
+            Ok((name.trim().to_string(), values))
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Input::from)
+}
+
+/// Generate and verify a recursive proof of circuit locally
+pub async fn prove(
+    r1cs_path: String,
+    witness_wasm_path: String,
+    input: Input,
+    steps: usize,
+    field: SupportedPrimeField,
+) -> Result<SNARKVerifyTask> {
+    let builder = SNARKTaskBuilder::from_local(r1cs_path, witness_wasm_path, field).await?;
+    let circuits = builder.gen_circuits(input, vec![], steps)?;
+    let task = SNARKBehaviour::gen_proof_task(circuits)?;
+    let proof = SNARKBehaviour::handle_snark_proof_task(&task)?;
+    if !SNARKBehaviour::handle_snark_verify_task(&proof, &task)? {
+        return Err(Error::VerifyError("generated proof is invalid".to_string()));
+    }
+    Ok(proof)
+}
@@ -2,26 +2,55 @@
 
 //! Processor of rings-node rpc server.
 
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::RwLock;
 use std::time::Duration;
 
+use rings_core::dht::quorum::QuorumWrite;
 use rings_core::dht::Did;
 use rings_core::dht::VNodeStorage;
+use rings_core::directory::account_directory_topic;
+use rings_core::directory::DeviceEntry;
+use rings_core::directory::DeviceLink;
+use rings_core::document::did_document_topic;
+use rings_core::document::is_public_endpoint;
+use rings_core::document::DidDocument;
+use rings_core::document::DidDocumentBuilder;
+use rings_core::document::Transport;
+use rings_core::ecc::pre;
+use rings_core::ecc::pre::SealedData;
+use rings_core::ecc::PublicKey;
+use rings_core::ecc::SecretKey;
 use rings_core::measure::MeasureImpl;
 use rings_core::message::Encoded;
 use rings_core::message::Encoder;
 use rings_core::message::Message;
+use rings_core::message::MessageVerification;
 use rings_core::prelude::uuid;
+use rings_core::revocation::revocation_topic;
+use rings_core::revocation::SessionRevocation;
 use rings_core::storage::MemStorage;
 use rings_core::swarm::Swarm;
 use rings_core::swarm::SwarmBuilder;
 use rings_rpc::protos::rings_node::*;
+use rings_rpc::protos::rings_node_handler::HandleRpc;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::backend::reencrypt::ReencryptBehaviour;
+#[cfg(feature = "snark")]
+use crate::backend::snark::SNARKBehaviour;
+use crate::backend::types::reencrypt::ReencryptGrant;
+use crate::backend::types::reencrypt::ReencryptMessage;
+use crate::backend::types::reencrypt::ReencryptRevocation;
 use crate::backend::types::BackendMessage;
 use crate::consts::DATA_REDUNDANT;
+use crate::consts::DEVICE_ANNOUNCE_INTERVAL;
+use crate::consts::DEVICE_ENTRY_TTL;
+use crate::consts::DHT_RESOLVE_TIMEOUT;
+use crate::consts::REVOCATION_REFRESH_INTERVAL;
 use crate::error::Error;
 use crate::error::Result;
 use crate::measure::PeriodicMeasure;
@@ -30,6 +59,7 @@ use crate::prelude::wasm_export;
 use crate::prelude::ChordStorageInterface;
 use crate::prelude::ChordStorageInterfaceCacheChecker;
 use crate::prelude::SessionSk;
+use crate::util::sleep;
 
 /// ProcessorConfig is usually serialized as json or yaml.
 /// There is a `from_config` method in [ProcessorBuilder] used to initialize the Builder with a serialized ProcessorConfig.
@@ -187,6 +217,9 @@ pub struct ProcessorBuilder {
     storage: Option<VNodeStorage>,
     measure: Option<MeasureImpl>,
     stabilize_interval: Duration,
+    allow_private_endpoints: bool,
+    #[cfg(feature = "snark")]
+    snark: Option<SNARKBehaviour>,
 }
 
 /// Processor for rings-node rpc server
@@ -195,6 +228,20 @@ pub struct Processor {
     /// a swarm instance
     pub swarm: Arc<Swarm>,
     stabilize_interval: Duration,
+    /// connect endpoints of loopback or private hosts advertised in DID documents
+    allow_private_endpoints: bool,
+    /// key fragments and capsule fragments of proxy re-encryption
+    pub reencrypt: ReencryptBehaviour,
+    /// unsigned DID document of this node and the published one, it's signed and published
+    /// again on session rotation
+    did_document: Arc<RwLock<Option<(DidDocumentBuilder, DidDocument)>>>,
+    /// link of this node to account of user, it's announced to account directory periodically
+    device_link: Arc<RwLock<Option<DeviceLink>>>,
+    /// the announced entry of device, it's touched again until it's about to expire
+    device_entry: Arc<RwLock<Option<DeviceEntry>>>,
+    /// snark tasks sent and proved by this node
+    #[cfg(feature = "snark")]
+    pub snark: SNARKBehaviour,
 }
 
 impl ProcessorBuilder {
@@ -215,6 +262,9 @@ impl ProcessorBuilder {
             storage: None,
             measure: None,
             stabilize_interval: config.stabilize_interval,
+            allow_private_endpoints: false,
+            #[cfg(feature = "snark")]
+            snark: None,
         })
     }
 
@@ -230,6 +280,20 @@ impl ProcessorBuilder {
         self
     }
 
+    /// Allow connecting endpoints of loopback, private or link-local hosts advertised in
+    /// DID documents, see [Processor::connect_with_did]. Only use it in a trusted network.
+    pub fn allow_private_endpoints(mut self, allow: bool) -> Self {
+        self.allow_private_endpoints = allow;
+        self
+    }
+
+    /// Set the snark behaviour for the processor.
+    #[cfg(feature = "snark")]
+    pub fn snark(mut self, snark: SNARKBehaviour) -> Self {
+        self.snark = Some(snark);
+        self
+    }
+
     /// Build the [Processor].
     pub fn build(self) -> Result<Processor> {
         self.session_sk
@@ -239,8 +303,12 @@ impl ProcessorBuilder {
 
         let storage = self.storage.unwrap_or_else(|| Box::new(MemStorage::new()));
 
-        let mut swarm_builder =
-            SwarmBuilder::new(self.network_id, &self.ice_servers, storage, self.session_sk);
+        let mut swarm_builder = SwarmBuilder::new(
+            self.network_id,
+            &self.ice_servers,
+            storage,
+            self.session_sk.clone(),
+        );
 
         if let Some(external_address) = self.external_address {
             swarm_builder = swarm_builder.external_address(external_address);
@@ -254,20 +322,367 @@ impl ProcessorBuilder {
         Ok(Processor {
             swarm,
             stabilize_interval: self.stabilize_interval,
+            allow_private_endpoints: self.allow_private_endpoints,
+            reencrypt: ReencryptBehaviour::default(),
+            did_document: Arc::new(RwLock::new(None)),
+            device_link: Arc::new(RwLock::new(None)),
+            device_entry: Arc::new(RwLock::new(None)),
+            #[cfg(feature = "snark")]
+            snark: self.snark.unwrap_or_default(),
         })
     }
 }
 
+/// Protocols of backend messages supported by this node, advertised in DID document.
+fn supported_protocols() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut protocols = vec!["plain_text", "extension", "service", "reencrypt"];
+    #[cfg(feature = "snark")]
+    protocols.push("snark");
+    protocols
+}
+
 impl Processor {
     /// Get current did
     pub fn did(&self) -> Did {
         self.swarm.did()
     }
 
-    /// Run stabilization daemon
+    /// Sign data with session of this node, the signature can be verified by anyone with the data
+    pub fn sign_data(&self, data: &[u8]) -> Result<MessageVerification> {
+        Ok(MessageVerification::new(data, &self.session_sk()?)?)
+    }
+
+    /// Current [SessionSk] of this node, it's replaced when session is renewed
+    pub fn session_sk(&self) -> Result<SessionSk> {
+        self.swarm.session_sk().map_err(Error::InternalError)
+    }
+
+    /// Replace session of this node with a renewed one of the same account,
+    /// the new session is announced to connected peers.
+    pub async fn rotate_session(&self, session_sk: SessionSk) -> Result<()> {
+        self.swarm
+            .rotate_session(session_sk)
+            .await
+            .map_err(Error::InternalError)?;
+        // The published document is signed by previous session.
+        let published = self.did_document.read().map_err(|_| Error::Lock)?.clone();
+        if let Some((builder, _)) = published {
+            if let Err(e) = self.sign_and_publish_did_document(builder).await {
+                tracing::warn!("Failed to publish DID document of new session: {:?}", e);
+            }
+        }
+        if let Err(e) = self.announce_device().await {
+            tracing::warn!("Failed to announce device with new session: {:?}", e);
+        }
+        Ok(())
+    }
+
+    /// Publish DID document of this node to DHT, which advertises services and the external
+    /// endpoint URL. It's signed again when session is rotated or the content is changed,
+    /// otherwise the published document is touched, so that republishing doesn't grow the
+    /// virtual node.
+    pub async fn publish_did_document(
+        &self,
+        services: Vec<String>,
+        endpoint: Option<String>,
+    ) -> Result<DidDocument> {
+        let mut builder = DidDocumentBuilder::new(crate::util::build_version());
+        for protocol in supported_protocols() {
+            builder = builder.protocol(protocol.to_string());
+        }
+        for service in services {
+            builder = builder.service(service);
+        }
+        if let Some(endpoint) = endpoint {
+            builder = builder.endpoint(endpoint);
+        }
+
+        let session = self.session_sk()?.session();
+        let published = self.did_document.read().map_err(|_| Error::Lock)?.clone();
+        match published {
+            Some((published_builder, document))
+                if published_builder == builder && document.session() == &session =>
+            {
+                self.touch_did_document(&document).await?;
+                Ok(document)
+            }
+            _ => self.sign_and_publish_did_document(builder).await,
+        }
+    }
+
+    async fn sign_and_publish_did_document(
+        &self,
+        builder: DidDocumentBuilder,
+    ) -> Result<DidDocument> {
+        let document = builder
+            .clone()
+            .build(&self.session_sk()?)
+            .map_err(Error::InternalError)?;
+        self.touch_did_document(&document).await?;
+        *self.did_document.write().map_err(|_| Error::Lock)? = Some((builder, document.clone()));
+        Ok(document)
+    }
+
+    async fn touch_did_document(&self, document: &DidDocument) -> Result<()> {
+        let encoded = Encoded::try_from(document).map_err(Error::InternalError)?;
+        <Swarm as ChordStorageInterface<DATA_REDUNDANT>>::storage_touch_data(
+            &self.swarm,
+            &did_document_topic(self.did()),
+            encoded,
+        )
+        .await
+        .map_err(Error::VNodeError)
+    }
+
+    /// Fetch data virtual node of topic from DHT, and resolve it until `resolve` returns
+    /// some value. Returns None if nothing is resolved in [DHT_RESOLVE_TIMEOUT] seconds.
+    async fn fetch_and_resolve<T>(
+        &self,
+        topic: &str,
+        resolve: impl Fn(&vnode::VirtualNode) -> Option<T>,
+    ) -> Result<Option<T>> {
+        let vid = vnode::VirtualNode::gen_did(topic).map_err(Error::InternalError)?;
+        self.storage_fetch(vid).await?;
+
+        // Fetched virtual node is put into local cache when it's received.
+        let interval = Duration::from_millis(100);
+        for _ in 0..DHT_RESOLVE_TIMEOUT * 10 {
+            let resolved = self
+                .storage_check_cache(vid)
+                .await
+                .and_then(|vnode| resolve(&vnode));
+            if resolved.is_some() {
+                return Ok(resolved);
+            }
+            sleep(interval).await?;
+        }
+        Ok(None)
+    }
+
+    /// Resolve the latest valid DID document of did from DHT.
+    /// Returns None if no document is found in [DHT_RESOLVE_TIMEOUT] seconds.
+    pub async fn resolve_did_document(&self, did: Did) -> Result<Option<DidDocument>> {
+        if let Err(e) = self.refresh_account_revocations(did).await {
+            tracing::debug!("failed to refresh revocations of {}: {:?}", did, e);
+        }
+        self.fetch_and_resolve(&did_document_topic(did), |vnode| {
+            DidDocument::resolve(vnode, did, self.swarm.revocations())
+        })
+        .await
+    }
+
+    /// Link this node to account of user as one of its devices, then announce it to the
+    /// account directory. It's announced again when session is rotated.
+    pub async fn link_device(&self, link: DeviceLink) -> Result<()> {
+        link.verify().map_err(Error::InternalError)?;
+        if link.device() != self.did() {
+            return Err(Error::InternalError(
+                rings_core::error::Error::InvalidDeviceLink(format!(
+                    "link of device {} cannot be used by {}",
+                    link.device(),
+                    self.did()
+                )),
+            ));
+        }
+        *self.device_link.write().map_err(|_| Error::Lock)? = Some(link);
+        self.announce_device().await?;
+        Ok(())
+    }
+
+    /// Announce this node to the directory of linked account, the entry expires in
+    /// [DEVICE_ENTRY_TTL] seconds. Returns None if node is not linked to any account.
+    /// The announced entry is touched again while it outlives the next announcement in
+    /// [DEVICE_ANNOUNCE_INTERVAL] seconds, so that announcing doesn't grow the directory.
+    pub async fn announce_device(&self) -> Result<Option<DeviceEntry>> {
+        let link = self.device_link.read().map_err(|_| Error::Lock)?.clone();
+        let Some(link) = link else {
+            return Ok(None);
+        };
+        let session_sk = self.session_sk()?;
+        let next_announcement =
+            rings_core::utils::get_epoch_ms() + DEVICE_ANNOUNCE_INTERVAL as u128 * 1000;
+        let announced = self.device_entry.read().map_err(|_| Error::Lock)?.clone();
+        let entry = match announced {
+            Some(entry)
+                if entry.link() == &link
+                    && entry.session() == &session_sk.session()
+                    && entry.expires_at() > next_announcement =>
+            {
+                entry
+            }
+            _ => DeviceEntry::new(link, &session_sk, DEVICE_ENTRY_TTL * 1000)
+                .map_err(Error::InternalError)?,
+        };
+        let encoded = Encoded::try_from(&entry).map_err(Error::InternalError)?;
+        <Swarm as ChordStorageInterface<DATA_REDUNDANT>>::storage_touch_data(
+            &self.swarm,
+            &account_directory_topic(entry.account_did()),
+            encoded,
+        )
+        .await
+        .map_err(Error::VNodeError)?;
+        *self.device_entry.write().map_err(|_| Error::Lock)? = Some(entry.clone());
+        Ok(Some(entry))
+    }
+
+    /// Resolve online devices of account from DHT, the most recently announced first.
+    /// Returns empty if no device is found in [DHT_RESOLVE_TIMEOUT] seconds.
+    pub async fn resolve_account_devices(&self, account: Did) -> Result<Vec<DeviceEntry>> {
+        if let Err(e) = self.refresh_account_revocations(account).await {
+            tracing::debug!("failed to refresh revocations of {}: {:?}", account, e);
+        }
+        let devices = self
+            .fetch_and_resolve(&account_directory_topic(account), |vnode| {
+                let devices = DeviceEntry::resolve(vnode, account, self.swarm.revocations());
+                (!devices.is_empty()).then_some(devices)
+            })
+            .await?;
+        Ok(devices.unwrap_or_default())
+    }
+
+    /// Send custom message to account of user, which is delivered to its online devices
+    /// other than this node. With `fan_out` it's sent to all of them, otherwise to one of them,
+    /// preferring connected devices and then the most recently announced ones.
+    /// An account without devices in directory is handled as a node itself, and it's an error
+    /// if this node is the only online device of account.
+    /// Returns the devices which message is sent to.
+    pub async fn send_account_message(
+        &self,
+        account: Did,
+        msg: &[u8],
+        fan_out: bool,
+    ) -> Result<Vec<Did>> {
+        let entries = self.resolve_account_devices(account).await?;
+        if entries.is_empty() {
+            self.send_message(account, msg).await?;
+            return Ok(vec![account]);
+        }
+
+        let mut devices: Vec<Did> = entries
+            .iter()
+            .map(|entry| entry.device())
+            .filter(|device| *device != self.did())
+            .collect();
+        if devices.is_empty() {
+            return Err(Error::NoOtherDeviceOnline(account));
+        }
+        // Sort is stable, so the order of announcement is kept among connected devices.
+        let connected: HashSet<String> = self.swarm.peers().into_iter().map(|p| p.did).collect();
+        devices.sort_by_key(|device| !connected.contains(&device.to_string()));
+
+        let mut sent = vec![];
+        let mut error = None;
+        for device in devices {
+            match self.send_message(device, msg).await {
+                Ok(_) => {
+                    sent.push(device);
+                    if !fan_out {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to send message to device {} of {}: {:?}",
+                        device,
+                        account,
+                        e
+                    );
+                    error = Some(e);
+                }
+            }
+        }
+        match error {
+            Some(e) if sent.is_empty() => Err(e),
+            _ => Ok(sent),
+        }
+    }
+
+    /// Time to wait before session should be renewed, which is `margin` ahead of its expiry.
+    /// The margin is capped to half of session lifetime, so a renewed session is never due at once.
+    pub fn session_renewal_due_in(&self, margin: Duration) -> Result<Duration> {
+        let session = self.session_sk()?.session();
+        let margin = margin.as_millis().min(session.ttl_ms() as u128 / 2);
+        let renew_at = session.expires_at().saturating_sub(margin);
+        let now = rings_core::utils::get_epoch_ms();
+        Ok(Duration::from_millis(
+            renew_at.saturating_sub(now).try_into().unwrap_or(u64::MAX),
+        ))
+    }
+
+    /// Revoke a session of the same account as this node. The revocation takes effect locally
+    /// at once, and is published to the revocation topic of account in DHT, so that other nodes
+    /// pick it up when they check sessions of the account.
+    pub async fn revoke_session(&self, revocation: SessionRevocation) -> Result<()> {
+        self.swarm
+            .revocations()
+            .save(revocation.clone())
+            .await
+            .map_err(Error::InternalError)?;
+        let encoded = Encoded::try_from(&revocation).map_err(Error::InternalError)?;
+        <Swarm as ChordStorageInterface<DATA_REDUNDANT>>::storage_touch_data(
+            &self.swarm,
+            &revocation_topic(revocation.account_did()),
+            encoded,
+        )
+        .await
+        .map_err(Error::VNodeError)
+    }
+
+    /// Fetch revocations of account from DHT and merge them into the local cache.
+    /// Returns the number of newly revoked sessions.
+    pub async fn refresh_account_revocations(&self, account: Did) -> Result<usize> {
+        let vid = vnode::VirtualNode::gen_did(&revocation_topic(account))
+            .map_err(Error::InternalError)?;
+        self.storage_fetch(vid).await?;
+        let Some(vnode) = self.storage_check_cache(vid).await else {
+            return Ok(0);
+        };
+        Ok(self.swarm.revocations().merge_vnode(&vnode, account).await)
+    }
+
+    /// Fetch revocations of this account, of connected peers and of the accounts whose sessions
+    /// were checked recently by this node, and merge them into the local cache.
+    /// Returns the number of newly revoked sessions.
+    pub async fn refresh_revocations(&self) -> Result<usize> {
+        let revocations = self.swarm.revocations();
+        revocations.watch(self.did());
+        for peer in self.swarm.peers() {
+            if let Ok(did) = Did::from_str(&peer.did) {
+                revocations.watch(did);
+            }
+        }
+        let mut merged = 0;
+        for account in revocations.watched_accounts() {
+            match self.refresh_account_revocations(account).await {
+                Ok(n) => merged += n,
+                Err(e) => tracing::debug!("failed to refresh revocations of {}: {:?}", account, e),
+            }
+        }
+        Ok(merged)
+    }
+
+    async fn refresh_revocations_periodically(&self) {
+        loop {
+            match self.refresh_revocations().await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("{} sessions are revoked", n),
+                Err(e) => tracing::debug!("failed to refresh revocations: {:?}", e),
+            }
+            if let Err(e) = sleep(Duration::from_secs(REVOCATION_REFRESH_INTERVAL)).await {
+                tracing::error!("revocation refresher stopped: {:?}", e);
+                return;
+            }
+        }
+    }
+
+    /// Run stabilization daemon and refresh session revocations periodically
     pub async fn listen(&self) {
         let stabilizer = self.swarm.stabilizer();
-        Arc::new(stabilizer).wait(self.stabilize_interval).await
+        futures::join!(
+            Arc::new(stabilizer).wait(self.stabilize_interval),
+            self.refresh_revocations_periodically(),
+        );
     }
 
     /// Connect peer with web3 did.
@@ -275,8 +690,51 @@ impl Processor {
     /// 1. PeerA has a connection with PeerB.
     /// 2. PeerC has a connection with PeerB.
     /// 3. PeerC can connect PeerA with PeerA's web3 address.
+    ///
+    /// WebRTC through DHT is tried at once. If that fails, DID document of the peer is
+    /// resolved, and its external endpoint is connected if it's advertised. Endpoints on
+    /// hosts which are not public are rejected, unless allowed by
+    /// [ProcessorBuilder::allow_private_endpoints].
     pub async fn connect_with_did(&self, did: Did) -> Result<()> {
-        self.swarm.connect(did).await.map_err(Error::ConnectError)?;
+        let error = match self.swarm.connect(did).await {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        tracing::info!("Failed to connect {} through DHT: {:?}", did, error);
+
+        let document = self.resolve_did_document(did).await.unwrap_or_else(|e| {
+            tracing::debug!("Failed to resolve DID document of {}: {:?}", did, e);
+            None
+        });
+        let endpoint = document
+            .as_ref()
+            .filter(|document| document.supports(Transport::Http))
+            .and_then(|document| document.endpoint());
+        let Some(endpoint) = endpoint else {
+            return Err(Error::ConnectError(error));
+        };
+        if !self.allow_private_endpoints && !is_public_endpoint(endpoint) {
+            return Err(Error::ConnectError(
+                rings_core::error::Error::InvalidDidDocument(format!(
+                    "endpoint {} of node {} is not on a public host",
+                    endpoint, did
+                )),
+            ));
+        }
+        let resp = self
+            .handle_rpc(ConnectPeerViaHttpRequest {
+                url: endpoint.to_string(),
+            })
+            .await?;
+        if resp.did != did.to_string() {
+            self.disconnect(Did::from_str(&resp.did)?).await?;
+            return Err(Error::ConnectError(
+                rings_core::error::Error::InvalidDidDocument(format!(
+                    "endpoint {} is not node {}",
+                    endpoint, did
+                )),
+            ));
+        }
         Ok(())
     }
 
@@ -344,6 +802,109 @@ impl Processor {
         .map_err(Error::VNodeError)
     }
 
+    /// write data approved by a quorum of group to its virtual node on DHT
+    pub async fn storage_quorum_write(&self, write: QuorumWrite) -> Result<()> {
+        <Swarm as ChordStorageInterface<DATA_REDUNDANT>>::storage_quorum_write(&self.swarm, write)
+            .await
+            .map_err(Error::VNodeError)
+    }
+
+    /// Seal data under delegating key, then append it to a virtual node on DHT.
+    /// Members granted by [Processor::grant_reencryption] can read it without the owner.
+    pub async fn storage_append_sealed(
+        &self,
+        topic: &str,
+        delegating: &PublicKey<33>,
+        data: &[u8],
+    ) -> Result<()> {
+        let sealed = SealedData::seal(delegating, data).map_err(Error::InternalError)?;
+        let encoded = Encoded::try_from(&sealed).map_err(Error::InternalError)?;
+        self.storage_append_data(topic, encoded).await
+    }
+
+    /// Grant a member to read data sealed under delegating key, by sending one key fragment
+    /// to each proxy. Any `threshold` of the proxies can re-encrypt data for the member.
+    /// The grant is at the epoch of current timestamp, which replaces earlier grants.
+    /// Each key fragment is sealed to session key in DID document of its proxy.
+    pub async fn grant_reencryption(
+        &self,
+        delegating: &SecretKey,
+        receiving: &PublicKey<33>,
+        proxies: &[Did],
+        threshold: usize,
+    ) -> Result<()> {
+        let kfrags = pre::generate_kfrags(delegating, receiving, threshold, proxies.len())
+            .map_err(Error::InternalError)?;
+        let epoch = rings_core::utils::get_epoch_ms() as u64;
+        let mut sealed = vec![];
+        for (proxy, kfrag) in proxies.iter().zip(kfrags) {
+            let document = self.resolve_did_document(*proxy).await?.ok_or_else(|| {
+                Error::ReencryptRejected(format!("DID document of proxy {} is not found", proxy))
+            })?;
+            let grant = ReencryptGrant::new(delegating, kfrag, epoch);
+            sealed.push((*proxy, grant.seal(&document.session_pubkey())?));
+        }
+        for (proxy, grant) in sealed {
+            self.send_backend_message(proxy, ReencryptMessage::Grant(grant).into())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Revoke a member from proxies. Data appended later should be sealed under a new
+    /// delegating key, since the member may have kept what it read.
+    pub async fn revoke_reencryption(
+        &self,
+        delegating: &SecretKey,
+        receiving: &PublicKey<33>,
+        proxies: &[Did],
+    ) -> Result<()> {
+        let revocation = ReencryptRevocation::new(
+            delegating,
+            receiving,
+            rings_core::utils::get_epoch_ms() as u64,
+        );
+        for proxy in proxies {
+            let msg = ReencryptMessage::Revoke(revocation.clone());
+            self.send_backend_message(*proxy, msg.into()).await?;
+        }
+        Ok(())
+    }
+
+    /// Ask proxies to re-encrypt sealed data for receiving key. The capsule fragments are
+    /// collected in background, only from these proxies, see [Processor::open_sealed].
+    pub async fn request_reencryption(
+        &self,
+        sealed: &SealedData,
+        receiving: &PublicKey<33>,
+        proxies: &[Did],
+    ) -> Result<()> {
+        self.reencrypt.request(sealed, *receiving, proxies)?;
+        for proxy in proxies {
+            let msg = ReencryptMessage::Request {
+                capsule: sealed.capsule,
+                delegating: sealed.delegating,
+                receiving: *receiving,
+            };
+            self.send_backend_message(*proxy, msg.into()).await?;
+        }
+        Ok(())
+    }
+
+    /// Open sealed data with capsule fragments received from proxies.
+    /// Returns None if not enough fragments are received yet.
+    pub fn open_sealed(
+        &self,
+        sealed: &SealedData,
+        receiving: &SecretKey,
+    ) -> Result<Option<Vec<u8>>> {
+        let data = self.reencrypt.open(sealed, receiving)?;
+        if data.is_some() {
+            self.reencrypt.forget(sealed);
+        }
+        Ok(data)
+    }
+
     /// register service
     pub async fn register_service(&self, name: &str) -> Result<()> {
         let encoded_did = self
@@ -365,6 +926,10 @@ impl Processor {
         Ok(NodeInfoResponse {
             version: crate::util::build_version(),
             swarm: Some(self.swarm.inspect().await.into()),
+            #[cfg(feature = "snark")]
+            snark_prover: Some(self.snark.prover_info()),
+            #[cfg(not(feature = "snark"))]
+            snark_prover: None,
         })
     }
 }
@@ -502,4 +1067,170 @@ mod test {
             got_msg1
         );
     }
+
+    #[tokio::test]
+    async fn test_processor_renew_session() {
+        use crate::provider::Provider;
+        use crate::provider::Signer;
+
+        let key = SecretKey::random();
+        let config = ProcessorConfig::new(
+            0,
+            "stun://stun.l.google.com:19302".to_string(),
+            SessionSk::new_with_seckey(&key).unwrap(),
+            3,
+        );
+        let processor = Arc::new(
+            ProcessorBuilder::from_config(&config)
+                .unwrap()
+                .build()
+                .unwrap(),
+        );
+        let provider = Provider::from_processor(processor.clone());
+        let previous = processor.session_sk().unwrap();
+
+        // Margin is capped to half of session lifetime.
+        let ttl = Duration::from_millis(previous.session().ttl_ms());
+        let due_in = processor.session_renewal_due_in(ttl * 2).unwrap();
+        assert!(due_in > ttl / 4 && due_in <= ttl / 2);
+        assert!(processor.session_renewal_due_in(Duration::ZERO).unwrap() > ttl / 2);
+
+        // Session signed by other account is rejected and the current one is kept.
+        let other = SecretKey::random();
+        let signer = Signer::Sync(Box::new(move |proof: String| other.sign(&proof).to_vec()));
+        assert!(provider.renew_session_with_signer(&signer).await.is_err());
+        assert_eq!(processor.session_sk().unwrap(), previous);
+
+        let signer = Signer::Sync(Box::new(move |proof: String| key.sign(&proof).to_vec()));
+        let renewed = provider.renew_session_with_signer(&signer).await.unwrap();
+        assert_ne!(renewed, previous);
+        assert_eq!(renewed.account_did(), processor.did());
+        assert_eq!(processor.session_sk().unwrap(), renewed);
+        assert_eq!(
+            processor.sign_data(b"data").unwrap().session,
+            renewed.session()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_processor_revoke_session() {
+        use rings_core::revocation::RevocationList;
+        use rings_core::revocation::SessionRevocationBuilder;
+
+        let key = SecretKey::random();
+        let config = ProcessorConfig::new(
+            0,
+            "stun://stun.l.google.com:19302".to_string(),
+            SessionSk::new_with_seckey(&key).unwrap(),
+            3,
+        );
+        let processor = ProcessorBuilder::from_config(&config)
+            .unwrap()
+            .build()
+            .unwrap();
+        let previous = processor.session_sk().unwrap();
+
+        let builder = SessionRevocationBuilder::new(
+            processor.did().to_string(),
+            "secp256k1".to_string(),
+            previous.session().session_id().to_string(),
+        );
+        let sig = key.sign(&builder.unsigned_proof().unwrap());
+        let revocation = builder.set_sig(sig.to_vec()).build().unwrap();
+        processor.revoke_session(revocation).await.unwrap();
+
+        // The revocation is already known locally, and it's published to DHT.
+        assert_eq!(processor.refresh_revocations().await.unwrap(), 0);
+        let vid = vnode::VirtualNode::gen_did(&revocation_topic(processor.did())).unwrap();
+        let vnode = processor.storage_check_cache(vid).await.unwrap();
+        let revocations = RevocationList::default();
+        assert_eq!(revocations.merge_vnode(&vnode, processor.did()).await, 1);
+        assert!(revocations.is_revoked(&previous.session()));
+
+        // Node cannot switch to the revoked session.
+        assert!(processor.rotate_session(previous).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_processor_republish_did_document() {
+        let key = SecretKey::random();
+        let config = ProcessorConfig::new(
+            0,
+            "stun://stun.l.google.com:19302".to_string(),
+            SessionSk::new_with_seckey(&key).unwrap(),
+            3,
+        );
+        let processor = ProcessorBuilder::from_config(&config)
+            .unwrap()
+            .build()
+            .unwrap();
+        let vid = vnode::VirtualNode::gen_did(&did_document_topic(processor.did())).unwrap();
+        let services = vec!["web".to_string()];
+
+        // Unchanged document is touched again instead of appended.
+        let document = processor
+            .publish_did_document(services.clone(), None)
+            .await
+            .unwrap();
+        let republished = processor
+            .publish_did_document(services.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(republished, document);
+        processor.storage_fetch(vid).await.unwrap();
+        let vnode = processor.storage_check_cache(vid).await.unwrap();
+        assert_eq!(vnode.data.len(), 1);
+
+        // Changed document is signed again.
+        let changed = processor
+            .publish_did_document(services, Some("https://example.com".to_string()))
+            .await
+            .unwrap();
+        assert_ne!(changed, document);
+        assert_eq!(changed.endpoint(), Some("https://example.com"));
+        processor.storage_fetch(vid).await.unwrap();
+        let vnode = processor.storage_check_cache(vid).await.unwrap();
+        assert_eq!(vnode.data.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_processor_reject_private_endpoint() {
+        let key = SecretKey::random();
+        let config = ProcessorConfig::new(
+            0,
+            "stun://stun.l.google.com:19302".to_string(),
+            SessionSk::new_with_seckey(&key).unwrap(),
+            3,
+        );
+        let processor = ProcessorBuilder::from_config(&config)
+            .unwrap()
+            .build()
+            .unwrap();
+        processor
+            .publish_did_document(vec![], Some("http://127.0.0.1:1".to_string()))
+            .await
+            .unwrap();
+
+        let err = processor
+            .connect_with_did(processor.did())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not on a public host"), "{}", err);
+
+        // Private endpoint is requested when it's allowed.
+        let processor = ProcessorBuilder::from_config(&config)
+            .unwrap()
+            .allow_private_endpoints(true)
+            .build()
+            .unwrap();
+        processor
+            .publish_did_document(vec![], Some("http://127.0.0.1:1".to_string()))
+            .await
+            .unwrap();
+        let err = processor
+            .connect_with_did(processor.did())
+            .await
+            .unwrap_err();
+        assert!(!err.to_string().contains("not on a public host"), "{}", err);
+    }
 }
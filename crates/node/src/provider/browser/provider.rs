@@ -10,7 +10,11 @@ use std::sync::Arc;
 use js_sys;
 use js_sys::Uint8Array;
 use rings_core::dht::Did;
+use rings_core::directory::DeviceLink;
+use rings_core::ecc::pre::SealedData;
 use rings_core::ecc::PublicKey;
+use rings_core::ecc::SecretKey;
+use rings_core::message::Encoded;
 use rings_core::prelude::vnode;
 use rings_core::prelude::vnode::VirtualNode;
 use rings_core::storage::idb::IdbStorage;
@@ -44,6 +48,28 @@ pub enum AddressType {
     Ed25519,
 }
 
+/// Wrap `async function (proof: string): Promise<Unit8Array>` as [AsyncSigner]
+fn wrapped_signer(signer: js_sys::Function) -> AsyncSigner {
+    Box::new(
+        move |data: String| -> Pin<Box<dyn Future<Output = Vec<u8>>>> {
+            let signer = signer.clone();
+            Box::pin(async move {
+                let signer = signer.clone();
+                let sig: js_sys::Uint8Array = Uint8Array::from(
+                    JsFuture::from(js_sys::Promise::from(
+                        signer
+                            .call1(&JsValue::NULL, &JsValue::from_str(&data))
+                            .expect("Failed on call external Js Function"),
+                    ))
+                    .await
+                    .expect("Failed await call external Js Promise"),
+                );
+                sig.to_vec()
+            })
+        },
+    )
+}
+
 /// A wrapper of Arc Ref of Provider
 #[derive(Clone)]
 #[wasm_export]
@@ -88,27 +114,6 @@ impl Provider {
         signer: js_sys::Function,
         backend_behaviour: Option<BackendBehaviour>,
     ) -> js_sys::Promise {
-        fn wrapped_signer(signer: js_sys::Function) -> AsyncSigner {
-            Box::new(
-                move |data: String| -> Pin<Box<dyn Future<Output = Vec<u8>>>> {
-                    let signer = signer.clone();
-                    Box::pin(async move {
-                        let signer = signer.clone();
-                        let sig: js_sys::Uint8Array = Uint8Array::from(
-                            JsFuture::from(js_sys::Promise::from(
-                                signer
-                                    .call1(&JsValue::NULL, &JsValue::from_str(&data))
-                                    .expect("Failed on call external Js Function"),
-                            ))
-                            .await
-                            .expect("Failed await call external Js Promise"),
-                        );
-                        sig.to_vec()
-                    })
-                },
-            )
-        }
-
         future_to_promise(async move {
             let signer = wrapped_signer(signer);
 
@@ -136,6 +141,43 @@ impl Provider {
             )
             .await?;
 
+            let revocation_storage = Box::new(
+                IdbStorage::new_with_cap_and_name(50000, "rings-node/revocations")
+                    .await
+                    .expect("Failed on create revocation storage"),
+            );
+            provider
+                .processor
+                .swarm
+                .revocations()
+                .set_storage(revocation_storage)
+                .await?;
+
+            #[cfg(feature = "snark")]
+            {
+                let snark_storage = Box::new(
+                    IdbStorage::new_with_cap_and_name(50000, "rings-node/snark")
+                        .await
+                        .expect("Failed on create snark storage"),
+                );
+                provider.snark().set_storage(snark_storage).await?;
+                let snark_params_storage = Box::new(
+                    IdbStorage::new_with_cap_and_name(50000, "rings-node/snark-params")
+                        .await
+                        .expect("Failed on create snark params storage"),
+                );
+                provider.snark().set_params_storage(snark_params_storage)?;
+                let snark_ledger_storage = Box::new(
+                    IdbStorage::new_with_cap_and_name(50000, "rings-node/snark-ledger")
+                        .await
+                        .expect("Failed on create snark ledger storage"),
+                );
+                provider
+                    .snark()
+                    .set_ledger_storage(snark_ledger_storage)
+                    .await?;
+            }
+
             if let Some(cb) = backend_behaviour {
                 let backend: Backend = Backend::new(Arc::new(provider.clone()), Box::new(cb));
                 provider
@@ -170,6 +212,13 @@ impl Provider {
         self.processor.did().to_string()
     }
 
+    /// get snark behaviour of provider, its tasks are persisted and can be queried by rpc,
+    /// use it as the SNARKTaskMessage handler of backend
+    #[cfg(feature = "snark")]
+    pub fn snark_behaviour(&self) -> crate::backend::snark::SNARKBehaviour {
+        self.snark()
+    }
+
     ///  create new unsigned Provider
     pub fn new_provider_with_storage(
         config: ProcessorConfig,
@@ -196,6 +245,33 @@ impl Provider {
             )
             .await
             .map_err(JsError::from)?;
+
+            let revocation_storage = Box::new(
+                IdbStorage::new_with_cap_and_name(50000, &format!("{}/revocations", storage_name))
+                    .await
+                    .expect("Failed on create revocation storage"),
+            );
+            provider
+                .processor
+                .swarm
+                .revocations()
+                .set_storage(revocation_storage)
+                .await
+                .map_err(JsError::from)?;
+
+            #[cfg(feature = "snark")]
+            {
+                let snark_storage = Box::new(
+                    IdbStorage::new_with_cap_and_name(50000, &format!("{}/snark", storage_name))
+                        .await
+                        .expect("Failed on create snark storage"),
+                );
+                provider
+                    .snark()
+                    .set_storage(snark_storage)
+                    .await
+                    .map_err(JsError::from)?;
+            }
             if let Some(cb) = backend_behaviour {
                 let backend: Backend = Backend::new(Arc::new(provider.clone()), Box::new(cb));
                 provider
@@ -230,6 +306,55 @@ impl Provider {
         })
     }
 
+    /// Renew session now with signer of account, the new session is announced to connected peers.
+    /// Signer is the same as the one of constructor, returns dumped new session.
+    pub fn renew_session(&self, signer: js_sys::Function) -> js_sys::Promise {
+        let ins = self.clone();
+        future_to_promise(async move {
+            let signer = Signer::Async(wrapped_signer(signer));
+            let session_sk = ins
+                .renew_session_with_signer(&signer)
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::from_str(
+                &session_sk.dump().map_err(JsError::from)?,
+            ))
+        })
+    }
+
+    /// Renew session with signer of account `margin` seconds ahead of its expiry, forever.
+    /// `on_renewed` is optional, and will be called with every dumped new session.
+    pub fn listen_session_renewal(
+        &self,
+        signer: js_sys::Function,
+        margin: u64,
+        on_renewed: Option<js_sys::Function>,
+    ) -> js_sys::Promise {
+        let ins = self.clone();
+        future_to_promise(async move {
+            let signer = Signer::Async(wrapped_signer(signer));
+            ins.session_renewal_daemon(
+                signer,
+                std::time::Duration::from_secs(margin),
+                |session_sk| {
+                    let Some(cb) = &on_renewed else {
+                        return;
+                    };
+                    match session_sk.dump() {
+                        Ok(dumped) => {
+                            if let Err(e) = cb.call1(&JsValue::NULL, &JsValue::from_str(&dumped)) {
+                                tracing::error!("on_renewed callback failed: {:?}", e);
+                            }
+                        }
+                        Err(e) => tracing::error!("Failed to dump session: {:?}", e),
+                    }
+                },
+            )
+            .await;
+            Ok(JsValue::null())
+        })
+    }
+
     /// connect peer with remote jsonrpc server url
     pub fn connect_peer_via_http(&self, remote_url: String) -> js_sys::Promise {
         log::debug!("remote_url: {}", remote_url);
@@ -262,6 +387,55 @@ impl Provider {
         })
     }
 
+    /// publish DID document of this node to DHT, which advertises services of this node,
+    /// returns the dumped document
+    pub fn publish_did_document(&self, services: Vec<String>) -> js_sys::Promise {
+        let p = self.processor.clone();
+        future_to_promise(async move {
+            let document = p
+                .publish_did_document(services, None)
+                .await
+                .map_err(JsError::from)?;
+            let dump = document.dump().map_err(JsError::from)?;
+            Ok(JsValue::from_str(&dump))
+        })
+    }
+
+    /// link this node to account of user as one of its devices, with a dumped link
+    /// signed by the account
+    pub fn link_device(&self, link: String) -> js_sys::Promise {
+        let p = self.processor.clone();
+        future_to_promise(async move {
+            let link = DeviceLink::from_str(&link).map_err(JsError::from)?;
+            p.link_device(link).await.map_err(JsError::from)?;
+            Ok(JsValue::null())
+        })
+    }
+
+    /// send plain text message to the devices of account, to all online devices if fan_out,
+    /// returns dids of the devices which message is sent to
+    pub fn send_message_to_account(
+        &self,
+        account: String,
+        msg: String,
+        fan_out: bool,
+    ) -> js_sys::Promise {
+        let p = self.processor.clone();
+        future_to_promise(async move {
+            let account = get_did(account.as_str(), AddressType::DEFAULT)?;
+            let data = bincode::serialize(&BackendMessage::PlainText(msg))
+                .map_err(|_| JsError::from(crate::error::Error::EncodeError))?;
+            let devices = p
+                .send_account_message(account, &data, fan_out)
+                .await
+                .map_err(JsError::from)?
+                .iter()
+                .map(|did| did.to_string())
+                .collect::<Vec<_>>();
+            Ok(js_value::serialize(&devices).map_err(JsError::from)?)
+        })
+    }
+
     /// get info for self, will return build version and inspection of swarm
     pub fn get_node_info(&self) -> js_sys::Promise {
         let p = self.processor.clone();
@@ -343,6 +517,110 @@ impl Provider {
         })
     }
 
+    /// seal data under delegating key, then append it to topic on DHT
+    /// - topic: topic of virtual node
+    /// - delegating: hex pubkey which data is sealed under
+    /// - data: plaintext
+    pub fn storage_append_sealed(
+        &self,
+        topic: String,
+        delegating: String,
+        data: js_sys::Uint8Array,
+    ) -> js_sys::Promise {
+        let p = self.processor.clone();
+        future_to_promise(async move {
+            let delegating = PublicKey::from_hex_string(&delegating).map_err(JsError::from)?;
+            p.storage_append_sealed(&topic, &delegating, &data.to_vec())
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::null())
+        })
+    }
+
+    /// grant a member to read data sealed under delegating key, via proxies
+    /// - delegating: secret key which data is sealed under
+    /// - receiving: hex pubkey of member
+    /// - proxies: dids of proxies
+    /// - threshold: number of proxies needed to re-encrypt data
+    pub fn grant_reencryption(
+        &self,
+        delegating: String,
+        receiving: String,
+        proxies: Vec<String>,
+        threshold: usize,
+    ) -> js_sys::Promise {
+        let p = self.processor.clone();
+        future_to_promise(async move {
+            let delegating = SecretKey::try_from(delegating.as_str()).map_err(JsError::from)?;
+            let receiving = PublicKey::from_hex_string(&receiving).map_err(JsError::from)?;
+            let proxies = get_dids(&proxies)?;
+            p.grant_reencryption(&delegating, &receiving, &proxies, threshold)
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::null())
+        })
+    }
+
+    /// revoke a member from proxies
+    /// - delegating: secret key which data is sealed under
+    /// - receiving: hex pubkey of member
+    /// - proxies: dids of proxies
+    pub fn revoke_reencryption(
+        &self,
+        delegating: String,
+        receiving: String,
+        proxies: Vec<String>,
+    ) -> js_sys::Promise {
+        let p = self.processor.clone();
+        future_to_promise(async move {
+            let delegating = SecretKey::try_from(delegating.as_str()).map_err(JsError::from)?;
+            let receiving = PublicKey::from_hex_string(&receiving).map_err(JsError::from)?;
+            let proxies = get_dids(&proxies)?;
+            p.revoke_reencryption(&delegating, &receiving, &proxies)
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::null())
+        })
+    }
+
+    /// ask proxies to re-encrypt sealed data for member
+    /// - sealed: encoded sealed data fetched from DHT
+    /// - receiving: hex pubkey of member
+    /// - proxies: dids of proxies
+    pub fn request_reencryption(
+        &self,
+        sealed: String,
+        receiving: String,
+        proxies: Vec<String>,
+    ) -> js_sys::Promise {
+        let p = self.processor.clone();
+        future_to_promise(async move {
+            let sealed: SealedData = Encoded::from_encoded_str(&sealed)
+                .decode()
+                .map_err(JsError::from)?;
+            let receiving = PublicKey::from_hex_string(&receiving).map_err(JsError::from)?;
+            let proxies = get_dids(&proxies)?;
+            p.request_reencryption(&sealed, &receiving, &proxies)
+                .await
+                .map_err(JsError::from)?;
+            Ok(JsValue::null())
+        })
+    }
+
+    /// open sealed data with fragments received from proxies,
+    /// returns null if not enough fragments are received yet
+    /// - sealed: encoded sealed data fetched from DHT
+    /// - receiving: secret key of member
+    pub fn open_sealed(&self, sealed: String, receiving: String) -> Result<JsValue, JsError> {
+        let sealed: SealedData = Encoded::from_encoded_str(&sealed).decode()?;
+        let receiving = SecretKey::try_from(receiving.as_str())?;
+        Ok(self
+            .processor
+            .open_sealed(&sealed, &receiving)?
+            .map(|data| Uint8Array::from(data.as_slice()).into())
+            .unwrap_or(JsValue::null()))
+    }
+
     /// send http request message to remote
     /// - destination: did
     /// - service: service name
@@ -475,12 +753,19 @@ fn get_did(address: &str, addr_type: AddressType) -> Result<Did, JsError> {
     Ok(did)
 }
 
+fn get_dids(addresses: &[String]) -> Result<Vec<Did>, JsError> {
+    addresses
+        .iter()
+        .map(|a| get_did(a, AddressType::DEFAULT))
+        .collect()
+}
+
 /// Get address from hex pubkey
 ///  * pubkey: hex pubkey
 #[wasm_export]
 pub fn get_address_from_hex_pubkey(pubkey: String) -> Result<String, JsError> {
     Ok(Did::from(
-        PublicKey::from_hex_string(pubkey.as_str())
+        PublicKey::<33>::from_hex_string(pubkey.as_str())
             .map_err(JsError::from)?
             .address(),
     )
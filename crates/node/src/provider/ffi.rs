@@ -40,6 +40,7 @@ use std::ffi::c_char;
 use std::ffi::CStr;
 use std::ffi::CString;
 use std::sync::Arc;
+use std::time::Duration;
 
 use futures::executor;
 use tokio::runtime::Runtime;
@@ -215,6 +216,33 @@ pub extern "C" fn request(
     }
 }
 
+/// Start renewing session of provider with signer, `margin` seconds ahead of its expiry.
+/// The signer is the same as the one of [new_provider_with_callback].
+/// This function will launch the renewal daemon in a new thread
+/// # Safety
+/// This function accept a ProviderPtr and will unsafety cast it into Arc based Provider
+#[no_mangle]
+pub extern "C" fn listen_session_renewal(
+    provider_ptr: *const ProviderPtr,
+    signer: extern "C" fn(*const c_char, *mut c_char) -> (),
+    margin: u64,
+) {
+    let provider: ProviderWithRuntime =
+        ProviderWithRuntime::from_raw(provider_ptr).expect("Provider ptr is invalid");
+    std::thread::spawn(move || {
+        provider.runtime.block_on(async {
+            provider
+                .provider
+                .session_renewal_daemon(
+                    Signer::Sync(Box::new(wrapped_signer(signer))),
+                    Duration::from_secs(margin),
+                    |_| {},
+                )
+                .await;
+        })
+    });
+}
+
 /// Craft a new Provider with signer and callback ptr
 /// # Safety
 ///
@@ -229,26 +257,6 @@ pub unsafe extern "C" fn new_provider_with_callback(
     signer: extern "C" fn(*const c_char, *mut c_char) -> (),
     callback_ptr: *const FFIBackendBehaviour,
 ) -> ProviderPtr {
-    fn wrapped_signer(
-        signer: extern "C" fn(*const c_char, *mut c_char) -> (),
-    ) -> impl Fn(String) -> Vec<u8> {
-        move |data: String| -> Vec<u8> {
-            let c_data = CString::new(data).expect("Failed to convert String to CString");
-            // 64 bytes sig + \0 here
-            let mut sig = Vec::<u8>::with_capacity(65);
-            let sig_ptr = sig.as_mut_ptr() as *mut c_char;
-            signer(c_data.as_ptr(), sig_ptr);
-
-            let c_ret = c_char_to_bytes(sig_ptr, 65).expect("Failed to convert c char to [u8]");
-            let c_ret_len = c_ret.len();
-            assert!(
-                c_ret.len() == 65,
-                "sig length({c_ret_len} < 64) is invalid: {c_ret:?}"
-            );
-            c_ret
-        }
-    }
-
     let provider: Provider = match (|| -> Result<Provider> {
         let ice: String = c_char_to_string(ice_server)?;
         let acc: String = c_char_to_string(account)?;
@@ -283,6 +291,26 @@ pub unsafe extern "C" fn new_provider_with_callback(
     ret
 }
 
+fn wrapped_signer(
+    signer: extern "C" fn(*const c_char, *mut c_char) -> (),
+) -> impl Fn(String) -> Vec<u8> {
+    move |data: String| -> Vec<u8> {
+        let c_data = CString::new(data).expect("Failed to convert String to CString");
+        // 64 bytes sig + \0 here
+        let mut sig = Vec::<u8>::with_capacity(65);
+        let sig_ptr = sig.as_mut_ptr() as *mut c_char;
+        signer(c_data.as_ptr(), sig_ptr);
+
+        let c_ret = c_char_to_bytes(sig_ptr, 65).expect("Failed to convert c char to [u8]");
+        let c_ret_len = c_ret.len();
+        assert!(
+            c_ret.len() == 65,
+            "sig length({c_ret_len} < 64) is invalid: {c_ret:?}"
+        );
+        c_ret
+    }
+}
+
 fn c_char_to_string(ptr: *const c_char) -> Result<String> {
     let c_str: &CStr = unsafe { CStr::from_ptr(ptr) };
     // Drop none utf8 sym here.
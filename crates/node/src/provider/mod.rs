@@ -4,8 +4,14 @@
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
+use rings_core::dht::Did;
 use rings_core::dht::VNodeStorage;
+use rings_core::message::MessageVerification;
+use rings_core::prelude::uuid;
+use rings_core::session::AccountSigner;
+use rings_core::session::SessionSk;
 use rings_core::session::SessionSkBuilder;
 use rings_core::storage::MemStorage;
 use rings_core::swarm::callback::SharedSwarmCallback;
@@ -14,6 +20,7 @@ use rings_rpc::protos::rings_node_handler::InternalRpcHandler;
 use crate::backend::types::BackendMessage;
 use crate::backend::types::MessageHandler;
 use crate::backend::Backend;
+use crate::consts::SESSION_RENEWAL_RETRY_INTERVAL;
 use crate::error::Error;
 use crate::error::Result;
 use crate::measure::MeasureStorage;
@@ -22,6 +29,7 @@ use crate::prelude::wasm_export;
 use crate::processor::Processor;
 use crate::processor::ProcessorBuilder;
 use crate::processor::ProcessorConfig;
+use crate::util::sleep;
 
 #[cfg(feature = "browser")]
 pub mod browser;
@@ -55,6 +63,22 @@ pub enum Signer {
     Sync(Box<dyn Fn(String) -> Vec<u8>>),
     /// Async signer
     Async(AsyncSigner),
+    /// Signer of account, which may keep the key in an external signer process
+    Account(Box<dyn AccountSigner>),
+}
+
+impl Signer {
+    /// Sign data with signer
+    pub async fn sign(&self, data: String) -> Vec<u8> {
+        match self {
+            Signer::Sync(s) => s(data),
+            Signer::Async(s) => s(data).await,
+            Signer::Account(s) => s.sign(&data).await.unwrap_or_else(|e| {
+                tracing::error!("Account signer failed: {:?}", e);
+                vec![]
+            }),
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -110,16 +134,105 @@ impl Provider {
     ) -> Result<Provider> {
         let mut sk_builder = SessionSkBuilder::new(account, account_type);
         let proof = sk_builder.unsigned_proof();
-        let sig = match signer {
-            Signer::Sync(s) => s(proof),
-            Signer::Async(s) => s(proof).await,
-        };
+        let sig = signer.sign(proof).await;
         sk_builder = sk_builder.set_session_sig(sig.to_vec());
         let session_sk = sk_builder.build().map_err(Error::InternalError)?;
         let config = ProcessorConfig::new(network_id, ice_servers, session_sk, stabilize_interval);
         Self::new_provider_with_storage_internal(config, vnode_storage, measure_storage).await
     }
 
+    /// Did of this node
+    pub(crate) fn did(&self) -> Did {
+        self.processor.did()
+    }
+
+    /// Session of this node, see [Processor::session_sk]
+    pub(crate) fn session_sk(&self) -> Result<SessionSk> {
+        self.processor.session_sk()
+    }
+
+    /// Sign data with session of this node, see [Processor::sign_data]
+    pub(crate) fn sign_data(&self, data: &[u8]) -> Result<MessageVerification> {
+        self.processor.sign_data(data)
+    }
+
+    /// Send backend message to did, see [Processor::send_backend_message]
+    pub(crate) async fn send_backend_message(
+        &self,
+        destination: Did,
+        msg: BackendMessage,
+    ) -> Result<uuid::Uuid> {
+        self.processor.send_backend_message(destination, msg).await
+    }
+
+    /// Request a new session of the same account from signer, then rotate to it,
+    /// see [Processor::rotate_session]
+    pub async fn renew_session_with_signer(&self, signer: &Signer) -> Result<SessionSk> {
+        let builder =
+            SessionSkBuilder::renew(&self.processor.session_sk()?).map_err(Error::InternalError)?;
+        let sig = signer.sign(builder.unsigned_proof()).await;
+        let session_sk = builder
+            .set_session_sig(sig)
+            .build()
+            .map_err(Error::InternalError)?;
+        self.processor.rotate_session(session_sk.clone()).await?;
+        Ok(session_sk)
+    }
+
+    /// Renew session with signer `margin` ahead of its expiry, forever.
+    /// Failed renewals are retried every [SESSION_RENEWAL_RETRY_INTERVAL] seconds,
+    /// `on_renewed` is called with every renewed session.
+    pub async fn session_renewal_daemon(
+        &self,
+        signer: Signer,
+        margin: Duration,
+        on_renewed: impl Fn(&SessionSk),
+    ) {
+        loop {
+            let due_in = match self.processor.session_renewal_due_in(margin) {
+                Ok(d) => d,
+                Err(e) => {
+                    tracing::error!("Failed to check session expiry: {:?}", e);
+                    Duration::from_secs(SESSION_RENEWAL_RETRY_INTERVAL)
+                }
+            };
+            if !due_in.is_zero() {
+                // Sleep at most one retry interval, so that clock changes are noticed.
+                let wait = due_in.min(Duration::from_secs(SESSION_RENEWAL_RETRY_INTERVAL));
+                if let Err(e) = sleep(wait).await {
+                    tracing::error!("Session renewal daemon stopped: {:?}", e);
+                    return;
+                }
+                continue;
+            }
+            match self.renew_session_with_signer(&signer).await {
+                Ok(session_sk) => {
+                    tracing::info!("Session renewed, expires at {}", session_sk.expires_at());
+                    on_renewed(&session_sk);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to renew session: {:?}", e);
+                    if let Err(e) = sleep(Duration::from_secs(SESSION_RENEWAL_RETRY_INTERVAL)).await
+                    {
+                        tracing::error!("Session renewal daemon stopped: {:?}", e);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get proxy re-encryption behaviour of processor
+    pub fn reencrypt(&self) -> crate::backend::reencrypt::ReencryptBehaviour {
+        self.processor.reencrypt.clone()
+    }
+
+    /// Get snark behaviour of processor, which tracks snark tasks of this node
+    #[cfg(feature = "snark")]
+    pub fn snark(&self) -> crate::backend::snark::SNARKBehaviour {
+        self.processor.snark.clone()
+    }
+
     /// Set callback for swarm, it can be T, or (T0, T1, T2)
     #[cfg(not(feature = "browser"))]
     pub fn set_backend_callback<T>(&self, callback: T) -> Result<()>
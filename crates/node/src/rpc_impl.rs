@@ -14,15 +14,20 @@ use jsonrpc_core::types::error::Error;
 use jsonrpc_core::types::error::ErrorCode;
 use jsonrpc_core::Result;
 use rings_core::dht::Did;
+use rings_core::directory::DeviceLink;
+use rings_core::document::DidDocument;
 use rings_core::message::Decoder;
 use rings_core::message::Encoded;
 use rings_core::message::Encoder;
 use rings_core::message::MessagePayload;
 use rings_core::message::MessageVerificationExt;
 use rings_core::prelude::vnode::VirtualNode;
+use rings_core::revocation::SessionRevocation;
 use rings_rpc::protos::rings_node::*;
 use rings_rpc::protos::rings_node_handler::HandleRpc;
 
+#[cfg(feature = "snark")]
+use crate::backend::snark::record::SNARK_TASK_TIMEOUT;
 use crate::error::Error as ServerError;
 use crate::processor::Processor;
 use crate::seed::Seed;
@@ -325,6 +330,177 @@ impl HandleRpc<NodeDidRequest, NodeDidResponse> for Processor {
     }
 }
 
+#[cfg_attr(feature = "browser", async_trait(?Send))]
+#[cfg_attr(not(feature = "browser"), async_trait)]
+impl HandleRpc<ListSnarkTasksRequest, ListSnarkTasksResponse> for Processor {
+    #[cfg(feature = "snark")]
+    async fn handle_rpc(&self, _req: ListSnarkTasksRequest) -> Result<ListSnarkTasksResponse> {
+        self.snark.expire_tasks(SNARK_TASK_TIMEOUT).await?;
+        Ok(ListSnarkTasksResponse {
+            tasks: self.snark.list_tasks(),
+        })
+    }
+
+    #[cfg(not(feature = "snark"))]
+    async fn handle_rpc(&self, _req: ListSnarkTasksRequest) -> Result<ListSnarkTasksResponse> {
+        Err(snark_disabled())
+    }
+}
+
+#[cfg_attr(feature = "browser", async_trait(?Send))]
+#[cfg_attr(not(feature = "browser"), async_trait)]
+impl HandleRpc<GetSnarkTaskRequest, GetSnarkTaskResponse> for Processor {
+    #[cfg(feature = "snark")]
+    async fn handle_rpc(&self, req: GetSnarkTaskRequest) -> Result<GetSnarkTaskResponse> {
+        self.snark.expire_tasks(SNARK_TASK_TIMEOUT).await?;
+        let task_id = uuid::Uuid::parse_str(&req.task_id)
+            .map_err(|_| Error::invalid_params(format!("Invalid task id: {}", req.task_id)))?;
+        let record = self.snark.get_task_record(req.task_id)?;
+        Ok(GetSnarkTaskResponse {
+            task: Some(record.to_info(&task_id)),
+        })
+    }
+
+    #[cfg(not(feature = "snark"))]
+    async fn handle_rpc(&self, _req: GetSnarkTaskRequest) -> Result<GetSnarkTaskResponse> {
+        Err(snark_disabled())
+    }
+}
+
+#[cfg_attr(feature = "browser", async_trait(?Send))]
+#[cfg_attr(not(feature = "browser"), async_trait)]
+impl HandleRpc<GetSnarkProofRequest, GetSnarkProofResponse> for Processor {
+    #[cfg(feature = "snark")]
+    async fn handle_rpc(&self, req: GetSnarkProofRequest) -> Result<GetSnarkProofResponse> {
+        let task_id = req.task_id.clone();
+        let proof = self.snark.get_task_proof(req.task_id)?.ok_or_else(|| {
+            Error::invalid_params(format!("Proof of {} is not received", task_id))
+        })?;
+        Ok((&proof).into())
+    }
+
+    #[cfg(not(feature = "snark"))]
+    async fn handle_rpc(&self, _req: GetSnarkProofRequest) -> Result<GetSnarkProofResponse> {
+        Err(snark_disabled())
+    }
+}
+
+#[cfg_attr(feature = "browser", async_trait(?Send))]
+#[cfg_attr(not(feature = "browser"), async_trait)]
+impl HandleRpc<RevokeSessionRequest, RevokeSessionResponse> for Processor {
+    async fn handle_rpc(&self, req: RevokeSessionRequest) -> Result<RevokeSessionResponse> {
+        let revocation = SessionRevocation::from_str(&req.revocation)
+            .map_err(|e| Error::invalid_params(format!("Invalid revocation: {e:?}")))?;
+        self.revoke_session(revocation).await?;
+        Ok(RevokeSessionResponse {})
+    }
+}
+
+#[cfg_attr(feature = "browser", async_trait(?Send))]
+#[cfg_attr(not(feature = "browser"), async_trait)]
+impl HandleRpc<ResolveDidRequest, ResolveDidResponse> for Processor {
+    async fn handle_rpc(&self, req: ResolveDidRequest) -> Result<ResolveDidResponse> {
+        let did = s2d(&req.did)?;
+        let Some(document) = self.resolve_did_document(did).await? else {
+            return Ok(ResolveDidResponse::default());
+        };
+        let raw = document.dump().map_err(ServerError::InternalError)?;
+        Ok(ResolveDidResponse {
+            document: Some(did_document_info(&document)),
+            raw,
+        })
+    }
+}
+
+#[cfg_attr(feature = "browser", async_trait(?Send))]
+#[cfg_attr(not(feature = "browser"), async_trait)]
+impl HandleRpc<LinkDeviceRequest, LinkDeviceResponse> for Processor {
+    async fn handle_rpc(&self, req: LinkDeviceRequest) -> Result<LinkDeviceResponse> {
+        let link = DeviceLink::from_str(&req.link)
+            .map_err(|e| Error::invalid_params(format!("Invalid device link: {e:?}")))?;
+        let account = link.account_did().to_string();
+        self.link_device(link).await?;
+        Ok(LinkDeviceResponse { account })
+    }
+}
+
+#[cfg_attr(feature = "browser", async_trait(?Send))]
+#[cfg_attr(not(feature = "browser"), async_trait)]
+impl HandleRpc<ResolveAccountRequest, ResolveAccountResponse> for Processor {
+    async fn handle_rpc(&self, req: ResolveAccountRequest) -> Result<ResolveAccountResponse> {
+        let account = s2d(&req.account)?;
+        let devices = self
+            .resolve_account_devices(account)
+            .await?
+            .iter()
+            .map(|entry| AccountDevice {
+                did: entry.device().to_string(),
+                session_id: entry.session().session_id().to_string(),
+                ts_ms: entry.ts_ms().try_into().unwrap_or(u64::MAX),
+                expires_at: entry.expires_at().try_into().unwrap_or(u64::MAX),
+            })
+            .collect();
+        Ok(ResolveAccountResponse { devices })
+    }
+}
+
+#[cfg_attr(feature = "browser", async_trait(?Send))]
+#[cfg_attr(not(feature = "browser"), async_trait)]
+impl HandleRpc<SendAccountMessageRequest, SendAccountMessageResponse> for Processor {
+    async fn handle_rpc(
+        &self,
+        req: SendAccountMessageRequest,
+    ) -> Result<SendAccountMessageResponse> {
+        let account = s2d(&req.account)?;
+        let data = base64::decode(req.data)
+            .map_err(|_| Error::invalid_params("Base64 decode data failed"))?;
+        let devices = self
+            .send_account_message(account, &data, req.fan_out)
+            .await?
+            .iter()
+            .map(|did| did.to_string())
+            .collect();
+        Ok(SendAccountMessageResponse { devices })
+    }
+}
+
+fn did_document_info(document: &DidDocument) -> rings_rpc::protos::rings_node::DidDocument {
+    let account_type = document
+        .session()
+        .account()
+        .to_entity_and_type()
+        .map(|(_, account_type)| account_type)
+        .unwrap_or_default();
+    rings_rpc::protos::rings_node::DidDocument {
+        id: document.id().to_string(),
+        session_id: document.session().session_id().to_string(),
+        session_pubkey: document
+            .session_pubkey()
+            .to_base58_string()
+            .unwrap_or_default(),
+        account_type,
+        protocols: document.protocols().to_vec(),
+        version: document.version().to_string(),
+        services: document.services().to_vec(),
+        endpoint: document.endpoint().unwrap_or_default().to_string(),
+        transports: document
+            .transports()
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+        ts_ms: document.ts_ms().try_into().unwrap_or(u64::MAX),
+    }
+}
+
+#[cfg(not(feature = "snark"))]
+fn snark_disabled() -> Error {
+    Error {
+        code: ErrorCode::MethodNotFound,
+        message: "snark is not enabled".to_string(),
+        data: None,
+    }
+}
+
 /// Get did from string or return InvalidParam Error
 fn s2d(s: &str) -> Result<Did> {
     Did::from_str(s).map_err(|_| Error::invalid_params(format!("Invalid Did: {s}")))
@@ -1,5 +1,40 @@
 use crate::backend::snark::*;
 
+type F = crate::backend::snark::Field;
+
+const WASM: &str = "../snark/src/tests/native/circoms/simple_bn256.wasm";
+const R1CS: &str = "../snark/src/tests/native/circoms/simple_bn256.r1cs";
+
+/// Task builder of the simple circom circuit over a field, with input `step_in = [4, 2]`
+struct SimpleCircuit {
+    builder: SNARKTaskBuilder,
+    field: SupportedPrimeField,
+}
+
+impl SimpleCircuit {
+    async fn new(field: SupportedPrimeField) -> Self {
+        let builder =
+            SNARKTaskBuilder::from_local(R1CS.to_string(), WASM.to_string(), field.clone())
+                .await
+                .unwrap();
+        Self { builder, field }
+    }
+
+    fn input(&self) -> Input {
+        vec![("step_in".to_string(), vec![
+            F::from_u64(4u64, self.field.clone()),
+            F::from_u64(2u64, self.field.clone()),
+        ])]
+        .into()
+    }
+
+    fn circuits(&self, steps: usize) -> Vec<Circuit> {
+        self.builder
+            .gen_circuits(self.input(), vec![], steps)
+            .unwrap()
+    }
+}
+
 #[tokio::test]
 pub async fn test_gen_proof_and_verify() {
     let wasm = "../snark/src/tests/native/circoms/simple_bn256.wasm";
@@ -24,3 +59,619 @@ pub async fn test_gen_proof_and_verify() {
     let ret = SNARKBehaviour::handle_snark_verify_task(&proof, &task).unwrap();
     assert!(ret)
 }
+
+#[tokio::test]
+pub async fn test_distributed_proof_task() {
+    use crate::backend::snark::coordinator::DistributeOptions;
+    use crate::prelude::rings_core::ecc::SecretKey;
+
+    let circuits = SimpleCircuit::new(SupportedPrimeField::Vesta)
+        .await
+        .circuits(4);
+    let task = SNARKBehaviour::gen_proof_task(circuits).unwrap();
+
+    let behaviour = SNARKBehaviour::default();
+    let provers = vec![
+        SecretKey::random().address().into(),
+        SecretKey::random().address().into(),
+    ];
+    let options = DistributeOptions {
+        parts: 2,
+        ..Default::default()
+    };
+    let task_id = behaviour
+        .new_distributed_task(&task, provers, options)
+        .await
+        .unwrap();
+    let progress = behaviour
+        .get_distributed_task_progress(task_id.to_string())
+        .unwrap();
+    assert_eq!(progress.total, 2);
+    assert_eq!(progress.pending, 2);
+
+    let parts = task.split(2);
+    // sub tasks are linked by their public io
+    assert_eq!(parts[0].io().unwrap().1, parts[1].io().unwrap().0);
+    let sub_task_ids = behaviour
+        .distributed
+        .get(&task_id)
+        .unwrap()
+        .parts
+        .iter()
+        .map(|p| p.task_id)
+        .collect::<Vec<_>>();
+
+    // proof of another sub task is rejected
+    let proof = SNARKBehaviour::handle_snark_proof_task(&parts[1]).unwrap();
+    behaviour
+        .on_sub_task_proof(sub_task_ids[0], &proof)
+        .await
+        .unwrap();
+    let progress = behaviour
+        .get_distributed_task_progress(task_id.to_string())
+        .unwrap();
+    assert_eq!(progress.done, 0);
+
+    behaviour
+        .on_sub_task_proof(sub_task_ids[1], &proof)
+        .await
+        .unwrap();
+    assert!(!behaviour.get_task_result(task_id.to_string()).unwrap());
+
+    // proof which doesn't prove the expected output of sub task is rejected
+    let proof = SNARKBehaviour::handle_snark_proof_task(&parts[0]).unwrap();
+    let expected = std::mem::replace(
+        &mut behaviour.distributed.get_mut(&task_id).unwrap().parts[0].output,
+        "[]".to_string(),
+    );
+    behaviour
+        .on_sub_task_proof(sub_task_ids[0], &proof)
+        .await
+        .unwrap();
+    assert!(!behaviour
+        .get_distributed_task_progress(task_id.to_string())
+        .unwrap()
+        .is_finished());
+
+    behaviour.distributed.get_mut(&task_id).unwrap().parts[0].output = expected;
+    behaviour
+        .on_sub_task_proof(sub_task_ids[0], &proof)
+        .await
+        .unwrap();
+    let progress = behaviour
+        .get_distributed_task_progress(task_id.to_string())
+        .unwrap();
+    assert!(progress.is_finished());
+    assert!(behaviour.get_task_result(task_id.to_string()).unwrap());
+    assert_eq!(
+        behaviour
+            .get_distributed_task_proofs(task_id.to_string())
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[tokio::test]
+pub async fn test_task_record_lifecycle() {
+    use crate::backend::snark::record::SNARKTaskRecord;
+    use crate::backend::snark::record::SNARKTaskStatus;
+    use crate::prelude::rings_core::storage::KvStorageInterface;
+    use crate::prelude::rings_core::storage::MemStorage;
+
+    let stored_id = uuid::Uuid::new_v4();
+    let storage = MemStorage::<SNARKTaskRecord>::new();
+    storage
+        .put(&stored_id.to_string(), &SNARKTaskRecord::new(None, None))
+        .await
+        .unwrap();
+
+    let snark = SNARKBehaviour::default();
+    snark.set_storage(Box::new(storage)).await.unwrap();
+    let record = snark.get_task_record(stored_id.to_string()).unwrap();
+    assert_eq!(record.status, SNARKTaskStatus::Queued);
+
+    let task_id = uuid::Uuid::new_v4();
+    snark
+        .save_record(task_id, SNARKTaskRecord::new(None, None))
+        .await
+        .unwrap();
+    snark
+        .update_status(task_id, SNARKTaskStatus::Sent, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        snark.get_task_record(task_id.to_string()).unwrap().status,
+        SNARKTaskStatus::Sent
+    );
+    assert_eq!(snark.list_tasks().len(), 2);
+
+    futures_timer::Delay::new(std::time::Duration::from_millis(10)).await;
+    snark.expire_tasks(0).await.unwrap();
+    let record = snark.get_task_record(task_id.to_string()).unwrap();
+    assert_eq!(record.status, SNARKTaskStatus::TimedOut);
+    assert!(snark.get_task_proof(task_id.to_string()).unwrap().is_none());
+    assert!(snark
+        .get_task_record(uuid::Uuid::new_v4().to_string())
+        .is_err());
+}
+
+#[tokio::test]
+pub async fn test_prover_queue_limits() {
+    use crate::backend::snark::prover::ProverOptions;
+    use crate::prelude::rings_core::dht::Did;
+    use crate::prelude::rings_core::ecc::SecretKey;
+
+    let circuit = SimpleCircuit::new(SupportedPrimeField::Vesta).await;
+    let task = SNARKBehaviour::gen_proof_task(circuit.circuits(4)).unwrap();
+    assert_eq!(task.size().0, 4);
+
+    let behaviour = SNARKBehaviour::default();
+    behaviour.set_prover_options(ProverOptions {
+        max_queue: 2,
+        max_per_requester: 1,
+        max_steps: 4,
+        ..Default::default()
+    });
+    let alice: Did = SecretKey::random().address().into();
+    let bob: Did = SecretKey::random().address().into();
+    let charlie: Did = SecretKey::random().address().into();
+
+    // task larger than limit is rejected
+    let large = SNARKBehaviour::gen_proof_task(circuit.circuits(5)).unwrap();
+    assert!(behaviour
+        .enqueue_proof_job(alice, uuid::Uuid::new_v4(), large)
+        .is_err());
+
+    // quota of requester
+    let alice_task = uuid::Uuid::new_v4();
+    behaviour
+        .enqueue_proof_job(alice, alice_task, task.clone())
+        .unwrap();
+    assert!(behaviour
+        .enqueue_proof_job(alice, uuid::Uuid::new_v4(), task.clone())
+        .is_err());
+
+    // queue depth
+    behaviour
+        .enqueue_proof_job(bob, uuid::Uuid::new_v4(), task.clone())
+        .unwrap();
+    assert!(behaviour
+        .enqueue_proof_job(charlie, uuid::Uuid::new_v4(), task.clone())
+        .is_err());
+    assert_eq!(behaviour.prover_info().queued, 2);
+
+    // only requester can cancel its task
+    assert!(!behaviour.cancel_proof_job(bob, alice_task));
+    assert!(behaviour.cancel_proof_job(alice, alice_task));
+    assert_eq!(behaviour.prover_info().queued, 1);
+    behaviour
+        .enqueue_proof_job(charlie, uuid::Uuid::new_v4(), task)
+        .unwrap();
+}
+
+#[tokio::test]
+pub async fn test_groth16_proof_task() {
+    use crate::backend::types::snark::SNARKVerifyTask;
+
+    let circuit = SimpleCircuit::new(SupportedPrimeField::Bn256KZG).await;
+    let gen_circuit = || circuit.circuits(1).remove(0);
+    let task = SNARKBehaviour::gen_groth16_proof_task(gen_circuit()).unwrap();
+    assert_eq!(task.split(2).len(), 1);
+    let proof = SNARKBehaviour::handle_snark_proof_task(&task).unwrap();
+    assert!(matches!(proof, SNARKVerifyTask::Groth16Bn256(_)));
+    let ret = SNARKBehaviour::handle_snark_verify_task(&proof, &task).unwrap();
+    assert!(ret);
+
+    // proof with keys of another setup doesn't verify against the pinned verifier key
+    let forged = SNARKBehaviour::gen_groth16_proof_task(gen_circuit()).unwrap();
+    let forged_proof = SNARKBehaviour::handle_snark_proof_task(&forged).unwrap();
+    assert!(!SNARKBehaviour::handle_snark_verify_task(&forged_proof, &task).unwrap());
+
+    let export = SNARKBehaviour::export_solidity_verifier(&proof, &task).unwrap();
+    assert!(export.contract.contains("function verifyProof("));
+    assert!(export.calldata.starts_with("0x"));
+    // selector and 8 words of proof, then public inputs
+    assert_eq!((export.calldata.len() - 2 - 8) % 64, 0);
+    assert!(export.calldata.len() > 2 + 8 + 8 * 64);
+    assert!(SNARKBehaviour::export_solidity_verifier(&forged_proof, &task).is_err());
+}
+
+#[tokio::test]
+pub async fn test_public_params_cache() {
+    use crate::backend::snark::params::SNARKPublicParams;
+    use crate::backend::types::snark::SNARKProofTask;
+    use crate::prelude::rings_core::dht::Did;
+    use crate::prelude::rings_core::ecc::SecretKey;
+    use crate::prelude::rings_core::storage::MemStorage;
+
+    let circuit = SimpleCircuit::new(SupportedPrimeField::Vesta).await;
+
+    let behaviour = SNARKBehaviour::default();
+    let storage = MemStorage::<SNARKPublicParams>::new();
+    behaviour.set_params_storage(Box::new(storage)).unwrap();
+
+    let task = behaviour
+        .gen_cached_proof_task(circuit.circuits(2))
+        .await
+        .unwrap();
+    let params_ref = task.params_ref().unwrap().clone();
+    assert!(behaviour
+        .get_matched_params(&params_ref)
+        .await
+        .unwrap()
+        .is_some());
+
+    // circuits of the same shape share public params
+    let other = behaviour
+        .gen_cached_proof_task(circuit.circuits(3))
+        .await
+        .unwrap();
+    assert_eq!(other.params_ref(), Some(&params_ref));
+
+    // public params are not sent with task
+    let json = serde_json::to_string(&task).unwrap();
+    let mut received: SNARKProofTask = serde_json::from_str(&json).unwrap();
+    assert!(received.public_params().is_none());
+    assert_eq!(received.params_ref(), Some(&params_ref));
+
+    // public params of pasta curves are deterministic, they can be regenerated
+    received.ensure_public_params().unwrap();
+    assert_eq!(
+        received.public_params().unwrap().digest(),
+        params_ref.digest
+    );
+    let proof = SNARKBehaviour::handle_snark_proof_task(&received).unwrap();
+    assert!(SNARKBehaviour::handle_snark_verify_task(&proof, &task).unwrap());
+
+    // public params not matching the digest are rejected
+    let tampered = json.replace(&params_ref.digest, &"0".repeat(params_ref.digest.len()));
+    let mut received: SNARKProofTask = serde_json::from_str(&tampered).unwrap();
+    let params = behaviour.get_public_params(&params_ref.key).await.unwrap();
+    assert!(received.set_public_params(params.unwrap()).is_err());
+    assert!(received.ensure_public_params().is_err());
+
+    // tasks waiting for public params are not run, but counted by limits of prover queue
+    let prover = SNARKBehaviour::default();
+    let requester: Did = SecretKey::random().address().into();
+    let received: SNARKProofTask = serde_json::from_str(&json).unwrap();
+    prover
+        .enqueue_proof_job(requester, uuid::Uuid::new_v4(), received)
+        .unwrap();
+    let info = prover.prover_info();
+    assert_eq!((info.queued, info.running), (1, 0));
+
+    // public params with KZG commitment keys are not regenerated
+    let circuits = SimpleCircuit::new(SupportedPrimeField::Bn256KZG)
+        .await
+        .circuits(2);
+    let task = SNARKBehaviour::gen_proof_task(circuits).unwrap();
+    let mut received: SNARKProofTask =
+        serde_json::from_str(&serde_json::to_string(&task).unwrap()).unwrap();
+    assert!(!received.params_regenerable());
+    assert!(received.ensure_public_params().is_err());
+}
+
+#[tokio::test]
+pub async fn test_quote_and_receipt() {
+    use crate::backend::snark::market::*;
+    use crate::backend::snark::prover::ProverOptions;
+    use crate::prelude::rings_core::ecc::SecretKey;
+    use crate::prelude::rings_core::message::MessageVerification;
+    use crate::prelude::SessionSk;
+
+    let circuits = SimpleCircuit::new(SupportedPrimeField::Vesta)
+        .await
+        .circuits(3);
+    let task = SNARKBehaviour::gen_proof_task(circuits).unwrap();
+    let description = task.description().unwrap();
+    assert_eq!(description.steps, 3);
+
+    let behaviour = SNARKBehaviour::default();
+    behaviour.set_prover_options(ProverOptions {
+        price_per_step: 10,
+        require_quote: true,
+        ..Default::default()
+    });
+    let prover_sk = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+    let prover = prover_sk.account_did();
+    let alice = SessionSk::new_with_seckey(&SecretKey::random())
+        .unwrap()
+        .account_did();
+    let bob = SessionSk::new_with_seckey(&SecretKey::random())
+        .unwrap()
+        .account_did();
+
+    // task without quote is rejected
+    assert!(behaviour
+        .enqueue_proof_job(alice, uuid::Uuid::new_v4(), task.clone())
+        .is_err());
+
+    // quoted task is only accepted from the requester
+    let task_id = uuid::Uuid::new_v4();
+    let quote = behaviour
+        .quote_task(prover, task_id, &description, alice)
+        .unwrap();
+    assert_eq!(quote.price, 30);
+    assert!(behaviour
+        .enqueue_proof_job(bob, task_id, task.clone())
+        .is_err());
+
+    let task_id = uuid::Uuid::new_v4();
+    behaviour
+        .quote_task(prover, task_id, &description, alice)
+        .unwrap();
+    // a quote is not overwritten, and quotes of other requesters are kept apart
+    assert!(behaviour
+        .quote_task(prover, task_id, &description, alice)
+        .is_err());
+    behaviour
+        .quote_task(prover, task_id, &description, bob)
+        .unwrap();
+    behaviour
+        .enqueue_proof_job(alice, task_id, task.clone())
+        .unwrap();
+
+    // quote is signed by prover
+    let sign = |data: &SNARKQuote, sk: &SessionSk| Signed {
+        data: data.clone(),
+        verification: MessageVerification::new(&serde_json::to_vec(data).unwrap(), sk).unwrap(),
+    };
+    let signed = sign(&quote, &prover_sk);
+    assert!(signed.verify(prover));
+    assert!(!signed.verify(alice));
+    let mut tampered = signed.clone();
+    tampered.data.price = 1;
+    assert!(!tampered.verify(prover));
+
+    // quote of unknown task is dropped, quote signed by others is rejected
+    assert!(behaviour.on_quote(&signed, prover).is_ok());
+    assert!(behaviour.on_quote(&signed, bob).is_err());
+    assert!(behaviour.list_quotes(task_id.to_string()).is_err());
+
+    // proof hash is stable through serialization
+    let proof = SNARKBehaviour::handle_snark_proof_task(&task).unwrap();
+    let json = serde_json::to_string(&proof).unwrap();
+    assert_eq!(
+        proof_hash(&proof).unwrap(),
+        proof_hash(&serde_json::from_str(&json).unwrap()).unwrap()
+    );
+    assert!(behaviour.list_receipts().is_empty());
+
+    // proof is verified with the pinned verifier key only, not the one sent with it
+    assert_eq!(task.vk_hash().len(), 64);
+    assert!(SNARKBehaviour::handle_snark_verify_task(&proof, &task).unwrap());
+    let mut pinned = serde_json::to_value(&task).unwrap();
+    pinned["VastaPallas"]["vk_hash"] = serde_json::json!("00");
+    let pinned: crate::backend::types::snark::SNARKProofTask =
+        serde_json::from_value(pinned).unwrap();
+    assert!(!SNARKBehaviour::handle_snark_verify_task(&proof, &pinned).unwrap());
+}
+
+#[tokio::test]
+pub async fn test_offline_snark_tools() {
+    use rings_snark::prelude::nova::provider::VestaEngine;
+    use rings_snark::prelude::nova::traits::Engine;
+    use rings_snark::r1cs;
+    use rings_snark::r1cs::writer;
+
+    use crate::native::snark;
+
+    let info = snark::inspect_r1cs(R1CS, SupportedPrimeField::Vesta).unwrap();
+    assert_eq!(info.constraints, 2);
+    assert_eq!(info.variables, 5);
+    assert_eq!(info.public, 4);
+    assert_eq!(info.private, 0);
+
+    type F = <VestaEngine as Engine>::Scalar;
+    let mut calculator = r1cs::load_circom_witness_calculator_local(WASM).unwrap();
+    let input = vec![("step_in".to_string(), vec![F::from(4u64), F::from(2u64)])];
+    let mut witness = calculator.calculate_witness::<F>(input, true).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("rings-snark-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let json = dir.join("witness.json");
+    let wtns = dir.join("witness.wtns");
+    writer::write_witness_to_json_file(&witness, &json).unwrap();
+
+    // json to binary and back
+    assert_eq!(
+        snark::convert_witness(&json, &wtns, SupportedPrimeField::Vesta).unwrap(),
+        5
+    );
+    assert_eq!(
+        snark::check_witness(R1CS, &wtns, SupportedPrimeField::Vesta).unwrap(),
+        2
+    );
+    snark::convert_witness(&wtns, &json, SupportedPrimeField::Vesta).unwrap();
+    assert_eq!(
+        snark::check_witness(R1CS, &json, SupportedPrimeField::Vesta).unwrap(),
+        2
+    );
+
+    // the first failing constraint is reported
+    witness[4] += F::from(1u64);
+    writer::write_witness_to_json_file(&witness, &json).unwrap();
+    let err = snark::check_witness(R1CS, &json, SupportedPrimeField::Vesta).unwrap_err();
+    assert!(matches!(
+        err,
+        crate::error::Error::RingsSNARKError(rings_snark::error::Error::UnsatisfiedConstraint(1))
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let input =
+        snark::parse_input(&["step_in=4, 2".to_string()], SupportedPrimeField::Vesta).unwrap();
+    assert!(snark::parse_input(&["step_in".to_string()], SupportedPrimeField::Vesta).is_err());
+    let proof = snark::prove(
+        r1cs.to_string(),
+        wasm.to_string(),
+        input,
+        2,
+        SupportedPrimeField::Vesta,
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        proof,
+        crate::backend::types::snark::SNARKVerifyTask::VastaPallas(_)
+    ));
+}
+
+#[tokio::test]
+pub async fn test_progress_and_checkpoint() {
+    use crate::backend::snark::progress::*;
+    use crate::backend::snark::prover::ProverOptions;
+    use crate::backend::snark::record::SNARKTaskRecord;
+    use crate::backend::types::snark::SNARKProofTask;
+    use crate::backend::types::snark::SNARKTask;
+    use crate::prelude::rings_core::dht::Did;
+    use crate::prelude::rings_core::ecc::SecretKey;
+
+    let circuits = SimpleCircuit::new(SupportedPrimeField::Vesta)
+        .await
+        .circuits(4);
+    let task = SNARKBehaviour::gen_proof_task(circuits).unwrap();
+
+    // prover reports every step and checkpoints every 2 steps
+    let options = ProverOptions {
+        progress_interval: 1,
+        checkpoint_interval: 2,
+        ..Default::default()
+    };
+    let mut folding = task.clone();
+    let mut reports = vec![];
+    fold_with_progress(&mut folding, &options, |msg| reports.push(msg)).unwrap();
+    assert_eq!(folding.folded(), 4);
+    let progress = reports
+        .iter()
+        .filter_map(|m| match m {
+            SNARKTask::SNARKProgress(p) => Some(p.folded),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(progress, vec![1, 2, 3, 4]);
+    let checkpoints = reports
+        .iter()
+        .filter_map(|m| match m {
+            SNARKTask::SNARKCheckpoint(t) => Some(t.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(checkpoints.len(), 1);
+    assert_eq!(checkpoints[0].folded(), 2);
+
+    // requester keeps progress and checkpoint of its prover only
+    let behaviour = SNARKBehaviour::default();
+    let prover: Did = SecretKey::random().address().into();
+    let other: Did = SecretKey::random().address().into();
+    let task_id = uuid::Uuid::new_v4();
+    behaviour
+        .save_record(
+            task_id,
+            SNARKTaskRecord::new(Some(task.clone()), Some(prover)),
+        )
+        .await
+        .unwrap();
+    let p = SNARKProgress {
+        folded: 2,
+        steps: 4,
+        eta: 1,
+    };
+    behaviour.on_progress(task_id, &p, other).await.unwrap();
+    behaviour
+        .on_checkpoint(task_id, &checkpoints[0], other)
+        .await
+        .unwrap();
+    let info = behaviour.get_task_record(task_id.to_string()).unwrap();
+    assert!(info.progress.is_none());
+    assert!(info.checkpoint.is_none());
+    behaviour.on_progress(task_id, &p, prover).await.unwrap();
+    behaviour
+        .on_checkpoint(task_id, &checkpoints[0], prover)
+        .await
+        .unwrap();
+    let info = behaviour.list_tasks().pop().unwrap();
+    assert_eq!((info.folded, info.steps, info.eta), (2, 4, Some(1)));
+    assert!(info.resumable);
+
+    // another prover resumes from checkpoint, the proof verifies against the original task
+    let checkpoint = behaviour
+        .get_task_record(task_id.to_string())
+        .unwrap()
+        .checkpoint
+        .unwrap();
+    let checkpoint: SNARKProofTask =
+        serde_json::from_str(&serde_json::to_string(&checkpoint).unwrap()).unwrap();
+    assert_eq!(checkpoint.folded(), 2);
+    let proof = SNARKBehaviour::handle_snark_proof_task(&checkpoint).unwrap();
+    assert!(SNARKBehaviour::handle_snark_verify_task(&proof, &task).unwrap());
+}
+
+#[tokio::test]
+pub async fn test_task_messages_from_prover_only() {
+    use std::sync::Arc;
+
+    use crate::backend::snark::record::SNARKTaskRecord;
+    use crate::backend::snark::record::SNARKTaskStatus;
+    use crate::backend::types::snark::SNARKTask;
+    use crate::backend::types::snark::SNARKTaskMessage;
+    use crate::backend::types::BackendMessage;
+    use crate::backend::types::MessageHandler;
+    use crate::prelude::rings_core::ecc::SecretKey;
+    use crate::prelude::rings_core::message::MessagePayload;
+    use crate::prelude::SessionSk;
+    use crate::provider::Provider;
+
+    let provider = Arc::new(Provider::from_processor(Arc::new(
+        crate::tests::native::prepare_processor().await,
+    )));
+    let prover = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+    let other = SessionSk::new_with_seckey(&SecretKey::random()).unwrap();
+    let behaviour = SNARKBehaviour::default();
+    let task_id = uuid::Uuid::new_v4();
+    behaviour
+        .save_record(
+            task_id,
+            SNARKTaskRecord::new(None, Some(prover.account_did())),
+        )
+        .await
+        .unwrap();
+    behaviour
+        .update_status(task_id, SNARKTaskStatus::Sent, None)
+        .await
+        .unwrap();
+
+    let handle = |session_sk: &SessionSk, task: SNARKTask| {
+        let msg = SNARKTaskMessage { task_id, task };
+        let did = session_sk.account_did();
+        let ctx = MessagePayload::new_send(
+            BackendMessage::SNARKTaskMessage(msg.clone()),
+            session_sk,
+            did,
+            did,
+        )
+        .unwrap();
+        let behaviour = &behaviour;
+        let provider = provider.clone();
+        async move {
+            MessageHandler::<SNARKTaskMessage>::handle_message(behaviour, provider, &ctx, &msg)
+                .await
+                .unwrap()
+        }
+    };
+    let status = || {
+        behaviour
+            .get_task_record(task_id.to_string())
+            .unwrap()
+            .status
+    };
+
+    handle(&other, SNARKTask::SNARKProving).await;
+    assert_eq!(status(), SNARKTaskStatus::Sent);
+    handle(&prover, SNARKTask::SNARKProving).await;
+    assert_eq!(status(), SNARKTaskStatus::Proving);
+    handle(&other, SNARKTask::SNARKRejected("busy".to_string())).await;
+    assert_eq!(status(), SNARKTaskStatus::Proving);
+    handle(&prover, SNARKTask::SNARKRejected("busy".to_string())).await;
+    assert_eq!(status(), SNARKTaskStatus::Failed);
+}
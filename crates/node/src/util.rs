@@ -72,6 +72,17 @@ pub fn build_version() -> String {
     infos.join("-")
 }
 
+/// Sleep for a duration, works in both native and browser.
+pub(crate) async fn sleep(duration: std::time::Duration) -> crate::error::Result<()> {
+    #[cfg(not(target_arch = "wasm32"))]
+    futures_timer::Delay::new(duration).await;
+    #[cfg(target_arch = "wasm32")]
+    rings_core::utils::js_utils::window_sleep(duration.as_millis().try_into().unwrap_or(i32::MAX))
+        .await
+        .map_err(|e| crate::error::Error::JsError(format!("Failed to sleep: {:?}", e)))?;
+    Ok(())
+}
+
 /// Expand path with "~" to absolute path.
 #[cfg(feature = "node")]
 pub fn expand_home<P>(path: P) -> Result<std::path::PathBuf, Error>
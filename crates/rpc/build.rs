@@ -8,6 +8,15 @@ fn main() {
     let config: BuildConfig = serde_yaml::from_str(config_content).unwrap();
     Builder::from(config).build_protos();
 
+    // Only services are generated, messages are shared with `src/protos/rings_node.rs`
+    #[cfg(feature = "grpc")]
+    tonic_build::configure()
+        .build_client(true)
+        .build_server(true)
+        .extern_path(".rings_node", "crate::protos::rings_node")
+        .compile(&["src/protos/rings_node.proto"], &["src/protos"])
+        .unwrap();
+
     Command::new("cargo")
         .args(["+nightly", "fmt"])
         .output()
@@ -0,0 +1,308 @@
+//! Authentication of rings-node internal api.
+//!
+//! Two kinds of credentials are supported:
+//! - Bearer token, sent as `Authorization: Bearer <token>`.
+//! - Signed request. The client signs `<method>\n<path>\n<timestamp>\n<nonce>\n<body>` with its
+//!   secp256k1 key, and sends the did of key, the timestamp in milliseconds, a random nonce and the
+//!   base64 encoded signature in headers [HEADER_SIGNER], [HEADER_TIMESTAMP], [HEADER_NONCE] and
+//!   [HEADER_SIGNATURE]. Requests signed more than [SIGNED_REQUEST_MAX_AGE] ago are rejected, and
+//!   the server remembers nonces in a [NonceCache] to reject replayed requests. Since the http
+//!   method and path are signed, a signature of a bodyless request such as `GET /status` can't be
+//!   used on another endpoint.
+//!
+//! Each method requires a [Scope], see [crate::method::Method::scope].
+#![warn(missing_docs)]
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use rings_core::dht::Did;
+use rings_core::ecc::signers::secp256k1;
+use rings_core::ecc::SecretKey;
+use rings_core::prelude::uuid;
+use rings_core::utils::get_epoch_ms;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::error::Result;
+
+/// Header of bearer token.
+pub const HEADER_AUTHORIZATION: &str = "authorization";
+/// Header of signer did of signed request.
+pub const HEADER_SIGNER: &str = "x-rings-signer";
+/// Header of timestamp of signed request, in milliseconds.
+pub const HEADER_TIMESTAMP: &str = "x-rings-timestamp";
+/// Header of random nonce of signed request.
+pub const HEADER_NONCE: &str = "x-rings-nonce";
+/// Header of base64 encoded signature of signed request.
+pub const HEADER_SIGNATURE: &str = "x-rings-signature";
+/// Max age of signed request in milliseconds.
+pub const SIGNED_REQUEST_MAX_AGE: u128 = 5 * 60 * 1000;
+/// Max number of nonces remembered by [NonceCache].
+pub const NONCE_CACHE_MAX_SIZE: usize = 65536;
+
+/// Permission required by rpc methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Inspect node, peers and topics.
+    Read,
+    /// Send messages, publish to topics and register services.
+    Messaging,
+    /// Connect and disconnect peers.
+    Admin,
+}
+
+/// Credential used by [crate::jsonrpc::Client].
+#[derive(Debug, Clone)]
+pub enum Credential {
+    /// Bearer token.
+    Bearer(String),
+    /// Sign each request with the key.
+    Signed(SecretKey),
+}
+
+/// A signed request received by server, see [verify_signed_request].
+#[derive(Debug, Clone, Copy)]
+pub struct SignedRequest<'a> {
+    /// Value of [HEADER_SIGNER].
+    pub signer: &'a str,
+    /// Value of [HEADER_TIMESTAMP].
+    pub timestamp: &'a str,
+    /// Value of [HEADER_NONCE].
+    pub nonce: &'a str,
+    /// Value of [HEADER_SIGNATURE].
+    pub signature: &'a str,
+    /// Http method, such as `POST`.
+    pub method: &'a str,
+    /// Path of request url, such as `/status`.
+    pub path: &'a str,
+    /// Body of request, empty if the request has no body.
+    pub body: &'a str,
+}
+
+/// Nonces of accepted signed requests, used by server to reject replayed requests.
+/// A nonce is forgotten after [SIGNED_REQUEST_MAX_AGE], when the request is rejected by its
+/// timestamp anyway. At most [NONCE_CACHE_MAX_SIZE] nonces are remembered, new requests are
+/// rejected if the cache is full.
+#[derive(Debug, Default)]
+pub struct NonceCache {
+    inner: Mutex<NonceCacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct NonceCacheInner {
+    seen: HashMap<(Did, String), u128>,
+    order: VecDeque<(u128, Did, String)>,
+}
+
+impl Credential {
+    /// Headers carrying the credential of a request with the http method, path and body.
+    pub fn headers(&self, method: &str, path: &str, body: &str) -> Vec<(&'static str, String)> {
+        match self {
+            Self::Bearer(token) => vec![(HEADER_AUTHORIZATION, format!("Bearer {}", token))],
+            Self::Signed(key) => {
+                let timestamp = get_epoch_ms();
+                let nonce = uuid::Uuid::new_v4().to_string();
+                let sig = key.sign(&signed_message(method, path, timestamp, &nonce, body));
+                vec![
+                    (HEADER_SIGNER, Did::from(key.address()).to_string()),
+                    (HEADER_TIMESTAMP, timestamp.to_string()),
+                    (HEADER_NONCE, nonce),
+                    (HEADER_SIGNATURE, base64::encode(sig)),
+                ]
+            }
+        }
+    }
+}
+
+/// Message signed by client of a signed request.
+pub fn signed_message(
+    method: &str,
+    path: &str,
+    timestamp: u128,
+    nonce: &str,
+    body: &str,
+) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        method.to_ascii_uppercase(),
+        path,
+        timestamp,
+        nonce,
+        body
+    )
+}
+
+impl NonceCache {
+    /// Remember the nonce of signer. Returns false if it's seen, or the cache is full.
+    pub fn check_and_insert(&self, signer: Did, nonce: &str, timestamp: u128) -> bool {
+        let Ok(mut inner) = self.inner.lock() else {
+            return false;
+        };
+        let now = get_epoch_ms();
+        while let Some((ts, did, nonce)) = inner.order.front().cloned() {
+            if now.saturating_sub(ts) <= SIGNED_REQUEST_MAX_AGE {
+                break;
+            }
+            inner.order.pop_front();
+            inner.seen.remove(&(did, nonce));
+        }
+
+        let key = (signer, nonce.to_string());
+        if inner.seen.contains_key(&key) || inner.seen.len() >= NONCE_CACHE_MAX_SIZE {
+            return false;
+        }
+        // Use the time of receiving, so that a nonce is kept until its request expires
+        let ts = now.max(timestamp);
+        inner.seen.insert(key.clone(), ts);
+        inner.order.push_back((ts, key.0, key.1));
+        true
+    }
+}
+
+/// Parse token from value of `Authorization` header.
+pub fn parse_bearer_token(value: &str) -> Option<&str> {
+    value
+        .strip_prefix("Bearer ")
+        .or_else(|| value.strip_prefix("bearer "))
+        .map(|t| t.trim())
+}
+
+/// Verify a signed request and return the did of signer.
+/// The nonce is remembered in `nonces`, so the same request is accepted only once.
+pub fn verify_signed_request(req: &SignedRequest, nonces: &NonceCache) -> Result<Did> {
+    let did = Did::from_str(req.signer).map_err(|_| Error::InvalidHeaders)?;
+    let timestamp: u128 = req.timestamp.parse().map_err(|_| Error::InvalidHeaders)?;
+    let sig = base64::decode(req.signature).map_err(|_| Error::InvalidHeaders)?;
+    if req.nonce.is_empty() {
+        return Err(Error::InvalidHeaders);
+    }
+
+    if get_epoch_ms().abs_diff(timestamp) > SIGNED_REQUEST_MAX_AGE {
+        return Err(Error::InvalidSignature);
+    }
+    let msg = signed_message(req.method, req.path, timestamp, req.nonce, req.body);
+    if !secp256k1::verify(msg.as_bytes(), &did.into(), sig) {
+        return Err(Error::InvalidSignature);
+    }
+    if !nonces.check_and_insert(did, req.nonce, timestamp) {
+        return Err(Error::ReplayedRequest);
+    }
+    Ok(did)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_request() {
+        let key = SecretKey::random();
+        let body = r#"{"jsonrpc":"2.0","method":"nodeInfo","params":{},"id":1}"#;
+        let headers = Credential::Signed(key).headers("POST", "/", body);
+        let get = |name: &str| {
+            headers
+                .iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.clone())
+                .unwrap()
+        };
+        let (signer, timestamp, nonce, signature) = (
+            get(HEADER_SIGNER),
+            get(HEADER_TIMESTAMP),
+            get(HEADER_NONCE),
+            get(HEADER_SIGNATURE),
+        );
+        let req = SignedRequest {
+            signer: &signer,
+            timestamp: &timestamp,
+            nonce: &nonce,
+            signature: &signature,
+            method: "POST",
+            path: "/",
+            body,
+        };
+
+        let nonces = NonceCache::default();
+        assert!(verify_signed_request(
+            &SignedRequest {
+                body: "tampered",
+                ..req
+            },
+            &nonces
+        )
+        .is_err());
+        assert!(verify_signed_request(
+            &SignedRequest {
+                method: "GET",
+                path: "/status",
+                body: "",
+                ..req
+            },
+            &nonces
+        )
+        .is_err());
+
+        let did = verify_signed_request(&req, &nonces).unwrap();
+        assert_eq!(did, Did::from(key.address()));
+        // Replayed request is rejected
+        assert!(matches!(
+            verify_signed_request(&req, &nonces),
+            Err(Error::ReplayedRequest)
+        ));
+
+        let expired = get_epoch_ms() - SIGNED_REQUEST_MAX_AGE - 1;
+        let sig = base64::encode(key.sign(&signed_message("POST", "/", expired, "n", body)));
+        let expired = expired.to_string();
+        assert!(verify_signed_request(
+            &SignedRequest {
+                timestamp: &expired,
+                nonce: "n",
+                signature: &sig,
+                ..req
+            },
+            &nonces
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_nonce_cache() {
+        let did = Did::from(SecretKey::random().address());
+        let other = Did::from(SecretKey::random().address());
+        let now = get_epoch_ms();
+        let nonces = NonceCache::default();
+
+        assert!(nonces.check_and_insert(did, "a", now));
+        assert!(!nonces.check_and_insert(did, "a", now));
+        assert!(nonces.check_and_insert(other, "a", now));
+
+        // Expired nonces are evicted
+        let expired = now - SIGNED_REQUEST_MAX_AGE - 1;
+        let stale = NonceCache::default();
+        stale
+            .inner
+            .lock()
+            .unwrap()
+            .order
+            .push_back((expired, did, "b".to_string()));
+        stale
+            .inner
+            .lock()
+            .unwrap()
+            .seen
+            .insert((did, "b".to_string()), expired);
+        assert!(stale.check_and_insert(did, "c", now));
+        assert!(stale.check_and_insert(did, "b", now));
+    }
+
+    #[test]
+    fn test_parse_bearer_token() {
+        assert_eq!(parse_bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(parse_bearer_token("Basic abc"), None);
+    }
+}
@@ -13,4 +13,6 @@ pub enum Error {
     InvalidSignature,
     #[error("Invalid headers.")]
     InvalidHeaders,
+    #[error("Replayed request.")]
+    ReplayedRequest,
 }
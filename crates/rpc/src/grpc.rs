@@ -0,0 +1,68 @@
+//! rings-rpc gRPC client
+//!
+//! The services are defined in `src/protos/rings_node.proto`, clients of other languages can be
+//! generated from the same file. The internal service accepts bearer tokens only, see
+//! [crate::auth].
+#![warn(missing_docs)]
+
+use tonic::metadata::MetadataValue;
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::Channel;
+use tonic::transport::Endpoint;
+use tonic::Request;
+use tonic::Status;
+
+use crate::auth::HEADER_AUTHORIZATION;
+pub use crate::protos::rings_node_grpc::external_service_client::ExternalServiceClient;
+pub use crate::protos::rings_node_grpc::external_service_server::ExternalService;
+pub use crate::protos::rings_node_grpc::external_service_server::ExternalServiceServer;
+pub use crate::protos::rings_node_grpc::internal_service_client::InternalServiceClient;
+pub use crate::protos::rings_node_grpc::internal_service_server::InternalService;
+pub use crate::protos::rings_node_grpc::internal_service_server::InternalServiceServer;
+
+/// Client of internal service which sends bearer token in each request.
+pub type InternalClient = InternalServiceClient<InterceptedService<Channel, BearerToken>>;
+
+/// Interceptor which adds `Authorization: Bearer <token>` to requests.
+#[derive(Debug, Clone, Default)]
+pub struct BearerToken(Option<String>);
+
+impl BearerToken {
+    /// Create interceptor, requests are sent without credential if token is None.
+    pub fn new(token: Option<String>) -> Self {
+        Self(token)
+    }
+}
+
+impl Interceptor for BearerToken {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(token) = self.0.as_ref() {
+            let value = MetadataValue::try_from(format!("Bearer {}", token))
+                .map_err(|_| Status::invalid_argument("invalid token"))?;
+            req.metadata_mut().insert(HEADER_AUTHORIZATION, value);
+        }
+        Ok(req)
+    }
+}
+
+/// Connect to internal service of rings node, such as `http://127.0.0.1:50002`.
+pub async fn connect_internal(
+    endpoint_url: &str,
+    token: Option<String>,
+) -> Result<InternalClient, tonic::transport::Error> {
+    let channel = Endpoint::from_shared(endpoint_url.to_string())?
+        .connect()
+        .await?;
+    Ok(InternalServiceClient::with_interceptor(
+        channel,
+        BearerToken::new(token),
+    ))
+}
+
+/// Connect to external service of rings node.
+pub async fn connect_external(
+    endpoint_url: &str,
+) -> Result<ExternalServiceClient<Channel>, tonic::transport::Error> {
+    ExternalServiceClient::connect(endpoint_url.to_string()).await
+}
@@ -3,6 +3,7 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::auth::Credential;
 use crate::method::Method;
 use crate::prelude::reqwest::Client as HttpClient;
 use crate::protos::rings_node::*;
@@ -11,6 +12,7 @@ use crate::protos::rings_node::*;
 pub struct Client {
     client: HttpClient,
     endpoint_url: String,
+    credential: Option<Credential>,
 }
 
 /// The errors returned by the client.
@@ -42,9 +44,16 @@ impl Client {
         Self {
             client: HttpClient::default(),
             endpoint_url: endpoint_url.to_string(),
+            credential: None,
         }
     }
 
+    /// Authenticate requests with the credential
+    pub fn with_credential(mut self, credential: Credential) -> Self {
+        self.credential = Some(credential);
+        self
+    }
+
     pub async fn call_method<T>(&self, method: Method, req: &impl Serialize) -> Result<T>
     where T: DeserializeOwned {
         use jsonrpc_core::*;
@@ -69,12 +78,20 @@ impl Client {
     async fn do_jsonrpc_request(&self, req: &jsonrpc_core::Request) -> Result<serde_json::Value> {
         let body = serde_json::to_string(req).map_err(|e| RpcError::Client(e.to_string()))?;
 
-        let req = self
+        let mut req = self
             .client
             .post(self.endpoint_url.as_str())
             .header("content-type", "application/json")
-            .header("accept", "application/json")
-            .body(body);
+            .header("accept", "application/json");
+        if let Some(credential) = self.credential.as_ref() {
+            let path = crate::prelude::reqwest::Url::parse(&self.endpoint_url)
+                .map(|url| url.path().to_string())
+                .unwrap_or_else(|_| "/".to_string());
+            for (name, value) in credential.headers("POST", &path, &body) {
+                req = req.header(name, value);
+            }
+        }
+        let req = req.body(body);
 
         let resp = req
             .send()
@@ -211,4 +228,51 @@ impl Client {
     pub async fn node_did(&self, req: &NodeDidRequest) -> Result<NodeDidResponse> {
         self.call_method(Method::NodeDid, req).await
     }
+
+    pub async fn list_snark_tasks(
+        &self,
+        req: &ListSnarkTasksRequest,
+    ) -> Result<ListSnarkTasksResponse> {
+        self.call_method(Method::ListSnarkTasks, req).await
+    }
+
+    pub async fn get_snark_task(&self, req: &GetSnarkTaskRequest) -> Result<GetSnarkTaskResponse> {
+        self.call_method(Method::GetSnarkTask, req).await
+    }
+
+    pub async fn get_snark_proof(
+        &self,
+        req: &GetSnarkProofRequest,
+    ) -> Result<GetSnarkProofResponse> {
+        self.call_method(Method::GetSnarkProof, req).await
+    }
+
+    pub async fn revoke_session(
+        &self,
+        req: &RevokeSessionRequest,
+    ) -> Result<RevokeSessionResponse> {
+        self.call_method(Method::RevokeSession, req).await
+    }
+
+    pub async fn resolve_did(&self, req: &ResolveDidRequest) -> Result<ResolveDidResponse> {
+        self.call_method(Method::ResolveDid, req).await
+    }
+
+    pub async fn link_device(&self, req: &LinkDeviceRequest) -> Result<LinkDeviceResponse> {
+        self.call_method(Method::LinkDevice, req).await
+    }
+
+    pub async fn resolve_account(
+        &self,
+        req: &ResolveAccountRequest,
+    ) -> Result<ResolveAccountResponse> {
+        self.call_method(Method::ResolveAccount, req).await
+    }
+
+    pub async fn send_account_message(
+        &self,
+        req: &SendAccountMessageRequest,
+    ) -> Result<SendAccountMessageResponse> {
+        self.call_method(Method::SendAccountMessage, req).await
+    }
 }
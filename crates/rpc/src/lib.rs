@@ -1,5 +1,8 @@
 //! rings rpc library
+pub mod auth;
 pub mod error;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod jsonrpc;
 pub mod method;
 pub mod prelude;
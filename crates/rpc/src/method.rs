@@ -1,6 +1,7 @@
 //! Rpc methods.
 #![warn(missing_docs)]
 
+use super::auth::Scope;
 use super::error::Error;
 use super::error::Result;
 
@@ -40,6 +41,22 @@ pub enum Method {
     NodeInfo,
     /// Retrieve Node DID
     NodeDid,
+    /// List snark tasks
+    ListSnarkTasks,
+    /// Get status of snark task
+    GetSnarkTask,
+    /// Get proof of snark task
+    GetSnarkProof,
+    /// Publish a session revocation
+    RevokeSession,
+    /// Resolve DID document of a node
+    ResolveDid,
+    /// Link node to account as a device
+    LinkDevice,
+    /// Resolve online devices of an account
+    ResolveAccount,
+    /// Send custom message to devices of an account
+    SendAccountMessage,
 }
 
 impl Method {
@@ -62,6 +79,44 @@ impl Method {
             Method::LookupService => "lookupService",
             Method::NodeInfo => "nodeInfo",
             Method::NodeDid => "nodeDid",
+            Method::ListSnarkTasks => "listSnarkTasks",
+            Method::GetSnarkTask => "getSnarkTask",
+            Method::GetSnarkProof => "getSnarkProof",
+            Method::RevokeSession => "revokeSession",
+            Method::ResolveDid => "resolveDid",
+            Method::LinkDevice => "linkDevice",
+            Method::ResolveAccount => "resolveAccount",
+            Method::SendAccountMessage => "sendAccountMessage",
+        }
+    }
+
+    /// Return the scope required to call the method
+    pub fn scope(&self) -> Scope {
+        match self {
+            Method::ListPeers
+            | Method::FetchTopicMessages
+            | Method::LookupService
+            | Method::NodeInfo
+            | Method::NodeDid
+            | Method::ListSnarkTasks
+            | Method::GetSnarkTask
+            | Method::GetSnarkProof
+            | Method::ResolveDid
+            | Method::ResolveAccount => Scope::Read,
+            Method::SendCustomMessage
+            | Method::SendBackendMessage
+            | Method::SendAccountMessage
+            | Method::PublishMessageToTopic
+            | Method::RegisterService => Scope::Messaging,
+            Method::ConnectPeerViaHttp
+            | Method::ConnectWithDid
+            | Method::ConnectWithSeed
+            | Method::CreateOffer
+            | Method::AnswerOffer
+            | Method::AcceptAnswer
+            | Method::Disconnect
+            | Method::RevokeSession
+            | Method::LinkDevice => Scope::Admin,
         }
     }
 }
@@ -93,6 +148,14 @@ impl TryFrom<&str> for Method {
             "lookupService" => Method::LookupService,
             "nodeInfo" => Method::NodeInfo,
             "nodeDid" => Method::NodeDid,
+            "listSnarkTasks" => Method::ListSnarkTasks,
+            "getSnarkTask" => Method::GetSnarkTask,
+            "getSnarkProof" => Method::GetSnarkProof,
+            "revokeSession" => Method::RevokeSession,
+            "resolveDid" => Method::ResolveDid,
+            "linkDevice" => Method::LinkDevice,
+            "resolveAccount" => Method::ResolveAccount,
+            "sendAccountMessage" => Method::SendAccountMessage,
             _ => return Err(Error::InvalidMethod),
         })
     }
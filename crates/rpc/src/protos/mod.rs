@@ -1,6 +1,13 @@
 pub mod rings_node;
 pub mod rings_node_handler;
 
+/// gRPC services of rings node, generated by tonic
+#[cfg(feature = "grpc")]
+#[allow(clippy::all, missing_docs)]
+pub mod rings_node_grpc {
+    tonic::include_proto!("rings_node");
+}
+
 use rings_core::inspect::ConnectionInspect;
 use rings_core::inspect::StorageInspect;
 use rings_core::inspect::SwarmInspect;
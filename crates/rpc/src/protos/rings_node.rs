@@ -264,11 +264,29 @@ pub struct SwarmInfo {
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnarkProverInfo {
+    /// Count of proof tasks waiting in queue
+    #[prost(uint32, tag = "1")]
+    pub queued: u32,
+    /// Count of proof tasks being proved
+    #[prost(uint32, tag = "2")]
+    pub running: u32,
+    #[prost(uint32, tag = "3")]
+    pub max_concurrency: u32,
+    #[prost(uint32, tag = "4")]
+    pub max_queue: u32,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct NodeInfoResponse {
     #[prost(string, tag = "1")]
     pub version: ::prost::alloc::string::String,
     #[prost(message, optional, tag = "2")]
     pub swarm: ::core::option::Option<SwarmInfo>,
+    /// Absent if snark is not enabled
+    #[prost(message, optional, tag = "3")]
+    pub snark_prover: ::core::option::Option<SnarkProverInfo>,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -281,3 +299,317 @@ pub struct NodeDidResponse {
     #[prost(string, tag = "1")]
     pub did: ::prost::alloc::string::String,
 }
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeEventsRequest {
+    /// Kinds of event to receive, such as `connection_state_change`, all kinds if empty
+    #[prost(string, repeated, tag = "1")]
+    pub events: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Only receive events related to these peers if not empty
+    #[prost(string, repeated, tag = "2")]
+    pub peers: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConnectionStateChangeEvent {
+    #[prost(string, tag = "1")]
+    pub peer: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub state: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CustomMessageEvent {
+    #[prost(string, tag = "1")]
+    pub from: ::prost::alloc::string::String,
+    /// Base64 encoded message
+    #[prost(string, tag = "2")]
+    pub data: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackendMessageEvent {
+    #[prost(string, tag = "1")]
+    pub from: ::prost::alloc::string::String,
+    /// Json encoded backend message
+    #[prost(string, tag = "2")]
+    pub data: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TopicMessageEvent {
+    #[prost(string, tag = "1")]
+    pub topic: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub data: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StabilizationEvent {
+    #[prost(string, repeated, tag = "1")]
+    pub successors: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "2")]
+    pub predecessor: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionRenewedEvent {
+    /// Did of peer
+    #[prost(string, tag = "1")]
+    pub peer: ::prost::alloc::string::String,
+    /// Did of the new session
+    #[prost(string, tag = "2")]
+    pub session: ::prost::alloc::string::String,
+    /// Timestamp in milliseconds when the new session expires
+    #[prost(uint64, tag = "3")]
+    pub expires_at: u64,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NodeEvent {
+    #[prost(oneof = "node_event::Event", tags = "1, 2, 3, 4, 5, 6")]
+    pub event: ::core::option::Option<node_event::Event>,
+}
+/// Nested message and enum types in `NodeEvent`.
+pub mod node_event {
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Event {
+        #[prost(message, tag = "1")]
+        ConnectionStateChange(super::ConnectionStateChangeEvent),
+        #[prost(message, tag = "2")]
+        CustomMessage(super::CustomMessageEvent),
+        #[prost(message, tag = "3")]
+        BackendMessage(super::BackendMessageEvent),
+        #[prost(message, tag = "4")]
+        TopicMessage(super::TopicMessageEvent),
+        #[prost(message, tag = "5")]
+        Stabilization(super::StabilizationEvent),
+        #[prost(message, tag = "6")]
+        SessionRenewed(super::SessionRenewedEvent),
+    }
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeTopicRequest {
+    #[prost(string, tag = "1")]
+    pub topic: ::prost::alloc::string::String,
+    /// Messages before this index are not sent
+    #[prost(int64, tag = "2")]
+    pub skip: i64,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnarkTaskInfo {
+    #[prost(string, tag = "1")]
+    pub task_id: ::prost::alloc::string::String,
+    /// One of queued, sent, proving, proof_received, verified, failed, timed_out, cancelled
+    #[prost(string, tag = "2")]
+    pub status: ::prost::alloc::string::String,
+    /// Did of prover
+    #[prost(string, optional, tag = "3")]
+    pub prover: ::core::option::Option<::prost::alloc::string::String>,
+    /// Timestamp of creation in milliseconds
+    #[prost(uint64, tag = "4")]
+    pub created_at: u64,
+    /// Timestamp of last status change in milliseconds
+    #[prost(uint64, tag = "5")]
+    pub updated_at: u64,
+    /// Reason of failure
+    #[prost(string, optional, tag = "6")]
+    pub error: ::core::option::Option<::prost::alloc::string::String>,
+    /// Count of steps folded by prover
+    #[prost(uint64, tag = "7")]
+    pub folded: u64,
+    /// Count of all steps of task
+    #[prost(uint64, tag = "8")]
+    pub steps: u64,
+    /// Estimated seconds to fold the remaining steps, reported by prover
+    #[prost(uint64, optional, tag = "9")]
+    pub eta: ::core::option::Option<u64>,
+    /// Prover sent a checkpoint, the task can be resumed on another prover
+    #[prost(bool, tag = "10")]
+    pub resumable: bool,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSnarkTasksRequest {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSnarkTasksResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub tasks: ::prost::alloc::vec::Vec<SnarkTaskInfo>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSnarkTaskRequest {
+    #[prost(string, tag = "1")]
+    pub task_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSnarkTaskResponse {
+    #[prost(message, optional, tag = "1")]
+    pub task: ::core::option::Option<SnarkTaskInfo>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSnarkProofRequest {
+    #[prost(string, tag = "1")]
+    pub task_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSnarkProofResponse {
+    /// Curve cycle of proof, such as `Bn256KZGGrumpkin`
+    #[prost(string, tag = "1")]
+    pub curve: ::prost::alloc::string::String,
+    /// Json encoded compressed proof and verifier key
+    #[prost(string, tag = "2")]
+    pub proof: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevokeSessionRequest {
+    /// Dumped revocation signed by account of the session
+    #[prost(string, tag = "1")]
+    pub revocation: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevokeSessionResponse {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResolveDidRequest {
+    #[prost(string, tag = "1")]
+    pub did: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DidDocument {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub session_id: ::prost::alloc::string::String,
+    /// Base58 encoded compressed public key of session
+    #[prost(string, tag = "3")]
+    pub session_pubkey: ::prost::alloc::string::String,
+    /// Type of account, such as `secp256k1`
+    #[prost(string, tag = "4")]
+    pub account_type: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "5")]
+    pub protocols: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "6")]
+    pub version: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "7")]
+    pub services: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// External endpoint URL, empty if node has none
+    #[prost(string, tag = "8")]
+    pub endpoint: ::prost::alloc::string::String,
+    /// Transports which node can be connected by, such as `webrtc` and `http`
+    #[prost(string, repeated, tag = "9")]
+    pub transports: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(uint64, tag = "10")]
+    pub ts_ms: u64,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResolveDidResponse {
+    /// Absent if no valid document is found
+    #[prost(message, optional, tag = "1")]
+    pub document: ::core::option::Option<DidDocument>,
+    /// Dumped document, which can be verified by anyone, empty if not found
+    #[prost(string, tag = "2")]
+    pub raw: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LinkDeviceRequest {
+    /// Dumped link signed by account of user, whose device is this node
+    #[prost(string, tag = "1")]
+    pub link: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LinkDeviceResponse {
+    /// Did of the account which this node is linked to
+    #[prost(string, tag = "1")]
+    pub account: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResolveAccountRequest {
+    #[prost(string, tag = "1")]
+    pub account: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AccountDevice {
+    /// Did of the node of device
+    #[prost(string, tag = "1")]
+    pub did: ::prost::alloc::string::String,
+    /// Did of current session of device
+    #[prost(string, tag = "2")]
+    pub session_id: ::prost::alloc::string::String,
+    /// Timestamp when device announced it is online
+    #[prost(uint64, tag = "3")]
+    pub ts_ms: u64,
+    /// Timestamp when device is considered offline
+    #[prost(uint64, tag = "4")]
+    pub expires_at: u64,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResolveAccountResponse {
+    /// Online devices of account, the most recently announced first
+    #[prost(message, repeated, tag = "1")]
+    pub devices: ::prost::alloc::vec::Vec<AccountDevice>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SendAccountMessageRequest {
+    #[prost(string, tag = "1")]
+    pub account: ::prost::alloc::string::String,
+    /// Base64 encoded custom message
+    #[prost(string, tag = "2")]
+    pub data: ::prost::alloc::string::String,
+    /// Send to all online devices instead of one of them
+    #[prost(bool, tag = "3")]
+    pub fan_out: bool,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SendAccountMessageResponse {
+    /// Dids of devices which message is sent to
+    #[prost(string, repeated, tag = "1")]
+    pub devices: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
@@ -48,7 +48,15 @@ impl InternalRpcHandler {
             + HandleRpc<RegisterServiceRequest, RegisterServiceResponse>
             + HandleRpc<LookupServiceRequest, LookupServiceResponse>
             + HandleRpc<NodeInfoRequest, NodeInfoResponse>
-            + HandleRpc<NodeDidRequest, NodeDidResponse>,
+            + HandleRpc<NodeDidRequest, NodeDidResponse>
+            + HandleRpc<ListSnarkTasksRequest, ListSnarkTasksResponse>
+            + HandleRpc<GetSnarkTaskRequest, GetSnarkTaskResponse>
+            + HandleRpc<GetSnarkProofRequest, GetSnarkProofResponse>
+            + HandleRpc<RevokeSessionRequest, RevokeSessionResponse>
+            + HandleRpc<ResolveDidRequest, ResolveDidResponse>
+            + HandleRpc<LinkDeviceRequest, LinkDeviceResponse>
+            + HandleRpc<ResolveAccountRequest, ResolveAccountResponse>
+            + HandleRpc<SendAccountMessageRequest, SendAccountMessageResponse>,
     {
         let method = Method::try_from(method.as_str()).map_err(|_| Error {
             code: ErrorCode::MethodNotFound,
@@ -153,6 +161,54 @@ impl InternalRpcHandler {
                 let resp = processor.handle_rpc(req).await?;
                 serde_json::to_value(resp).map_err(|_| Error::new(ErrorCode::ParseError))
             }
+            Method::ListSnarkTasks => {
+                let req = serde_json::from_value::<ListSnarkTasksRequest>(params)
+                    .map_err(|e| Error::invalid_params(e.to_string()))?;
+                let resp = processor.handle_rpc(req).await?;
+                serde_json::to_value(resp).map_err(|_| Error::new(ErrorCode::ParseError))
+            }
+            Method::GetSnarkTask => {
+                let req = serde_json::from_value::<GetSnarkTaskRequest>(params)
+                    .map_err(|e| Error::invalid_params(e.to_string()))?;
+                let resp = processor.handle_rpc(req).await?;
+                serde_json::to_value(resp).map_err(|_| Error::new(ErrorCode::ParseError))
+            }
+            Method::GetSnarkProof => {
+                let req = serde_json::from_value::<GetSnarkProofRequest>(params)
+                    .map_err(|e| Error::invalid_params(e.to_string()))?;
+                let resp = processor.handle_rpc(req).await?;
+                serde_json::to_value(resp).map_err(|_| Error::new(ErrorCode::ParseError))
+            }
+            Method::RevokeSession => {
+                let req = serde_json::from_value::<RevokeSessionRequest>(params)
+                    .map_err(|e| Error::invalid_params(e.to_string()))?;
+                let resp = processor.handle_rpc(req).await?;
+                serde_json::to_value(resp).map_err(|_| Error::new(ErrorCode::ParseError))
+            }
+            Method::ResolveDid => {
+                let req = serde_json::from_value::<ResolveDidRequest>(params)
+                    .map_err(|e| Error::invalid_params(e.to_string()))?;
+                let resp = processor.handle_rpc(req).await?;
+                serde_json::to_value(resp).map_err(|_| Error::new(ErrorCode::ParseError))
+            }
+            Method::LinkDevice => {
+                let req = serde_json::from_value::<LinkDeviceRequest>(params)
+                    .map_err(|e| Error::invalid_params(e.to_string()))?;
+                let resp = processor.handle_rpc(req).await?;
+                serde_json::to_value(resp).map_err(|_| Error::new(ErrorCode::ParseError))
+            }
+            Method::ResolveAccount => {
+                let req = serde_json::from_value::<ResolveAccountRequest>(params)
+                    .map_err(|e| Error::invalid_params(e.to_string()))?;
+                let resp = processor.handle_rpc(req).await?;
+                serde_json::to_value(resp).map_err(|_| Error::new(ErrorCode::ParseError))
+            }
+            Method::SendAccountMessage => {
+                let req = serde_json::from_value::<SendAccountMessageRequest>(params)
+                    .map_err(|e| Error::invalid_params(e.to_string()))?;
+                let resp = processor.handle_rpc(req).await?;
+                serde_json::to_value(resp).map_err(|_| Error::new(ErrorCode::ParseError))
+            }
         }
     }
 }
@@ -1,7 +1,11 @@
 //! implement bellman proof system for circuit, this is useful for plonk and growth16
 
 use super::Circuit;
+use crate::error;
+use crate::error::Error;
 use crate::prelude::bellman;
+use crate::prelude::bellman::pairing::ff::PrimeField as BellmanPrimeField;
+use crate::prelude::bellman::pairing::ff::PrimeFieldRepr;
 use crate::prelude::bellman::pairing::Engine;
 use crate::prelude::bellman::ConstraintSystem;
 use crate::prelude::bellman::Index;
@@ -54,3 +58,97 @@ where E::Fr: ff::PrimeField
         Ok(())
     }
 }
+
+/// Convert element of circuit field to the same element of bellman field, both fields should
+/// have the same modulus.
+pub fn convert_field<F, Fr>(f: &F) -> error::Result<Fr>
+where
+    F: ff::PrimeField,
+    Fr: BellmanPrimeField,
+{
+    let mut repr = Fr::Repr::default();
+    repr.read_le(f.to_repr().as_ref())?;
+    Fr::from_repr(repr).map_err(|e| Error::FieldConversion(e.to_string()))
+}
+
+/// Circuit with r1cs and witness converted to the scalar field of bellman engine
+pub struct BellmanCircuit<E: Engine> {
+    num_inputs: usize,
+    num_aux: usize,
+    constraints: Vec<BellmanConstraint<E>>,
+    witness: Vec<E::Fr>,
+}
+
+type BellmanConstraint<E> = (
+    Vec<(usize, <E as Engine>::Fr)>,
+    Vec<(usize, <E as Engine>::Fr)>,
+    Vec<(usize, <E as Engine>::Fr)>,
+);
+
+impl<E: Engine> BellmanCircuit<E> {
+    /// Convert circuit to bellman engine
+    pub fn try_from_circuit<F: ff::PrimeField>(circuit: &Circuit<F>) -> error::Result<Self> {
+        let convert_lc = |lc: &Vec<(usize, F)>| {
+            lc.iter()
+                .map(|(i, f)| Ok((*i, convert_field::<F, E::Fr>(f)?)))
+                .collect::<error::Result<Vec<_>>>()
+        };
+        let constraints = circuit
+            .r1cs
+            .constraints
+            .iter()
+            .map(|(a, b, c)| Ok((convert_lc(a)?, convert_lc(b)?, convert_lc(c)?)))
+            .collect::<error::Result<Vec<_>>>()?;
+        let witness = circuit
+            .witness
+            .iter()
+            .map(convert_field::<F, E::Fr>)
+            .collect::<error::Result<Vec<_>>>()?;
+        Ok(Self {
+            num_inputs: circuit.r1cs.num_inputs,
+            num_aux: circuit.r1cs.num_aux,
+            constraints,
+            witness,
+        })
+    }
+}
+
+impl<E: Engine> bellman::Circuit<E> for BellmanCircuit<E> {
+    fn synthesize<CS: ConstraintSystem<E>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        for i in 1..self.num_inputs {
+            let f = self.witness[i];
+            cs.alloc_input(|| format!("variable {}", i), || Ok(f))?;
+        }
+        for i in 0..self.num_aux {
+            let f = self.witness[i + self.num_inputs];
+            cs.alloc(|| format!("aux {}", i), || Ok(f))?;
+        }
+
+        let num_inputs = self.num_inputs;
+        let make_lc = |lc_data: &Vec<(usize, E::Fr)>| {
+            lc_data.iter().fold(
+                LinearCombination::<E>::zero(),
+                |lc: LinearCombination<E>, (index, coeff)| {
+                    let index = if *index < num_inputs {
+                        Index::Input(*index)
+                    } else {
+                        Index::Aux(*index - num_inputs)
+                    };
+                    lc + (*coeff, Variable::new_unchecked(index))
+                },
+            )
+        };
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            // 0 * LC = 0 must be ignored
+            if !((constraint.0.is_empty() || constraint.1.is_empty()) && constraint.2.is_empty()) {
+                cs.enforce(
+                    || format!("{}", i),
+                    |_| make_lc(&constraint.0),
+                    |_| make_lc(&constraint.1),
+                    |_| make_lc(&constraint.2),
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -15,10 +15,16 @@ use ff::PrimeField;
 use nova_snark::traits::circuit::StepCircuit;
 use serde::Deserialize;
 use serde::Serialize;
+use tiny_keccak::Hasher;
+use tiny_keccak::Keccak;
 
 use crate::error::Result;
+#[cfg(feature = "native-witness")]
+use crate::r1cs::Symbols;
 use crate::r1cs::R1CS;
 use crate::witness::calculator::WitnessCalculator;
+#[cfg(feature = "native-witness")]
+use crate::witness::solver::WitnessSolver;
 
 pub mod bellman;
 pub mod bellpepper;
@@ -146,55 +152,122 @@ impl<F: PrimeField> WasmCircuitGenerator<F> {
     where
         F: PrimeField,
     {
-        fn reshape<F: PrimeField>(input: &[(String, Vec<F>)], output: &[F]) -> Input<F> {
-            let mut ret = vec![];
-            let mut iter = output.iter();
-
-            for (val, vec) in input.iter() {
-                let size = vec.len();
-                let mut new_vec: Vec<F> = Vec::with_capacity(size);
-                for _ in 0..size {
-                    if let Some(item) = iter.next() {
-                        new_vec.push(*item);
-                    } else {
-                        panic!(
-                            "Failed on reshape output {:?} as input format {:?}",
-                            output, input
-                        )
-                    }
-                }
-                ret.push((val.clone(), new_vec));
-            }
-            ret.into()
+        let mut calc = self.calculator.borrow_mut();
+        gen_recursive_circuit(
+            self.r1cs.clone(),
+            public_input,
+            private_inputs,
+            times,
+            |input| calc.calculate_witness::<F>(input, sanity_check),
+        )
+    }
+}
+
+/// Circuit generator which solves witness from constraints of r1cs, without the wasm runtime
+#[cfg(feature = "native-witness")]
+pub struct SolverCircuitGenerator<F: PrimeField> {
+    r1cs: Arc<R1CS<F>>,
+    solver: Rc<RefCell<WitnessSolver<F>>>,
+}
+
+#[cfg(feature = "native-witness")]
+impl<F: PrimeField> SolverCircuitGenerator<F> {
+    /// Create new instance, inputs are mapped to signals by symbols of circuit
+    pub fn new(r1cs: R1CS<F>, symbols: Symbols) -> Self {
+        let r1cs = Arc::new(r1cs);
+        Self {
+            solver: Rc::new(RefCell::new(WitnessSolver::new(r1cs.clone(), symbols))),
+            r1cs,
         }
+    }
+
+    /// Generate circuit from input
+    pub fn gen_circuit(&self, input: Input<F>, sanity_check: bool) -> Result<Circuit<F>> {
+        let witness = self
+            .solver
+            .borrow_mut()
+            .calculate_witness(input.to_vec(), sanity_check)?;
+        Ok(Circuit::<F> {
+            r1cs: self.r1cs.clone(),
+            witness,
+        })
+    }
 
+    /// Generate recursive circuit list
+    /// Which use $output_{i-1}$ as $input_i$
+    pub fn gen_recursive_circuit(
+        &self,
+        public_input: Input<F>,
+        private_inputs: Vec<Input<F>>,
+        times: usize,
+        sanity_check: bool,
+    ) -> Result<Vec<Circuit<F>>> {
+        let mut solver = self.solver.borrow_mut();
+        gen_recursive_circuit(
+            self.r1cs.clone(),
+            public_input,
+            private_inputs,
+            times,
+            |input| solver.calculate_witness(input, sanity_check),
+        )
+    }
+}
+
+/// Generate recursive circuit list with witness calculating function
+fn gen_recursive_circuit<F, W>(
+    r1cs: Arc<R1CS<F>>,
+    public_input: Input<F>,
+    private_inputs: Vec<Input<F>>,
+    times: usize,
+    mut calculate_witness: W,
+) -> Result<Vec<Circuit<F>>>
+where
+    F: PrimeField,
+    W: FnMut(Vec<(String, Vec<F>)>) -> Result<Vec<F>>,
+{
+    fn reshape<F: PrimeField>(input: &[(String, Vec<F>)], output: &[F]) -> Input<F> {
         let mut ret = vec![];
-        let mut calc = self.calculator.borrow_mut();
-        let mut latest_output: Input<F> = vec![].into();
-        for i in 0..times {
-            let witness: Vec<F> = if latest_output.is_empty() {
-                let mut input = public_input.clone();
-                if let Some(p) = private_inputs.get(i) {
-                    input.input.extend(p.to_owned());
-                }
-                calc.calculate_witness::<F>(input.to_vec(), sanity_check)?
-            } else {
-                let mut input = latest_output.clone();
-                if let Some(p) = private_inputs.get(i) {
-                    input.input.extend(p.to_owned());
+        let mut iter = output.iter();
+
+        for (val, vec) in input.iter() {
+            let size = vec.len();
+            let mut new_vec: Vec<F> = Vec::with_capacity(size);
+            for _ in 0..size {
+                if let Some(item) = iter.next() {
+                    new_vec.push(*item);
+                } else {
+                    panic!(
+                        "Failed on reshape output {:?} as input format {:?}",
+                        output, input
+                    )
                 }
-                calc.calculate_witness::<F>(input.to_vec(), sanity_check)?
-            };
-            let circom = Circuit::<F> {
-                r1cs: self.r1cs.clone(),
-                witness: witness.clone(),
-            };
-            log::trace!("witness: {:?}, r1cs: {:?}", witness, self.r1cs);
-            latest_output = reshape(&public_input, &circom.get_public_outputs());
-            ret.push(circom);
+            }
+            ret.push((val.clone(), new_vec));
+        }
+        ret.into()
+    }
+
+    let mut ret = vec![];
+    let mut latest_output: Input<F> = vec![].into();
+    for i in 0..times {
+        let mut input = if latest_output.is_empty() {
+            public_input.clone()
+        } else {
+            latest_output.clone()
+        };
+        if let Some(p) = private_inputs.get(i) {
+            input.input.extend(p.to_owned());
         }
-        Ok(ret)
+        let witness: Vec<F> = calculate_witness(input.to_vec())?;
+        let circom = Circuit::<F> {
+            r1cs: r1cs.clone(),
+            witness: witness.clone(),
+        };
+        log::trace!("witness: {:?}, r1cs: {:?}", witness, r1cs);
+        latest_output = reshape(&public_input, &circom.get_public_outputs());
+        ret.push(circom);
     }
+    Ok(ret)
 }
 
 impl<F: PrimeField> Circuit<F> {
@@ -203,6 +276,21 @@ impl<F: PrimeField> Circuit<F> {
         Self { r1cs, witness }
     }
 
+    /// get number of constraints of circuit
+    pub fn num_constraints(&self) -> usize {
+        self.r1cs.constraints.len()
+    }
+
+    /// Keccak digest of circuit shape in hex, which is the r1cs without witness.
+    /// Circuits of the same shape share public params.
+    pub fn shape_digest(&self) -> Result<String> {
+        let mut hash = [0u8; 32];
+        let mut keccak = Keccak::v256();
+        keccak.update(&serde_json::to_vec(self.r1cs.as_ref())?);
+        keccak.finalize(&mut hash);
+        Ok(hash.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
     /// get public outputs from witness
     pub fn get_public_outputs(&self) -> Vec<F> {
         // witness: <1> <Outputs> <Inputs> <Auxs>
@@ -211,6 +299,11 @@ impl<F: PrimeField> Circuit<F> {
         self.witness[1..output_count + 1].to_vec()
     }
 
+    /// get public signals from witness, outputs followed by public inputs
+    pub fn get_public_signals(&self) -> Vec<F> {
+        self.witness[1..self.r1cs.num_inputs].to_vec()
+    }
+
     /// get public inputs from witness
     pub fn get_public_inputs(&self) -> Vec<F> {
         // witness: <1> <Outputs> <Inputs> <Auxs>
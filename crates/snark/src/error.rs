@@ -15,6 +15,15 @@ pub enum Error {
     /// Error on loading witness from binary
     #[error("Failed to load witnesses: {0}")]
     WitnessFailedOnLoad(String),
+    /// Error on writing witness
+    #[error("Failed to write witnesses: {0}")]
+    WitnessFailedOnWrite(String),
+    /// Witness has different length with variables of r1cs
+    #[error("Mismatched witness length, expected {0}, got {1}")]
+    WitnessLengthMismatch(usize, usize),
+    /// Witness not satisfies the constraint at index
+    #[error("Witness does not satisfy constraint {0}")]
+    UnsatisfiedConstraint(usize),
     /// Error on compiling witness
     #[error("Error on witness compilling: {0}")]
     WitnessWasmRuntimeError(Box<wasmer::RuntimeError>),
@@ -39,6 +48,27 @@ pub enum Error {
     /// Error on call nova snark
     #[error("Error on nova snark: {0}")]
     NovaError(#[from] nova_snark::errors::NovaError),
+    /// Error on call bellman
+    #[error("Error on bellman: {0}")]
+    BellmanError(#[from] bellman_ce::SynthesisError),
+    /// Failed to convert field element between field implementations
+    #[error("Failed to convert field element: {0}")]
+    FieldConversion(String),
+    /// Wrong number of steps for proof system
+    #[error("Invalid count of steps: {0}")]
+    InvalidStepCount(usize),
+    /// Error on serializing with serde_json
+    #[error("Serde json error: {0}")]
+    SerdeJsonError(#[from] serde_json::Error),
+    /// Failed to export solidity verifier
+    #[error("Failed to export solidity verifier: {0}")]
+    SolidityExport(String),
+    /// Failed to solve witness from constraints of r1cs
+    #[error("Failed to solve witness: {0}")]
+    WitnessSolveError(String),
+    /// Invalid line of symbols file
+    #[error("Failed to load symbols, invalid line: {0}")]
+    LoadSymbols(String),
 }
 
 impl From<wasmer::RuntimeError> for Error {
@@ -1,12 +1,14 @@
 //! Rings SNARK
 //! ===============
-//! This implementation is based on NOVA
+//! This implementation is based on NOVA, Groth16 is supported for single step circuits,
+//! see [proof_system].
 
 #![warn(missing_docs)]
 
 pub mod circuit;
 pub mod error;
 pub mod prelude;
+pub mod proof_system;
 pub mod r1cs;
 pub mod snark;
 #[cfg(test)]
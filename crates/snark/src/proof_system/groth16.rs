@@ -0,0 +1,104 @@
+//! Groth16 proof system
+//! ==============
+//! Groth16 proves a single circuit with circuit specific keys. Circuits are converted to the
+//! field of bellman engine, so the circuit must be loaded with the scalar field of the engine,
+//! such as bn256 scalar for `Bn256`.
+//!
+//! Public inputs are the public signals of circom, outputs followed by public inputs, see
+//! [Circuit::get_public_signals].
+use std::marker::PhantomData;
+
+use super::ProofSystem;
+use crate::circuit::bellman::convert_field;
+use crate::circuit::bellman::BellmanCircuit;
+use crate::circuit::Circuit;
+use crate::error::Error;
+use crate::error::Result;
+use crate::prelude::bellman::groth16;
+use crate::prelude::bellman::pairing::Engine;
+use crate::prelude::ff::PrimeField;
+
+/// Groth16 over pairing engine `E`
+pub struct Groth16<E: Engine>(PhantomData<E>);
+
+impl<E, F> ProofSystem<F> for Groth16<E>
+where
+    E: Engine,
+    F: PrimeField,
+{
+    type ProverKey = groth16::Parameters<E>;
+    type VerifierKey = groth16::VerifyingKey<E>;
+    type Proof = groth16::Proof<E>;
+
+    fn setup(circuit: &Circuit<F>) -> Result<(Self::ProverKey, Self::VerifierKey)> {
+        let circuit = BellmanCircuit::<E>::try_from_circuit(circuit)?;
+        let params = groth16::generate_random_parameters(circuit, &mut rand::thread_rng())?;
+        let vk = params.vk.clone();
+        Ok((params, vk))
+    }
+
+    fn prove(pk: &Self::ProverKey, circuits: &[Circuit<F>]) -> Result<Self::Proof> {
+        let [circuit] = circuits else {
+            return Err(Error::InvalidStepCount(circuits.len()));
+        };
+        let circuit = BellmanCircuit::<E>::try_from_circuit(circuit)?;
+        Ok(groth16::create_random_proof(
+            circuit,
+            pk,
+            &mut rand::thread_rng(),
+        )?)
+    }
+
+    fn verify(vk: &Self::VerifierKey, proof: &Self::Proof, public_inputs: &[F]) -> Result<bool> {
+        let inputs = public_inputs
+            .iter()
+            .map(convert_field::<F, E::Fr>)
+            .collect::<Result<Vec<_>>>()?;
+        let pvk = groth16::prepare_verifying_key(vk);
+        Ok(groth16::verify_proof(&pvk, proof, &inputs)?)
+    }
+}
+
+/// Encoding of groth16 keys and proofs, which is the encoding of bellman
+pub trait Groth16Codec: Sized {
+    /// encode to bytes
+    fn to_bytes(&self) -> Result<Vec<u8>>;
+    /// decode from bytes
+    fn from_bytes(bytes: &[u8]) -> Result<Self>;
+}
+
+impl<E: Engine> Groth16Codec for groth16::Proof<E> {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.write(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::read(bytes)?)
+    }
+}
+
+impl<E: Engine> Groth16Codec for groth16::VerifyingKey<E> {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.write(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::read(bytes)?)
+    }
+}
+
+impl<E: Engine> Groth16Codec for groth16::Parameters<E> {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.write(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::read(bytes, false)?)
+    }
+}
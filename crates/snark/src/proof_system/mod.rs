@@ -0,0 +1,33 @@
+//! Proof systems of circom circuits
+//! ==============
+//! [ProofSystem] is the common interface of proving systems supported by rings-snark:
+//!
+//! * [nova::Nova]: recursive proving with folding, then compressed with spartan.
+//! * [groth16::Groth16]: pairing based proving of a single circuit, the proof is small and cheap
+//!   to verify, which is accepted by on-chain verifiers, see [solidity].
+use crate::circuit::Circuit;
+use crate::error::Result;
+use crate::prelude::ff::PrimeField;
+
+pub mod groth16;
+pub mod nova;
+pub mod solidity;
+
+/// A proving system of circuits over field `F`
+pub trait ProofSystem<F: PrimeField> {
+    /// key of prover
+    type ProverKey;
+    /// key of verifier
+    type VerifierKey;
+    /// proof
+    type Proof;
+
+    /// Generate keys from the shape of circuit, witness of circuit is not used
+    fn setup(circuit: &Circuit<F>) -> Result<(Self::ProverKey, Self::VerifierKey)>;
+
+    /// Prove steps of circuit, systems without recursion only accept a single step
+    fn prove(pk: &Self::ProverKey, circuits: &[Circuit<F>]) -> Result<Self::Proof>;
+
+    /// Verify proof with public inputs, see implementations for the layout of public inputs
+    fn verify(vk: &Self::VerifierKey, proof: &Self::Proof, public_inputs: &[F]) -> Result<bool>;
+}
@@ -0,0 +1,82 @@
+//! Nova proof system
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::ProofSystem;
+use crate::circuit::Circuit;
+use crate::error::Error;
+use crate::error::Result;
+use crate::prelude::nova::traits::snark::RelaxedR1CSSNARKTrait;
+use crate::prelude::nova::traits::Engine;
+use crate::snark::CompressedSNARK;
+use crate::snark::ProverKey;
+use crate::snark::PublicParams;
+use crate::snark::VerifierKey;
+use crate::snark::SNARK;
+
+/// Nova with compressed snark `S1` and `S2`, public inputs are inputs of the first step
+pub struct Nova<E1, E2, S1, S2>(PhantomData<(E1, E2, S1, S2)>);
+
+/// Compressed proof of steps
+#[derive(Serialize, Deserialize)]
+pub struct NovaProof<E1, E2, S1, S2>
+where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<E2>,
+{
+    /// compressed proof
+    pub proof: CompressedSNARK<E1, E2, S1, S2>,
+    /// count of folded steps
+    pub num_steps: usize,
+}
+
+impl<E1, E2, S1, S2> ProofSystem<E1::Scalar> for Nova<E1, E2, S1, S2>
+where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<E1>,
+    S2: RelaxedR1CSSNARKTrait<E2>,
+{
+    type ProverKey = (PublicParams<E1, E2>, ProverKey<E1, E2, S1, S2>);
+    type VerifierKey = VerifierKey<E1, E2, S1, S2>;
+    type Proof = NovaProof<E1, E2, S1, S2>;
+
+    fn setup(circuit: &Circuit<E1::Scalar>) -> Result<(Self::ProverKey, Self::VerifierKey)> {
+        let pp = SNARK::<E1, E2>::gen_pp::<S1, S2>(circuit.clone())?;
+        let (pk, vk) = SNARK::<E1, E2>::compress_setup::<S1, S2>(&pp)?;
+        Ok(((pp, pk), vk))
+    }
+
+    fn prove(pk: &Self::ProverKey, circuits: &[Circuit<E1::Scalar>]) -> Result<Self::Proof> {
+        let (pp, pk) = pk;
+        let first = circuits
+            .first()
+            .ok_or(Error::InvalidStepCount(circuits.len()))?;
+        let mut snark = SNARK::<E1, E2>::new(first, pp, first.get_public_inputs(), vec![
+            E2::Scalar::from(0),
+        ])?;
+        snark.fold_all(pp, circuits.to_vec())?;
+        Ok(NovaProof {
+            proof: snark.compress_prove::<S1, S2>(pp, pk)?,
+            num_steps: circuits.len(),
+        })
+    }
+
+    fn verify(
+        vk: &Self::VerifierKey,
+        proof: &Self::Proof,
+        public_inputs: &[E1::Scalar],
+    ) -> Result<bool> {
+        let ret = SNARK::<E1, E2>::compress_verify::<S1, S2>(
+            &proof.proof,
+            vk,
+            proof.num_steps,
+            public_inputs,
+        );
+        Ok(ret.is_ok())
+    }
+}
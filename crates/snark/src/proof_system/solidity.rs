@@ -0,0 +1,207 @@
+//! Solidity verifier of Groth16
+//! ==============
+//! Export a verifier contract from the verifier key of [Groth16](super::groth16::Groth16) over
+//! bn256, and encode proofs as calldata of the contract, so proofs produced off-chain can be
+//! settled on-chain. The contract verifies proofs with the bn256 precompiles of EVM
+//! (ecAdd `0x06`, ecMul `0x07` and ecPairing `0x08`).
+//!
+//! Nova compressed proofs are not supported, there is no precompile for the curve cycles.
+use tiny_keccak::Hasher;
+use tiny_keccak::Keccak;
+
+use crate::error::Error;
+use crate::error::Result;
+use crate::prelude::bellman::groth16::Proof;
+use crate::prelude::bellman::groth16::VerifyingKey;
+use crate::prelude::bellman::pairing::bn256::Bn256;
+use crate::prelude::bellman::pairing::bn256::Fq;
+use crate::prelude::bellman::pairing::bn256::G1Affine;
+use crate::prelude::bellman::pairing::bn256::G2Affine;
+use crate::prelude::bellman::pairing::ff::PrimeField;
+use crate::prelude::bellman::pairing::ff::PrimeFieldRepr;
+use crate::prelude::bellman::pairing::CurveAffine;
+use crate::prelude::ff;
+
+/// Name of exported contract
+pub const VERIFIER_CONTRACT_NAME: &str = "Groth16Verifier";
+
+/// Encode element of base field as a big endian word
+fn encode_fq(f: &Fq) -> Result<[u8; 32]> {
+    let mut word = [0u8; 32];
+    f.into_repr().write_be(&mut word[..])?;
+    Ok(word)
+}
+
+/// Encode a G1 point as `(x, y)` words, the point at infinity is `(0, 0)`
+pub fn encode_g1(p: &G1Affine) -> Result<[[u8; 32]; 2]> {
+    let (x, y) = p.into_xy_unchecked();
+    Ok([encode_fq(&x)?, encode_fq(&y)?])
+}
+
+/// Encode a G2 point in the order of EVM precompiles, `(x.c1, x.c0, y.c1, y.c0)`
+pub fn encode_g2(p: &G2Affine) -> Result<[[u8; 32]; 4]> {
+    let (x, y) = p.into_xy_unchecked();
+    Ok([
+        encode_fq(&x.c1)?,
+        encode_fq(&x.c0)?,
+        encode_fq(&y.c1)?,
+        encode_fq(&y.c0)?,
+    ])
+}
+
+/// Encode element of scalar field as a big endian word
+pub fn encode_scalar<F: ff::PrimeField>(f: &F) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word.copy_from_slice(f.to_repr().as_ref());
+    word.reverse();
+    word
+}
+
+fn hex_word(word: &[u8; 32]) -> String {
+    let digits = word
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("0x{}", digits)
+}
+
+/// Signature of `verifyProof` with `n` public inputs
+pub fn verify_proof_signature(n: usize) -> String {
+    format!(
+        "verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[{}])",
+        n
+    )
+}
+
+/// Selector of `verifyProof` with `n` public inputs
+pub fn verify_proof_selector(n: usize) -> [u8; 4] {
+    let mut hash = [0u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(verify_proof_signature(n).as_bytes());
+    keccak.finalize(&mut hash);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Encode proof and public inputs as calldata of `verifyProof`
+pub fn proof_calldata<F: ff::PrimeField>(
+    proof: &Proof<Bn256>,
+    public_inputs: &[F],
+) -> Result<Vec<u8>> {
+    let mut data = verify_proof_selector(public_inputs.len()).to_vec();
+    let words = encode_g1(&proof.a)?
+        .into_iter()
+        .chain(encode_g2(&proof.b)?)
+        .chain(encode_g1(&proof.c)?)
+        .chain(public_inputs.iter().map(encode_scalar));
+    for word in words {
+        data.extend_from_slice(&word);
+    }
+    Ok(data)
+}
+
+fn g1_literal(p: &G1Affine) -> Result<String> {
+    let [x, y] = encode_g1(p)?;
+    Ok(format!("[uint256({}), {}]", hex_word(&x), hex_word(&y)))
+}
+
+fn g2_literal(p: &G2Affine) -> Result<String> {
+    let [x1, x0, y1, y0] = encode_g2(p)?;
+    Ok(format!(
+        "[uint256({}), {}, {}, {}]",
+        hex_word(&x1),
+        hex_word(&x0),
+        hex_word(&y1),
+        hex_word(&y0)
+    ))
+}
+
+/// Export verifier contract of verifier key
+pub fn verifier_contract(vk: &VerifyingKey<Bn256>) -> Result<String> {
+    let Some((ic0, ics)) = vk.ic.split_first() else {
+        return Err(Error::SolidityExport(
+            "verifier key has no input commitments".to_string(),
+        ));
+    };
+    let n = ics.len();
+    let mut accumulate = String::new();
+    for (i, ic) in ics.iter().enumerate() {
+        accumulate.push_str(&format!(
+            "        vkX = ecAdd(vkX, ecMul({}, input[{}]));\n",
+            g1_literal(ic)?,
+            i
+        ));
+    }
+
+    Ok(format!(
+        r#"// SPDX-License-Identifier: MIT
+// Generated by rings-snark, do not edit.
+pragma solidity ^0.8.0;
+
+contract {name} {{
+    // modulus of base field of bn256
+    uint256 constant Q = 21888242871839275222246405745257275088696311157297424988159193346661405581583;
+    // modulus of scalar field of bn256
+    uint256 constant R = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[{n}] calldata input
+    ) public view returns (bool) {{
+        for (uint256 i = 0; i < {n}; i++) {{
+            require(input[i] < R, "input out of field");
+        }}
+        uint256[2] memory vkX = {ic0};
+{accumulate}
+        uint256[24] memory p;
+        uint256[2] memory negA = negate([a[0], a[1]]);
+        uint256[4][4] memory g2 = [
+            [b[0][0], b[0][1], b[1][0], b[1][1]],
+            {beta},
+            {gamma},
+            {delta}
+        ];
+        uint256[2][4] memory g1 = [negA, {alpha}, vkX, [c[0], c[1]]];
+        for (uint256 i = 0; i < 4; i++) {{
+            p[i * 6] = g1[i][0];
+            p[i * 6 + 1] = g1[i][1];
+            p[i * 6 + 2] = g2[i][0];
+            p[i * 6 + 3] = g2[i][1];
+            p[i * 6 + 4] = g2[i][2];
+            p[i * 6 + 5] = g2[i][3];
+        }}
+        (bool ok, bytes memory out) = address(8).staticcall(abi.encode(p));
+        return ok && out.length == 32 && abi.decode(out, (uint256)) == 1;
+    }}
+
+    function negate(uint256[2] memory p) internal pure returns (uint256[2] memory) {{
+        if (p[0] == 0 && p[1] == 0) {{
+            return p;
+        }}
+        return [p[0], Q - (p[1] % Q)];
+    }}
+
+    function ecAdd(uint256[2] memory p1, uint256[2] memory p2) internal view returns (uint256[2] memory) {{
+        (bool ok, bytes memory out) = address(6).staticcall(abi.encode(p1[0], p1[1], p2[0], p2[1]));
+        require(ok, "ecAdd failed");
+        return abi.decode(out, (uint256[2]));
+    }}
+
+    function ecMul(uint256[2] memory p, uint256 s) internal view returns (uint256[2] memory) {{
+        (bool ok, bytes memory out) = address(7).staticcall(abi.encode(p[0], p[1], s));
+        require(ok, "ecMul failed");
+        return abi.decode(out, (uint256[2]));
+    }}
+}}
+"#,
+        name = VERIFIER_CONTRACT_NAME,
+        n = n,
+        ic0 = g1_literal(ic0)?,
+        accumulate = accumulate,
+        alpha = g1_literal(&vk.alpha_g1)?,
+        beta = g2_literal(&vk.beta_g2)?,
+        gamma = g2_literal(&vk.gamma_g2)?,
+        delta = g2_literal(&vk.delta_g2)?,
+    ))
+}
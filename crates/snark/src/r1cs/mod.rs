@@ -1,6 +1,8 @@
 //! Utils for r1cs
 pub mod reader;
+pub mod writer;
 
+use std::collections::HashMap;
 use std::io::Cursor;
 
 use ff::PrimeField;
@@ -15,6 +17,10 @@ use crate::witness::calculator::WitnessCalculator;
 /// type of constraint
 pub(crate) type Constraint<F> = (Vec<(usize, F)>, Vec<(usize, F)>, Vec<(usize, F)>);
 
+/// Symbols of signals, map of full name of signal, such as `main.step_in[0]`, to its wire in
+/// witness. Signals eliminated by circom have no wire.
+pub type Symbols = HashMap<String, Option<usize>>;
+
 /// R1CS
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct R1CS<F: PrimeField> {
@@ -28,6 +34,33 @@ pub struct R1CS<F: PrimeField> {
     pub constraints: Vec<Constraint<F>>,
 }
 
+impl<F: PrimeField> R1CS<F> {
+    /// Check that witness satisfies every constraint, the index of the first
+    /// unsatisfied constraint is reported with [Error::UnsatisfiedConstraint].
+    pub fn check_witness(&self, witness: &[F]) -> Result<()> {
+        if witness.len() != self.num_variables {
+            return Err(Error::WitnessLengthMismatch(
+                self.num_variables,
+                witness.len(),
+            ));
+        }
+        let eval = |lc: &[(usize, F)]| -> Result<F> {
+            lc.iter().try_fold(F::ZERO, |acc, (i, coeff)| {
+                let w = witness
+                    .get(*i)
+                    .ok_or(Error::WitnessLengthMismatch(*i + 1, witness.len()))?;
+                Ok(acc + *w * coeff)
+            })
+        };
+        for (idx, (a, b, c)) in self.constraints.iter().enumerate() {
+            if eval(a)? * eval(b)? != eval(c)? {
+                return Err(Error::UnsatisfiedConstraint(idx));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Path of a r1cs
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Path {
@@ -90,9 +123,9 @@ pub async fn load_r1cs<F: PrimeField>(path: Path, format: Format) -> Result<R1CS
 pub async fn load_witness_remote<F: PrimeField>(url: &str, format: Format) -> Result<TyWitness<F>> {
     let data = fetch(url).await?;
     let ret = match format {
-        Format::Json => reader::load_witness_from_bin_reader::<F, Cursor<Vec<u8>>>(data)
+        Format::Json => reader::load_witness_from_json::<F, Cursor<Vec<u8>>>(data),
+        Format::Bin => reader::load_witness_from_bin_reader::<F, Cursor<Vec<u8>>>(data)
             .map_err(|e| Error::WitnessFailedOnLoad(e.to_string()))?,
-        Format::Bin => reader::load_witness_from_json::<F, Cursor<Vec<u8>>>(data),
     };
     Ok(ret)
 }
@@ -103,8 +136,8 @@ pub fn load_witness_local<F: PrimeField>(
     format: Format,
 ) -> Result<TyWitness<F>> {
     let ret = match format {
-        Format::Json => reader::load_witness_from_bin_file::<F>(path),
-        Format::Bin => reader::load_witness_from_json_file::<F>(path),
+        Format::Json => reader::load_witness_from_json_file::<F>(path),
+        Format::Bin => reader::load_witness_from_bin_file::<F>(path),
     };
     Ok(ret)
 }
@@ -117,6 +150,25 @@ pub async fn load_witness<F: PrimeField>(path: Path, format: Format) -> Result<T
     }
 }
 
+/// Fetch remote symbols, which is the `.sym` file generated by circom
+pub async fn load_symbols_remote(url: &str) -> Result<Symbols> {
+    let data = fetch(url).await?;
+    reader::load_symbols_from_reader(data)
+}
+
+/// Load local symbols, which is the `.sym` file generated by circom
+pub fn load_symbols_local(path: impl AsRef<std::path::Path>) -> Result<Symbols> {
+    reader::load_symbols_from_file(path)
+}
+
+/// Load symbols, the resource path can be remote or local
+pub async fn load_symbols(path: Path) -> Result<Symbols> {
+    match path {
+        Path::Local(p) => load_symbols_local(p),
+        Path::Remote(url) => load_symbols_remote(&url).await,
+    }
+}
+
 /// Load witness calculator from local path
 pub fn load_circom_witness_calculator_local(
     path: impl AsRef<std::path::Path>,
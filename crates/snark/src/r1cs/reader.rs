@@ -17,6 +17,7 @@ use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fs::File;
 use std::fs::OpenOptions;
+use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Read;
 use std::io::Seek;
@@ -34,6 +35,7 @@ use serde::Serialize;
 use crate::error::Error;
 use crate::error::Result;
 use crate::r1cs::Constraint;
+use crate::r1cs::Symbols;
 use crate::r1cs::R1CS;
 
 /// Represents the JSON structure of a Circuit.
@@ -359,6 +361,33 @@ pub fn load_r1cs_from_bin<Fr: PrimeField, R: Read + Seek>(reader: R) -> R1CS<Fr>
     }
 }
 
+/// load symbols from `.sym` file of circom by filename
+pub fn load_symbols_from_file(filename: impl AsRef<Path>) -> Result<Symbols> {
+    let reader = OpenOptions::new().read(true).open(filename)?;
+    load_symbols_from_reader(BufReader::new(reader))
+}
+
+/// load symbols from `.sym` file of circom by a reader, each line of the file is
+/// `label,wire,component,name`, the wire of signals eliminated by circom is `-1`
+pub fn load_symbols_from_reader<R: BufRead>(reader: R) -> Result<Symbols> {
+    let mut symbols = Symbols::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.trim().splitn(4, ',').collect();
+        let [_, wire, _, name] = fields[..] else {
+            return Err(Error::LoadSymbols(line));
+        };
+        let wire = wire
+            .parse::<i64>()
+            .map_err(|_| Error::LoadSymbols(line.clone()))?;
+        symbols.insert(name.to_string(), usize::try_from(wire).ok());
+    }
+    Ok(symbols)
+}
+
 /// load r1cs file by filename with autodetect encoding (bin or json)
 pub fn load_r1cs<Fr: PrimeField>(filename: impl AsRef<Path>) -> R1CS<Fr> {
     if filename.as_ref().ends_with("json") {
@@ -0,0 +1,123 @@
+//! Module for write witness
+//! ================
+//! The binary format is the `.wtns` format of circom (version 2), which can be loaded by
+//! [crate::r1cs::reader::load_witness_from_bin_reader] and snarkjs. The json format
+//! is an array of decimal strings.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+use byteorder::LittleEndian;
+use byteorder::WriteBytesExt;
+use crypto_bigint::Encoding;
+use crypto_bigint::U256;
+use ff::PrimeField;
+
+use crate::error::Error;
+use crate::error::Result;
+
+/// Size of field elements in bytes, only 256 bits fields are supported
+const FIELD_SIZE: u32 = 32;
+
+/// write witness to bin file by filename
+pub fn write_witness_to_bin_file<Fr: PrimeField>(
+    witness: &[Fr],
+    filename: impl AsRef<Path>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(filename)?);
+    write_witness_to_bin_writer(witness, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// write witness in `.wtns` format to a writer
+pub fn write_witness_to_bin_writer<Fr: PrimeField, W: Write>(
+    witness: &[Fr],
+    mut writer: W,
+) -> Result<()> {
+    if Fr::ZERO.to_repr().as_ref().len() != FIELD_SIZE as usize {
+        return Err(Error::WitnessFailedOnWrite(
+            "unsupported field byte size".to_string(),
+        ));
+    }
+    let prime = U256::from_be_hex(Fr::MODULUS.trim_start_matches("0x")).to_le_bytes();
+    let witness_len = u32::try_from(witness.len())
+        .map_err(|_| Error::WitnessFailedOnWrite("witness is too long".to_string()))?;
+
+    // ruby -e 'p "wtns".bytes' => [119, 116, 110, 115]
+    writer.write_all(&[119, 116, 110, 115])?;
+    // version
+    writer.write_u32::<LittleEndian>(2)?;
+    // num of sections
+    writer.write_u32::<LittleEndian>(2)?;
+    // the first section: field size, prime and length of witness
+    writer.write_u32::<LittleEndian>(1)?;
+    writer.write_u64::<LittleEndian>(4 + u64::from(FIELD_SIZE) + 4)?;
+    writer.write_u32::<LittleEndian>(FIELD_SIZE)?;
+    writer.write_all(&prime)?;
+    writer.write_u32::<LittleEndian>(witness_len)?;
+    // the second section: witness
+    writer.write_u32::<LittleEndian>(2)?;
+    writer.write_u64::<LittleEndian>(u64::from(witness_len) * u64::from(FIELD_SIZE))?;
+    for w in witness {
+        writer.write_all(w.to_repr().as_ref())?;
+    }
+    Ok(())
+}
+
+/// write witness to json file by filename
+pub fn write_witness_to_json_file<Fr: PrimeField>(
+    witness: &[Fr],
+    filename: impl AsRef<Path>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(filename)?);
+    write_witness_to_json_writer(witness, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// write witness as json array of decimal strings to a writer
+pub fn write_witness_to_json_writer<Fr: PrimeField, W: Write>(
+    witness: &[Fr],
+    writer: W,
+) -> Result<()> {
+    let witness: Vec<String> = witness.iter().map(field_to_decimal).collect();
+    serde_json::to_writer(writer, &witness)?;
+    Ok(())
+}
+
+/// Decimal representation of a field element, which is the inverse of [PrimeField::from_str_vartime]
+pub fn field_to_decimal<Fr: PrimeField>(f: &Fr) -> String {
+    const BASE: u64 = 1_000_000_000;
+    // little endian u32 limbs of the canonical representation
+    let mut limbs: Vec<u32> = f
+        .to_repr()
+        .as_ref()
+        .chunks(4)
+        .map(|c| {
+            c.iter()
+                .rev()
+                .fold(0u32, |acc, b| (acc << 8) | u32::from(*b))
+        })
+        .collect();
+    // little endian digits in base 10^9
+    let mut digits = vec![];
+    while limbs.iter().any(|l| *l != 0) {
+        let mut rem = 0u64;
+        for l in limbs.iter_mut().rev() {
+            let cur = (rem << 32) | u64::from(*l);
+            *l = (cur / BASE) as u32;
+            rem = cur % BASE;
+        }
+        digits.push(rem);
+    }
+    match digits.split_last() {
+        None => "0".to_string(),
+        Some((last, rest)) => rest.iter().rev().fold(last.to_string(), |mut s, d| {
+            s.push_str(&format!("{:09}", d));
+            s
+        }),
+    }
+}
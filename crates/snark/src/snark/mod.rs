@@ -13,6 +13,7 @@ use utils::serialize_forward;
 
 use crate::circuit::Circuit;
 use crate::error::Result;
+use crate::prelude::ff::PrimeField;
 use crate::prelude::nova;
 use crate::prelude::nova::traits::circuit::TrivialCircuit;
 use crate::prelude::nova::traits::snark::RelaxedR1CSSNARKTrait;
@@ -68,6 +69,24 @@ where
         nova::PublicParams<E1, E2, Circuit<<E1 as Engine>::Scalar>, TrivialCircuit<E2::Scalar>>,
 }
 
+impl<E1, E2> PublicParams<E1, E2>
+where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+    /// Digest of public params in hex, public params generated with randomness,
+    /// such as KZG commitment keys, can be told apart by digest
+    pub fn digest(&self) -> String {
+        self.inner
+            .digest()
+            .to_repr()
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
 impl<E1, E2> std::fmt::Debug for PublicParams<E1, E2>
 where
     E1: Engine<Base = <E2 as Engine>::Scalar>,
@@ -188,3 +188,167 @@ pub async fn test_calcu_bn256_recursive_snark_with_private_input() -> Result<()>
     assert!(ret.is_ok());
     Ok(())
 }
+
+#[tokio::test]
+pub async fn test_groth16_proof_system() -> Result<()> {
+    use crate::prelude::bellman::pairing::bn256::Bn256;
+    use crate::prelude::nova::provider::Bn256EngineKZG;
+    use crate::proof_system::groth16::Groth16;
+    use crate::proof_system::groth16::Groth16Codec;
+    use crate::proof_system::ProofSystem;
+    type F = <Bn256EngineKZG as Engine>::Scalar;
+    type G = Groth16<Bn256>;
+
+    let r1cs = r1cs::load_r1cs::<F>(
+        r1cs::Path::Local("src/tests/native/circoms/simple_bn256.r1cs".to_string()),
+        r1cs::Format::Bin,
+    )
+    .await
+    .unwrap();
+    let witness_calculator = r1cs::load_circom_witness_calculator(r1cs::Path::Local(
+        "src/tests/native/circoms/simple_bn256.wasm".to_string(),
+    ))
+    .await
+    .unwrap();
+    let circuit_generator = circuit::WasmCircuitGenerator::<F>::new(r1cs, witness_calculator);
+    let input: Input<F> = vec![("step_in".to_string(), vec![F::from(4u64), F::from(2u64)])].into();
+    let circuit = circuit_generator.gen_circuit(input, true).unwrap();
+
+    let (pk, vk) = <G as ProofSystem<F>>::setup(&circuit)?;
+    let proof = <G as ProofSystem<F>>::prove(&pk, &[circuit.clone()])?;
+    let public_signals = circuit.get_public_signals();
+    assert!(<G as ProofSystem<F>>::verify(&vk, &proof, &public_signals)?);
+
+    let mut wrong_signals = public_signals.clone();
+    wrong_signals[0] += F::from(1u64);
+    assert!(!<G as ProofSystem<F>>::verify(&vk, &proof, &wrong_signals)?);
+
+    // proof and key survive encoding
+    let proof = Groth16Codec::from_bytes(&proof.to_bytes()?)?;
+    let vk = Groth16Codec::from_bytes(&vk.to_bytes()?)?;
+    assert!(<G as ProofSystem<F>>::verify(&vk, &proof, &public_signals)?);
+
+    // groth16 is not recursive
+    assert!(<G as ProofSystem<F>>::prove(&pk, &[circuit.clone(), circuit]).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_groth16_solidity_verifier() -> Result<()> {
+    use std::io::Write;
+    use std::process::Command;
+    use std::process::Stdio;
+
+    use revm::db::InMemoryDB;
+    use revm::primitives::CreateScheme;
+    use revm::primitives::ExecutionResult;
+    use revm::primitives::Output;
+    use revm::primitives::TransactTo;
+
+    use crate::prelude::bellman::pairing::bn256::Bn256;
+    use crate::prelude::nova::provider::Bn256EngineKZG;
+    use crate::proof_system::groth16::Groth16;
+    use crate::proof_system::solidity;
+    use crate::proof_system::ProofSystem;
+    type F = <Bn256EngineKZG as Engine>::Scalar;
+    type G = Groth16<Bn256>;
+
+    // compile contract with solc, the path of solc can be set by env `SOLC`
+    fn compile(contract: &str) -> Vec<u8> {
+        let solc = std::env::var("SOLC").unwrap_or_else(|_| "solc".to_string());
+        let mut child = Command::new(&solc)
+            .args(["--optimize", "--combined-json", "bin", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|e| panic!("solc is required to test verifier, {}: {}", solc, e));
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(contract.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let output: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let name = format!("<stdin>:{}", solidity::VERIFIER_CONTRACT_NAME);
+        let bin = output["contracts"][name]["bin"].as_str().unwrap();
+        (0..bin.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&bin[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    let r1cs = r1cs::load_r1cs::<F>(
+        r1cs::Path::Local("src/tests/native/circoms/simple_bn256.r1cs".to_string()),
+        r1cs::Format::Bin,
+    )
+    .await
+    .unwrap();
+    let witness_calculator = r1cs::load_circom_witness_calculator(r1cs::Path::Local(
+        "src/tests/native/circoms/simple_bn256.wasm".to_string(),
+    ))
+    .await
+    .unwrap();
+    let circuit_generator = circuit::WasmCircuitGenerator::<F>::new(r1cs, witness_calculator);
+    let input: Input<F> = vec![("step_in".to_string(), vec![F::from(4u64), F::from(2u64)])].into();
+    let circuit = circuit_generator.gen_circuit(input, true).unwrap();
+
+    let (pk, vk) = <G as ProofSystem<F>>::setup(&circuit)?;
+    let proof = <G as ProofSystem<F>>::prove(&pk, &[circuit.clone()])?;
+    let public_signals = circuit.get_public_signals();
+
+    let contract = solidity::verifier_contract(&vk)?;
+    assert!(contract.contains(&format!("contract {}", solidity::VERIFIER_CONTRACT_NAME)));
+    assert!(contract.contains(&format!("uint256[{}] calldata input", public_signals.len())));
+    assert_eq!(vk.ic.len(), public_signals.len() + 1);
+
+    let calldata = solidity::proof_calldata(&proof, &public_signals)?;
+    assert_eq!(
+        calldata[..4],
+        solidity::verify_proof_selector(public_signals.len())
+    );
+    assert_eq!(calldata.len(), 4 + 32 * (8 + public_signals.len()));
+
+    // deploy verifier to a local evm
+    let mut evm = revm::new();
+    evm.database(InMemoryDB::default());
+    evm.env.tx.transact_to = TransactTo::Create(CreateScheme::Create);
+    evm.env.tx.data = compile(&contract).into();
+    let verifier = match evm.transact_commit().unwrap() {
+        ExecutionResult::Success {
+            output: Output::Create(_, Some(address)),
+            ..
+        } => address,
+        ret => panic!("failed to deploy verifier: {:?}", ret),
+    };
+    let mut verify_proof = |calldata: &[u8]| {
+        evm.env.tx.transact_to = TransactTo::Call(verifier);
+        evm.env.tx.data = calldata.to_vec().into();
+        match evm.transact_ref().unwrap().result {
+            ExecutionResult::Success {
+                output: Output::Call(out),
+                ..
+            } => out.len() == 32 && out[31] == 1 && out[..31].iter().all(|b| *b == 0),
+            _ => false,
+        }
+    };
+    assert!(verify_proof(&calldata));
+
+    // proofs of other public inputs are rejected
+    let mut tampered = calldata.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(!verify_proof(&tampered));
+
+    // forged proof, with `c` replaced by `a`, is rejected
+    let mut forged = calldata.clone();
+    let a = forged[4..4 + 64].to_vec();
+    forged[4 + 6 * 32..4 + 8 * 32].copy_from_slice(&a);
+    assert!(!verify_proof(&forged));
+    Ok(())
+}
@@ -90,3 +90,266 @@ pub async fn test_load_witness_remote() -> Result<()> {
     assert_eq![witness[0], F::from(1u64)];
     Ok(())
 }
+
+#[tokio::test]
+pub async fn test_check_and_convert_witness() -> Result<()> {
+    use std::io::Cursor;
+
+    use crate::r1cs::reader;
+    use crate::r1cs::writer;
+
+    type F = <VestaEngine as Engine>::Scalar;
+    let r1cs = r1cs::load_r1cs_local::<F>(
+        "src/tests/native/circoms/simple_bn256.r1cs",
+        r1cs::Format::Bin,
+    )
+    .unwrap();
+    let mut witness_calculator = r1cs::load_circom_witness_calculator(r1cs::Path::Local(
+        "src/tests/native/circoms/simple_bn256.wasm".to_string(),
+    ))
+    .await
+    .unwrap();
+    let input = vec![("step_in".to_string(), vec![F::from(4u64), F::from(2u64)])];
+    let witness = witness_calculator
+        .calculate_witness::<F>(input, true)
+        .unwrap();
+    r1cs.check_witness(&witness).unwrap();
+
+    // roundtrip of bin format
+    let mut bin = vec![];
+    writer::write_witness_to_bin_writer(&witness, &mut bin).unwrap();
+    let loaded = reader::load_witness_from_bin_reader::<F, _>(Cursor::new(bin)).unwrap();
+    assert_eq!(loaded, witness);
+
+    // roundtrip of json format
+    let mut json = vec![];
+    writer::write_witness_to_json_writer(&witness, &mut json).unwrap();
+    let loaded = reader::load_witness_from_json::<F, _>(Cursor::new(json));
+    assert_eq!(loaded, witness);
+    assert_eq!(writer::field_to_decimal(&F::from(0u64)), "0");
+    assert_eq!(
+        writer::field_to_decimal(&F::from(12345678901234567890u64)),
+        "12345678901234567890"
+    );
+    assert_eq!(
+        writer::field_to_decimal(&-F::from(1u64)),
+        "28948022309329048855892746252171976963363056481941560715954676764349967630336"
+    );
+
+    // tampered witness fails on the first constraint
+    let mut tampered = witness.clone();
+    tampered[1] += F::from(1u64);
+    assert!(matches!(
+        r1cs.check_witness(&tampered),
+        Err(crate::error::Error::UnsatisfiedConstraint(0))
+    ));
+    assert!(matches!(
+        r1cs.check_witness(&witness[1..]),
+        Err(crate::error::Error::WitnessLengthMismatch(_, _))
+    ));
+    Ok(())
+}
+
+/// Symbols of input signal `name` of main component, with elements at wires from `offset`
+#[cfg(feature = "native-witness")]
+fn input_symbols(name: &str, offset: usize, len: usize) -> r1cs::Symbols {
+    (0..len)
+        .map(|i| (format!("main.{}[{}]", name, i), Some(offset + i)))
+        .collect()
+}
+
+#[cfg(feature = "native-witness")]
+#[tokio::test]
+pub async fn test_load_symbols() -> Result<()> {
+    let sym = "1,1,0,main.step_out[0]\n2,2,0,main.step_out[1]\n3,-1,0,main.step_in[0]\n4,3,0,main.step_in[1]\n5,4,1,main.sub.x\n";
+    let symbols = r1cs::reader::load_symbols_from_reader(sym.as_bytes())?;
+    assert_eq!(symbols.len(), 5);
+    assert_eq!(symbols["main.step_in[0]"], None);
+    assert_eq!(symbols["main.step_in[1]"], Some(3));
+    assert_eq!(symbols["main.sub.x"], Some(4));
+    assert!(r1cs::reader::load_symbols_from_reader("1,1,main.x\n".as_bytes()).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "native-witness")]
+#[tokio::test]
+pub async fn test_solve_witness_as_wasm() -> Result<()> {
+    use std::sync::Arc;
+
+    use crate::witness::solver::WitnessSolver;
+
+    type F = <VestaEngine as Engine>::Scalar;
+    // both circuits have 2 outputs, followed by 2 public inputs
+    for (name, signal) in [("simple_bn256", "step_in"), ("test_poseidon_hash", "inp")] {
+        let r1cs = r1cs::load_r1cs_local::<F>(
+            format!("src/tests/native/circoms/{}.r1cs", name),
+            r1cs::Format::Bin,
+        )
+        .unwrap();
+        let mut witness_calculator = r1cs::load_circom_witness_calculator(r1cs::Path::Local(
+            format!("src/tests/native/circoms/{}.wasm", name),
+        ))
+        .await
+        .unwrap();
+        let mut solver = WitnessSolver::new(Arc::new(r1cs), input_symbols(signal, 3, 2));
+        let input = vec![(signal.to_string(), vec![F::from(4u64), F::from(2u64)])];
+        let expected = witness_calculator
+            .calculate_witness::<F>(input.clone(), true)
+            .unwrap();
+        let witness = solver.calculate_witness(input, true).unwrap();
+        assert_eq!(witness, expected, "witness of {} mismatch", name);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "native-witness")]
+#[tokio::test]
+pub async fn test_solve_witness_by_name() -> Result<()> {
+    use std::sync::Arc;
+
+    use crate::witness::solver::WitnessSolver;
+
+    type F = <VestaEngine as Engine>::Scalar;
+    let r1cs = Arc::new(r1cs::load_r1cs_local::<F>(
+        "src/tests/native/circoms/simple_bn256.r1cs",
+        r1cs::Format::Bin,
+    )?);
+    // elements are ordered by index instead of the order of symbols
+    let symbols = r1cs::Symbols::from([
+        ("main.step_in[1]".to_string(), Some(4)),
+        ("main.step_in[0]".to_string(), Some(3)),
+    ]);
+    let mut solver = WitnessSolver::new(r1cs.clone(), symbols);
+    let input = vec![("step_in".to_string(), vec![F::from(4u64), F::from(2u64)])];
+    let witness = solver.calculate_witness(input, true)?;
+    assert_eq!(witness[3..5], [F::from(4u64), F::from(2u64)]);
+
+    let mut solver = WitnessSolver::new(r1cs, input_symbols("step_in", 3, 2));
+    let unknown = vec![("x".to_string(), vec![F::from(4u64), F::from(2u64)])];
+    assert!(solver.calculate_witness(unknown, true).is_err());
+    let short = vec![("step_in".to_string(), vec![F::from(4u64)])];
+    assert!(solver.calculate_witness(short, true).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "native-witness")]
+#[tokio::test]
+pub async fn test_solve_witness_with_hints() -> Result<()> {
+    use std::sync::Arc;
+
+    use crate::prelude::ff::Field;
+    use crate::r1cs::R1CS;
+    use crate::witness::solver::WitnessSolver;
+
+    type F = <VestaEngine as Engine>::Scalar;
+    let one = F::from(1u64);
+
+    // Num2Bits(4): signal input in; signal output out[4];
+    // out[i] <-- (in >> i) & 1; out[i] * (out[i] - 1) === 0; sum(out[i] * 2^i) === in;
+    // witness: <1> <out[0..4]> <in>
+    let mut constraints: Vec<_> = (1..5)
+        .map(|w| (vec![(w, one)], vec![(w, one), (0, -one)], vec![]))
+        .collect();
+    constraints.push((
+        vec![],
+        vec![],
+        (1..5)
+            .map(|w| (w, F::from(1u64 << (w - 1))))
+            .chain([(5, -one)])
+            .collect(),
+    ));
+    let num2bits = Arc::new(R1CS {
+        num_inputs: 6,
+        num_aux: 0,
+        num_variables: 6,
+        constraints,
+    });
+    let mut solver = WitnessSolver::new(
+        num2bits,
+        r1cs::Symbols::from([("main.in".to_string(), Some(5))]),
+    );
+    let witness = solver.calculate_witness(vec![("in".to_string(), vec![F::from(11u64)])], true)?;
+    let bits = [1u64, 1, 0, 1, 11].map(F::from);
+    assert_eq!(witness[1..], bits);
+    // 16 doesn't fit in 4 bits
+    assert!(solver
+        .calculate_witness(vec![("in".to_string(), vec![F::from(16u64)])], true)
+        .is_err());
+
+    // IsZero: signal input in; signal output out; signal inv;
+    // inv <-- in != 0 ? 1 / in : 0; out <== -in * inv + 1; in * out === 0;
+    // witness: <1> <out> <in> <inv>
+    let is_zero = Arc::new(R1CS {
+        num_inputs: 3,
+        num_aux: 1,
+        num_variables: 4,
+        constraints: vec![
+            (vec![(2, one)], vec![(3, one)], vec![(0, one), (1, -one)]),
+            (vec![(2, one)], vec![(1, one)], vec![]),
+        ],
+    });
+    let mut solver = WitnessSolver::new(
+        is_zero,
+        r1cs::Symbols::from([("main.in".to_string(), Some(2))]),
+    );
+    let witness = solver.calculate_witness(vec![("in".to_string(), vec![F::from(5u64)])], true)?;
+    let inv = F::from(5u64).invert().unwrap();
+    assert_eq!(witness, vec![one, F::from(0u64), F::from(5u64), inv]);
+    let witness = solver.calculate_witness(vec![("in".to_string(), vec![F::from(0u64)])], true)?;
+    assert_eq!(witness, vec![one, one, F::from(0u64), F::from(0u64)]);
+    Ok(())
+}
+
+#[cfg(feature = "native-witness")]
+#[tokio::test]
+pub async fn test_solve_witness_unconstrained() -> Result<()> {
+    use std::sync::Arc;
+
+    use crate::witness::solver::WitnessSolver;
+
+    type F = <VestaEngine as Engine>::Scalar;
+    // first output of this circuit is assigned but not constrained
+    let r1cs = r1cs::load_r1cs_local::<F>(
+        "src/tests/native/circoms/simple_bn256_priv.r1cs",
+        r1cs::Format::Bin,
+    )
+    .unwrap();
+    let mut solver = WitnessSolver::new(Arc::new(r1cs), input_symbols("step_in", 3, 2));
+    let input = vec![("step_in".to_string(), vec![F::from(4u64), F::from(2u64)])];
+    assert!(solver.calculate_witness(input, true).is_err());
+    Ok(())
+}
+#[cfg(feature = "native-witness")]
+#[tokio::test]
+pub async fn test_solve_recursive_circuit() -> Result<()> {
+    use crate::circuit;
+    use crate::circuit::Input;
+
+    type F = <VestaEngine as Engine>::Scalar;
+    let r1cs = r1cs::load_r1cs_local::<F>(
+        "src/tests/native/circoms/simple_bn256.r1cs",
+        r1cs::Format::Bin,
+    )
+    .unwrap();
+    let witness_calculator = r1cs::load_circom_witness_calculator(r1cs::Path::Local(
+        "src/tests/native/circoms/simple_bn256.wasm".to_string(),
+    ))
+    .await
+    .unwrap();
+    let wasm_generator = circuit::WasmCircuitGenerator::<F>::new(r1cs.clone(), witness_calculator);
+    let solver_generator =
+        circuit::SolverCircuitGenerator::<F>::new(r1cs, input_symbols("step_in", 3, 2));
+
+    let input: Input<F> = vec![("step_in".to_string(), vec![F::from(4u64), F::from(2u64)])].into();
+    let expected = wasm_generator
+        .gen_recursive_circuit(input.clone(), vec![], 5, true)
+        .unwrap();
+    let circuits = solver_generator
+        .gen_recursive_circuit(input, vec![], 5, true)
+        .unwrap();
+    assert_eq!(circuits.len(), 5);
+    for (c, e) in circuits.iter().zip(expected.iter()) {
+        assert_eq!(c.get_public_signals(), e.get_public_signals());
+    }
+    Ok(())
+}
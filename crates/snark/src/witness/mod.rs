@@ -5,6 +5,8 @@
 pub mod calculator;
 pub mod circom;
 pub mod memory;
+#[cfg(feature = "native-witness")]
+pub mod solver;
 
 use std::hash::Hasher;
 
@@ -0,0 +1,416 @@
+//! Witness solver over constraints of r1cs
+//! ========================
+//! An alternative of [WitnessCalculator](super::calculator::WitnessCalculator) which doesn't
+//! need the wasm runtime. Signals are solved by propagating known signals through constraints:
+//! a constraint with exactly one unknown signal, which is not multiplied by itself, solves it.
+//!
+//! Signals assigned by hints with `<--` are not always determined by a single constraint,
+//! they're solved by the patterns of common templates once nothing else can be solved:
+//!
+//! * Binary decomposition, such as `Num2Bits`: a linear constraint of boolean signals, with
+//!   coefficients of distinct powers of two, solves the signals as bits of its constant.
+//! * Free signals, such as `inv` of `IsZero` when its input is zero: a signal whose constraints
+//!   are satisfied by any value is assigned zero.
+//!
+//! Other hints, such as division with remainder, still need the wasm calculator.
+//!
+//! R1CS keeps no names of signals, so inputs are mapped to signals by the [Symbols] of circuit,
+//! which is the `.sym` file generated by circom.
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use ff::PrimeField;
+
+use crate::error::Error;
+use crate::error::Result;
+use crate::r1cs::Symbols;
+use crate::r1cs::R1CS;
+
+/// Witness solver of r1cs
+pub struct WitnessSolver<F: PrimeField> {
+    r1cs: Arc<R1CS<F>>,
+    symbols: Symbols,
+    /// signals of each constraint, without the constant signal
+    signals: Vec<Vec<usize>>,
+    /// constraints of each signal
+    occurrences: Vec<Vec<usize>>,
+}
+
+impl<F: PrimeField> WitnessSolver<F> {
+    /// Create a new instance with symbols of circuit
+    pub fn new(r1cs: Arc<R1CS<F>>, symbols: Symbols) -> Self {
+        let mut occurrences = vec![vec![]; r1cs.num_variables];
+        let signals = r1cs
+            .constraints
+            .iter()
+            .enumerate()
+            .map(|(i, (a, b, c))| {
+                let mut signals: Vec<usize> = a
+                    .iter()
+                    .chain(b.iter())
+                    .chain(c.iter())
+                    .map(|(w, _)| *w)
+                    .filter(|w| *w != 0)
+                    .collect();
+                signals.sort_unstable();
+                signals.dedup();
+                for w in signals.iter() {
+                    occurrences[*w].push(i);
+                }
+                signals
+            })
+            .collect();
+        Self {
+            r1cs,
+            symbols,
+            signals,
+            occurrences,
+        }
+    }
+
+    /// Wires of input signal of main component, elements of arrays are ordered by their indices.
+    /// Wires of signals eliminated by circom are None.
+    fn input_wires(&self, name: &str) -> Result<Vec<Option<usize>>> {
+        let prefix = format!("main.{}", name);
+        let mut wires: Vec<(Vec<usize>, Option<usize>)> = self
+            .symbols
+            .iter()
+            .filter_map(|(symbol, wire)| {
+                let indices = parse_indices(symbol.strip_prefix(&prefix)?)?;
+                Some((indices, *wire))
+            })
+            .collect();
+        if wires.is_empty() {
+            return Err(Error::WitnessSolveError(format!(
+                "input signal {} not found",
+                name
+            )));
+        }
+        wires.sort_unstable();
+        Ok(wires.into_iter().map(|(_, wire)| wire).collect())
+    }
+
+    /// Calculate witness from inputs, which is `<1> <Outputs> <Inputs> <Auxs>`.
+    /// Inputs are mapped to signals by name, see module docs.
+    pub fn calculate_witness(
+        &mut self,
+        input: Vec<(String, Vec<F>)>,
+        sanity_check: bool,
+    ) -> Result<Vec<F>> {
+        let r1cs = self.r1cs.clone();
+        let mut witness: Vec<Option<F>> = vec![None; r1cs.num_variables];
+        witness[0] = Some(F::ONE);
+
+        for (name, values) in input {
+            let wires = self.input_wires(&name)?;
+            if wires.len() != values.len() {
+                return Err(Error::WitnessSolveError(format!(
+                    "input signal {} has {} elements, got {}",
+                    name,
+                    wires.len(),
+                    values.len()
+                )));
+            }
+            for (wire, v) in wires.into_iter().zip(values) {
+                let Some(wire) = wire else {
+                    continue;
+                };
+                if wire >= r1cs.num_variables {
+                    return Err(Error::WitnessSolveError(format!(
+                        "wire {} of input signal {} exceeds {} signals",
+                        wire, name, r1cs.num_variables
+                    )));
+                }
+                witness[wire] = Some(v);
+            }
+        }
+
+        let mut unknowns: Vec<usize> = self
+            .signals
+            .iter()
+            .map(|s| s.iter().filter(|w| witness[**w].is_none()).count())
+            .collect();
+        let mut queue: VecDeque<usize> =
+            (0..unknowns.len()).filter(|i| unknowns[*i] == 1).collect();
+        loop {
+            while let Some(i) = queue.pop_front() {
+                if unknowns[i] != 1 {
+                    continue;
+                }
+                if let Some((w, v)) = self.solve_constraint(i, &witness) {
+                    self.assign(w, v, &mut witness, &mut unknowns, &mut queue);
+                }
+            }
+            let mut solved = self.solve_linear_system(&witness, &unknowns);
+            if solved.is_empty() {
+                solved = self.solve_hints(&witness, &unknowns);
+            }
+            if solved.is_empty() {
+                break;
+            }
+            for (w, v) in solved {
+                if witness[w].is_none() {
+                    self.assign(w, v, &mut witness, &mut unknowns, &mut queue);
+                }
+            }
+        }
+
+        let unsolved = witness.iter().filter(|w| w.is_none()).count();
+        if unsolved > 0 {
+            return Err(Error::WitnessSolveError(format!(
+                "{} signals can't be solved from constraints",
+                unsolved
+            )));
+        }
+        let witness: Vec<F> = witness.into_iter().flatten().collect();
+
+        if sanity_check {
+            for (i, (a, b, c)) in r1cs.constraints.iter().enumerate() {
+                if eval(a, &witness) * eval(b, &witness) != eval(c, &witness) {
+                    return Err(Error::WitnessSolveError(format!(
+                        "constraint {} is not satisfied",
+                        i
+                    )));
+                }
+            }
+        }
+        Ok(witness)
+    }
+
+    /// Assign signal, and queue constraints left with exactly one unknown signal
+    fn assign(
+        &self,
+        w: usize,
+        v: F,
+        witness: &mut [Option<F>],
+        unknowns: &mut [usize],
+        queue: &mut VecDeque<usize>,
+    ) {
+        witness[w] = Some(v);
+        for c in self.occurrences[w].iter() {
+            unknowns[*c] -= 1;
+            if unknowns[*c] == 1 {
+                queue.push_back(*c);
+            }
+        }
+    }
+
+    /// Solve the only unknown signal of constraint `A * B = C`, the constraint is
+    /// linear on the signal as `(a0 + a1 * x) * (b0 + b1 * x) = c0 + c1 * x` with `a1 * b1 = 0`.
+    fn solve_constraint(&self, i: usize, witness: &[Option<F>]) -> Option<(usize, F)> {
+        let w = *self.signals[i].iter().find(|w| witness[**w].is_none())?;
+        let (a, b, c) = &self.r1cs.constraints[i];
+        let (a0, a1) = eval_partial(a, w, witness);
+        let (b0, b1) = eval_partial(b, w, witness);
+        let (c0, c1) = eval_partial(c, w, witness);
+        if !bool::from((a1 * b1).is_zero()) {
+            return None;
+        }
+        let coeff = a0 * b1 + a1 * b0 - c1;
+        let inv = Option::<F>::from(coeff.invert())?;
+        Some((w, (c0 - a0 * b0) * inv))
+    }
+
+    /// Linearize constraint `A * B = C` as `sum(coeff * x) + constant = 0` over its unknown
+    /// signals, which requires unknown signals not to be on both sides of the multiplication.
+    fn linearize(&self, i: usize, witness: &[Option<F>]) -> Option<(BTreeMap<usize, F>, F)> {
+        let (a, b, c) = &self.r1cs.constraints[i];
+        let (ra, ka) = eval_linear(a, witness);
+        let (rb, kb) = eval_linear(b, witness);
+        if !ra.is_empty() && !rb.is_empty() {
+            return None;
+        }
+        let (rc, kc) = eval_linear(c, witness);
+        let mut row = BTreeMap::new();
+        sub_row(&mut row, &ra, -kb);
+        sub_row(&mut row, &rb, -ka);
+        sub_row(&mut row, &rc, F::ONE);
+        if row.is_empty() {
+            return None;
+        }
+        Some((row, ka * kb - kc))
+    }
+
+    /// Solve signals determined by the linear system of constraints with unknown signals,
+    /// by reducing it to reduced row echelon form.
+    fn solve_linear_system(&self, witness: &[Option<F>], unknowns: &[usize]) -> Vec<(usize, F)> {
+        // pivot signal -> (row, constant)
+        let mut pivots: BTreeMap<usize, (BTreeMap<usize, F>, F)> = BTreeMap::new();
+        for i in (0..unknowns.len()).filter(|i| unknowns[*i] > 0) {
+            let Some((mut row, mut k)) = self.linearize(i, witness) else {
+                continue;
+            };
+            while let Some(v) = row.keys().find(|v| pivots.contains_key(v)).copied() {
+                let f = row[&v];
+                let (pivot_row, pivot_k) = &pivots[&v];
+                sub_row(&mut row, pivot_row, f);
+                k -= f * pivot_k;
+            }
+            let Some((v, inv)) = row
+                .iter()
+                .next()
+                .and_then(|(v, c)| Option::<F>::from(c.invert()).map(|inv| (*v, inv)))
+            else {
+                continue;
+            };
+            row.values_mut().for_each(|c| *c *= inv);
+            k *= inv;
+            for (pivot_row, pivot_k) in pivots.values_mut() {
+                if let Some(f) = pivot_row.get(&v).copied() {
+                    sub_row(pivot_row, &row, f);
+                    *pivot_k -= f * k;
+                }
+            }
+            pivots.insert(v, (row, k));
+        }
+        pivots
+            .into_iter()
+            .filter(|(_, (row, _))| row.len() == 1)
+            .map(|(v, (_, k))| (v, -k))
+            .collect()
+    }
+
+    /// Solve signals assigned by hints of common templates, see module docs.
+    /// Binary decompositions are tried first, free signals are assigned only if there is none.
+    fn solve_hints(&self, witness: &[Option<F>], unknowns: &[usize]) -> Vec<(usize, F)> {
+        let bits = (0..unknowns.len())
+            .filter(|i| unknowns[*i] > 1)
+            .find_map(|i| self.solve_bits(i, witness));
+        if let Some(bits) = bits {
+            return bits;
+        }
+        (0..witness.len())
+            .filter(|w| witness[*w].is_none() && !self.occurrences[*w].is_empty())
+            .filter(|w| {
+                self.occurrences[*w]
+                    .iter()
+                    .all(|c| unknowns[*c] == 1 && self.is_free(*c, *w, witness))
+            })
+            .map(|w| (w, F::ZERO))
+            .collect()
+    }
+
+    /// Solve constraint `sum(c * 2^j * x_j) + k = 0` of boolean signals as bits of `-k / c`,
+    /// which is the binary decomposition of `Num2Bits` with hint `(in >> j) & 1`.
+    fn solve_bits(&self, i: usize, witness: &[Option<F>]) -> Option<Vec<(usize, F)>> {
+        let (row, k) = self.linearize(i, witness)?;
+        if row.len() < 2 || !row.keys().all(|w| self.is_boolean(*w, witness)) {
+            return None;
+        }
+        let pow2: Vec<F> = std::iter::successors(Some(F::ONE), |p| Some(p.double()))
+            .take(F::NUM_BITS as usize)
+            .collect();
+        // find the coefficient `c`, which makes others `c * 2^j`
+        let (exps, mut value) = row.values().find_map(|c| {
+            let inv = Option::<F>::from(c.invert())?;
+            let exps = row
+                .iter()
+                .map(|(w, coeff)| Some((*w, pow2.iter().position(|p| *p == *coeff * inv)?)))
+                .collect::<Option<Vec<_>>>()?;
+            Some((exps, -k * inv))
+        })?;
+        if exps.iter().map(|(_, j)| *j).collect::<BTreeSet<_>>().len() != exps.len() {
+            return None;
+        }
+        let len = exps.iter().map(|(_, j)| j + 1).max()?;
+        let mut bits = Vec::with_capacity(len);
+        for _ in 0..len {
+            let bit = if bool::from(value.is_odd()) {
+                F::ONE
+            } else {
+                F::ZERO
+            };
+            bits.push(bit);
+            value = (value - bit) * F::TWO_INV;
+        }
+        if !bool::from(value.is_zero()) {
+            return None;
+        }
+        Some(exps.into_iter().map(|(w, j)| (w, bits[j])).collect())
+    }
+
+    /// Check if signal is constrained to be 0 or 1, by a constraint of only the signal,
+    /// such as `x * (x - 1) = 0`
+    fn is_boolean(&self, w: usize, witness: &[Option<F>]) -> bool {
+        self.occurrences[w]
+            .iter()
+            .filter(|c| self.signals[**c] == [w])
+            .any(|c| {
+                let (a, b, c) = &self.r1cs.constraints[*c];
+                let (a0, a1) = eval_partial(a, w, witness);
+                let (b0, b1) = eval_partial(b, w, witness);
+                let (c0, c1) = eval_partial(c, w, witness);
+                !bool::from((a1 * b1).is_zero())
+                    && a0 * b0 == c0
+                    && (a0 + a1) * (b0 + b1) == c0 + c1
+            })
+    }
+
+    /// Check if constraint with the only unknown signal `w` is satisfied by any value of it
+    fn is_free(&self, i: usize, w: usize, witness: &[Option<F>]) -> bool {
+        let (a, b, c) = &self.r1cs.constraints[i];
+        let (a0, a1) = eval_partial(a, w, witness);
+        let (b0, b1) = eval_partial(b, w, witness);
+        let (c0, c1) = eval_partial(c, w, witness);
+        bool::from((a1 * b1).is_zero())
+            && bool::from((a0 * b1 + a1 * b0 - c1).is_zero())
+            && a0 * b0 == c0
+    }
+}
+
+/// Parse indices of array element from the rest of its name, such as `[1][0]`,
+/// the rest of a signal which is not an array is empty
+fn parse_indices(s: &str) -> Option<Vec<usize>> {
+    if s.is_empty() {
+        return Some(vec![]);
+    }
+    s.strip_prefix('[')?
+        .strip_suffix(']')?
+        .split("][")
+        .map(|i| i.parse().ok())
+        .collect()
+}
+
+/// Subtract `f * other` from row, dropping zero coefficients
+fn sub_row<F: PrimeField>(row: &mut BTreeMap<usize, F>, other: &BTreeMap<usize, F>, f: F) {
+    for (w, c) in other.iter() {
+        let coeff = *row.get(w).unwrap_or(&F::ZERO) - f * c;
+        if bool::from(coeff.is_zero()) {
+            row.remove(w);
+        } else {
+            row.insert(*w, coeff);
+        }
+    }
+}
+
+/// Evaluate linear combination as coefficients of unknown signals and a constant
+fn eval_linear<F: PrimeField>(lc: &[(usize, F)], witness: &[Option<F>]) -> (BTreeMap<usize, F>, F) {
+    let mut row = BTreeMap::new();
+    let mut k = F::ZERO;
+    for (w, coeff) in lc.iter() {
+        match witness[*w] {
+            Some(v) => k += *coeff * v,
+            None => sub_row(&mut row, &BTreeMap::from([(*w, *coeff)]), -F::ONE),
+        }
+    }
+    (row, k)
+}
+
+/// Evaluate linear combination as `v0 + v1 * x`, where `x` is the unknown signal
+fn eval_partial<F: PrimeField>(lc: &[(usize, F)], unknown: usize, witness: &[Option<F>]) -> (F, F) {
+    lc.iter().fold((F::ZERO, F::ZERO), |(v0, v1), (w, coeff)| {
+        if *w == unknown {
+            (v0, v1 + coeff)
+        } else {
+            (v0 + *coeff * witness[*w].unwrap_or(F::ZERO), v1)
+        }
+    })
+}
+
+/// Evaluate linear combination with full witness
+fn eval<F: PrimeField>(lc: &[(usize, F)], witness: &[F]) -> F {
+    lc.iter()
+        .fold(F::ZERO, |acc, (w, coeff)| acc + *coeff * witness[*w])
+}
@@ -85,6 +85,17 @@ void listen(const struct ProviderPtr *provider_ptr);
  */
 const char *request(const struct ProviderPtr *provider_ptr, const char *method, const char *params);
 
+/**
+ * Start renewing session of provider with signer, `margin` seconds ahead of its expiry.
+ * The signer is the same as the one of [new_provider_with_callback].
+ * This function will launch the renewal daemon in a new thread
+ * # Safety
+ * This function accept a ProviderPtr and will unsafety cast it into Arc based Provider
+ */
+void listen_session_renewal(const struct ProviderPtr *provider_ptr,
+                            void (*signer)(const char*, char*),
+                            uint64_t margin);
+
 /**
  * Craft a new Provider with signer and callback ptr
  * # Safety